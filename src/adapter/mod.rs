@@ -0,0 +1,15 @@
+//!
+//! # Adapter support
+//!
+//! The types in this module are the building blocks used by the streaming ropes in this crate, made available so that
+//! other crates can implement their own streaming wrappers (for a particular GUI toolkit, for example) without copying
+//! them. Unlike the `node_api` feature, this module is covered by semantic versioning.
+//!
+//! `RopeChangeSet` records and coalesces the ranges changed by a series of edits in the same way as `PullRope`, and
+//! `read_pending_change()` turns those ranges back into actions by reading the updated rope.
+//!
+
+mod rope_change_set;
+#[cfg(test)] mod tests;
+
+pub use self::rope_change_set::*;
@@ -0,0 +1,399 @@
+use crate::api::*;
+
+use std::mem;
+use std::slice;
+use std::vec;
+use std::ops::{Range, Index};
+
+///
+/// A range of cells that has changed since the changes were last taken from a `RopeChangeSet`
+///
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PendingChange {
+    /// Where these values were originally in the rope
+    pub original_range: Range<usize>,
+
+    /// Where the replacement values appear in the updated rope
+    pub new_range: Range<usize>,
+
+    /// True if the attributes for this range have changed
+    pub changed_attributes: bool
+}
+
+///
+/// Tracks the ranges of a rope that have changed, coalescing overlapping edits so that the changes can be sent on later
+/// as a small number of actions
+///
+/// This is the change tracking used by `PullRope`. Each change records where it was in the rope when tracking started
+/// and where it is in the rope now, so the difference in position between the two versions of the rope can be worked
+/// out at any point. The changes are kept in ascending order and never overlap.
+///
+#[derive(Clone, Debug, Default)]
+pub struct RopeChangeSet {
+    /// The changes that have ocurred since the last time they were taken (kept in ascending order)
+    changes: Vec<PendingChange>
+}
+
+impl RopeChangeSet {
+    ///
+    /// Creates a change set with no changes
+    ///
+    pub fn new() -> RopeChangeSet {
+        RopeChangeSet {
+            changes: vec![]
+        }
+    }
+
+    ///
+    /// The number of changed ranges
+    ///
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    ///
+    /// True if there are no changes
+    ///
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    ///
+    /// The changed ranges, in ascending order
+    ///
+    #[inline]
+    pub fn changes(&self) -> &[PendingChange] {
+        &self.changes
+    }
+
+    ///
+    /// Iterates over the changed ranges, in ascending order
+    ///
+    #[inline]
+    pub fn iter(&self) -> impl '_+ExactSizeIterator<Item=&PendingChange> {
+        self.changes.iter()
+    }
+
+    ///
+    /// Removes and returns the changed ranges, leaving the change set empty
+    ///
+    pub fn take_changes(&mut self) -> Vec<PendingChange> {
+        mem::take(&mut self.changes)
+    }
+
+    ///
+    /// Marks the region changed by an action (which is about to be applied to the rope)
+    ///
+    pub fn mark_action<Cell, Attribute>(&mut self, action: &RopeAction<Cell, Attribute>) {
+        match action {
+            RopeAction::Replace(range, new_values)                  => self.mark(range.clone(), new_values.len(), false),
+            RopeAction::SetAttributes(range, _attr)                 => self.mark(range.clone(), range.len(), true),
+            RopeAction::ReplaceAttributes(range, new_values, _attr) => self.mark(range.clone(), new_values.len(), true)
+        }
+    }
+
+    ///
+    /// Returns the index in the changes list that is either before or just after the specified position,
+    /// along with the difference in position from the original at that point
+    ///
+    pub fn find_change(&self, pos: usize) -> (usize, i64) {
+        let mut diff = 0;
+
+        // Changes can only replace or insert, they can't move things around: this means that both
+        // the 'old' and 'new' ranges will always be in order.
+        for idx in 0..self.changes.len() {
+            let change = &self.changes[idx];
+
+            if change.new_range.start <= pos && change.new_range.end > pos {
+                // Position is in the range of this change
+                return (idx, diff);
+            } else if change.new_range.start > pos {
+                // We've passed the change
+                return (idx, diff);
+            }
+
+            // Update the difference in position from this point
+            let old_len = change.original_range.len() as i64;
+            let new_len = change.new_range.len() as i64;
+
+            diff += old_len - new_len;
+        }
+
+        // Change not found: must be beyond the end of the change range
+        (self.changes.len(), diff)
+    }
+
+    #[cfg(test)]
+    fn check_integrity(&self) {
+        for change_idx in 1..self.changes.len() {
+            let last_change = &self.changes[change_idx-1];
+            let this_change = &self.changes[change_idx];
+
+            assert!(last_change.original_range.end <= this_change.original_range.start);
+            assert!(last_change.new_range.end <= this_change.new_range.start);
+        }
+    }
+
+    #[cfg(not(test))]
+    #[inline]
+    fn check_integrity(&self) {
+
+    }
+
+    ///
+    /// Marks a region as changed, merging it with any existing changes that it overlaps
+    ///
+    /// The range is in the rope as it is before the edit is applied, and `new_len` is the number of cells that will
+    /// replace it. `attrs_changed` should be true if the edit sets attributes.
+    ///
+    pub fn mark(&mut self, original_range: Range<usize>, new_len: usize, attrs_changed: bool) {
+        // Find the existing change corresponding to the start of the range
+        let (mut change_idx, mut diff)  = self.find_change(original_range.start);
+        let mut remaining_range         = original_range;
+        let mut remaining_length        = new_len;
+
+        loop {
+            self.check_integrity();
+
+            // If the index is beyond the end of the existing changes, then just add the edit range to the end
+            if change_idx >= self.changes.len() {
+                // Adjust the original range to match the new range
+                let original_start  = (remaining_range.start as i64) + diff;
+                let original_end    = (remaining_range.end as i64) + diff;
+                let original_start  = original_start as usize;
+                let original_end    = original_end as usize;
+
+                self.changes.push(PendingChange {
+                    original_range:     original_start..original_end,
+                    new_range:          remaining_range.start..(remaining_range.start+remaining_length),
+                    changed_attributes: attrs_changed
+                });
+
+                break;
+            } else if self.changes[change_idx].new_range.start <= remaining_range.start {
+                // We overlap with an existing range
+                self.changes[change_idx].changed_attributes = self.changes[change_idx].changed_attributes || attrs_changed;
+                let change = &self.changes[change_idx];
+
+                if remaining_range.end < change.new_range.end {
+                    // New change is entirely within the existing change
+                    let max_diff        = change.new_range.len() as i64;
+                    let length_diff     = (remaining_range.len() as i64) - (remaining_length as i64);
+                    let length_change   = max_diff.min(length_diff);
+
+                    if length_diff != 0 {
+                        // Adjust the length of the changed range
+                        self.changes[change_idx].new_range.end = (self.changes[change_idx].new_range.end as i64 - length_change) as usize;
+
+                        // Adjust the position of the following ranges
+                        for move_idx in (change_idx+1)..self.changes.len() {
+                            self.changes[move_idx].new_range.start  = (self.changes[move_idx].new_range.start as i64 - length_change) as usize;
+                            self.changes[move_idx].new_range.end    = (self.changes[move_idx].new_range.end as i64 - length_change) as usize;
+                        }
+                    }
+
+                    break;
+                } else {
+                    // Continue with the following range, by using all of the existing range
+                    let used_length         = change.new_range.end - remaining_range.start;
+                    remaining_range.start   += used_length;
+
+                    if remaining_length >= used_length {
+                        // The new items all fit within this range, so keep it as is
+                        remaining_length        -= used_length;
+                    } else {
+                        // The new range is shorter than the part of this range it overlaps, so shrink it by that much
+                        let length_diff = used_length - remaining_length;
+                        self.changes[change_idx].new_range.end    = change.new_range.end - length_diff;
+
+                        for move_idx in (change_idx+1)..self.changes.len() {
+                            self.changes[move_idx].new_range.start  = self.changes[move_idx].new_range.start - length_diff;
+                            self.changes[move_idx].new_range.end    = self.changes[move_idx].new_range.end - length_diff;
+                        }
+
+                        // The remaining range is moved by the same amount
+                        remaining_range.start   -= length_diff;
+                        remaining_range.end     -= length_diff;
+
+                        // The entire length is consumed
+                        remaining_length        = 0;
+
+                        if remaining_range.start == remaining_range.end {
+                            break;
+                        }
+                    }
+
+                    // New range will be overlapping or before the next change
+                    let change  = &self.changes[change_idx];
+                    let old_len = change.original_range.len() as i64;
+                    let new_len = change.new_range.len() as i64;
+
+                    diff        += old_len - new_len;
+                    change_idx  += 1;
+                }
+            } else {
+                // The range does not overlap an existing range
+                let next_range_start = if change_idx < self.changes.len() {
+                    self.changes[change_idx].new_range.start
+                } else {
+                    usize::MAX
+                };
+
+                if next_range_start >= remaining_range.end {
+                    // If the next range fits within the gap, then insert it and stop
+                    let original_start  = (remaining_range.start as i64) + diff;
+                    let original_end    = (remaining_range.end as i64) + diff;
+                    let original_start  = original_start as usize;
+                    let original_end    = original_end as usize;
+
+                    self.changes.insert(change_idx, PendingChange {
+                        original_range:     original_start..original_end,
+                        new_range:          remaining_range.start..(remaining_range.start+remaining_length),
+                        changed_attributes: attrs_changed
+                    });
+
+                    // New change is entirely within the existing gap
+                    let length_diff = (remaining_range.len() as i64) - (remaining_length as i64);
+
+                    if length_diff != 0 {
+                        // Adjust the position of the following ranges
+                        for move_idx in (change_idx+1)..self.changes.len() {
+                            self.changes[move_idx].new_range.start  = (self.changes[move_idx].new_range.start as i64 - length_diff) as usize;
+                            self.changes[move_idx].new_range.end    = (self.changes[move_idx].new_range.end as i64 - length_diff) as usize;
+                        }
+                    }
+
+                    break;
+                } else {
+                    // Fill in as much as possible from the gap and continue from here
+                    let gap_length      = next_range_start - remaining_range.start;
+                    let original_start  = (remaining_range.start as i64) + diff;
+                    let original_start  = original_start as usize;
+                    let gap_end         = original_start + gap_length;
+
+                    if gap_length <= remaining_length {
+                        // The new range covers the entire gap
+                        self.changes.insert(change_idx, PendingChange {
+                            original_range:     original_start..gap_end,
+                            new_range:          remaining_range.start..(remaining_range.start+gap_length),
+                            changed_attributes: attrs_changed
+                        });
+
+                        remaining_range.start   += gap_length;
+                        remaining_length        -= gap_length;
+                        change_idx              += 1;
+                    } else {
+                        // The new range needs to shrink the gap
+                        self.changes.insert(change_idx, PendingChange {
+                            original_range:     original_start..gap_end,
+                            new_range:          remaining_range.start..(remaining_range.start+remaining_length),
+                            changed_attributes: attrs_changed
+                        });
+
+                        // Shrink the future changes
+                        let length_diff = gap_length - remaining_length;
+
+                        // Adjust the position of the following ranges
+                        for move_idx in (change_idx+1)..self.changes.len() {
+                            self.changes[move_idx].new_range.start  = self.changes[move_idx].new_range.start - length_diff;
+                            self.changes[move_idx].new_range.end    = self.changes[move_idx].new_range.end - length_diff;
+                        }
+
+                        remaining_range.start   += remaining_length;
+                        remaining_range.end     -= gap_length-remaining_length;
+                        remaining_length        = 0;
+
+                        // Move to the next range
+                        let change              = &self.changes[change_idx];
+
+                        let old_len             = change.original_range.len() as i64;
+                        let new_len             = change.new_range.len() as i64;
+
+                        diff                    += old_len - new_len;
+                        change_idx              += 1;
+                    }
+                }
+            }
+        }
+
+        self.check_integrity();
+    }
+}
+
+impl<'a> IntoIterator for &'a RopeChangeSet {
+    type Item       = &'a PendingChange;
+    type IntoIter   = slice::Iter<'a, PendingChange>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.iter()
+    }
+}
+
+impl IntoIterator for RopeChangeSet {
+    type Item       = PendingChange;
+    type IntoIter   = vec::IntoIter<PendingChange>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.into_iter()
+    }
+}
+
+impl Index<usize> for RopeChangeSet {
+    type Output = PendingChange;
+
+    #[inline]
+    fn index(&self, idx: usize) -> &PendingChange {
+        &self.changes[idx]
+    }
+}
+
+///
+/// Reads the actions that will make a pending change to a copy of the rope in its original state
+///
+/// The rope should be in its current state. When there are several changes, they should be read in reverse order, so
+/// the actions can be applied directly to the original rope. An attribute change that covers several attribute runs
+/// produces one action for each run.
+///
+pub fn read_pending_change<R: ?Sized+Rope>(rope: &R, change: PendingChange) -> Vec<RopeAction<R::Cell, R::Attribute>> {
+    if change.original_range.is_empty() && change.new_range.is_empty() { return vec![]; }
+
+    if change.changed_attributes && change.new_range.len() > 0 {
+        // Replace the cells and attributes in this range
+
+        // Usually the attribute will cover the whole range but it's possible to create multiple attributes in a range via several updates: we work backwards until we've covered the entire range
+        let mut original_range  = change.original_range;
+        let new_range           = change.new_range;
+        let mut end_pos         = new_range.end;
+        let mut changes         = vec![];
+
+        loop {
+            // Read the attributes at the current end position
+            let (attribute, attribute_range)    = rope.read_attributes(end_pos-1);
+            let start_pos                       = new_range.start.max(attribute_range.start);
+            let valid_range                     = start_pos..end_pos;
+            let new_cells                       = rope.read_cells(valid_range).cloned().collect();
+
+            changes.push(RopeAction::ReplaceAttributes(original_range.clone(), new_cells, attribute.clone()));
+
+            // Stop once we reach the start of the changed range
+            if start_pos <= new_range.start { break; }
+
+            // Make the next change an insertion at the beginning of the range
+            original_range                      = original_range.start..original_range.start;
+
+            // Continue searching for attributes from the point we reached
+            end_pos                             = start_pos;
+        }
+
+        changes
+    } else {
+        // Just replace the cells in this range
+        let new_cells = rope.read_cells(change.new_range.clone()).cloned().collect::<Vec<_>>();
+
+        vec![RopeAction::Replace(change.original_range, new_cells)]
+    }
+}
@@ -0,0 +1,72 @@
+use super::*;
+use crate::*;
+
+#[test]
+fn tracked_changes_update_mirror() {
+    let mut rope    = AttributedRope::<u8, i64>::from_str("Hello, world");
+    let mut mirror  = rope.clone();
+    let mut change_set = RopeChangeSet::new();
+
+    let actions = vec![
+        RopeAction::Replace(7..12, b"rope".to_vec()),
+        RopeAction::SetAttributes(0..5, 1),
+        RopeAction::Replace(8..9, b"a".to_vec()),
+        RopeAction::Replace(2..2, b"!!".to_vec()),
+    ];
+
+    for action in actions {
+        change_set.mark_action(&action);
+        rope.edit(action);
+    }
+
+    // Overlapping edits are coalesced
+    assert!(change_set.len() == 2);
+    assert!(change_set[1] == PendingChange { original_range: 7..12, new_range: 9..13, changed_attributes: false });
+    assert!(change_set.find_change(11) == (1, -2));
+
+    for change in change_set.take_changes().into_iter().rev() {
+        read_pending_change(&rope, change).into_iter().for_each(|action| mirror.edit(action));
+    }
+
+    assert!(change_set.is_empty());
+    assert!(mirror.to_string_lossy() == "He!!llo, rape");
+    assert!(mirror.read_attribute_runs(0..mirror.len()).eq(rope.read_attribute_runs(0..rope.len())));
+}
+
+#[test]
+fn iterate_over_merged_changes() {
+    let mut change_set = RopeChangeSet::new();
+
+    change_set.mark(2..4, 3, false);
+    change_set.mark(3..5, 1, false);
+    change_set.mark(10..10, 2, true);
+
+    // The first two changes overlap, so they're merged into one
+    let ranges = change_set.iter().map(|change| (change.original_range.clone(), change.new_range.clone())).collect::<Vec<_>>();
+    assert!(ranges == vec![(2..4, 2..4), (10..10, 10..12)]);
+
+    let mut num_attribute_changes = 0;
+    for change in &change_set {
+        if change.changed_attributes { num_attribute_changes += 1; }
+    }
+    assert!(num_attribute_changes == 1);
+
+    assert!(change_set.into_iter().count() == 2);
+}
+
+#[test]
+fn change_set_merges_marked_changes() {
+    let mut changes = RopeChangeSet::new();
+
+    changes.mark(2..4, 3, false);
+    changes.mark(3..5, 1, false);
+    changes.mark(10..10, 2, true);
+    changes.mark(0..1, 1, true);
+
+    let merged = changes.iter().cloned().collect::<Vec<_>>();
+    assert!(merged == vec![
+        PendingChange { original_range: 0..1, new_range: 0..1, changed_attributes: true },
+        PendingChange { original_range: 2..4, new_range: 2..4, changed_attributes: false },
+        PendingChange { original_range: 10..10, new_range: 10..12, changed_attributes: true }
+    ]);
+}
@@ -0,0 +1,178 @@
+use super::rope_action::*;
+
+use std::ops::{Range};
+
+///
+/// Compacts a sequence of actions by combining actions that edit the same or neighbouring regions
+/// of a rope into a shorter sequence with the same effect.
+///
+/// This is useful for keeping long-running journals (or the actions that need to be sent to bring a
+/// remote rope up to date) from growing with redundant intermediate states: for example, typing a word
+/// one character at a time will produce a single `Replace` action after compaction, and setting the
+/// same attribute on overlapping ranges will produce a single `SetAttributes` action.
+///
+/// Actions are only combined when the result will produce the same cells and the same explicitly set
+/// attributes as the original sequence, so the compacted actions can be applied to a rope in place of
+/// the originals.
+///
+pub fn compact_actions<Cell, Attribute, ActionIter>(actions: ActionIter) -> Vec<RopeAction<Cell, Attribute>>
+where
+Attribute:  PartialEq,
+ActionIter: IntoIterator<Item=RopeAction<Cell, Attribute>> {
+    let mut compacted = vec![];
+
+    for action in actions {
+        // Actions that have no effect can just be removed from the list
+        if is_no_op(&action) {
+            continue;
+        }
+
+        // Try to combine with the preceding action: if that succeeds, the result might also combine with the action before that
+        let mut action = action;
+
+        while let Some(last_action) = compacted.pop() {
+            match combine_actions(last_action, action) {
+                Ok(combined) => {
+                    action = combined;
+                }
+
+                Err((last_action, next_action)) => {
+                    compacted.push(last_action);
+                    action = next_action;
+                    break;
+                }
+            }
+        }
+
+        if !is_no_op(&action) {
+            compacted.push(action);
+        }
+    }
+
+    compacted
+}
+
+///
+/// True if an action has no effect on the rope it's applied to
+///
+fn is_no_op<Cell, Attribute>(action: &RopeAction<Cell, Attribute>) -> bool {
+    match action {
+        RopeAction::Replace(range, cells)               => range.len() == 0 && cells.len() == 0,
+        RopeAction::SetAttributes(range, _)             => range.len() == 0,
+        RopeAction::ReplaceAttributes(_, _, _)          => false,
+    }
+}
+
+///
+/// Replaces the cells in `range` (relative to the start of `cells`) with `new_cells`, returning the number
+/// of cells in the range that were beyond the end of the original list
+///
+fn splice_cells<Cell>(cells: &mut Vec<Cell>, range: Range<usize>, new_cells: Vec<Cell>) -> usize {
+    let len         = cells.len();
+    let splice_end  = range.end.min(len);
+    let overrun     = range.end - splice_end;
+
+    cells.splice(range.start..splice_end, new_cells);
+
+    overrun
+}
+
+/// The result of trying to combine two actions: the original actions are returned if they can't be combined
+type CombineResult<Cell, Attribute> = Result<RopeAction<Cell, Attribute>, (RopeAction<Cell, Attribute>, RopeAction<Cell, Attribute>)>;
+
+///
+/// Attempts to combine two actions into one, returning the two actions unchanged if that isn't possible.
+/// `first` is the action that was applied to the rope first.
+///
+fn combine_actions<Cell, Attribute>(first: RopeAction<Cell, Attribute>, second: RopeAction<Cell, Attribute>) -> CombineResult<Cell, Attribute>
+where
+Attribute: PartialEq {
+    use RopeAction::*;
+
+    match (first, second) {
+        (Replace(first_range, mut first_cells), Replace(second_range, second_cells)) => {
+            // The range that the first action wrote to
+            let written_start   = first_range.start;
+            let written_end     = first_range.start + first_cells.len();
+
+            // A deletion followed by an edit at the same point can't be combined if the new cells would take their attributes
+            // from a different cell: the edit takes them from the cell before the deletion, or from the first cell after the
+            // deletion when it's at the start of the rope, but the combined action would take them from the first deleted cell
+            let attributes_differ = first_cells.len() == 0 && second_range.start == written_start && (written_start == 0 || second_range.end > written_end);
+
+            if attributes_differ {
+                Err((Replace(first_range, first_cells), Replace(second_range, second_cells)))
+            } else if second_range.start >= written_start && second_range.start <= written_end {
+                // Second action starts within the cells that were written by the first action, so the attributes will match
+                let overrun = splice_cells(&mut first_cells, (second_range.start-written_start)..(second_range.end-written_start), second_cells);
+
+                Ok(Replace(first_range.start..(first_range.end+overrun), first_cells))
+            } else if first_cells.len() == 0 && second_range.start <= written_start && second_range.end >= written_start {
+                // First action deleted some cells and the second action covers the point where the deletion occurred
+                Ok(Replace(second_range.start..(second_range.end+first_range.len()), second_cells))
+            } else {
+                Err((Replace(first_range, first_cells), Replace(second_range, second_cells)))
+            }
+        }
+
+        (ReplaceAttributes(first_range, mut first_cells, first_attr), ReplaceAttributes(second_range, second_cells, second_attr)) => {
+            let written_start   = first_range.start;
+            let written_end     = first_range.start + first_cells.len();
+
+            if first_attr == second_attr && second_range.start >= written_start && second_range.start <= written_end {
+                // Both actions write the same attribute, so the second action can be merged into the first
+                let overrun = splice_cells(&mut first_cells, (second_range.start-written_start)..(second_range.end-written_start), second_cells);
+
+                Ok(ReplaceAttributes(first_range.start..(first_range.end+overrun), first_cells, first_attr))
+            } else {
+                Err((ReplaceAttributes(first_range, first_cells, first_attr), ReplaceAttributes(second_range, second_cells, second_attr)))
+            }
+        }
+
+        (ReplaceAttributes(first_range, mut first_cells, first_attr), Replace(second_range, second_cells)) => {
+            let written_start   = first_range.start;
+            let written_end     = first_range.start + first_cells.len();
+
+            if second_range.start > written_start && second_range.start <= written_end {
+                // The new cells will take their attributes from the cells written by the first action
+                let overrun = splice_cells(&mut first_cells, (second_range.start-written_start)..(second_range.end-written_start), second_cells);
+
+                Ok(ReplaceAttributes(first_range.start..(first_range.end+overrun), first_cells, first_attr))
+            } else {
+                Err((ReplaceAttributes(first_range, first_cells, first_attr), Replace(second_range, second_cells)))
+            }
+        }
+
+        (Replace(first_range, first_cells), SetAttributes(second_range, second_attr)) => {
+            if first_cells.len() > 0 && second_range.start == first_range.start && second_range.end == first_range.start + first_cells.len() {
+                // Setting the attributes of exactly the cells that were just written
+                Ok(ReplaceAttributes(first_range, first_cells, second_attr))
+            } else {
+                Err((Replace(first_range, first_cells), SetAttributes(second_range, second_attr)))
+            }
+        }
+
+        (ReplaceAttributes(first_range, first_cells, first_attr), SetAttributes(second_range, second_attr)) => {
+            if first_cells.len() > 0 && second_range.start == first_range.start && second_range.end == first_range.start + first_cells.len() {
+                // The new attributes replace the attributes that were set by the first action
+                Ok(ReplaceAttributes(first_range, first_cells, second_attr))
+            } else {
+                Err((ReplaceAttributes(first_range, first_cells, first_attr), SetAttributes(second_range, second_attr)))
+            }
+        }
+
+        (SetAttributes(first_range, first_attr), SetAttributes(second_range, second_attr)) => {
+            if second_range.start <= first_range.start && second_range.end >= first_range.end {
+                // Second action overwrites all of the attributes set by the first
+                Ok(SetAttributes(second_range, second_attr))
+            } else if first_attr == second_attr && second_range.start <= first_range.end && second_range.end >= first_range.start {
+                // Setting the same attribute on overlapping or neighbouring ranges
+                Ok(SetAttributes(first_range.start.min(second_range.start)..first_range.end.max(second_range.end), first_attr))
+            } else {
+                Err((SetAttributes(first_range, first_attr), SetAttributes(second_range, second_attr)))
+            }
+        }
+
+        (first, second) => Err((first, second))
+    }
+}
@@ -1,7 +1,18 @@
 mod rope_trait;
 mod rope_mut_trait;
 mod rope_action;
+mod compact_actions;
+mod transform;
+mod split_action;
+mod rope_search;
+mod position_mapping;
+#[cfg(test)] mod tests;
 
 pub use self::rope_trait::*;
 pub use self::rope_mut_trait::*;
 pub use self::rope_action::*;
+pub use self::compact_actions::*;
+pub use self::transform::*;
+pub use self::split_action::*;
+pub use self::rope_search::*;
+pub use self::position_mapping::*;
@@ -0,0 +1,73 @@
+use super::rope_action::*;
+
+use std::ops::{Range};
+
+///
+/// Indicates which way a position moves when cells are inserted at it, or the cells around it are replaced
+///
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum AnchorBias {
+    /// The anchor stays to the left of any cells inserted at its position (it sticks to the cell before it)
+    Left,
+
+    /// The anchor moves to the right of any cells inserted at its position (it sticks to the cell after it)
+    Right
+}
+
+///
+/// Maps a position through a replacement of `range` with `new_len` cells
+///
+pub (crate) fn map_position(pos: usize, bias: AnchorBias, range: &Range<usize>, new_len: usize) -> usize {
+    if pos < range.start {
+        // Before the edit
+        pos
+    } else if pos > range.end {
+        // After the edit
+        pos - range.len() + new_len
+    } else if range.start != range.end && pos == range.start {
+        // At the start of the replaced cells: the cell before the anchor is unchanged
+        range.start
+    } else if range.start != range.end && pos == range.end {
+        // At the end of the replaced cells: the cell after the anchor is unchanged
+        range.start + new_len
+    } else {
+        // Insertion point or inside the replaced cells
+        match bias {
+            AnchorBias::Left    => range.start,
+            AnchorBias::Right   => range.start + new_len
+        }
+    }
+}
+
+///
+/// Maps a position in a rope to where it will be after a series of actions have been applied to it
+///
+/// The actions are applied in order, as they would be with `RopeMut::edit()`. When cells are inserted at the position,
+/// or the cells around it are replaced, the bias decides whether the position ends up before or after the new cells.
+/// `SetAttributes` actions don't move any cells, so they don't affect the position.
+///
+pub fn map_index<Cell, Attribute>(pos: usize, bias: AnchorBias, actions: &[RopeAction<Cell, Attribute>]) -> usize {
+    actions.iter()
+        .fold(pos, |pos, action| {
+            match action {
+                RopeAction::Replace(range, cells)               => map_position(pos, bias, range, cells.len()),
+                RopeAction::ReplaceAttributes(range, cells, _)  => map_position(pos, bias, range, cells.len()),
+                RopeAction::SetAttributes(_, _)                 => pos
+            }
+        })
+}
+
+///
+/// Maps a range in a rope to where it will be after a series of actions have been applied to it
+///
+/// The start and end of the range each have their own bias: a selection would usually use `AnchorBias::Right` for the
+/// start and `AnchorBias::Left` for the end so that it doesn't grow when text is typed at either end, and a highlight
+/// that should grow as text is typed at its end would use `AnchorBias::Right` for both. If all of the cells in the
+/// range are removed, the result is an empty range where they used to be.
+///
+pub fn map_range<Cell, Attribute>(range: Range<usize>, start_bias: AnchorBias, end_bias: AnchorBias, actions: &[RopeAction<Cell, Attribute>]) -> Range<usize> {
+    let start   = map_index(range.start, start_bias, actions);
+    let end     = map_index(range.end, end_bias, actions);
+
+    start..end.max(start)
+}
@@ -3,7 +3,11 @@ use std::ops::{Range};
 ///
 /// The editing action that can be performed on a rope
 ///
+/// With the `serde` feature enabled, actions can be serialized so that they can be sent to a mirror of a rope
+/// in another process.
+///
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RopeAction<Cell, Attribute> {
     /// Replaces a range of text in this rope. The attributes applied to 
     /// the new text will be the same as those applied to the first character 
@@ -16,3 +20,189 @@ pub enum RopeAction<Cell, Attribute> {
     /// Sets both the attributes and the value for a range of cells
     ReplaceAttributes(Range<usize>, Vec<Cell>, Attribute)
 }
+
+///
+/// A borrowed form of `RopeAction`, which refers to its cells and attributes rather than owning them
+///
+/// This is useful for observers that only need to inspect an action (for logging or measuring changes, for
+/// example), as it avoids needing to copy the cells that are being inserted into the rope. It serializes in the same
+/// format as `RopeAction`.
+///
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename = "RopeAction"))]
+pub enum RopeActionRef<'a, Cell, Attribute> {
+    /// Replaces a range of text in this rope. The attributes applied to 
+    /// the new text will be the same as those applied to the first character 
+    /// in the replaced range.
+    Replace(Range<usize>, &'a [Cell]),
+
+    /// Sets the attributes for a range of text in this rope
+    SetAttributes(Range<usize>, &'a Attribute),
+
+    /// Sets both the attributes and the value for a range of cells
+    ReplaceAttributes(Range<usize>, &'a [Cell], &'a Attribute)
+}
+
+impl<Cell, Attribute> RopeAction<Cell, Attribute> {
+    ///
+    /// Returns a borrowed version of this action
+    ///
+    pub fn as_action_ref<'a>(&'a self) -> RopeActionRef<'a, Cell, Attribute> {
+        match self {
+            RopeAction::Replace(range, cells)                       => RopeActionRef::Replace(range.clone(), cells),
+            RopeAction::SetAttributes(range, attribute)             => RopeActionRef::SetAttributes(range.clone(), attribute),
+            RopeAction::ReplaceAttributes(range, cells, attribute)  => RopeActionRef::ReplaceAttributes(range.clone(), cells, attribute)
+        }
+    }
+
+    ///
+    /// Returns the range of the rope that this action changes (in the rope before the action is applied)
+    ///
+    #[inline]
+    pub fn range(&self) -> Range<usize> {
+        self.as_action_ref().range()
+    }
+
+    ///
+    /// True if this action changes any of the cells in the specified range
+    ///
+    /// Insertions (and ranges that are empty) are treated as intersecting if they're at either end of the range, so
+    /// an action inserting cells at the end of a region will be considered to affect that region.
+    ///
+    #[inline]
+    pub fn intersects(&self, range: Range<usize>) -> bool {
+        self.as_action_ref().intersects(range)
+    }
+
+    ///
+    /// Returns the number of cells that the range will contain after this action has been applied
+    ///
+    #[inline]
+    pub fn new_len(&self) -> usize {
+        match self {
+            RopeAction::Replace(_, cells)               => cells.len(),
+            RopeAction::SetAttributes(range, _)         => range.len(),
+            RopeAction::ReplaceAttributes(_, cells, _)  => cells.len()
+        }
+    }
+
+    ///
+    /// Returns the same action applied to a different range (for moving an action to where its cells are in another rope)
+    ///
+    #[inline]
+    pub fn with_range(self, range: Range<usize>) -> Self {
+        match self {
+            RopeAction::Replace(_, cells)                       => RopeAction::Replace(range, cells),
+            RopeAction::SetAttributes(_, attribute)             => RopeAction::SetAttributes(range, attribute),
+            RopeAction::ReplaceAttributes(_, cells, attribute)  => RopeAction::ReplaceAttributes(range, cells, attribute)
+        }
+    }
+
+    ///
+    /// Splits this action into the part that affects the cells before `pos` and the part that affects the cells
+    /// from `pos` onwards
+    ///
+    /// Both parts use the positions from the rope before the action is applied, so applying the second part followed by
+    /// the first will have the same effect as the original action. Any cells written by the action are part of the first
+    /// part, unless the action starts at or after `pos`. An insertion at `pos` is returned as the second part.
+    ///
+    pub fn split_at(self, pos: usize) -> (Option<Self>, Option<Self>)
+    where
+    Attribute: Clone {
+        let range = self.range();
+
+        if pos <= range.start {
+            (None, Some(self))
+        } else if pos >= range.end {
+            (Some(self), None)
+        } else {
+            match self {
+                RopeAction::Replace(range, cells)                       => (Some(RopeAction::Replace(range.start..pos, cells)), Some(RopeAction::Replace(pos..range.end, vec![]))),
+                RopeAction::SetAttributes(range, attribute)             => (Some(RopeAction::SetAttributes(range.start..pos, attribute.clone())), Some(RopeAction::SetAttributes(pos..range.end, attribute))),
+                RopeAction::ReplaceAttributes(range, cells, attribute)  => (Some(RopeAction::ReplaceAttributes(range.start..pos, cells, attribute.clone())), Some(RopeAction::ReplaceAttributes(pos..range.end, vec![], attribute)))
+            }
+        }
+    }
+}
+
+impl<'a, Cell, Attribute> RopeActionRef<'a, Cell, Attribute> {
+    ///
+    /// Returns the range of the rope that this action changes (in the rope before the action is applied)
+    ///
+    #[inline]
+    pub fn range(&self) -> Range<usize> {
+        match self {
+            RopeActionRef::Replace(range, _)                => range.clone(),
+            RopeActionRef::SetAttributes(range, _)          => range.clone(),
+            RopeActionRef::ReplaceAttributes(range, _, _)   => range.clone()
+        }
+    }
+
+    ///
+    /// True if this action changes any of the cells in the specified range
+    ///
+    /// Insertions (and ranges that are empty) are treated as intersecting if they're at either end of the range, so
+    /// an action inserting cells at the end of a region will be considered to affect that region.
+    ///
+    pub fn intersects(&self, range: Range<usize>) -> bool {
+        let action_range = self.range();
+
+        if action_range.is_empty() || range.is_empty() {
+            action_range.start <= range.end && range.start <= action_range.end
+        } else {
+            action_range.start < range.end && range.start < action_range.end
+        }
+    }
+
+    ///
+    /// Splits this action into the part that affects the cells before `pos` and the part that affects the cells
+    /// from `pos` onwards (see `RopeAction::split_at()`)
+    ///
+    pub fn split_at(self, pos: usize) -> (Option<Self>, Option<Self>) {
+        let range = self.range();
+
+        if pos <= range.start {
+            (None, Some(self))
+        } else if pos >= range.end {
+            (Some(self), None)
+        } else {
+            match self {
+                RopeActionRef::Replace(range, cells)                        => (Some(RopeActionRef::Replace(range.start..pos, cells)), Some(RopeActionRef::Replace(pos..range.end, &[]))),
+                RopeActionRef::SetAttributes(range, attribute)              => (Some(RopeActionRef::SetAttributes(range.start..pos, attribute)), Some(RopeActionRef::SetAttributes(pos..range.end, attribute))),
+                RopeActionRef::ReplaceAttributes(range, cells, attribute)   => (Some(RopeActionRef::ReplaceAttributes(range.start..pos, cells, attribute)), Some(RopeActionRef::ReplaceAttributes(pos..range.end, &[], attribute)))
+            }
+        }
+    }
+}
+
+impl<'a, Cell, Attribute> RopeActionRef<'a, Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  Clone {
+    ///
+    /// Creates an owned copy of this action
+    ///
+    pub fn to_action(&self) -> RopeAction<Cell, Attribute> {
+        match self {
+            RopeActionRef::Replace(range, cells)                        => RopeAction::Replace(range.clone(), cells.to_vec()),
+            RopeActionRef::SetAttributes(range, attribute)              => RopeAction::SetAttributes(range.clone(), (*attribute).clone()),
+            RopeActionRef::ReplaceAttributes(range, cells, attribute)   => RopeAction::ReplaceAttributes(range.clone(), cells.to_vec(), (*attribute).clone())
+        }
+    }
+}
+
+impl<'a, Cell, Attribute> From<&'a RopeAction<Cell, Attribute>> for RopeActionRef<'a, Cell, Attribute> {
+    fn from(action: &'a RopeAction<Cell, Attribute>) -> RopeActionRef<'a, Cell, Attribute> {
+        action.as_action_ref()
+    }
+}
+
+impl<'a, Cell, Attribute> From<RopeActionRef<'a, Cell, Attribute>> for RopeAction<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  Clone {
+    fn from(action: RopeActionRef<'a, Cell, Attribute>) -> RopeAction<Cell, Attribute> {
+        action.to_action()
+    }
+}
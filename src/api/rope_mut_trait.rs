@@ -27,10 +27,46 @@ pub trait RopeMut : Rope {
         self.edit(RopeAction::SetAttributes(range, new_attributes));
     }
 
+    ///
+    /// Resets the attributes for a range of cells to the default attribute
+    ///
+    fn clear_attributes(&mut self, range: Range<usize>) {
+        self.set_attributes(range, Self::Attribute::default());
+    }
+
     ///
     /// Replaces a range of cells and sets the attributes for them.
     ///
     fn replace_attributes<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells, new_attributes: Self::Attribute) {
         self.edit(RopeAction::ReplaceAttributes(range, new_cells.into_iter().collect(), new_attributes));
     }
+
+    ///
+    /// Copies the attributes (but not the cells) from a range of another rope onto the range of the same length starting
+    /// at `dest_pos` in this rope
+    ///
+    /// This can be used to apply the formatting from a template, or to implement a 'paste formatting' command. Any part
+    /// of the range that's beyond the end of this rope is ignored.
+    ///
+    fn copy_attributes_from<SrcRope: Rope<Attribute=Self::Attribute>>(&mut self, src: &SrcRope, src_range: Range<usize>, dest_pos: usize) {
+        let runs = src.read_attribute_runs(src_range.clone())
+            .map(|(attribute, run_range)| ((run_range.start - src_range.start + dest_pos)..(run_range.end - src_range.start + dest_pos), attribute.clone()))
+            .collect::<Vec<_>>();
+
+        for (range, attribute) in runs {
+            self.set_attributes(range, attribute);
+        }
+    }
+
+    ///
+    /// Performs a series of edits to this rope as a single transaction
+    ///
+    /// Ropes that send notifications about their changes will send a single notification once the transaction has
+    /// completed instead of one for each edit.
+    ///
+    fn transaction<TransactionFn: FnOnce(&mut Self) -> ()>(&mut self, transaction_fn: TransactionFn)
+    where
+    Self: Sized {
+        transaction_fn(self);
+    }
 }
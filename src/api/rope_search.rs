@@ -0,0 +1,232 @@
+use super::rope_trait::*;
+
+use std::ops::{Range};
+
+///
+/// Searches for sequences of cells in a rope
+///
+/// These compare the cells a chunk at a time (see `Rope::read_chunks()`), so the rope doesn't need to be copied into
+/// a `Vec` first.
+///
+/// This is implemented for the ropes in this crate, but not for the types such as `Vec` or `String` that can also be
+/// used as ropes, as it would hide their own `starts_with()`, `ends_with()` and `contains()` methods.
+///
+pub trait RopeSearch : Rope
+where
+Self::Cell: PartialEq {
+    ///
+    /// True if the rope starts with the specified cells
+    ///
+    fn starts_with(&self, cells: &[Self::Cell]) -> bool {
+        if cells.len() > self.len() { return false; }
+
+        chunks_equal(self.read_chunks(0..cells.len()), cells)
+    }
+
+    ///
+    /// True if the rope ends with the specified cells
+    ///
+    fn ends_with(&self, cells: &[Self::Cell]) -> bool {
+        let len = self.len();
+        if cells.len() > len { return false; }
+
+        chunks_equal(self.read_chunks((len - cells.len())..len), cells)
+    }
+
+    ///
+    /// True if the specified cells appear anywhere in the rope
+    ///
+    fn contains(&self, cells: &[Self::Cell]) -> bool {
+        self.find(cells, 0).is_some()
+    }
+
+    ///
+    /// Finds the position of the first occurrence of the specified cells at or after the `from` position
+    ///
+    /// The rope is searched a chunk at a time, and matches that cross the boundaries between chunks are found. This
+    /// takes time proportional to the length of the rope plus the length of the needle.
+    ///
+    fn find(&self, needle: &[Self::Cell], from: usize) -> Option<usize> {
+        let len = self.len();
+        if from > len                       { return None; }
+        if needle.is_empty()                { return Some(from); }
+        if needle.len() > len - from        { return None; }
+
+        let mut matcher = NeedleMatcher::new(needle);
+        let mut pos     = from;
+
+        for chunk in self.read_chunks(from..len) {
+            for cell in chunk.iter() {
+                pos += 1;
+
+                if matcher.next_cell(cell) {
+                    return Some(pos - needle.len());
+                }
+            }
+        }
+
+        None
+    }
+
+    ///
+    /// Returns an iterator over the ranges of the non-overlapping occurrences of the needle at or after the `from` position
+    ///
+    /// The rope is read as the iterator is advanced, so a large rope can be searched a few matches at a time: use
+    /// `RopeMatches::position()` to find where to resume the search from later on. An empty needle does not match
+    /// anything.
+    ///
+    fn find_iter<'a>(&'a self, needle: &'a [Self::Cell], from: usize) -> RopeMatches<'a, Self::Cell> {
+        let len     = self.len();
+        let from    = from.min(len);
+
+        RopeMatches {
+            chunks:     self.read_chunks(from..len),
+            chunk:      &[],
+            matcher:    NeedleMatcher::new(needle),
+            pos:        from
+        }
+    }
+}
+
+///
+/// Iterator over the ranges where a needle appears in a rope, returned by `RopeSearch::find_iter()`
+///
+pub struct RopeMatches<'a, Cell> {
+    /// The chunks that have not been searched yet
+    chunks: Box<dyn 'a+Iterator<Item=&'a [Cell]>>,
+
+    /// The remaining cells in the chunk that's being searched
+    chunk: &'a [Cell],
+
+    /// Matches the cells against the needle
+    matcher: NeedleMatcher<'a, Cell>,
+
+    /// The position in the rope of the first cell in `chunk`
+    pos: usize
+}
+
+impl<'a, Cell> RopeMatches<'a, Cell> {
+    ///
+    /// The position that the search has reached
+    ///
+    /// Passing this to `find_iter()` will resume the search from the end of the last match that was returned (a
+    /// partial match that was in progress is searched again).
+    ///
+    pub fn position(&self) -> usize {
+        self.pos - self.matcher.matched
+    }
+}
+
+impl<'a, Cell> Iterator for RopeMatches<'a, Cell>
+where
+Cell: PartialEq {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        if self.matcher.needle.is_empty() { return None; }
+
+        loop {
+            while let Some((cell, remaining)) = self.chunk.split_first() {
+                self.chunk  = remaining;
+                self.pos    += 1;
+
+                if self.matcher.next_cell(cell) {
+                    // Matches don't overlap, so start again from the end of this match
+                    self.matcher.matched = 0;
+
+                    return Some((self.pos - self.matcher.needle.len())..self.pos);
+                }
+            }
+
+            self.chunk = self.chunks.next()?;
+        }
+    }
+}
+
+///
+/// Matches a needle against a sequence of cells using the Knuth-Morris-Pratt algorithm, which never needs to look
+/// back at cells that have already been read (so the cells can be read a chunk at a time)
+///
+pub (crate) struct NeedleMatcher<'a, Cell> {
+    /// The cells that are being searched for
+    needle: &'a [Cell],
+
+    /// For each prefix of the needle, the length of the longest proper prefix of the needle that is also a suffix of it
+    fallback: Vec<usize>,
+
+    /// The number of cells in the needle that match the most recent cells
+    matched: usize
+}
+
+impl<'a, Cell> NeedleMatcher<'a, Cell>
+where
+Cell: PartialEq {
+    ///
+    /// Creates a matcher for the specified needle
+    ///
+    pub (crate) fn new(needle: &'a [Cell]) -> NeedleMatcher<'a, Cell> {
+        let mut fallback    = vec![0; needle.len()];
+        let mut prefix_len  = 0;
+
+        for idx in 1..needle.len() {
+            while prefix_len > 0 && needle[idx] != needle[prefix_len] {
+                prefix_len = fallback[prefix_len-1];
+            }
+
+            if needle[idx] == needle[prefix_len] {
+                prefix_len += 1;
+            }
+
+            fallback[idx] = prefix_len;
+        }
+
+        NeedleMatcher {
+            needle:     needle,
+            fallback:   fallback,
+            matched:    0
+        }
+    }
+
+    ///
+    /// Matches the next cell, returning true if a full match of the needle ends at this cell
+    ///
+    pub (crate) fn next_cell(&mut self, cell: &Cell) -> bool {
+        if self.needle.is_empty() { return false; }
+
+        while self.matched > 0 && self.needle[self.matched] != *cell {
+            self.matched = self.fallback[self.matched-1];
+        }
+
+        if self.needle[self.matched] == *cell {
+            self.matched += 1;
+        }
+
+        if self.matched == self.needle.len() {
+            // Carry on from the longest prefix that can overlap with this match
+            self.matched = self.fallback[self.matched-1];
+            true
+        } else {
+            false
+        }
+    }
+}
+
+///
+/// True if the cells in the chunks are the same as the cells in the slice (the chunks must have the same total length as the slice)
+///
+fn chunks_equal<'a, Cell, Chunks>(chunks: Chunks, cells: &[Cell]) -> bool
+where
+Cell:   'a+PartialEq,
+Chunks: Iterator<Item=&'a [Cell]> {
+    let mut remaining = cells;
+
+    for chunk in chunks {
+        if chunk.len() > remaining.len() || chunk != &remaining[0..chunk.len()] {
+            return false;
+        }
+
+        remaining = &remaining[chunk.len()..];
+    }
+
+    remaining.is_empty()
+}
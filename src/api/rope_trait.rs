@@ -1,3 +1,5 @@
+use std::iter;
+use std::slice;
 use std::ops::{Range};
 
 ///
@@ -18,10 +20,50 @@ pub trait Rope {
     ///
     /// Reads the cell values for a range in this rope
     ///
-    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&Self::Cell>>;
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>>;
 
     ///
     /// Returns the attributes set at the specified location and their extent
     ///
     fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>);
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    /// Ropes that store their cells in contiguous blocks return each block as a single slice, which makes it possible to
+    /// process the cells in bulk. By default, each cell is returned as a separate slice.
+    ///
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        Box::new(self.read_cells(range).map(slice::from_ref))
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope, in order. The ranges returned are
+    /// limited to the requested range.
+    ///
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        let end     = range.end.min(self.len());
+        let mut pos = range.start;
+
+        Box::new(iter::from_fn(move || {
+            if pos >= end {
+                // Reached the end of the range
+                None
+            } else {
+                // Read the attributes at the current position and move to the end of the run
+                let (attribute, extent) = self.read_attributes(pos);
+
+                if extent.end <= pos {
+                    // Zero-length run (can't make progress)
+                    None
+                } else {
+                    let run_start   = pos;
+                    let run_end     = extent.end.min(end);
+                    pos             = run_end;
+
+                    Some((attribute, run_start..run_end))
+                }
+            }
+        }))
+    }
 }
@@ -0,0 +1,112 @@
+use super::rope_action::*;
+
+///
+/// Describes how actions should be divided up before they're sent to a consumer
+///
+#[derive(Debug)]
+pub enum ActionGranularity<Cell> {
+    /// Actions are sent as they are
+    Whole,
+
+    /// Actions are divided so that none of them write more than the specified number of cells
+    MaxCells(usize),
+
+    /// Actions are divided after each cell that matches the function (eg, `|c| *c == b'\n'` to divide into lines)
+    SplitAfter(fn(&Cell) -> bool)
+}
+
+// Implemented by hand as the derived versions would require `Cell: Copy`
+impl<Cell> Clone for ActionGranularity<Cell> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<Cell> Copy for ActionGranularity<Cell> { }
+
+///
+/// Divides an action that writes a lot of cells into a series of smaller actions that have the same effect when
+/// applied in order.
+///
+/// The first of the returned actions replaces the original range with the first group of cells, and the rest insert
+/// the remaining groups after it. `SetAttributes` actions don't write any cells so are always returned unchanged.
+///
+pub fn split_action<Cell, Attribute>(action: RopeAction<Cell, Attribute>, granularity: ActionGranularity<Cell>) -> Vec<RopeAction<Cell, Attribute>>
+where
+Attribute: Clone {
+    match action {
+        RopeAction::Replace(range, cells) => {
+            let mut pos = range.start;
+
+            split_cells(cells, granularity).into_iter()
+                .enumerate()
+                .map(|(idx, cells)| {
+                    let action_range    = if idx == 0 { range.clone() } else { pos..pos };
+                    pos                 += cells.len();
+
+                    RopeAction::Replace(action_range, cells)
+                })
+                .collect()
+        }
+
+        RopeAction::ReplaceAttributes(range, cells, attribute) => {
+            let mut pos = range.start;
+
+            split_cells(cells, granularity).into_iter()
+                .enumerate()
+                .map(|(idx, cells)| {
+                    let action_range    = if idx == 0 { range.clone() } else { pos..pos };
+                    pos                 += cells.len();
+
+                    RopeAction::ReplaceAttributes(action_range, cells, attribute.clone())
+                })
+                .collect()
+        }
+
+        set_attributes => vec![set_attributes]
+    }
+}
+
+///
+/// Divides a list of cells into groups according to a granularity (there's always at least one group)
+///
+fn split_cells<Cell>(cells: Vec<Cell>, granularity: ActionGranularity<Cell>) -> Vec<Vec<Cell>> {
+    match granularity {
+        ActionGranularity::Whole => vec![cells],
+
+        ActionGranularity::MaxCells(max_cells) => {
+            let max_cells   = max_cells.max(1);
+            let mut groups  = vec![];
+            let mut cells   = cells;
+
+            while cells.len() > max_cells {
+                let remaining = cells.split_off(max_cells);
+                groups.push(cells);
+                cells = remaining;
+            }
+
+            groups.push(cells);
+            groups
+        }
+
+        ActionGranularity::SplitAfter(is_split_point) => {
+            let mut groups  = vec![];
+            let mut current = vec![];
+
+            for cell in cells {
+                let is_split = is_split_point(&cell);
+                current.push(cell);
+
+                if is_split {
+                    groups.push(current);
+                    current = vec![];
+                }
+            }
+
+            // The last group is only empty if the cells ended with a split point
+            if current.len() > 0 || groups.len() == 0 {
+                groups.push(current);
+            }
+
+            groups
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use crate::*;
+
+fn apply_actions(initial: Vec<u8>, actions: &[RopeAction<u8, i64>]) -> AttributedRope<u8, i64> {
+    let mut rope = AttributedRope::from(initial);
+
+    for action in actions.iter() {
+        rope.edit(action.clone());
+    }
+
+    rope
+}
+
+#[test]
+fn compact_typing() {
+    let actions     = vec![
+        RopeAction::Replace(2..2, vec![10]),
+        RopeAction::Replace(3..3, vec![11]),
+        RopeAction::Replace(4..4, vec![12]),
+        RopeAction::Replace(5..5, vec![13]),
+    ];
+    let compacted   = compact_actions(actions.clone());
+
+    assert!(compacted == vec![RopeAction::Replace(2..2, vec![10, 11, 12, 13])]);
+    assert!(apply_actions(vec![1, 2, 3, 4], &actions) == apply_actions(vec![1, 2, 3, 4], &compacted));
+}
+
+#[test]
+fn compact_backspace() {
+    let actions     = vec![
+        RopeAction::Replace(5..6, vec![]),
+        RopeAction::Replace(4..5, vec![]),
+        RopeAction::Replace(3..4, vec![]),
+    ];
+    let compacted   = compact_actions(actions.clone());
+
+    assert!(compacted == vec![RopeAction::Replace(3..6, vec![])]);
+    assert!(apply_actions(vec![1, 2, 3, 4, 5, 6, 7, 8], &actions) == apply_actions(vec![1, 2, 3, 4, 5, 6, 7, 8], &compacted));
+}
+
+#[test]
+fn compact_overwrite_beyond_insertion() {
+    let actions     = vec![
+        RopeAction::Replace(1..2, vec![10, 11]),
+        RopeAction::Replace(2..5, vec![12]),
+    ];
+    let compacted   = compact_actions(actions.clone());
+
+    assert!(compacted == vec![RopeAction::Replace(1..4, vec![10, 12])]);
+    assert!(apply_actions(vec![1, 2, 3, 4, 5, 6], &actions) == apply_actions(vec![1, 2, 3, 4, 5, 6], &compacted));
+}
+
+#[test]
+fn compact_overlapping_attributes() {
+    let actions     = vec![
+        RopeAction::SetAttributes(1..3, 1),
+        RopeAction::SetAttributes(2..5, 1),
+        RopeAction::SetAttributes(0..2, 2),
+    ];
+    let compacted   = compact_actions(actions.clone());
+
+    assert!(compacted == vec![RopeAction::SetAttributes(1..5, 1), RopeAction::SetAttributes(0..2, 2)]);
+    assert!(apply_actions(vec![1, 2, 3, 4, 5, 6], &actions) == apply_actions(vec![1, 2, 3, 4, 5, 6], &compacted));
+}
+
+#[test]
+fn compact_replace_then_style() {
+    let actions     = vec![
+        RopeAction::Replace(1..2, vec![10, 11]),
+        RopeAction::SetAttributes(1..3, 2),
+        RopeAction::Replace(3..3, vec![12]),
+    ];
+    let compacted   = compact_actions(actions.clone());
+
+    assert!(compacted == vec![RopeAction::ReplaceAttributes(1..2, vec![10, 11, 12], 2)]);
+    assert!(apply_actions(vec![1, 2, 3, 4, 5, 6], &actions) == apply_actions(vec![1, 2, 3, 4, 5, 6], &compacted));
+}
+
+#[test]
+fn leave_unrelated_actions() {
+    let actions     = vec![
+        RopeAction::Replace(1..2, vec![10]),
+        RopeAction::Replace(5..6, vec![11]),
+        RopeAction::SetAttributes(0..1, 2),
+    ];
+    let compacted   = compact_actions(actions.clone());
+
+    assert!(compacted == actions);
+}
+
+#[test]
+fn transform_independent_edits() {
+    // Edits to separate parts of the rope should produce the same result whichever order they're applied in
+    let local       = RopeAction::Replace(1..2, vec![10, 11]);
+    let formatter   = RopeAction::ReplaceAttributes(5..7, vec![12], 3);
+
+    let local_first     = apply_actions(vec![1, 2, 3, 4, 5, 6, 7, 8], &[local.clone(), transform(&formatter, &local)]);
+    let formatter_first = apply_actions(vec![1, 2, 3, 4, 5, 6, 7, 8], &[formatter.clone(), transform(&local, &formatter)]);
+
+    assert!(transform(&formatter, &local) == RopeAction::ReplaceAttributes(6..8, vec![12], 3));
+    assert!(local_first.read_cells(0..10).cloned().collect::<Vec<_>>() == vec![1, 10, 11, 3, 4, 5, 12, 8]);
+    assert!(local_first.read_cells(0..10).eq(formatter_first.read_cells(0..10)));
+    assert!(local_first.read_attribute_runs(0..10).eq(formatter_first.read_attribute_runs(0..10)));
+}
+
+#[test]
+fn transform_overlapping_deletes() {
+    // Cells that have already been deleted aren't deleted again
+    assert!(transform::<u8, i64>(&RopeAction::Replace(2..6, vec![]), &RopeAction::Replace(4..8, vec![])) == RopeAction::Replace(2..4, vec![]));
+    assert!(transform::<u8, i64>(&RopeAction::Replace(4..8, vec![]), &RopeAction::Replace(2..6, vec![])) == RopeAction::Replace(2..4, vec![]));
+
+    // Cells inserted by another edit are only removed if the edit covers the whole range
+    assert!(transform::<u8, i64>(&RopeAction::Replace(2..6, vec![]), &RopeAction::Replace(3..4, vec![1, 2, 3])) == RopeAction::Replace(2..8, vec![]));
+    assert!(transform::<u8, i64>(&RopeAction::Replace(3..6, vec![]), &RopeAction::Replace(2..4, vec![1, 2, 3])) == RopeAction::Replace(5..7, vec![]));
+}
+
+#[test]
+fn transform_insertions() {
+    // Concurrent insertions at the same point put the rebased action after the applied one
+    assert!(transform::<u8, i64>(&RopeAction::Replace(3..3, vec![1]), &RopeAction::Replace(3..3, vec![2, 2])) == RopeAction::Replace(5..5, vec![1]));
+
+    // Insertions at the start of a replaced range go before it
+    assert!(transform::<u8, i64>(&RopeAction::Replace(3..3, vec![1]), &RopeAction::Replace(3..5, vec![2])) == RopeAction::Replace(3..3, vec![1]));
+
+    // Insertions inside a replaced range go after it
+    assert!(transform::<u8, i64>(&RopeAction::Replace(4..4, vec![1]), &RopeAction::Replace(3..5, vec![2])) == RopeAction::Replace(4..4, vec![1]));
+
+    // Setting attributes doesn't move anything
+    assert!(transform::<u8, i64>(&RopeAction::Replace(4..6, vec![1]), &RopeAction::SetAttributes(0..8, 2)) == RopeAction::Replace(4..6, vec![1]));
+}
+
+#[test]
+fn transform_pair_converges() {
+    let converges = |local: RopeAction<u8, i64>, remote: RopeAction<u8, i64>, expected: Vec<u8>| {
+        let (local_after_remote, remote_after_local) = transform_pair(&local, &remote);
+
+        let local_first     = apply_actions(vec![0, 1, 2, 3, 4, 5], &[local.clone(), remote_after_local]);
+        let remote_first    = apply_actions(vec![0, 1, 2, 3, 4, 5], &[remote.clone(), local_after_remote]);
+
+        assert!(local_first.read_cells(0..local_first.len()).cloned().collect::<Vec<_>>() == expected);
+        assert!(remote_first.read_cells(0..remote_first.len()).cloned().collect::<Vec<_>>() == expected);
+    };
+
+    // Insertions at the same position put the local cells first in both orders
+    converges(RopeAction::Replace(3..3, vec![10]), RopeAction::Replace(3..3, vec![20]), vec![0, 1, 2, 10, 20, 3, 4, 5]);
+    converges(RopeAction::Replace(3..3, vec![20]), RopeAction::Replace(3..3, vec![10]), vec![0, 1, 2, 20, 10, 3, 4, 5]);
+
+    // Overlapping deletions remove the union of the two ranges
+    converges(RopeAction::Replace(1..4, vec![]), RopeAction::Replace(2..5, vec![]), vec![0, 5]);
+    converges(RopeAction::Replace(2..5, vec![]), RopeAction::Replace(1..4, vec![]), vec![0, 5]);
+
+    // An insertion at the start of a deleted range is kept, but an insertion inside it is removed
+    converges(RopeAction::Replace(2..2, vec![10]), RopeAction::Replace(2..4, vec![]), vec![0, 1, 10, 4, 5]);
+    converges(RopeAction::Replace(2..4, vec![]), RopeAction::Replace(2..2, vec![10]), vec![0, 1, 10, 4, 5]);
+    converges(RopeAction::Replace(3..3, vec![10]), RopeAction::Replace(2..5, vec![]), vec![0, 1, 5]);
+
+    // When both actions replace the same range, the local action wins
+    converges(RopeAction::Replace(2..4, vec![10]), RopeAction::Replace(2..4, vec![20]), vec![0, 1, 10, 4, 5]);
+}
+
+#[test]
+fn transform_pair_converges_for_random_edits() {
+    let mut random      = StressRandom::new(1);
+    let mut random_edit = || {
+        let start   = random.next_range(0..7);
+        let end     = (start + random.next_range(0..4)).min(6);
+        let cells   = (0..random.next_range(0..3)).map(|_| 10 + random.next_range(0..20) as u8).collect();
+
+        RopeAction::<u8, i64>::Replace(start..end, cells)
+    };
+
+    for _ in 0..10000 {
+        let local   = random_edit();
+        let remote  = random_edit();
+
+        let (local_after_remote, remote_after_local) = transform_pair(&local, &remote);
+
+        let local_first     = apply_actions(vec![0, 1, 2, 3, 4, 5], &[local.clone(), remote_after_local]);
+        let remote_first    = apply_actions(vec![0, 1, 2, 3, 4, 5], &[remote.clone(), local_after_remote]);
+
+        assert!(local_first.read_cells(0..local_first.len()).eq(remote_first.read_cells(0..remote_first.len())), "{:?} {:?}", local, remote);
+    }
+}
+
+#[test]
+fn split_action_into_lines() {
+    let action  = RopeAction::<u8, i64>::ReplaceAttributes(2..4, b"one\ntwo\nthree".to_vec(), 3);
+    let split   = split_action(action.clone(), ActionGranularity::SplitAfter(|c| *c == b'\n'));
+
+    assert!(split == vec![
+        RopeAction::ReplaceAttributes(2..4, b"one\n".to_vec(), 3),
+        RopeAction::ReplaceAttributes(6..6, b"two\n".to_vec(), 3),
+        RopeAction::ReplaceAttributes(10..10, b"three".to_vec(), 3),
+    ]);
+
+    let expected = apply_actions(b"abcdef".to_vec(), &[action]);
+    let actual   = apply_actions(b"abcdef".to_vec(), &split);
+    assert!(expected.read_cells(0..expected.len()).eq(actual.read_cells(0..actual.len())));
+    assert!(expected.read_attribute_runs(0..expected.len()).eq(actual.read_attribute_runs(0..actual.len())));
+}
+
+#[test]
+fn split_action_max_cells() {
+    let split = split_action(RopeAction::<u8, i64>::Replace(0..1, vec![1, 2, 3, 4, 5]), ActionGranularity::MaxCells(2));
+    assert!(split == vec![RopeAction::Replace(0..1, vec![1, 2]), RopeAction::Replace(2..2, vec![3, 4]), RopeAction::Replace(4..4, vec![5])]);
+
+    // Deletions and attribute changes are left alone
+    assert!(split_action(RopeAction::<u8, i64>::Replace(0..10, vec![]), ActionGranularity::MaxCells(2)) == vec![RopeAction::Replace(0..10, vec![])]);
+    assert!(split_action(RopeAction::<u8, i64>::SetAttributes(0..10, 1), ActionGranularity::MaxCells(2)) == vec![RopeAction::SetAttributes(0..10, 1)]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_actions() {
+    let actions = vec![
+        RopeAction::<u8, i64>::Replace(1..3, vec![1, 2]),
+        RopeAction::SetAttributes(0..4, 7),
+        RopeAction::ReplaceAttributes(2..2, vec![3], 8),
+    ];
+
+    let json            = serde_json::to_string(&actions).unwrap();
+    let deserialized    = serde_json::from_str::<Vec<RopeAction<u8, i64>>>(&json).unwrap();
+    assert!(deserialized == actions);
+
+    // Borrowed actions serialize in the same format
+    let cells   = vec![1, 2];
+    let as_ref  = RopeActionRef::<u8, i64>::Replace(1..3, &cells);
+    assert!(serde_json::to_string(&as_ref).unwrap() == serde_json::to_string(&actions[0]).unwrap());
+}
+
+#[test]
+fn search_across_chunks() {
+    let mut rope = AttributedRope::<u8, i64>::from("The quick brown fox jumps over the lazy dog".bytes());
+    rope.set_attributes(6..12, 1);
+    rope.set_attributes(30..40, 2);
+
+    assert!(rope.read_chunks(0..rope.len()).count() > 1);
+
+    assert!(rope.starts_with(b"The quick"));
+    assert!(!rope.starts_with(b"quick"));
+    assert!(rope.ends_with(b"lazy dog"));
+    assert!(!rope.ends_with(b"lazy"));
+    assert!(rope.contains(b"quick brown"));
+    assert!(rope.contains(b"the lazy"));
+    assert!(!rope.contains(b"brown dog"));
+    assert!(!rope.starts_with(&[0; 100]));
+}
+
+#[test]
+fn find_across_chunks() {
+    let mut rope = AttributedRope::<u8, i64>::from("abaabab abaabaab".bytes());
+    rope.set_attributes(2..4, 1);
+    rope.set_attributes(10..12, 2);
+
+    assert!(rope.find(b"aab", 0) == Some(2));
+    assert!(rope.find(b"aab", 3) == Some(10));
+    assert!(rope.find(b"abaab", 1) == Some(8));
+    assert!(rope.find(b"abaab", 9) == Some(11));
+    assert!(rope.find(b"abb", 0).is_none());
+    assert!(rope.find(b"", 5) == Some(5));
+    assert!(rope.find(b"a", 100).is_none());
+}
+
+#[test]
+fn find_iter_across_chunks() {
+    let mut rope = AttributedRope::<u8, i64>::from("aaa baa aab aaaa".bytes());
+    rope.set_attributes(1..2, 1);
+    rope.set_attributes(9..13, 2);
+
+    assert!(rope.find_iter(b"aa", 0).collect::<Vec<_>>() == vec![0..2, 5..7, 8..10, 12..14, 14..16]);
+    assert!(rope.find_iter(b"aa", 6).collect::<Vec<_>>() == vec![8..10, 12..14, 14..16]);
+    assert!(rope.find_iter(b"", 0).next().is_none());
+    assert!(rope.find_iter(b"aa", 100).next().is_none());
+
+    // Searches can be resumed later on
+    let mut matches = rope.find_iter(b"aa", 0);
+    assert!(matches.next() == Some(0..2));
+    assert!(matches.next() == Some(5..7));
+
+    let resume_pos = matches.position();
+    assert!(resume_pos == 7);
+    assert!(rope.find_iter(b"aa", resume_pos).collect::<Vec<_>>() == vec![8..10, 12..14, 14..16]);
+}
+
+#[test]
+fn split_action_at_position() {
+    let action      = RopeAction::<u8, i64>::ReplaceAttributes(2..8, b"xyz".to_vec(), 3);
+    let insertion   = RopeAction::<u8, i64>::Replace(5..5, b"abc".to_vec());
+
+    assert!(action.range() == (2..8));
+    assert!(action.intersects(7..10));
+    assert!(!action.intersects(8..10));
+    assert!(insertion.intersects(0..5));
+    assert!(insertion.intersects(5..9));
+    assert!(!insertion.intersects(6..9));
+
+    assert!(insertion.clone().split_at(5) == (None, Some(insertion.clone())));
+    assert!(action.clone().split_at(8) == (Some(action.clone()), None));
+
+    // Applying the second half and then the first half should have the same effect as the original action
+    let (before, after) = action.clone().split_at(5);
+    assert!(before == Some(RopeAction::ReplaceAttributes(2..5, b"xyz".to_vec(), 3)));
+    assert!(after == Some(RopeAction::ReplaceAttributes(5..8, vec![], 3)));
+
+    let expected    = apply_actions(b"0123456789".to_vec(), &[action]);
+    let split       = apply_actions(b"0123456789".to_vec(), &[after.unwrap(), before.unwrap()]);
+    assert!(split.read_cells(0..split.len()).eq(expected.read_cells(0..expected.len())));
+    assert!(split.read_attribute_runs(0..split.len()).eq(expected.read_attribute_runs(0..expected.len())));
+}
+
+#[test]
+fn move_action_to_new_range() {
+    assert!(RopeAction::<u8, i64>::Replace(2..4, b"ab".to_vec()).with_range(12..14) == RopeAction::Replace(12..14, b"ab".to_vec()));
+    assert!(RopeAction::<u8, i64>::SetAttributes(2..4, 1).with_range(0..2) == RopeAction::SetAttributes(0..2, 1));
+    assert!(RopeAction::<u8, i64>::ReplaceAttributes(2..4, b"c".to_vec(), 2).with_range(3..3) == RopeAction::ReplaceAttributes(3..3, b"c".to_vec(), 2));
+}
+
+#[test]
+fn map_positions_through_actions() {
+    let actions: Vec<RopeAction<u8, i64>> = vec![
+        RopeAction::Replace(0..0, "Hello".bytes().collect()),
+        RopeAction::SetAttributes(0..5, 1),
+        RopeAction::Replace(2..4, "y".bytes().collect())
+    ];
+
+    // Positions after the edits move, positions before them don't
+    assert!(map_index(0, AnchorBias::Left, &actions) == 0);
+    assert!(map_index(0, AnchorBias::Right, &actions) == 4);
+    assert!(map_index(3, AnchorBias::Right, &actions) == 7);
+
+    // Ranges keep their biases at each end
+    assert!(map_range(0..0, AnchorBias::Right, AnchorBias::Right, &actions) == (4..4));
+    assert!(map_range(0..0, AnchorBias::Left, AnchorBias::Right, &actions) == (0..4));
+    assert!(map_range(3..6, AnchorBias::Right, AnchorBias::Left, &[RopeAction::<u8, i64>::Replace(2..7, vec![])]) == (2..2));
+}
+
+#[test]
+fn compact_delete_then_replace_at_start() {
+    let mut initial = AttributedRope::<u8, i64>::from("abcdefgh".bytes().collect::<Vec<_>>());
+    initial.set_attributes(4..8, 2);
+
+    let actions     = vec![
+        RopeAction::Replace(0..4, vec![]),
+        RopeAction::Replace(0..1, vec![b'x']),
+    ];
+    let compacted   = compact_actions(actions.clone());
+
+    let mut sequential  = initial.clone();
+    let mut compact     = initial.clone();
+    actions.into_iter().for_each(|action| sequential.edit(action));
+    compacted.into_iter().for_each(|action| compact.edit(action));
+
+    assert!(sequential.read_attribute_runs(0..4).collect::<Vec<_>>() == vec![(&2, 0..4)]);
+    assert!(compact.read_attribute_runs(0..4).collect::<Vec<_>>() == vec![(&2, 0..4)]);
+}
+
+#[test]
+fn compact_random_actions_on_attributed_ropes() {
+    for seed in 0..3000 {
+        // Generate a short rope with a couple of attribute runs to start with
+        let mut stress  = RopeStressTest::new(seed).with_max_length(32);
+        let mut initial = AttributedRope::<u8, u8>::new();
+        initial.edit(RopeAction::ReplaceAttributes(0..0, "abcdefghijkl".bytes().collect(), 1));
+        initial.edit(RopeAction::SetAttributes(4..8, 2));
+
+        // Apply some random actions in sequence
+        let mut sequential  = initial.clone();
+        let mut actions     = vec![];
+        for _ in 0..8 {
+            let action = stress.random_action(sequential.len());
+
+            sequential.edit(action.clone());
+            actions.push(action);
+        }
+
+        // Applying the compacted actions should have the same result
+        let mut compact = initial.clone();
+        compact_actions(actions.clone()).into_iter().for_each(|action| compact.edit(action));
+
+        let len = sequential.len();
+        assert!(compact.len() == len, "Seed {}: {:?}", seed, actions);
+        assert!(compact.read_cells(0..len).eq(sequential.read_cells(0..len)), "Seed {}: {:?}", seed, actions);
+        assert!(compact.read_attribute_runs(0..len).eq(sequential.read_attribute_runs(0..len)), "Seed {}: {:?}", seed, actions);
+    }
+}
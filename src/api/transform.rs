@@ -0,0 +1,131 @@
+use super::rope_action::*;
+
+use std::ops::{Range};
+
+///
+/// Maps the start of a range through an edit that replaced `edit_range` with `new_len` cells
+///
+/// `insert_before` is set if an insertion at this position should go before any cells inserted at the same position.
+///
+fn map_start(pos: usize, edit_range: &Range<usize>, new_len: usize, insert_before: bool) -> usize {
+    if pos < edit_range.start || (pos == edit_range.start && (edit_range.len() > 0 || insert_before)) {
+        // Before the edit (or at an insertion point where this range goes first)
+        pos
+    } else if pos >= edit_range.end {
+        // After the edit (or at an insertion point: the range will start after the inserted cells)
+        pos - edit_range.len() + new_len
+    } else {
+        // Inside the replaced cells: the range starts after the replacement
+        edit_range.start + new_len
+    }
+}
+
+///
+/// Maps the end of a range through an edit that replaced `edit_range` with `new_len` cells
+///
+fn map_end(pos: usize, edit_range: &Range<usize>, new_len: usize) -> usize {
+    if pos <= edit_range.start {
+        // Before the edit
+        pos
+    } else if pos >= edit_range.end {
+        // After the edit
+        pos - edit_range.len() + new_len
+    } else {
+        // Inside the replaced cells: the range ends before the replacement
+        edit_range.start
+    }
+}
+
+///
+/// Rebases an action so that it can be applied after another action that was made concurrently
+///
+/// `action` and `applied` are both edits to the same version of a rope. `applied` has already been applied to the
+/// rope, and the result of this function is a version of `action` that can be applied afterwards, with its range
+/// adjusted to account for the cells that `applied` inserted or removed:
+///
+///  * Cells that `applied` has already removed are not removed again
+///  * Cells that `applied` inserted are only replaced if `action` covers the entire range that `applied` changed
+///  * If both actions insert cells at the same position, the cells from `action` are placed after the cells from `applied`
+///
+/// `SetAttributes` does not move any cells, so rebasing over it leaves the action unchanged.
+///
+/// Because of the last rule, two sources that each rebase the other's insertion over their own will not agree on
+/// the order of the inserted cells. Use `transform_pair()` to merge edits from two sources.
+///
+pub fn transform<Cell, Attribute>(action: &RopeAction<Cell, Attribute>, applied: &RopeAction<Cell, Attribute>) -> RopeAction<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  Clone {
+    transform_action(action, applied, false, false)
+}
+
+///
+/// Rebases two concurrent actions over each other, returning `(local, remote)` where `local` can be applied after
+/// the original `remote` action and `remote` can be applied after the original `local` action
+///
+/// Applying either original action followed by the other rebased action produces the same cells. The cells removed
+/// by both actions are removed, and:
+///
+///  * If one action changes a range that's entirely inside the range changed by the other action, the other action
+///    wins and the rebased version of the first action does nothing (if the ranges are the same, the local action wins)
+///  * If both actions insert cells at the same position, the cells from the local action are placed first
+///
+/// Both sources must agree on which of them is the 'local' one for their edits to converge.
+///
+pub fn transform_pair<Cell, Attribute>(local: &RopeAction<Cell, Attribute>, remote: &RopeAction<Cell, Attribute>) -> (RopeAction<Cell, Attribute>, RopeAction<Cell, Attribute>)
+where
+Cell:       Clone,
+Attribute:  Clone {
+    (transform_action(local, remote, true, true), transform_action(remote, local, true, false))
+}
+
+///
+/// Rebases an action over an action that has already been applied
+///
+/// If `drop_covered` is set, an action that's entirely inside the range changed by the applied action (or that changes
+/// the same range, when `insert_before` is not set) is replaced by an action that does nothing. If `insert_before` is
+/// set, cells inserted by the action go before any cells inserted at the same position by the applied action.
+///
+fn transform_action<Cell, Attribute>(action: &RopeAction<Cell, Attribute>, applied: &RopeAction<Cell, Attribute>, drop_covered: bool, insert_before: bool) -> RopeAction<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  Clone {
+    // Work out what the applied action did to the cells in the rope
+    let (edit_range, new_len) = match applied {
+        RopeAction::Replace(range, cells)               => (range, cells.len()),
+        RopeAction::ReplaceAttributes(range, cells, _)  => (range, cells.len()),
+        RopeAction::SetAttributes(_, _)                 => { return action.clone(); }
+    };
+
+    // An action inside the applied range would be removed if the applied action was rebased over it, so it is removed here too
+    if drop_covered {
+        let range           = action.range();
+        let inside          = edit_range.start <= range.start && range.end <= edit_range.end;
+        let at_edge         = range.is_empty() && (range.start == edit_range.start || range.end == edit_range.end);
+        let wins_tie        = insert_before && range == *edit_range;
+
+        if inside && !at_edge && !wins_tie {
+            let pos = edit_range.start + new_len;
+
+            return match action {
+                RopeAction::Replace(_, _)                       => RopeAction::Replace(pos..pos, vec![]),
+                RopeAction::SetAttributes(_, attribute)         => RopeAction::SetAttributes(pos..pos, attribute.clone()),
+                RopeAction::ReplaceAttributes(_, _, attribute)  => RopeAction::ReplaceAttributes(pos..pos, vec![], attribute.clone())
+            };
+        }
+    }
+
+    // Map the range of the action through the edit
+    let transform_range = |range: &Range<usize>| {
+        let start   = map_start(range.start, edit_range, new_len, insert_before && range.is_empty());
+        let end     = map_end(range.end, edit_range, new_len);
+
+        start..end.max(start)
+    };
+
+    match action {
+        RopeAction::Replace(range, cells)                       => RopeAction::Replace(transform_range(range), cells.clone()),
+        RopeAction::SetAttributes(range, attribute)             => RopeAction::SetAttributes(transform_range(range), attribute.clone()),
+        RopeAction::ReplaceAttributes(range, cells, attribute)  => RopeAction::ReplaceAttributes(transform_range(range), cells.clone(), attribute.clone())
+    }
+}
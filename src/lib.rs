@@ -37,7 +37,18 @@
 //! assert!(rope.read_cells(0..rope.len()).cloned().collect::<Vec<_>>() == vec![1,42,4,5,6,7,8]);
 //! ```
 
+// The code in this crate favours explicit forms (eg `field: field` initialisers and `len() == 0` tests)
+#![allow(clippy::redundant_field_names, clippy::unused_unit, clippy::len_zero, clippy::needless_lifetimes)]
+#![allow(clippy::len_without_is_empty, clippy::new_without_default, clippy::needless_return, clippy::assign_op_pattern)]
+#![allow(clippy::while_let_loop, clippy::option_map_unit_fn, clippy::match_like_matches_macro, clippy::manual_inspect)]
+#![allow(clippy::cmp_owned)]
+
+#[macro_use] mod trace;
+mod sync;
+mod thread_safety;
+
 pub mod api;
+pub mod adapter;
 pub mod rope;
 pub mod stream_rope;
 
@@ -0,0 +1,110 @@
+use crate::api::*;
+
+use std::ops::{Range};
+
+///
+/// Identifies an attribute stored in an `AttributeRegistry`
+///
+/// Ropes can use this as their attribute type in place of the attribute itself, so that many ropes can share
+/// one copy of each attribute. The default ID always refers to the default attribute.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Default)]
+pub struct AttributeId(pub u32);
+
+impl AttributeId {
+    pub fn idx(self) -> usize {
+        self.0 as usize
+    }
+}
+
+///
+/// An attribute registry stores a set of attributes that can be referenced by ID from any number of ropes
+///
+/// This is useful when an application manages many documents that use the same set of styles: each distinct
+/// attribute is stored once in the registry, and the ropes themselves only need to store a small `AttributeId`
+/// for each run. Attributes are never removed from the registry, so IDs remain valid for its lifetime.
+///
+#[derive(Clone, Debug)]
+pub struct AttributeRegistry<Attribute> {
+    /// The attributes that have been registered (indexed by ID)
+    attributes: Vec<Attribute>
+}
+
+impl<Attribute> AttributeRegistry<Attribute>
+where
+Attribute: PartialEq+Clone+Default {
+    ///
+    /// Creates a new attribute registry, containing only the default attribute
+    ///
+    pub fn new() -> AttributeRegistry<Attribute> {
+        AttributeRegistry {
+            attributes: vec![Attribute::default()]
+        }
+    }
+
+    ///
+    /// Returns the ID for an attribute, registering it if it's not already in the registry
+    ///
+    pub fn register(&mut self, attribute: Attribute) -> AttributeId {
+        if let Some(existing) = self.find(&attribute) {
+            // Each attribute is only stored once
+            existing
+        } else {
+            // Add a new attribute
+            let id = AttributeId(self.attributes.len() as u32);
+            self.attributes.push(attribute);
+
+            id
+        }
+    }
+
+    ///
+    /// Finds the ID of an attribute if it has previously been registered
+    ///
+    pub fn find(&self, attribute: &Attribute) -> Option<AttributeId> {
+        self.attributes.iter()
+            .position(|existing| existing == attribute)
+            .map(|idx| AttributeId(idx as u32))
+    }
+
+    ///
+    /// Returns the attribute corresponding to an ID
+    ///
+    /// IDs that were not generated by this registry resolve to the default attribute.
+    ///
+    pub fn resolve(&self, id: AttributeId) -> &Attribute {
+        self.attributes.get(id.idx()).unwrap_or(&self.attributes[0])
+    }
+
+    ///
+    /// The number of attributes stored in this registry (including the default attribute)
+    ///
+    pub fn len(&self) -> usize {
+        self.attributes.len()
+    }
+
+    ///
+    /// Returns the attributes set at a position in a rope that uses IDs from this registry, along with their extent
+    ///
+    pub fn read_attributes<'a, TRope: Rope<Attribute=AttributeId>>(&'a self, rope: &TRope, pos: usize) -> (&'a Attribute, Range<usize>) {
+        let (id, range) = rope.read_attributes(pos);
+
+        (self.resolve(*id), range)
+    }
+
+    ///
+    /// Reads the attribute runs for a range of a rope that uses IDs from this registry
+    ///
+    pub fn read_attribute_runs<'a, TRope: Rope<Attribute=AttributeId>>(&'a self, rope: &'a TRope, range: Range<usize>) -> impl 'a+Iterator<Item=(&'a Attribute, Range<usize>)> {
+        rope.read_attribute_runs(range)
+            .map(move |(id, range)| (self.resolve(*id), range))
+    }
+}
+
+impl<Attribute> Default for AttributeRegistry<Attribute>
+where
+Attribute: PartialEq+Clone+Default {
+    fn default() -> AttributeRegistry<Attribute> {
+        Self::new()
+    }
+}
@@ -0,0 +1,61 @@
+use super::attributed_rope::*;
+
+use crate::api::*;
+
+use std::ops::{Range};
+
+///
+/// The attributes of a rope, stored as a table of distinct attributes and a list of runs that refer to them by index
+///
+/// This is returned by `AttributedRope::attribute_table()`. Renderers and serializers usually prefer this form, as
+/// each attribute only needs to be converted (into a style, a GPU buffer entry or a serialized value) once, no matter
+/// how many runs use it.
+///
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct AttributeTable<Attribute> {
+    /// The distinct attributes used in the rope, in the order they first appear
+    pub attributes: Vec<Attribute>,
+
+    /// The attribute runs in the rope, in order, along with the index into `attributes` of the attribute for each run
+    pub runs: Vec<(Range<usize>, usize)>
+}
+
+impl<Attribute> AttributeTable<Attribute> {
+    ///
+    /// Returns the attribute for the run at the specified index
+    ///
+    pub fn run_attribute(&self, run_idx: usize) -> Option<&Attribute> {
+        self.runs.get(run_idx)
+            .map(|(_, attribute_idx)| &self.attributes[*attribute_idx])
+    }
+}
+
+impl<Cell, Attribute> AttributedRope<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Returns the attributes of this rope as a table of distinct attributes and a list of runs that refer to them
+    ///
+    pub fn attribute_table(&self) -> AttributeTable<Attribute> {
+        let mut table = AttributeTable {
+            attributes: vec![],
+            runs:       vec![]
+        };
+
+        for (attribute, range) in self.read_attribute_runs(0..self.len()) {
+            // Attributes often alternate, so it's quicker to search from the most recently added attribute
+            let attribute_idx = match table.attributes.iter().rposition(|existing| existing == attribute) {
+                Some(existing_idx)  => existing_idx,
+                None                => {
+                    table.attributes.push(attribute.clone());
+                    table.attributes.len()-1
+                }
+            };
+
+            table.runs.push((range, attribute_idx));
+        }
+
+        table
+    }
+}
@@ -1,5 +1,6 @@
 use super::node::*;
 use super::branch::*;
+use super::rope_leaf::*;
 use super::attributed_rope_iterator::*;
 
 use crate::api::*;
@@ -27,16 +28,25 @@ const SPLIT_LENGTH: usize = 32;
 /// like a text editor around, although for interactive applications, the streaming rope classes might be
 /// more suitable as they can dynamically notify about their updates.
 ///
+/// Cloning an attributed rope is cheap: the cells are shared between the copies until one of them is edited,
+/// at which point only the parts of the rope that are changed are copied.
+///
 #[derive(Clone)]
 pub struct AttributedRope<Cell, Attribute> {
     /// The nodes that make up this rope
     pub (super) nodes: Vec<RopeNode<Cell, Attribute>>,
 
     /// The index of the root node
-    root_node_idx: RopeNodeIndex,
+    pub (super) root_node_idx: RopeNodeIndex,
 
     /// List of nodes that are not being used
-    free_nodes: Vec<usize>
+    pub (super) free_nodes: Vec<usize>,
+
+    /// The ID of the leaf stored at each node index, and the ID of the leaf it was split from if it's unchanged since the split
+    pub (super) leaf_ids: Vec<(LeafId, Option<LeafId>)>,
+
+    /// The ID to assign to the next leaf that's created or edited
+    pub (super) next_leaf_id: u64
 }
 
 impl<Cell, Attribute> AttributedRope<Cell, Attribute> 
@@ -48,64 +58,107 @@ Attribute:  PartialEq+Clone+Default {
     ///
     pub fn new() -> AttributedRope<Cell, Attribute> {
         AttributedRope {
-            nodes:          vec![RopeNode::Leaf(None, vec![], Arc::new(Attribute::default()))],
+            nodes:          vec![RopeNode::Leaf(None, Arc::new(vec![]), Arc::new(Attribute::default()))],
             root_node_idx:  RopeNodeIndex(0),
-            free_nodes:     vec![]
+            free_nodes:     vec![],
+            leaf_ids:       vec![(LeafId(0), None)],
+            next_leaf_id:   1
         }
     }
 
     ///
-    /// Verifies that the tree is valid (lengths are correct, all empty nodes in free list)
+    /// Verifies that the tree is valid (see `check_integrity()`)
     ///
     /// In non-test configurations, this is just a no-op, this just ensures that bugs are caught early
     /// and close to the code that is broken.
     ///
     #[cfg(test)]
-    fn verify_tree(&self, why: &'static str) { 
-        // Every empty node must be in the free nodes list
-        for node_idx in 0..self.nodes.len() {
-            if let RopeNode::Empty = &self.nodes[node_idx] {
-                assert!(self.free_nodes.contains(&node_idx), "Empty node not in free list: {}", why);
-            }
-        }
-
-        // Every free node must be empty
-        for free_idx in self.free_nodes.iter() {
-            assert!(if let RopeNode::Empty = self.nodes[*free_idx] { true } else { false }, "Free node not empty: {}", why);
+    pub (super) fn verify_tree(&self, why: &'static str) { 
+        if let Err(err) = self.check_integrity() {
+            panic!("Invalid tree ({}): {}", err, why);
         }
-
-        // All node lengths must be valid (sum of the child nodes for branchs)
-        for node_idx in 0..self.nodes.len() {
-            if let RopeNode::Branch(branch) = &self.nodes[node_idx] {
-                let left_len = self.nodes[branch.left.idx()].len();
-                let right_len = self.nodes[branch.right.idx()].len();
-
-                assert!(branch.length == left_len + right_len, "Incorrect branch length: {}", why);
-            }
-        }
-     }
+    }
 
     #[cfg(not(test))]
     #[inline]
-    fn verify_tree(&self, _why: &str) { }
+    pub (super) fn verify_tree(&self, _why: &str) { }
 
     ///
     /// Creates a rope from a list of cells
     ///
+    /// The cells are divided into leaves of `SPLIT_LENGTH` cells under a balanced tree, so the rope can be edited
+    /// efficiently straight away.
+    ///
     pub fn from<NewCells: IntoIterator<Item=Cell>>(cells: NewCells) -> AttributedRope<Cell, Attribute> {
-        AttributedRope {
-            nodes:          vec![RopeNode::Leaf(None, cells.into_iter().collect(), Arc::new(Attribute::default()))],
-            root_node_idx:  RopeNodeIndex(0),
-            free_nodes:     vec![]
+        let mut rope = AttributedRope::new();
+        rope.rebuild_balanced(cells.into_iter(), Arc::new(Attribute::default()));
+
+        rope
+    }
+
+    ///
+    /// Replaces the whole content of this rope with a balanced tree of leaves containing the specified cells, which all
+    /// have the same attribute
+    ///
+    fn rebuild_balanced<NewCells: Iterator<Item=Cell>>(&mut self, cells: NewCells, attribute: Arc<Attribute>) {
+        // Divide the cells into leaves
+        let mut cells   = cells;
+        let mut leaves  = vec![];
+
+        loop {
+            let leaf = cells.by_ref().take(SPLIT_LENGTH).collect::<Vec<_>>();
+            if leaf.is_empty() && !leaves.is_empty() { break; }
+
+            let is_last = leaf.len() < SPLIT_LENGTH;
+            leaves.push(leaf);
+
+            if is_last { break; }
+        }
+
+        // Replace the existing nodes with a balanced tree
+        self.nodes.clear();
+        self.free_nodes.clear();
+        self.leaf_ids.clear();
+
+        let num_leaves      = leaves.len();
+        self.root_node_idx  = self.store_balanced(&mut leaves.into_iter(), num_leaves, None, &attribute).0;
+
+        self.verify_tree("Post-rebuild");
+    }
+
+    ///
+    /// Stores a balanced subtree containing the next `num_leaves` leaves, returning the index of its root node and its length
+    ///
+    fn store_balanced(&mut self, leaves: &mut impl Iterator<Item=Vec<Cell>>, num_leaves: usize, parent: Option<RopeNodeIndex>, attribute: &Arc<Attribute>) -> (RopeNodeIndex, usize) {
+        if num_leaves <= 1 {
+            let cells   = leaves.next().unwrap_or_default();
+            let length  = cells.len();
+
+            (self.store_new_node(RopeNode::Leaf(parent, Arc::new(cells), Arc::clone(attribute))), length)
+        } else {
+            // The branch node is stored before its children so that the root of the tree is the first node
+            let branch_idx              = self.store_new_node(RopeNode::Empty);
+            let (left, left_length)     = self.store_balanced(leaves, num_leaves / 2, Some(branch_idx), attribute);
+            let (right, right_length)   = self.store_balanced(leaves, num_leaves - num_leaves / 2, Some(branch_idx), attribute);
+
+            self.nodes[branch_idx.idx()] = RopeNode::Branch(RopeBranch {
+                left:       left,
+                right:      right,
+                length:     left_length + right_length,
+                single_run: true,
+                parent:     parent
+            });
+
+            (branch_idx, left_length + right_length)
         }
     }
 
     ///
     /// Allocates space for a new node, stores it and returns the index that it was written to
     ///
-    fn store_new_node(&mut self, node: RopeNode<Cell, Attribute>) -> RopeNodeIndex {
+    pub (super) fn store_new_node(&mut self, node: RopeNode<Cell, Attribute>) -> RopeNodeIndex {
         // Try to use an existing empty node if there is one
-        if let Some(free_node) = self.free_nodes.pop() {
+        let node_idx = if let Some(free_node) = self.free_nodes.pop() {
             // Store in this free node
             self.nodes[free_node] = node;
             RopeNodeIndex(free_node)
@@ -113,8 +166,29 @@ Attribute:  PartialEq+Clone+Default {
             // Create a new node
             let free_node = self.nodes.len();
             self.nodes.push(node);
+            self.leaf_ids.push((LeafId(0), None));
             RopeNodeIndex(free_node)
-        }
+        };
+
+        // Every new node is treated as a new leaf
+        self.assign_leaf_id(node_idx, None);
+
+        node_idx
+    }
+
+    ///
+    /// Gives the node at the specified index a new leaf ID (called whenever the contents of a leaf are changed)
+    ///
+    pub (super) fn assign_leaf_id(&mut self, node_idx: RopeNodeIndex, split_from: Option<LeafId>) {
+        self.leaf_ids[node_idx.idx()]   = (LeafId(self.next_leaf_id), split_from);
+        self.next_leaf_id               += 1;
+    }
+
+    ///
+    /// Retrieves the leaf ID and original leaf ID for a node
+    ///
+    pub (super) fn leaf_id(&self, node_idx: RopeNodeIndex) -> (LeafId, Option<LeafId>) {
+        self.leaf_ids[node_idx.idx()]
     }
 
     ///
@@ -136,31 +210,41 @@ Attribute:  PartialEq+Clone+Default {
     ///
     /// Divides a node into two (replacing a leaf node with a branch node). Returns the left-hand node of the split
     ///
-    fn split(&mut self, leaf_node_idx: RopeNodeIndex, split_index: usize) -> RopeNodeIndex {
+    pub (super) fn split(&mut self, leaf_node_idx: RopeNodeIndex, split_index: usize) -> RopeNodeIndex {
         // Take the leaf node (this leaves it empty)
         let leaf_node = self.nodes[leaf_node_idx.idx()].take();
 
         match leaf_node {
             RopeNode::Leaf(parent, cells, attribute) => {
-                // Split the cells into two halves
-                let mut cells       = cells;
-                let right_cells     = cells.drain(split_index..cells.len()).collect::<Vec<_>>();
-                let left_cells      = cells;
+                rope_event!(node = leaf_node_idx.idx(), split_index = split_index, "split");
+
+                // The new leaves remember the leaf they were split from (which might itself have been split from another leaf)
+                let split_from = match self.leaf_id(leaf_node_idx) { (_, Some(original_id)) => original_id, (leaf_id, None) => leaf_id };
+
+                // Split the cells into two halves (copying them if they're shared with another rope)
+                let (left_cells, right_cells) = match Arc::try_unwrap(cells) {
+                    Ok(mut cells)   => { let right_cells = cells.split_off(split_index); (cells, right_cells) }
+                    Err(cells)      => (cells[0..split_index].to_vec(), cells[split_index..].to_vec())
+                };
                 let length          = left_cells.len() + right_cells.len();
 
                 // Generate the left and right nodes (the current leaf node will become the branch node)
-                let left_node       = RopeNode::Leaf(Some(leaf_node_idx), left_cells, attribute.clone());
-                let right_node      = RopeNode::Leaf(Some(leaf_node_idx), right_cells, attribute.clone());
+                let left_node       = RopeNode::Leaf(Some(leaf_node_idx), Arc::new(left_cells), attribute.clone());
+                let right_node      = RopeNode::Leaf(Some(leaf_node_idx), Arc::new(right_cells), attribute.clone());
 
                 let left_idx        = self.store_new_node(left_node);
                 let right_idx       = self.store_new_node(right_node);
 
+                self.assign_leaf_id(left_idx, Some(split_from));
+                self.assign_leaf_id(right_idx, Some(split_from));
+
                 // Replace the leaf node with the new node
                 self.nodes[leaf_node_idx.idx()] = RopeNode::Branch(RopeBranch {
-                    left:   left_idx,
-                    right:  right_idx,
-                    length: length,
-                    parent: parent
+                    left:       left_idx,
+                    right:      right_idx,
+                    length:     length,
+                    single_run: true,
+                    parent:     parent
                 });
 
                 self.verify_tree("Post-split");
@@ -226,14 +310,14 @@ Attribute:  PartialEq+Clone+Default {
     /// Testing method that calls join_to_right on a specific leaf node
     ///
     #[cfg(test)]
-    pub (super) fn join_at(&mut self, pos: usize) {
-        self.join_to_right(self.find_leaf(pos).1);
+    pub (super) fn join_at(&mut self, pos: usize, policy: JoinPolicy) -> bool {
+        self.join_to_right(self.find_leaf(pos).1, policy)
     }
 
     ///
     /// Corrects the length of a branch node (and its parents if needed) by adding the lengths of its child nodes
     ///
-    fn correct_branch_length(&mut self, branch_node_idx: RopeNodeIndex) {
+    pub (super) fn correct_branch_length(&mut self, branch_node_idx: RopeNodeIndex) {
         let mut next_node = Some(branch_node_idx);
 
         // Process the node and move to the parent
@@ -268,13 +352,142 @@ Attribute:  PartialEq+Clone+Default {
     }
 
     ///
-    /// Joins a leaf node to the node immediately to the right
+    /// Returns the attribute of the first leaf under a node
+    ///
+    pub (super) fn first_attribute<'a>(&'a self, node_idx: RopeNodeIndex) -> &'a Arc<Attribute> {
+        let mut node_idx = node_idx;
+
+        loop {
+            match &self.nodes[node_idx.idx()] {
+                RopeNode::Branch(branch)            => { node_idx = branch.left; }
+                RopeNode::Leaf(_, _, attribute)     => { return attribute; }
+                RopeNode::Empty                     => { panic!("Found an empty node in the tree"); }
+            }
+        }
+    }
+
+    ///
+    /// True if every leaf under a node has the same attribute (always true for a leaf node)
+    ///
+    pub (super) fn is_single_run(&self, node_idx: RopeNodeIndex) -> bool {
+        match &self.nodes[node_idx.idx()] {
+            RopeNode::Branch(branch)    => branch.single_run,
+            _                           => true
+        }
+    }
+
+    ///
+    /// Works out whether or not a branch with the specified child nodes is a single attribute run
+    ///
+    pub (super) fn branch_is_single_run(&self, left_idx: RopeNodeIndex, right_idx: RopeNodeIndex) -> bool {
+        if !self.is_single_run(left_idx) || !self.is_single_run(right_idx) {
+            false
+        } else {
+            let left_attr   = self.first_attribute(left_idx);
+            let right_attr  = self.first_attribute(right_idx);
+
+            Arc::ptr_eq(left_attr, right_attr) || (**left_attr).eq(&**right_attr)
+        }
+    }
+
+    ///
+    /// Recalculates which branches are single attribute runs, starting at a node and moving up to the root of the tree
+    /// (called after the attributes of a leaf have changed or the tree has been restructured)
+    ///
+    pub (super) fn correct_run_summary(&mut self, node_idx: RopeNodeIndex) {
+        let mut next_node = Some(node_idx);
+
+        while let Some(current_node) = next_node {
+            let (single_run, parent) = match &self.nodes[current_node.idx()] {
+                RopeNode::Branch(branch)    => (self.branch_is_single_run(branch.left, branch.right), branch.parent),
+                node                        => { next_node = node.parent(); continue; }
+            };
+
+            if let RopeNode::Branch(branch) = &mut self.nodes[current_node.idx()] {
+                branch.single_run = single_run;
+            }
+
+            next_node = parent;
+        }
+    }
+
+    ///
+    /// Finds the end of the attribute run that continues after a leaf node, skipping over whole subtrees that are a
+    /// single run with the same attribute. Returns the offset where the run ends and the leaf that starts there.
+    ///
+    /// `leaf_end` is the offset of the end of the leaf, and the search stops once it reaches `max_end` (in which case
+    /// no leaf is returned).
+    ///
+    pub (super) fn find_run_end(&self, leaf_node_idx: RopeNodeIndex, leaf_end: usize, attribute: &Attribute, max_end: usize) -> (usize, Option<RopeNodeIndex>) {
+        let mut offset  = leaf_end;
+        let mut subtree = self.next_subtree_to_the_right(leaf_node_idx);
+
+        while let Some(node_idx) = subtree {
+            if offset >= max_end { return (offset, None); }
+
+            let node        = &self.nodes[node_idx.idx()];
+            let continues   = node.len() == 0 || (self.is_single_run(node_idx) && (**self.first_attribute(node_idx)).eq(attribute));
+
+            if continues {
+                // The whole subtree is part of the run
+                offset  += node.len();
+                subtree = self.next_subtree_to_the_right(node_idx);
+            } else if let RopeNode::Branch(branch) = node {
+                // Part of the subtree might continue the run
+                subtree = Some(branch.left);
+            } else {
+                // Found a leaf with a different attribute
+                return (offset, Some(node_idx));
+            }
+        }
+
+        (offset, None)
+    }
+
+    ///
+    /// Finds the subtree that immediately follows a node (None if the node is at the end of the rope)
+    ///
+    fn next_subtree_to_the_right(&self, node_idx: RopeNodeIndex) -> Option<RopeNodeIndex> {
+        let mut current_node = node_idx;
+
+        while let Some(parent_idx) = self.nodes[current_node.idx()].parent() {
+            match &self.nodes[parent_idx.idx()] {
+                RopeNode::Branch(branch) if branch.left == current_node => { return Some(branch.right); }
+                _                                                       => { current_node = parent_idx; }
+            }
+        }
+
+        None
+    }
+
+    ///
+    /// Joins a leaf node to the node immediately to the right, returning true if the nodes were joined
+    ///
+    /// The policy decides which attribute the joined leaf has. Joining an empty leaf with `KeepRight` or leaves with the
+    /// same attribute doesn't change the attribute of any cells, so that's what the editing operations use.
     ///
-    fn join_to_right(&mut self, leaf_node_idx: RopeNodeIndex) {
+    pub (super) fn join_to_right(&mut self, leaf_node_idx: RopeNodeIndex, policy: JoinPolicy) -> bool {
         self.verify_tree("pre-join");
 
         // Fetch the node to the right (we do nothing if there's no node to the right)
-        let right_node_idx = match self.next_leaf_to_the_right(leaf_node_idx) { Some(rhs) => rhs, None => { return; } };
+        let right_node_idx = match self.next_leaf_to_the_right(leaf_node_idx) { Some(rhs) => rhs, None => { return false; } };
+
+        // Decide on the attribute for the joined leaf (None to keep the attribute of the right-hand leaf)
+        let joined_attribute = match (&self.nodes[leaf_node_idx.idx()], &self.nodes[right_node_idx.idx()]) {
+            (RopeNode::Leaf(_, _, lhs_attr), RopeNode::Leaf(_, _, rhs_attr)) => {
+                let same_attribute = Arc::ptr_eq(lhs_attr, rhs_attr) || (**lhs_attr).eq(&**rhs_attr);
+
+                match policy {
+                    JoinPolicy::KeepRight                       => None,
+                    JoinPolicy::KeepLeft if same_attribute      => None,
+                    JoinPolicy::KeepLeft                        => Some(Arc::clone(lhs_attr)),
+                    JoinPolicy::MustMatch if same_attribute     => None,
+                    JoinPolicy::MustMatch                       => { return false; }
+                }
+            }
+
+            _ => None
+        };
 
         // Take the leaf node, leaving it empty
         let leaf_node = self.nodes[leaf_node_idx.idx()].take();
@@ -282,8 +495,10 @@ Attribute:  PartialEq+Clone+Default {
         // Remove the branch node for this leaf
         match leaf_node {
             RopeNode::Leaf(parent_node_idx, lhs_cells, _)   => {
+                rope_event!(node = leaf_node_idx.idx(), right_node = right_node_idx.idx(), "join");
+
                 // Fetch the parent node
-                let parent_node_idx = match parent_node_idx { Some(idx) => idx, None => { return; } };
+                let parent_node_idx = match parent_node_idx { Some(idx) => idx, None => { return false; } };
 
                 // Take the parent node too
                 let parent_node     = self.nodes[parent_node_idx.idx()].take();
@@ -327,11 +542,8 @@ Attribute:  PartialEq+Clone+Default {
                 }
 
                 // Change the remaining node so its parent is the grandparent node
-                match &mut self.nodes[remaining_node_idx.idx()] {
-                    RopeNode::Leaf(parent, _, _)    => { *parent = grandparent_node_idx; }
-                    RopeNode::Branch(branch)        => { branch.parent = grandparent_node_idx; }
-                    RopeNode::Empty                 => { panic!("Found an unexpected empty node"); }
-                }
+                debug_assert!(!matches!(self.nodes[remaining_node_idx.idx()], RopeNode::Empty), "Found an unexpected empty node");
+                self.nodes[remaining_node_idx.idx()].set_parent(grandparent_node_idx);
 
                 // The parent and leaf node are no longer referenced
                 self.free_nodes.push(leaf_node_idx.idx());
@@ -342,7 +554,8 @@ Attribute:  PartialEq+Clone+Default {
                     match &mut self.nodes[right_node_idx.idx()] {
                         RopeNode::Leaf(parent_idx, rhs_cells, _) => {
                             // The LHS cells are at the start of the new node, so swap them into the existing node
-                            let mut cells = lhs_cells;
+                            let rhs_cells = Arc::make_mut(rhs_cells);
+                            let mut cells = Arc::try_unwrap(lhs_cells).unwrap_or_else(|cells| (*cells).clone());
                             mem::swap(&mut cells, rhs_cells);
 
                             // After the swap, lhs_cells contain the cells to append to the end
@@ -350,6 +563,7 @@ Attribute:  PartialEq+Clone+Default {
 
                             // Fix this node's length
                             parent_idx.map(|parent_idx| self.correct_branch_length(parent_idx));
+                            self.assign_leaf_id(right_node_idx, None);
                         }
 
                         _ => {
@@ -360,16 +574,211 @@ Attribute:  PartialEq+Clone+Default {
 
                 // Fix the grandparent node length
                 grandparent_node_idx.map(|grandparent_node_idx| self.correct_branch_length(grandparent_node_idx));
+
+                // Replace the attribute of the joined node if the policy requires it
+                if let Some(joined_attribute) = joined_attribute {
+                    if let RopeNode::Leaf(_, _, rhs_attr) = &mut self.nodes[right_node_idx.idx()] {
+                        *rhs_attr = joined_attribute;
+                    }
+
+                    self.assign_leaf_id(right_node_idx, None);
+                }
+
+                // Removing the leaf might have made its neighbours part of the same run
+                self.correct_run_summary(right_node_idx);
             }
 
             leaf_node => {
                 // Not a leaf node: replace it and stop
                 debug_assert!(false, "Tried to join a non-leaf node");
                 self.nodes[leaf_node_idx.idx()] = leaf_node;
+                return false;
             }
         }
 
         self.verify_tree("post-join");
+        true
+    }
+
+    ///
+    /// True if two leaf nodes can be coalesced into a single node (same attributes and a short enough combined length)
+    ///
+    fn can_coalesce(&self, left_idx: RopeNodeIndex, right_idx: RopeNodeIndex) -> bool {
+        match (&self.nodes[left_idx.idx()], &self.nodes[right_idx.idx()]) {
+            (RopeNode::Leaf(_, left_cells, left_attr), RopeNode::Leaf(_, right_cells, right_attr)) => {
+                left_cells.len() + right_cells.len() <= SPLIT_LENGTH
+                    && (Arc::ptr_eq(left_attr, right_attr) || (**left_attr).eq(&**right_attr))
+            }
+
+            _ => false
+        }
+    }
+
+    ///
+    /// Merges neighbouring leaf nodes with the same attributes in the specified range, provided that the resulting leaf
+    /// is not too long
+    ///
+    /// Setting attributes can leave the tree fragmented into many small leaves with the same attributes, which slows down
+    /// reading the attributes and iterating over cells. `set_attributes` and `replace_attributes` coalesce the leaves around
+    /// the range that they edit automatically.
+    ///
+    pub fn coalesce_range(&mut self, range: Range<usize>) {
+        // Start from the leaf before the range, so it can be joined with the first leaf if necessary
+        let (mut node_offset, mut node_idx) = self.find_leaf(range.start);
+
+        if let Some(prev_idx) = self.next_leaf_to_the_left(node_idx) {
+            node_offset -= self.nodes[prev_idx.idx()].len();
+            node_idx    = prev_idx;
+        }
+
+        // Join leaves until we pass the end of the range
+        loop {
+            // Stop once the next node is beyond the end of the range
+            let next_offset = node_offset + self.nodes[node_idx.idx()].len();
+            if next_offset > range.end { break; }
+
+            let next_idx = match self.next_leaf_to_the_right(node_idx) { Some(idx) => idx, None => { break; } };
+
+            if self.can_coalesce(node_idx, next_idx) {
+                // Cells are moved into the next node, which will start at the same offset as the current node
+                self.join_to_right(node_idx, JoinPolicy::MustMatch);
+            } else {
+                // Move on to the next node
+                node_offset += self.nodes[node_idx.idx()].len();
+            }
+
+            node_idx = next_idx;
+        }
+
+        self.verify_tree("Post coalesce");
+    }
+
+    ///
+    /// Merges all of the neighbouring leaf nodes with the same attributes in this rope (see `coalesce_range`)
+    ///
+    pub fn coalesce(&mut self) {
+        let len = self.len();
+        self.coalesce_range(0..len);
+    }
+
+    ///
+    /// Sets the attributes for a range of cells, starting at the specified leaf node (which must contain the start of the
+    /// range or be before it). Returns the leaf node that follows the range and its offset, which can be used to set the
+    /// attributes of a later range without searching the tree again.
+    ///
+    /// The leaves are not coalesced afterwards.
+    ///
+    fn set_attributes_from_leaf(&mut self, leaf_offset: usize, leaf_node_idx: RopeNodeIndex, range: Range<usize>, new_attributes: &Arc<Attribute>) -> (usize, RopeNodeIndex) {
+        let mut leaf_offset     = leaf_offset;
+        let mut leaf_node_idx   = leaf_node_idx;
+        let mut remaining_range = range;
+
+        // Iterate until we've covered the entire range
+        while remaining_range.start < remaining_range.end {
+            let leaf_node   = &self.nodes[leaf_node_idx.idx()];
+            let leaf_len    = leaf_node.len();
+            let leaf_attr   = match leaf_node { RopeNode::Leaf(_, _, leaf_attributes) => leaf_attributes, _ => { break; } };
+
+            // remaining_range.start must be within the current leaf node
+            if (**leaf_attr).eq(&**new_attributes) || remaining_range.start >= leaf_offset + leaf_len {
+                // This region already has the correct attributes or the range starts after this leaf, so move to the right
+                let next_idx    = match self.next_leaf_to_the_right(leaf_node_idx) { Some(idx) => idx, None => { break; } };
+
+                remaining_range.start = remaining_range.start.max(leaf_offset + leaf_len);
+                leaf_offset     += leaf_len;
+                leaf_node_idx   = next_idx;
+
+            } else if remaining_range.start != leaf_offset {
+                // The attributes start in the middle of the current leaf node, so split it and try again
+                let split_pos   = remaining_range.start - leaf_offset;
+                leaf_node_idx   = self.split(leaf_node_idx, split_pos);
+                leaf_node_idx   = match self.next_leaf_to_the_right(leaf_node_idx) { Some(idx) => idx, None => { break; } };
+                leaf_offset     += split_pos;
+
+            } else if remaining_range.end < leaf_offset + leaf_len {
+                // The attributes end before the end of the current leaf node, so split it and try again
+                let split_pos   = remaining_range.end - leaf_offset;
+                leaf_node_idx   = self.split(leaf_node_idx, split_pos);
+
+            } else {
+                // The entire range is to be set with the new attribute
+                match &mut self.nodes[leaf_node_idx.idx()] {
+                    RopeNode::Leaf(_, _, leaf_attributes)   => { *leaf_attributes = Arc::clone(new_attributes); }
+                    _                                       => { debug_assert!(false, "Missing leaf node"); }
+                }
+                self.assign_leaf_id(leaf_node_idx, None);
+                self.correct_run_summary(leaf_node_idx);
+
+                // Move to the right to continue setting attributes
+                let next_idx    = match self.next_leaf_to_the_right(leaf_node_idx) { Some(idx) => idx, None => { break; } };
+
+                remaining_range.start = leaf_offset + leaf_len;
+                leaf_offset     += leaf_len;
+                leaf_node_idx   = next_idx;
+
+            }
+        }
+
+        (leaf_offset, leaf_node_idx)
+    }
+
+    ///
+    /// Sets the attributes for a series of ranges in a single pass over the rope
+    ///
+    /// This is equivalent to calling `set_attributes` for each span in turn, but when the spans are sorted by their
+    /// start position (as the output of a syntax highlighter usually is), the tree is only searched once instead of once
+    /// per span. Spans that are out of order still work, but need a new search of the tree.
+    ///
+    pub fn apply_attribute_spans<Spans: IntoIterator<Item=(Range<usize>, Attribute)>>(&mut self, spans: Spans) {
+        let len         = self.len();
+        let mut cursor  = None;
+        let mut changed = None::<Range<usize>>;
+
+        for (range, new_attributes) in spans {
+            let range = range.start.min(len)..range.end.min(len);
+            if range.start >= range.end { continue; }
+
+            // Carry on from the leaf after the previous span if possible
+            let (leaf_offset, leaf_node_idx) = match cursor {
+                Some((leaf_offset, leaf_node_idx)) if leaf_offset <= range.start    => (leaf_offset, leaf_node_idx),
+                _                                                                   => self.find_leaf(range.start)
+            };
+
+            cursor  = Some(self.set_attributes_from_leaf(leaf_offset, leaf_node_idx, range.clone(), &Arc::new(new_attributes)));
+            changed = Some(match changed {
+                Some(changed)   => changed.start.min(range.start)..changed.end.max(range.end),
+                None            => range
+            });
+        }
+
+        // Merge any leaves that have ended up with the same attributes
+        if let Some(changed) = changed {
+            self.coalesce_range(changed);
+        }
+    }
+
+    ///
+    /// Replaces the attributes in a range of cells with the result of calling a function on each of the existing
+    /// attribute runs in that range
+    ///
+    /// This can be used to change part of a composite attribute (toggling a style, for example) without reading
+    /// and setting each run individually.
+    ///
+    pub fn map_attributes<MapFn: FnMut(&Attribute) -> Attribute>(&mut self, range: Range<usize>, mut map_fn: MapFn) {
+        // Work out the new attributes for each run, merging runs that end up with the same attribute
+        let mut new_runs: Vec<(Range<usize>, Attribute)> = vec![];
+
+        for (attribute, run_range) in self.read_attribute_runs(range) {
+            let new_attribute = map_fn(attribute);
+
+            match new_runs.last_mut() {
+                Some((last_range, last_attribute)) if *last_attribute == new_attribute => { last_range.end = run_range.end; }
+                _                                                                      => { new_runs.push((run_range, new_attribute)); }
+            }
+        }
+
+        // Update the rope
+        self.apply_attribute_spans(new_runs);
     }
 
     ///
@@ -379,6 +788,9 @@ Attribute:  PartialEq+Clone+Default {
         self.verify_tree("Pre replace_cells");
 
         if let RopeNode::Leaf(parent_idx, cells, _attributes) = &mut self.nodes[leaf_node_idx.idx()] {
+            // Copy the cells if they're shared with another rope
+            let cells = Arc::make_mut(cells);
+
             // Adjust the range to fit in the cell range
             let mut range = range;
             if range.start > cells.len()    { range.start = cells.len(); }
@@ -393,6 +805,7 @@ Attribute:  PartialEq+Clone+Default {
 
             // Update the lengths in the branches above this node
             let mut parent_idx = *parent_idx;
+            self.assign_leaf_id(leaf_node_idx, None);
 
             while let Some(branch_idx) = parent_idx {
                 if let RopeNode::Branch(branch) = &mut self.nodes[branch_idx.idx()] {
@@ -489,6 +902,50 @@ Attribute:  PartialEq+Clone+Default {
         }
     }
 
+    ///
+    /// Finds the next leaf-node to the left of a particular node (None for the first node in the tree)
+    ///
+    pub (super) fn next_leaf_to_the_left(&self, node_idx: RopeNodeIndex) -> Option<RopeNodeIndex> {
+        // The initial node is the 'right' node which we're trying to find the LHS for
+        let mut right_node_idx          = node_idx;
+        let mut maybe_parent_node_idx   = self.nodes[right_node_idx.idx()].parent();
+
+        // Move up the tree until the right node is on the right-hand side
+        let mut left_node_idx           = None;
+
+        while let Some(parent_node_idx) = maybe_parent_node_idx {
+            if let RopeNode::Branch(parent_branch) = &self.nodes[parent_node_idx.idx()] {
+                if right_node_idx == parent_branch.right {
+                    // We can follow the LHS of the parent node to find the neighboring element
+                    left_node_idx = Some(parent_branch.left);
+                    break;
+                } else {
+                    // Move up the tree
+                    debug_assert!(right_node_idx == parent_branch.left);
+
+                    maybe_parent_node_idx   = parent_branch.parent;
+                    right_node_idx          = parent_node_idx;
+                }
+            } else {
+                debug_assert!(false, "Parent node was not a branch");
+                maybe_parent_node_idx = None;
+            }
+        }
+
+        // Move left then down from the parent node until we reach a leaf node
+        if let Some(left_node_idx) = left_node_idx {
+            let mut next_node = left_node_idx;
+
+            while let RopeNode::Branch(branch) = &self.nodes[next_node.idx()] {
+                next_node = branch.right;
+            }
+
+            Some(next_node)
+        } else {
+            None
+        }
+    }
+
     ///
     /// Performs a replacement operation on a particular leaf node
     ///
@@ -530,32 +987,113 @@ Attribute:  PartialEq+Clone+Default {
             }
 
             // Join any empty nodes that are left after this operation
-            empty_nodes.into_iter().for_each(|empty_cell_idx| self.join_to_right(empty_cell_idx));
+            empty_nodes.into_iter().for_each(|empty_cell_idx| { self.join_to_right(empty_cell_idx, JoinPolicy::KeepRight); });
         }
 
         // If the original target node is empty, join it to the right
         if self.nodes[leaf_node_idx.idx()].len() == 0 {
-            self.join_to_right(leaf_node_idx);
+            self.join_to_right(leaf_node_idx, JoinPolicy::KeepRight);
         }
 
         self.verify_tree("Post replace leaf");
     }
 
+    ///
+    /// Returns the cell at the specified position, or `None` if the position is beyond the end of the rope
+    ///
+    pub fn get(&self, pos: usize) -> Option<&Cell> {
+        if pos >= self.len() { return None; }
+
+        // find_leaf() returns the leaf to the left if pos is on a boundary, so move right until we find the leaf containing the cell
+        let (mut leaf_offset, mut leaf_node_idx) = self.find_leaf(pos);
+
+        loop {
+            match &self.nodes[leaf_node_idx.idx()] {
+                RopeNode::Leaf(_, cells, _) if pos - leaf_offset < cells.len()  => { return cells.get(pos - leaf_offset); }
+                RopeNode::Leaf(_, cells, _)                                     => { leaf_offset += cells.len(); }
+                _                                                               => { return None; }
+            }
+
+            leaf_node_idx = self.next_leaf_to_the_right(leaf_node_idx)?;
+        }
+    }
+
     ///
     /// Reads the cell values for a range in this rope
     ///
     pub fn read_cells<'a>(&'a self, range: Range<usize>) -> AttributedRopeIterator<'a, Cell, Attribute> {
-        // Find the first cell in the range
-        let (node_offset, node_idx) = self.find_leaf(range.start);
+        // The iterator can be read from both ends, so limit the range to the cells that are actually in the rope
+        let len                             = self.len();
+        let range                           = range.start.min(len)..range.end.min(len).max(range.start.min(len));
+
+        // Find the first and last cells in the range
+        let (node_offset, node_idx)         = self.find_leaf(range.start);
+        let (back_node_offset, back_idx)    = self.find_leaf(range.end);
 
         // Create an iterator for the remaining cells
         AttributedRopeIterator {
             rope:               self,
             node_idx:           node_idx,
             node_offset:        range.start-node_offset,
+            back_node_idx:      back_idx,
+            back_node_offset:   range.end-back_node_offset,
             remaining_cells:    range.end-range.start
         }
     }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, one for each leaf node the range intersects
+    ///
+    /// This is more efficient than `read_cells` for bulk processing: the slices can be copied, hashed or written out
+    /// in one operation rather than a cell at a time. Chunks are always returned in the order they appear in the rope.
+    ///
+    pub fn read_chunks<'a>(&'a self, range: Range<usize>) -> AttributedRopeChunkIterator<'a, Cell, Attribute> {
+        // Find the first cell in the range
+        let (node_offset, node_idx) = self.find_leaf(range.start);
+
+        // Create an iterator for the remaining chunks
+        AttributedRopeChunkIterator {
+            rope:               self,
+            node_idx:           Some(node_idx),
+            node_offset:        range.start-node_offset,
+            remaining_cells:    range.end.max(range.start)-range.start
+        }
+    }
+
+    ///
+    /// Reads the leaves that make up a range of this rope, in the order they appear in the rope
+    ///
+    /// Each leaf is returned with a `LeafId` that stays the same until the leaf itself is edited, so data derived from
+    /// the cells of a leaf can be cached and re-used when edits elsewhere in the rope move it to a new position.
+    ///
+    pub fn read_leaves<'a>(&'a self, range: Range<usize>) -> AttributedRopeLeafIterator<'a, Cell, Attribute> {
+        // Find the first cell in the range
+        let (node_offset, node_idx) = self.find_leaf(range.start);
+
+        AttributedRopeLeafIterator {
+            rope:               self,
+            node_idx:           Some(node_idx),
+            node_offset:        range.start-node_offset,
+            pos:                range.start,
+            remaining_cells:    range.end.max(range.start)-range.start
+        }
+    }
+    ///
+    /// Reads the attribute runs that intersect a range of this rope, in order. The ranges returned are
+    /// limited to the requested range.
+    ///
+    pub fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> AttributedRopeRunIterator<'a, Cell, Attribute> {
+        // Find the leaf containing the first cell
+        let (node_offset, node_idx) = self.find_leaf(range.start);
+
+        AttributedRopeRunIterator {
+            rope:           self,
+            node_idx:       Some(node_idx),
+            node_offset:    node_offset,
+            pos:            range.start,
+            end:            range.end.min(self.len())
+        }
+    }
 }
 
 impl<Cell, Attribute> Rope for AttributedRope<Cell, Attribute> 
@@ -579,10 +1117,18 @@ Attribute:  PartialEq+Clone+Default {
     ///
     /// Reads the cell values for a range in this rope
     ///
-    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&Self::Cell>> {
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
         Box::new(self.read_cells(range))
     }
 
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, one for each leaf node the range intersects
+    ///
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        let len = self.len();
+        Box::new(self.read_chunks(range.start.min(len)..range.end.min(len)))
+    }
+
     ///
     /// Returns the attributes set at the specified location and their extent
     ///
@@ -639,8 +1185,42 @@ Attribute:  PartialEq+Clone+Default {
             last_node_idx = next_node_idx;
         }
 
+        // Move to the left to find where the attributes start
+        let mut first_node_idx = leaf_node_idx;
+        loop {
+            // Move to the left
+            let prev_node_idx = match self.next_leaf_to_the_left(first_node_idx) {
+                Some(idx)   => idx,
+                None        => { break; }
+            };
+
+            // Check that the attributes match the following node
+            let prev_node = &self.nodes[prev_node_idx.idx()];
+            match prev_node {
+                RopeNode::Leaf(_, _, prev_attr)  => {
+                    if !(**prev_attr).eq(&**attributes) {
+                        break;
+                    }
+                }
+                _ => { debug_assert!(false, "Neighboring node was not a leaf node"); break; }
+            }
+
+            // Grow the extent if the attributes match
+            extent.start -= prev_node.len();
+
+            // Keep going from this previous node
+            first_node_idx = prev_node_idx;
+        }
+
         (&**attributes, extent)
     }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        Box::new(self.read_attribute_runs(range))
+    }
 }
 
 impl<Cell, Attribute> RopeMut for AttributedRope<Cell, Attribute> 
@@ -663,6 +1243,17 @@ Attribute:  PartialEq+Clone+Default {
     /// as the attributes that were applied to the first cell in the replacement range
     ///
     fn replace<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells) {
+        rope_span!("replace", start = range.start, end = range.end);
+
+        // Cells added to an empty rope (eg, when loading a document) are divided into a balanced tree of leaves
+        if self.len() == 0 {
+            let (attribute, _) = self.read_attributes(0);
+            let attribute = Arc::new(attribute.clone());
+
+            self.rebuild_balanced(new_cells.into_iter(), attribute);
+            return;
+        }
+
         // Find the replacement position
         let (mut leaf_offset, mut leaf_node) = self.find_leaf(range.start);
 
@@ -687,6 +1278,8 @@ Attribute:  PartialEq+Clone+Default {
     /// Sets the attributes for a range of cells
     ///
     fn set_attributes(&mut self, range: Range<usize>, new_attributes: Self::Attribute) {
+        rope_span!("set_attributes", start = range.start, end = range.end);
+
         let len                 = self.len();
         let mut remaining_range = range;
         let new_attributes      = Arc::new(new_attributes);
@@ -695,53 +1288,42 @@ Attribute:  PartialEq+Clone+Default {
         if remaining_range.start > len  { remaining_range.start = len; }
         if remaining_range.end > len    { remaining_range.end = len; }
 
-        // Get the current leaf node
-        let (mut leaf_offset, mut leaf_node_idx) = self.find_leaf(remaining_range.start);
+        let range_start         = remaining_range.start;
+        let range_end           = remaining_range.end;
 
-        // Iterate until we've covered the entire range
-        while remaining_range.start < remaining_range.end {
-            let leaf_node   = &self.nodes[leaf_node_idx.idx()];
-            let leaf_len    = leaf_node.len();
-            let leaf_attr   = match leaf_node { RopeNode::Leaf(_, _, leaf_attributes) => leaf_attributes, _ => { break; } };
+        // Set the attributes starting from the leaf containing the start of the range
+        let (leaf_offset, leaf_node_idx) = self.find_leaf(range_start);
+        self.set_attributes_from_leaf(leaf_offset, leaf_node_idx, remaining_range, &new_attributes);
 
-            // remaining_range.start must be within the current leaf node
-            if (**leaf_attr).eq(&*new_attributes) {
-                // This region already has the correct attributes, so move to the right
-                remaining_range.start = leaf_offset + leaf_len;
-                leaf_offset     += leaf_len;
-                leaf_node_idx   = match self.next_leaf_to_the_right(leaf_node_idx) { Some(idx) => idx, None => { break; } };
-
-            } else if remaining_range.start >= leaf_offset + leaf_len {
-                // Range starts at the end of the current leaf node, so move to the right
-                leaf_offset     += leaf_len;
-                leaf_node_idx   = match self.next_leaf_to_the_right(leaf_node_idx) { Some(idx) => idx, None => { break; } };
+        // Merge any leaves that have ended up with the same attributes
+        self.coalesce_range(range_start..range_end);
+    }
 
-            } else if remaining_range.start != leaf_offset {
-                // The attributes start in the middle of the current leaf node, so split it and try again
-                let split_pos   = remaining_range.start - leaf_offset;
-                leaf_node_idx   = self.split(leaf_node_idx, split_pos);
-                leaf_node_idx   = match self.next_leaf_to_the_right(leaf_node_idx) { Some(idx) => idx, None => { break; } };
-                leaf_offset     += split_pos;
+    ///
+    /// Copies the attributes (but not the cells) from a range of another rope onto the range of the same length starting
+    /// at `dest_pos` in this rope
+    ///
+    fn copy_attributes_from<SrcRope: Rope<Attribute=Self::Attribute>>(&mut self, src: &SrcRope, src_range: Range<usize>, dest_pos: usize) {
+        let runs = src.read_attribute_runs(src_range.clone())
+            .map(|(attribute, run_range)| ((run_range.start - src_range.start + dest_pos)..(run_range.end - src_range.start + dest_pos), attribute.clone()))
+            .collect::<Vec<_>>();
 
-            } else if remaining_range.end < leaf_offset + leaf_len {
-                // The attributes end before the end of the current leaf node, so split it and try again
-                let split_pos   = remaining_range.end - leaf_offset;
-                leaf_node_idx   = self.split(leaf_node_idx, split_pos);
+        self.apply_attribute_spans(runs);
+    }
 
-            } else {
-                // The entire range is to be set with the new attribute
-                match &mut self.nodes[leaf_node_idx.idx()] {
-                    RopeNode::Leaf(_, _, leaf_attributes)   => { *leaf_attributes = Arc::clone(&new_attributes); }
-                    _                                       => { debug_assert!(false, "Missing leaf node"); }
-                }
+    ///
+    /// Resets the attributes for a range of cells to the default attribute, merging the cleared leaves
+    /// with their neighbours where possible
+    ///
+    fn clear_attributes(&mut self, range: Range<usize>) {
+        let len = self.len();
+        if range.start >= len || range.start >= range.end { return; }
 
-                // Move to the right to continue setting attributes
-                remaining_range.start = leaf_offset + leaf_len;
-                leaf_offset     += leaf_len;
-                leaf_node_idx   = match self.next_leaf_to_the_right(leaf_node_idx) { Some(idx) => idx, None => { break; } };
+        self.set_attributes(range.clone(), Attribute::default());
 
-            }
-        }
+        // The cleared range may join up with default runs on either side, so coalesce the whole run
+        let (_, run_range) = self.read_attributes(range.start);
+        self.coalesce_range(run_range);
     }
 
     ///
@@ -753,6 +1335,12 @@ Attribute:  PartialEq+Clone+Default {
         //   * range is in an existing cell with the same attributes (just add to the cell)
         //   * range is at the start of an existing cell with different attributes but covers the entire cell (change the attributes and replace the cell)
 
+        let new_cells                       = new_cells.into_iter().collect::<Vec<_>>();
+        let range_start                     = range.start;
+        let new_len                         = new_cells.len();
+
+        rope_span!("replace_attributes", start = range.start, end = range.end, inserted = new_len);
+
         let (leaf_offset, leaf_node_idx)    = self.find_leaf(range.start);
         let leaf_node                       = &self.nodes[leaf_node_idx.idx()];
         let leaf_attributes                 = match leaf_node {
@@ -760,7 +1348,7 @@ Attribute:  PartialEq+Clone+Default {
             _                                   => { debug_assert!(false, "Failed to find leafnode while replacing text"); return; }
         };
 
-        if (&**leaf_attributes).eq(&new_attributes) {
+        if (**leaf_attributes).eq(&new_attributes) {
             // Attributes are unchanged for this node
             // TODO: a small optimisation would be to avoid searching for the node again in this step
             self.replace(range, new_cells);
@@ -772,6 +1360,8 @@ Attribute:  PartialEq+Clone+Default {
                 RopeNode::Leaf(_, _, attributes)    => *attributes = Arc::new(new_attributes),
                 _                                   => debug_assert!(false, "Failed to find a leaf node to set attributes on")
             }
+            self.assign_leaf_id(leaf_node_idx, None);
+            self.correct_run_summary(leaf_node_idx);
 
             // Replace contents
             // TODO: same optimisation as before
@@ -785,12 +1375,16 @@ Attribute:  PartialEq+Clone+Default {
                 RopeNode::Leaf(_, _, attributes)    => *attributes = Arc::new(new_attributes),
                 _                                   => debug_assert!(false, "Failed to find a leaf node to set attributes on")
             }
+            self.assign_leaf_id(empty_node_idx, None);
+            self.correct_run_summary(empty_node_idx);
 
             // Replace contents
             // TODO: same optimisation as before
-            let range_start = range.start;
             self.replace_leaf(range, range_start, empty_node_idx, new_cells.into_iter());
         }
+
+        // Merge the new cells with their neighbours if they have the same attributes
+        self.coalesce_range(range_start..(range_start+new_len));
     }
 }
 
@@ -802,3 +1396,8 @@ Attribute:  PartialEq+Clone+Default {
         Self::new()
     }
 }
+
+impl<Cell, Attribute> RopeSearch for AttributedRope<Cell, Attribute>
+where
+Cell:       Clone+PartialEq,
+Attribute:  PartialEq+Clone+Default { }
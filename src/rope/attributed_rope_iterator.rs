@@ -1,5 +1,8 @@
 use super::node::*;
 use super::attributed_rope::*;
+use super::rope_leaf::*;
+
+use std::ops::{Range};
 
 ///
 /// Iterator that reads a range of cells in an attributed rope
@@ -14,6 +17,12 @@ pub struct AttributedRopeIterator<'a, Cell, Attribute> {
     /// The offset of the node
     pub (super) node_offset: usize,
 
+    /// The node that's being read from the end of the range
+    pub (super) back_node_idx: RopeNodeIndex,
+
+    /// The offset in the back node just after the next cell to read from the end of the range
+    pub (super) back_node_offset: usize,
+
     /// The remaining number of cells to read from this iterator
     pub (super) remaining_cells: usize
 }
@@ -64,3 +73,247 @@ Attribute:  PartialEq+Clone+Default {
         }
     }
 }
+
+impl<'a, Cell, Attribute> DoubleEndedIterator for AttributedRopeIterator<'a, Cell, Attribute>
+where   
+Cell:       Clone, 
+Attribute:  PartialEq+Clone+Default {
+    fn next_back(&mut self) -> Option<&'a Cell> {
+        if self.remaining_cells == 0 {
+            // No more cells to read
+            None
+        } else {
+            // Try to read the cell before the back offset in the current node
+            let node = &self.rope.nodes[self.back_node_idx.idx()];
+
+            if let RopeNode::Leaf(_, cells, _) = node {
+                if self.back_node_offset > 0 {
+                    // Move to the previous item
+                    self.back_node_offset   -= 1;
+                    self.remaining_cells    -= 1;
+
+                    // Fetch the cell
+                    Some(&cells[self.back_node_offset])
+                } else {
+                    // Passed over the start of the node
+                    if let Some(next_node) = self.rope.next_leaf_to_the_left(self.back_node_idx) {
+                        // Move on to the end of the preceding node, and try again
+                        self.back_node_idx      = next_node;
+                        self.back_node_offset   = self.rope.nodes[next_node.idx()].len();
+
+                        self.next_back()
+                    } else {
+                        // Overran the start of the rope
+                        None
+                    }
+                }
+            } else {
+                // Not a leaf node
+                debug_assert!(false, "Rope iterator expects to only encounter leaf nodes");
+                None
+            }
+        }
+    }
+}
+
+///
+/// Iterator that reads a range of cells in an attributed rope as a series of slices
+///
+pub struct AttributedRopeChunkIterator<'a, Cell, Attribute> {
+    /// The rope that's being read
+    pub (super) rope: &'a AttributedRope<Cell, Attribute>,
+
+    /// The node that's being read (None if we've reached the end of the rope)
+    pub (super) node_idx: Option<RopeNodeIndex>,
+
+    /// The offset within the node of the start of the next chunk
+    pub (super) node_offset: usize,
+
+    /// The remaining number of cells to read from this iterator
+    pub (super) remaining_cells: usize
+}
+
+impl<'a, Cell, Attribute> Iterator for AttributedRopeChunkIterator<'a, Cell, Attribute>
+where   
+Cell:       Clone, 
+Attribute:  PartialEq+Clone+Default {
+    type Item = &'a [Cell];
+
+    fn next(&mut self) -> Option<&'a [Cell]> {
+        while self.remaining_cells > 0 {
+            // Fetch the current node (stopping if we've reached the end of the rope)
+            let node_idx = self.node_idx?;
+
+            if let RopeNode::Leaf(_, cells, _) = &self.rope.nodes[node_idx.idx()] {
+                // Read as much as possible from the current node
+                let start   = self.node_offset.min(cells.len());
+                let end     = (start + self.remaining_cells).min(cells.len());
+
+                // The next chunk starts at the beginning of the following node
+                self.node_idx           = self.rope.next_leaf_to_the_right(node_idx);
+                self.node_offset        = 0;
+                self.remaining_cells    -= end - start;
+
+                // Empty nodes or ranges starting at the end of a node generate no chunks
+                if start < end {
+                    return Some(&cells[start..end]);
+                }
+            } else {
+                // Not a leaf node
+                debug_assert!(false, "Rope iterator expects to only encounter leaf nodes");
+                return None;
+            }
+        }
+
+        None
+    }
+}
+
+///
+/// Iterator that reads the leaves that make up a range of an attributed rope
+///
+pub struct AttributedRopeLeafIterator<'a, Cell, Attribute> {
+    /// The rope that's being read
+    pub (super) rope: &'a AttributedRope<Cell, Attribute>,
+
+    /// The node that's being read (None if we've reached the end of the rope)
+    pub (super) node_idx: Option<RopeNodeIndex>,
+
+    /// The offset within the node of the start of the next leaf
+    pub (super) node_offset: usize,
+
+    /// The position in the rope of the next cell to read
+    pub (super) pos: usize,
+
+    /// The remaining number of cells to read from this iterator
+    pub (super) remaining_cells: usize
+}
+
+impl<'a, Cell, Attribute> Iterator for AttributedRopeLeafIterator<'a, Cell, Attribute>
+where   
+Cell:       Clone, 
+Attribute:  PartialEq+Clone+Default {
+    type Item = RopeLeaf<'a, Cell, Attribute>;
+
+    fn next(&mut self) -> Option<RopeLeaf<'a, Cell, Attribute>> {
+        while self.remaining_cells > 0 {
+            // Fetch the current node (stopping if we've reached the end of the rope)
+            let node_idx = self.node_idx?;
+
+            if let RopeNode::Leaf(_, cells, attribute) = &self.rope.nodes[node_idx.idx()] {
+                // Read as much as possible from the current node
+                let start   = self.node_offset.min(cells.len());
+                let end     = (start + self.remaining_cells).min(cells.len());
+                let pos     = self.pos;
+
+                // The next leaf starts at the beginning of the following node
+                self.node_idx           = self.rope.next_leaf_to_the_right(node_idx);
+                self.node_offset        = 0;
+                self.pos                += end - start;
+                self.remaining_cells    -= end - start;
+
+                // Empty nodes or ranges starting at the end of a node are skipped
+                if start < end {
+                    let (id, split_from) = self.rope.leaf_id(node_idx);
+
+                    return Some(RopeLeaf {
+                        id:         id,
+                        split_from: split_from,
+                        range:      pos..(pos + end - start),
+                        cells:      &cells[start..end],
+                        attribute:  &**attribute
+                    });
+                }
+            } else {
+                // Not a leaf node
+                debug_assert!(false, "Rope iterator expects to only encounter leaf nodes");
+                return None;
+            }
+        }
+
+        None
+    }
+}
+
+///
+/// Iterator that reads the attribute runs in a range of an attributed rope
+///
+pub struct AttributedRopeRunIterator<'a, Cell, Attribute> {
+    /// The rope that's being read
+    pub (super) rope: &'a AttributedRope<Cell, Attribute>,
+
+    /// The next leaf node to read (None if we've reached the end of the rope)
+    pub (super) node_idx: Option<RopeNodeIndex>,
+
+    /// The offset of the start of the next leaf node within the rope
+    pub (super) node_offset: usize,
+
+    /// The start of the next run to return
+    pub (super) pos: usize,
+
+    /// The end of the range being read
+    pub (super) end: usize
+}
+
+impl<'a, Cell, Attribute> Iterator for AttributedRopeRunIterator<'a, Cell, Attribute>
+where   
+Cell:       Clone, 
+Attribute:  PartialEq+Clone+Default {
+    type Item = (&'a Attribute, Range<usize>);
+
+    fn next(&mut self) -> Option<(&'a Attribute, Range<usize>)> {
+        let mut run: Option<(&'a Attribute, Range<usize>)> = None;
+
+        // Gather leaf nodes until the attribute changes or we reach the end of the range
+        while self.pos < self.end {
+            let node_idx = match self.node_idx { Some(idx) => idx, None => { break; } };
+
+            let (cells, attribute) = match &self.rope.nodes[node_idx.idx()] {
+                RopeNode::Leaf(_, cells, attribute) => (cells, &**attribute),
+                _                                   => { debug_assert!(false, "Run iterator expects to only encounter leaf nodes"); return None; }
+            };
+
+            let node_end = self.node_offset + cells.len();
+
+            if node_end <= self.pos {
+                // Nothing to read from this node (empty or the position is at the end of the node)
+                self.node_offset    = node_end;
+                self.node_idx       = self.rope.next_leaf_to_the_right(node_idx);
+                continue;
+            }
+
+            // Stop if this node has a different attribute to the run we're building
+            if let Some((run_attribute, _)) = &run {
+                if !(*run_attribute).eq(attribute) {
+                    break;
+                }
+            }
+
+            if node_end > self.end {
+                // The range ends within this node
+                match &mut run {
+                    Some((_, run_range))    => { run_range.end = self.end; }
+                    None                    => { run = Some((attribute, self.pos..self.end)); }
+                }
+
+                self.pos = self.end;
+                break;
+            }
+
+            // Read the rest of this node, then skip any subtrees to the right that continue the same run
+            let (skip_end, next_node)   = self.rope.find_run_end(node_idx, node_end, attribute, self.end);
+            let run_end                 = skip_end.min(self.end);
+
+            match &mut run {
+                Some((_, run_range))    => { run_range.end = run_end; }
+                None                    => { run = Some((attribute, self.pos..run_end)); }
+            }
+
+            self.pos            = run_end;
+            self.node_offset    = skip_end;
+            self.node_idx       = next_node;
+        }
+
+        run
+    }
+}
@@ -14,6 +14,10 @@ pub struct RopeBranch {
     /// The total length of all the substrings under this branch
     pub length: usize,
 
+    /// True if every leaf under this branch has the same attribute, so the whole branch is part of a single attribute run
+    /// (if this is true for a branch, it's also true for any branches beneath it)
+    pub single_run: bool,
+
     /// The parent of this branch, or None if this is the root node
     pub parent: Option<RopeNodeIndex>
 }
@@ -0,0 +1,175 @@
+use crate::api::*;
+
+use std::ops::{Range};
+
+// Plain buffers can be treated as read-only ropes with a single (empty) attribute covering the whole buffer.
+// This makes it possible to use generic code written against the `Rope` trait without creating an `AttributedRope`.
+
+///
+/// Limits a range to the length of a buffer
+///
+#[inline]
+fn clip_range(range: Range<usize>, len: usize) -> Range<usize> {
+    let start   = range.start.min(len);
+    let end     = range.end.min(len).max(start);
+
+    start..end
+}
+
+impl<Cell> Rope for [Cell]
+where
+Cell: Clone {
+    type Cell       = Cell;
+    type Attribute  = ();
+
+    #[inline]
+    fn len(&self) -> usize {
+        <[Cell]>::len(self)
+    }
+
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Cell>> {
+        Box::new(self[clip_range(range, <[Cell]>::len(self))].iter())
+    }
+
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Cell]>> {
+        let chunk = &self[clip_range(range, <[Cell]>::len(self))];
+
+        Box::new(Some(chunk).filter(|chunk| !chunk.is_empty()).into_iter())
+    }
+
+    #[inline]
+    fn read_attributes<'a>(&'a self, _pos: usize) -> (&'a (), Range<usize>) {
+        (&(), 0..<[Cell]>::len(self))
+    }
+}
+
+impl<'b, Cell> Rope for &'b [Cell]
+where
+Cell: Clone {
+    type Cell       = Cell;
+    type Attribute  = ();
+
+    #[inline]
+    fn len(&self) -> usize {
+        <[Cell]>::len(self)
+    }
+
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Cell>> {
+        Rope::read_cells(*self, range)
+    }
+
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Cell]>> {
+        Rope::read_chunks(*self, range)
+    }
+
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a (), Range<usize>) {
+        Rope::read_attributes(*self, pos)
+    }
+}
+
+impl<Cell> Rope for Vec<Cell>
+where
+Cell: Clone {
+    type Cell       = Cell;
+    type Attribute  = ();
+
+    #[inline]
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Cell>> {
+        Rope::read_cells(self.as_slice(), range)
+    }
+
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Cell]>> {
+        Rope::read_chunks(self.as_slice(), range)
+    }
+
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a (), Range<usize>) {
+        Rope::read_attributes(self.as_slice(), pos)
+    }
+}
+
+impl Rope for str {
+    type Cell       = u8;
+    type Attribute  = ();
+
+    #[inline]
+    fn len(&self) -> usize {
+        str::len(self)
+    }
+
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a u8>> {
+        Rope::read_cells(self.as_bytes(), range)
+    }
+
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [u8]>> {
+        Rope::read_chunks(self.as_bytes(), range)
+    }
+
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a (), Range<usize>) {
+        Rope::read_attributes(self.as_bytes(), pos)
+    }
+}
+
+impl<'b> Rope for &'b str {
+    type Cell       = u8;
+    type Attribute  = ();
+
+    #[inline]
+    fn len(&self) -> usize {
+        str::len(self)
+    }
+
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a u8>> {
+        Rope::read_cells(self.as_bytes(), range)
+    }
+
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [u8]>> {
+        Rope::read_chunks(self.as_bytes(), range)
+    }
+
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a (), Range<usize>) {
+        Rope::read_attributes(self.as_bytes(), pos)
+    }
+}
+
+impl Rope for String {
+    type Cell       = u8;
+    type Attribute  = ();
+
+    #[inline]
+    fn len(&self) -> usize {
+        String::len(self)
+    }
+
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a u8>> {
+        Rope::read_cells(self.as_bytes(), range)
+    }
+
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [u8]>> {
+        Rope::read_chunks(self.as_bytes(), range)
+    }
+
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a (), Range<usize>) {
+        Rope::read_attributes(self.as_bytes(), pos)
+    }
+}
@@ -0,0 +1,276 @@
+use crate::api::*;
+
+use bytes::{Bytes};
+
+use std::sync::*;
+use std::ops::{Range};
+
+///
+/// A section of a `BytesRope`
+///
+#[derive(Clone)]
+struct BytesPiece<Attribute> {
+    /// The position of the first byte of this piece in the rope
+    start: usize,
+
+    /// The bytes in this piece
+    bytes: Bytes,
+
+    /// The attribute applied to the bytes in this piece
+    attribute: Arc<Attribute>
+}
+
+///
+/// A rope of bytes that stores its content as `bytes::Bytes` buffers (available with the `bytes` feature)
+///
+/// A rope can be created from a buffer (such as one that was memory mapped or received from the network) without
+/// copying it, and editing the rope only copies the bytes that are inserted: the rest of the rope continues to refer to
+/// the original buffer. `read_chunks()` returns the content as `Bytes` slices, so it can be passed to I/O layers
+/// without copying either.
+///
+/// This stores its buffers in a list, so it's best suited to ropes that are edited in a small number of places, such as
+/// a file that's been loaded for editing.
+///
+#[derive(Clone)]
+pub struct BytesRope<Attribute> {
+    /// The pieces making up this rope, in order
+    pieces: Vec<BytesPiece<Attribute>>,
+
+    /// The total number of bytes in this rope
+    len: usize,
+
+    /// The attribute returned for an empty rope
+    default_attribute: Attribute
+}
+
+impl<Attribute> BytesRope<Attribute>
+where
+Attribute: PartialEq+Clone+Default {
+    ///
+    /// Creates a new, empty rope
+    ///
+    pub fn new() -> BytesRope<Attribute> {
+        BytesRope {
+            pieces:             vec![],
+            len:                0,
+            default_attribute:  Attribute::default()
+        }
+    }
+
+    ///
+    /// Reads the bytes in a range of this rope as a series of `Bytes` slices, which share memory with the rope
+    ///
+    pub fn read_chunks<'a>(&'a self, range: Range<usize>) -> impl 'a+Iterator<Item=Bytes> {
+        let range = range.start.min(self.len)..range.end.min(self.len);
+
+        self.pieces[self.piece_index(range.start)..].iter()
+            .take_while(move |piece| piece.start < range.end)
+            .map(move |piece| {
+                let start   = range.start.max(piece.start) - piece.start;
+                let end     = range.end.min(piece.start + piece.bytes.len()) - piece.start;
+
+                piece.bytes.slice(start..end)
+            })
+            .filter(|chunk| !chunk.is_empty())
+    }
+
+    ///
+    /// Returns the index of the piece containing the specified position (or the number of pieces if the position is at the end of the rope)
+    ///
+    fn piece_index(&self, pos: usize) -> usize {
+        self.pieces.partition_point(|piece| piece.start + piece.bytes.len() <= pos)
+    }
+
+    ///
+    /// Ensures that a piece starts at the specified position, returning its index
+    ///
+    fn split_at(&mut self, pos: usize) -> usize {
+        let piece_idx = self.piece_index(pos);
+        if piece_idx >= self.pieces.len() || self.pieces[piece_idx].start == pos {
+            return piece_idx;
+        }
+
+        // Split the piece without copying the bytes
+        let piece       = &mut self.pieces[piece_idx];
+        let offset      = pos - piece.start;
+        let new_piece   = BytesPiece {
+            start:      pos,
+            bytes:      piece.bytes.slice(offset..),
+            attribute:  Arc::clone(&piece.attribute)
+        };
+        piece.bytes.truncate(offset);
+
+        self.pieces.insert(piece_idx+1, new_piece);
+        piece_idx+1
+    }
+
+    ///
+    /// Updates the start positions of the pieces from the specified index onwards
+    ///
+    fn update_positions(&mut self, first_piece_idx: usize) {
+        let mut pos = if first_piece_idx > 0 {
+            let previous = &self.pieces[first_piece_idx-1];
+            previous.start + previous.bytes.len()
+        } else {
+            0
+        };
+
+        for piece in self.pieces[first_piece_idx..].iter_mut() {
+            piece.start = pos;
+            pos         += piece.bytes.len();
+        }
+
+        self.len = pos;
+    }
+
+    ///
+    /// Replaces a range of bytes with a new buffer, which will have the specified attribute (or the attribute of the
+    /// first byte in the range if there is none)
+    ///
+    fn replace_bytes(&mut self, range: Range<usize>, new_bytes: Bytes, attribute: Option<Arc<Attribute>>) {
+        let start       = range.start.min(self.len);
+        let range       = start..range.end.min(self.len).max(start);
+        let attribute   = attribute.unwrap_or_else(|| {
+            let piece_idx = self.piece_index(range.start).min(self.pieces.len().saturating_sub(1));
+
+            self.pieces.get(piece_idx)
+                .map(|piece| Arc::clone(&piece.attribute))
+                .unwrap_or_else(|| Arc::new(Attribute::default()))
+        });
+
+        let first_idx   = self.split_at(range.start);
+        let last_idx    = self.split_at(range.end);
+
+        if new_bytes.is_empty() {
+            self.pieces.drain(first_idx..last_idx);
+        } else {
+            self.pieces.splice(first_idx..last_idx, [BytesPiece { start: range.start, bytes: new_bytes, attribute: attribute }]);
+        }
+
+        self.update_positions(first_idx);
+    }
+}
+
+impl<Attribute> Default for BytesRope<Attribute>
+where
+Attribute: PartialEq+Clone+Default {
+    fn default() -> Self {
+        BytesRope::new()
+    }
+}
+
+impl<Attribute> From<Bytes> for BytesRope<Attribute>
+where
+Attribute: PartialEq+Clone+Default {
+    ///
+    /// Creates a rope that refers to the content of a buffer without copying it
+    ///
+    fn from(bytes: Bytes) -> BytesRope<Attribute> {
+        let mut rope = BytesRope::new();
+        rope.replace_bytes(0..0, bytes, None);
+
+        rope
+    }
+}
+
+impl<Attribute> Rope for BytesRope<Attribute>
+where
+Attribute: PartialEq+Clone+Default {
+    type Cell       = u8;
+    type Attribute  = Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a u8>> {
+        let range = range.start.min(self.len)..range.end.min(self.len);
+
+        Box::new(self.pieces[self.piece_index(range.start)..].iter()
+            .take_while(move |piece| piece.start < range.end)
+            .flat_map(move |piece| {
+                let start   = range.start.max(piece.start) - piece.start;
+                let end     = range.end.min(piece.start + piece.bytes.len()) - piece.start;
+
+                piece.bytes[start..end].iter()
+            }))
+    }
+
+    ///
+    /// Reads the bytes in a range of this rope as a series of slices, one for each buffer the range intersects
+    ///
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [u8]>> {
+        let range = range.start.min(self.len)..range.end.min(self.len);
+
+        Box::new(self.pieces[self.piece_index(range.start)..].iter()
+            .take_while(move |piece| piece.start < range.end)
+            .map(move |piece| {
+                let start   = range.start.max(piece.start) - piece.start;
+                let end     = range.end.min(piece.start + piece.bytes.len()) - piece.start;
+
+                &piece.bytes[start..end]
+            })
+            .filter(|chunk| !chunk.is_empty()))
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Attribute, Range<usize>) {
+        if self.pieces.is_empty() {
+            return (&self.default_attribute, 0..0);
+        }
+
+        // Find the piece at this position, then extend the range over any neighbouring pieces with the same attribute
+        let piece_idx       = self.piece_index(pos).min(self.pieces.len()-1);
+        let attribute       = &self.pieces[piece_idx].attribute;
+        let mut first_idx   = piece_idx;
+        let mut last_idx    = piece_idx;
+
+        while first_idx > 0 && *self.pieces[first_idx-1].attribute == **attribute                   { first_idx -= 1; }
+        while last_idx+1 < self.pieces.len() && *self.pieces[last_idx+1].attribute == **attribute   { last_idx += 1; }
+
+        let start   = self.pieces[first_idx].start;
+        let end     = self.pieces[last_idx].start + self.pieces[last_idx].bytes.len();
+
+        (&**attribute, start..end)
+    }
+}
+
+impl<Attribute> RopeMut for BytesRope<Attribute>
+where
+Attribute: PartialEq+Clone+Default {
+    ///
+    /// Performs the specified editing action to this rope
+    ///
+    fn edit(&mut self, action: RopeAction<u8, Attribute>) {
+        match action {
+            RopeAction::Replace(range, cells)                       => self.replace_bytes(range, Bytes::from(cells), None),
+            RopeAction::ReplaceAttributes(range, cells, attribute)  => self.replace_bytes(range, Bytes::from(cells), Some(Arc::new(attribute))),
+
+            RopeAction::SetAttributes(range, attribute)             => {
+                let range       = range.start.min(self.len)..range.end.min(self.len);
+                if range.start >= range.end { return; }
+
+                let first_idx   = self.split_at(range.start);
+                let last_idx    = self.split_at(range.end);
+                let attribute   = Arc::new(attribute);
+
+                for piece in self.pieces[first_idx..last_idx].iter_mut() {
+                    piece.attribute = Arc::clone(&attribute);
+                }
+            }
+        }
+    }
+}
+
+impl<Attribute> RopeSearch for BytesRope<Attribute>
+where
+Attribute: PartialEq+Clone+Default { }
@@ -0,0 +1,112 @@
+use super::attributed_rope::*;
+
+use crate::api::*;
+
+use std::fmt;
+use std::str;
+use std::ops::{Range};
+
+///
+/// A rope of Unicode characters
+///
+/// Positions in this rope are character indexes rather than byte offsets, so it's not possible to split a multi-byte
+/// character when editing it. This is an ordinary `AttributedRope`, so it works with all of the other rope types and
+/// the `RopeAction` streaming machinery. Use `char_action_to_utf8()` and `utf8_action_to_char()` to mirror edits
+/// between a `CharRope` and a UTF-8 rope.
+///
+pub type CharRope<Attribute> = AttributedRope<char, Attribute>;
+
+impl<'a, Attribute> From<&'a str> for AttributedRope<char, Attribute>
+where
+Attribute: PartialEq+Clone+Default {
+    ///
+    /// Creates a rope containing the characters of a string
+    ///
+    fn from(string: &'a str) -> Self {
+        AttributedRope::from(string.chars())
+    }
+}
+
+impl<Attribute> From<String> for AttributedRope<char, Attribute>
+where
+Attribute: PartialEq+Clone+Default {
+    ///
+    /// Creates a rope containing the characters of a string
+    ///
+    fn from(string: String) -> Self {
+        AttributedRope::from(string.chars())
+    }
+}
+
+impl<'a, Attribute> From<&'a AttributedRope<char, Attribute>> for String
+where
+Attribute: PartialEq+Clone+Default {
+    ///
+    /// Converts a rope of characters to a string
+    ///
+    fn from(rope: &'a AttributedRope<char, Attribute>) -> String {
+        rope.read_cells(0..rope.len()).collect()
+    }
+}
+
+impl<Attribute> fmt::Display for AttributedRope<char, Attribute>
+where
+Attribute: PartialEq+Clone+Default {
+    ///
+    /// Displays the text in this rope
+    ///
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.read_cells(0..self.len()).try_for_each(|chr| fmt::Write::write_char(f, *chr))
+    }
+}
+
+///
+/// Converts a range of character indexes in a rope of characters to a range of UTF-8 byte offsets
+///
+fn char_range_to_utf8<TRope: ?Sized+Rope<Cell=char>>(rope: &TRope, range: &Range<usize>) -> Range<usize> {
+    let start   = rope.read_cells(0..range.start).map(|chr| chr.len_utf8()).sum::<usize>();
+    let len     = rope.read_cells(range.clone()).map(|chr| chr.len_utf8()).sum::<usize>();
+
+    start..(start + len)
+}
+
+///
+/// Converts an action for a rope of characters into the action that makes the same change to a UTF-8 rope
+///
+/// The rope should be in the state before the action is applied.
+///
+pub fn char_action_to_utf8<TRope: ?Sized+Rope<Cell=char>>(rope: &TRope, action: &RopeAction<char, TRope::Attribute>) -> RopeAction<u8, TRope::Attribute> {
+    let encode = |chars: &Vec<char>| chars.iter().collect::<String>().into_bytes();
+
+    match action {
+        RopeAction::Replace(range, chars)                       => RopeAction::Replace(char_range_to_utf8(rope, range), encode(chars)),
+        RopeAction::ReplaceAttributes(range, chars, attribute)  => RopeAction::ReplaceAttributes(char_range_to_utf8(rope, range), encode(chars), attribute.clone()),
+        RopeAction::SetAttributes(range, attribute)             => RopeAction::SetAttributes(char_range_to_utf8(rope, range), attribute.clone())
+    }
+}
+
+///
+/// Converts an action for a UTF-8 rope into the action that makes the same change to a rope of characters, or `None`
+/// if the action would split a character or inserts bytes that are not valid UTF-8
+///
+/// The rope should be in the state before the action is applied.
+///
+pub fn utf8_action_to_char<TRope: ?Sized+Rope<Cell=u8>>(rope: &TRope, action: &RopeAction<u8, TRope::Attribute>) -> Option<RopeAction<char, TRope::Attribute>> {
+    let decode      = |bytes: &Vec<u8>| str::from_utf8(bytes).ok().map(|text| text.chars().collect::<Vec<_>>());
+    let to_chars    = |range: &Range<usize>| {
+        // Positions at the start of a continuation byte are within a character
+        let is_boundary = |pos: usize| rope.read_cells(pos..(pos+1)).next().map(|byte| (byte & 0xc0) != 0x80).unwrap_or(true);
+        if !is_boundary(range.start) || !is_boundary(range.end) { return None; }
+
+        let start   = rope.read_cells(0..range.start).filter(|byte| (*byte & 0xc0) != 0x80).count();
+        let len     = rope.read_cells(range.clone()).filter(|byte| (*byte & 0xc0) != 0x80).count();
+
+        Some(start..(start + len))
+    };
+
+    match action {
+        RopeAction::Replace(range, bytes)                       => Some(RopeAction::Replace(to_chars(range)?, decode(bytes)?)),
+        RopeAction::ReplaceAttributes(range, bytes, attribute)  => Some(RopeAction::ReplaceAttributes(to_chars(range)?, decode(bytes)?, attribute.clone())),
+        RopeAction::SetAttributes(range, attribute)             => Some(RopeAction::SetAttributes(to_chars(range)?, attribute.clone()))
+    }
+}
@@ -0,0 +1,154 @@
+use crate::api::*;
+
+use std::ops::{Range};
+
+///
+/// A combining rope wraps another rope and changes how `set_attributes` works: instead of replacing the attributes
+/// in the range, the new attribute is combined with each of the existing attribute runs using a function.
+///
+/// This is useful for attributes that are sets or maps of properties. For example, if the attribute is a set of styles,
+/// the combining function can add the styles from the new attribute to the existing styles, so that applying 'bold' to
+/// a range keeps any other styles that were already present in each part of the range.
+///
+/// `replace_attributes` still sets the attributes of the new cells directly, as there are no existing attributes to
+/// combine them with.
+///
+#[derive(Clone)]
+pub struct CombiningRope<BaseRope, CombineFn> 
+where
+BaseRope:   RopeMut,
+CombineFn:  Fn(&BaseRope::Attribute, &BaseRope::Attribute) -> BaseRope::Attribute {
+    /// The rope that attributes will be combined in
+    rope: BaseRope,
+
+    /// Function that combines an existing attribute (first parameter) with a new attribute (second parameter)
+    combine_fn: CombineFn
+}
+
+impl<BaseRope, CombineFn> CombiningRope<BaseRope, CombineFn>
+where
+BaseRope:   RopeMut,
+CombineFn:  Fn(&BaseRope::Attribute, &BaseRope::Attribute) -> BaseRope::Attribute {
+    ///
+    /// Creates a new combining rope from a base rope and a function that combines an existing attribute (first
+    /// parameter) with a new attribute (second parameter)
+    ///
+    pub fn from(rope: BaseRope, combine_fn: CombineFn) -> CombiningRope<BaseRope, CombineFn> {
+        CombiningRope {
+            rope:       rope,
+            combine_fn: combine_fn
+        }
+    }
+}
+
+impl<BaseRope, CombineFn> Rope for CombiningRope<BaseRope, CombineFn>
+where
+BaseRope:   RopeMut,
+CombineFn:  Fn(&BaseRope::Attribute, &BaseRope::Attribute) -> BaseRope::Attribute {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.rope.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        self.rope.read_cells(range)
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.read_chunks(range)
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.rope.read_attributes(pos)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.read_attribute_runs(range)
+    }
+}
+
+impl<BaseRope, CombineFn> RopeMut for CombiningRope<BaseRope, CombineFn>
+where
+BaseRope:   RopeMut,
+CombineFn:  Fn(&BaseRope::Attribute, &BaseRope::Attribute) -> BaseRope::Attribute {
+    ///
+    /// Performs the specified editing action to this rope
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        match action {
+            RopeAction::SetAttributes(range, new_attributes)    => self.set_attributes(range, new_attributes),
+            action                                              => self.rope.edit(action)
+        }
+    }
+
+    ///
+    /// Replaces a range of cells. The attributes applied to the new cells will be the same
+    /// as the attributes that were applied to the first cell in the replacement range
+    ///
+    #[inline]
+    fn replace<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells) {
+        self.rope.replace(range, new_cells);
+    }
+
+    ///
+    /// Combines the attributes for a range of cells with the existing attributes
+    ///
+    fn set_attributes(&mut self, range: Range<usize>, new_attributes: Self::Attribute) {
+        // Work out the new attributes for each of the existing runs
+        let combined_runs = self.rope.read_attribute_runs(range)
+            .map(|(existing, run_range)| (run_range, (self.combine_fn)(existing, &new_attributes)))
+            .collect::<Vec<_>>();
+
+        // Write them back to the rope
+        for (run_range, combined) in combined_runs {
+            self.rope.set_attributes(run_range, combined);
+        }
+    }
+
+    ///
+    /// Resets the attributes for a range of cells to the default attribute (without combining them with the existing attributes)
+    ///
+    #[inline]
+    fn clear_attributes(&mut self, range: Range<usize>) {
+        self.rope.clear_attributes(range);
+    }
+
+    ///
+    /// Replaces a range of cells and sets the attributes for them.
+    ///
+    #[inline]
+    fn replace_attributes<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells, new_attributes: Self::Attribute) {
+        self.rope.replace_attributes(range, new_cells, new_attributes);
+    }
+}
+
+impl<BaseRope, CombineFn> RopeSearch for CombiningRope<BaseRope, CombineFn>
+where
+BaseRope:       RopeMut,
+CombineFn:      Fn(&BaseRope::Attribute, &BaseRope::Attribute) -> BaseRope::Attribute,
+BaseRope::Cell: PartialEq { }
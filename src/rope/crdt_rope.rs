@@ -0,0 +1,479 @@
+use crate::api::*;
+
+use std::mem;
+use std::ops::{Range};
+
+///
+/// A stable identifier for a cell (or attribute change) in a `CrdtRope`
+///
+/// Identifiers are ordered by their counter (a Lamport timestamp) and then by the replica that created them.
+///
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct CrdtId {
+    /// The Lamport timestamp when this ID was created
+    pub counter: u64,
+
+    /// The replica that created this ID
+    pub replica: u64
+}
+
+///
+/// An operation that can be exchanged between the replicas of a `CrdtRope`
+///
+#[derive(Clone, PartialEq, Debug)]
+pub enum CrdtOperation<Cell, Attribute> {
+    /// Inserts a run of cells after the cell with the specified ID (or at the start of the rope). The cells have
+    /// consecutive IDs starting at the specified ID.
+    Insert { id: CrdtId, after: Option<CrdtId>, cells: Vec<Cell>, attribute: Attribute },
+
+    /// Deletes the cells with the specified IDs
+    Delete { ids: Vec<CrdtId> },
+
+    /// Sets the attributes of the cells with the specified IDs. The attribute with the latest timestamp wins if
+    /// there are concurrent changes.
+    SetAttributes { ids: Vec<CrdtId>, attribute: Attribute, timestamp: CrdtId }
+}
+
+///
+/// A cell in a CRDT rope (deleted cells are kept as tombstones so that operations from other replicas can refer to them)
+///
+/// The values and attributes of the cells that are not deleted are stored in the base rope.
+///
+#[derive(Clone)]
+struct CrdtElement {
+    /// The ID of this cell
+    id: CrdtId,
+
+    /// The timestamp of the operation that set the attribute for this cell
+    attribute_timestamp: CrdtId,
+
+    /// True if this cell has been deleted
+    deleted: bool
+}
+
+///
+/// A CRDT rope is a rope that can be edited concurrently by several replicas, which exchange operations and
+/// converge on the same content without needing any central coordination
+///
+/// Every cell is given a stable identifier, and edits are described by `CrdtOperation`s referring to these
+/// identifiers (using the RGA algorithm for insertions). Local edits generate operations that can be retrieved
+/// with `take_operations()` and sent to the other replicas, which apply them with `apply_operation()`. Operations
+/// can arrive in any order and more than once.
+///
+/// The visible cells are stored in a base rope, so a `PullRope` or one of the push ropes can be used as the base
+/// to stream the changes made by both local and remote edits.
+///
+pub struct CrdtRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// The ID of this replica (must be unique amongst the replicas that are editing the rope, and not 0)
+    replica: u64,
+
+    /// The Lamport clock for this replica
+    clock: u64,
+
+    /// The rope containing the visible cells
+    rope: BaseRope,
+
+    /// The cells in the rope, including the deleted cells
+    elements: Vec<CrdtElement>,
+
+    /// Operations generated by local edits that have not been retrieved yet
+    outgoing: Vec<CrdtOperation<BaseRope::Cell, BaseRope::Attribute>>,
+
+    /// Remote operations that are waiting for the cells they depend on to arrive
+    waiting: Vec<CrdtOperation<BaseRope::Cell, BaseRope::Attribute>>
+}
+
+impl<BaseRope> CrdtRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Creates a new CRDT rope for a replica
+    ///
+    /// Any cells already in the base rope are given IDs belonging to replica 0, so replicas that are created from
+    /// ropes with identical content can exchange operations with each other.
+    ///
+    pub fn new(replica: u64, rope: BaseRope) -> CrdtRope<BaseRope> {
+        let initial_timestamp   = CrdtId { counter: 0, replica: 0 };
+        let mut elements        = vec![];
+
+        for idx in 0..rope.len() {
+            elements.push(CrdtElement {
+                id:                     CrdtId { counter: (idx + 1) as u64, replica: 0 },
+                attribute_timestamp:    initial_timestamp,
+                deleted:                false
+            });
+        }
+
+        CrdtRope {
+            replica:    replica,
+            clock:      elements.len() as u64,
+            rope:       rope,
+            elements:   elements,
+            outgoing:   vec![],
+            waiting:    vec![]
+        }
+    }
+
+    ///
+    /// Returns the ID of this replica
+    ///
+    pub fn replica(&self) -> u64 {
+        self.replica
+    }
+
+    ///
+    /// Retrieves the operations generated by local edits since the last call, which should be sent to the other replicas
+    ///
+    pub fn take_operations(&mut self) -> Vec<CrdtOperation<BaseRope::Cell, BaseRope::Attribute>> {
+        mem::take(&mut self.outgoing)
+    }
+
+    ///
+    /// True if there are remote operations that can't be applied until other operations arrive
+    ///
+    pub fn has_waiting_operations(&self) -> bool {
+        self.waiting.len() > 0
+    }
+
+    ///
+    /// Returns the ID of the visible cell at the specified position
+    ///
+    pub fn id_at(&self, pos: usize) -> Option<CrdtId> {
+        self.elements.iter()
+            .filter(|element| !element.deleted)
+            .nth(pos)
+            .map(|element| element.id)
+    }
+
+    ///
+    /// Generates a new ID (or the first of a run of IDs) for an operation created by this replica
+    ///
+    fn next_id(&mut self, count: u64) -> CrdtId {
+        let id      = CrdtId { counter: self.clock + 1, replica: self.replica };
+        self.clock  += count.max(1);
+
+        id
+    }
+
+    ///
+    /// Updates the clock after seeing an ID from another replica
+    ///
+    fn observe_id(&mut self, id: CrdtId, count: u64) {
+        self.clock = self.clock.max(id.counter + count.max(1) - 1);
+    }
+
+    ///
+    /// Finds the index of an element by ID
+    ///
+    fn element_index(&self, id: CrdtId) -> Option<usize> {
+        self.elements.iter().position(|element| element.id == id)
+    }
+
+    ///
+    /// Returns the visible position of the element at the specified index
+    ///
+    fn visible_position(&self, element_idx: usize) -> usize {
+        self.elements[0..element_idx].iter().filter(|element| !element.deleted).count()
+    }
+
+    ///
+    /// Returns the indexes of the elements for a range of visible cells
+    ///
+    fn element_indexes(&self, range: Range<usize>) -> Vec<usize> {
+        self.elements.iter()
+            .enumerate()
+            .filter(|(_, element)| !element.deleted)
+            .skip(range.start)
+            .take(range.len())
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    ///
+    /// Inserts an element into the list using the RGA rules, returning its index (or None if the element it follows is not known yet)
+    ///
+    fn integrate(&mut self, element: CrdtElement, after: Option<CrdtId>) -> Option<usize> {
+        // Find the element that this should follow
+        let mut idx = match after {
+            None        => 0,
+            Some(after) => self.element_index(after)? + 1
+        };
+
+        // Skip any elements that were inserted at the same point with a later ID
+        while idx < self.elements.len() && self.elements[idx].id > element.id {
+            idx += 1;
+        }
+
+        self.elements.insert(idx, element);
+
+        Some(idx)
+    }
+
+    ///
+    /// Inserts a run of cells, returning false if the cell they follow is not known yet. Cells that are already in the rope are ignored.
+    ///
+    fn insert_cells(&mut self, id: CrdtId, after: Option<CrdtId>, cells: Vec<BaseRope::Cell>, attribute: BaseRope::Attribute, timestamp: CrdtId) -> bool {
+        if let Some(after) = after {
+            if self.element_index(after).is_none() { return false; }
+        }
+
+        let mut after = after;
+
+        for (offset, cell) in cells.into_iter().enumerate() {
+            let cell_id = CrdtId { counter: id.counter + offset as u64, replica: id.replica };
+
+            if self.element_index(cell_id).is_none() {
+                let element = CrdtElement {
+                    id:                     cell_id,
+                    attribute_timestamp:    timestamp,
+                    deleted:                false
+                };
+
+                if let Some(idx) = self.integrate(element, after) {
+                    // Update the visible rope
+                    let pos = self.visible_position(idx);
+                    self.rope.replace_attributes(pos..pos, vec![cell], attribute.clone());
+                }
+            }
+
+            after = Some(cell_id);
+        }
+
+        true
+    }
+
+    ///
+    /// Deletes the cells with the specified IDs, returning the IDs that are not known yet
+    ///
+    fn delete_cells(&mut self, ids: Vec<CrdtId>) -> Vec<CrdtId> {
+        let mut unknown = vec![];
+
+        for id in ids {
+            match self.element_index(id) {
+                None        => unknown.push(id),
+                Some(idx)   => {
+                    if !self.elements[idx].deleted {
+                        let pos = self.visible_position(idx);
+
+                        self.elements[idx].deleted = true;
+                        self.rope.replace(pos..(pos+1), vec![]);
+                    }
+                }
+            }
+        }
+
+        unknown
+    }
+
+    ///
+    /// Sets the attributes of the cells with the specified IDs, returning the IDs that are not known yet
+    ///
+    fn set_cell_attributes(&mut self, ids: Vec<CrdtId>, attribute: BaseRope::Attribute, timestamp: CrdtId) -> Vec<CrdtId> {
+        let mut unknown = vec![];
+
+        for id in ids {
+            match self.element_index(id) {
+                None        => unknown.push(id),
+                Some(idx)   => {
+                    if self.elements[idx].attribute_timestamp < timestamp {
+                        self.elements[idx].attribute_timestamp    = timestamp;
+
+                        if !self.elements[idx].deleted {
+                            let pos = self.visible_position(idx);
+                            self.rope.set_attributes(pos..(pos+1), attribute.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        unknown
+    }
+
+    ///
+    /// Attempts to apply a remote operation, returning the part of it that could not be applied yet
+    ///
+    fn try_apply(&mut self, operation: CrdtOperation<BaseRope::Cell, BaseRope::Attribute>) -> Option<CrdtOperation<BaseRope::Cell, BaseRope::Attribute>> {
+        match operation {
+            CrdtOperation::Insert { id, after, cells, attribute } => {
+                self.observe_id(id, cells.len() as u64);
+
+                if self.insert_cells(id, after, cells.clone(), attribute.clone(), id) {
+                    None
+                } else {
+                    Some(CrdtOperation::Insert { id, after, cells, attribute })
+                }
+            }
+
+            CrdtOperation::Delete { ids } => {
+                let unknown = self.delete_cells(ids);
+
+                if unknown.len() == 0 { None } else { Some(CrdtOperation::Delete { ids: unknown }) }
+            }
+
+            CrdtOperation::SetAttributes { ids, attribute, timestamp } => {
+                self.observe_id(timestamp, 1);
+                let unknown = self.set_cell_attributes(ids, attribute.clone(), timestamp);
+
+                if unknown.len() == 0 { None } else { Some(CrdtOperation::SetAttributes { ids: unknown, attribute, timestamp }) }
+            }
+        }
+    }
+
+    ///
+    /// Applies an operation generated by another replica to this rope
+    ///
+    /// Operations that refer to cells that haven't arrived yet are kept until the operations that create those cells have been applied.
+    ///
+    pub fn apply_operation(&mut self, operation: CrdtOperation<BaseRope::Cell, BaseRope::Attribute>) {
+        match self.try_apply(operation) {
+            Some(remaining) => { self.waiting.push(remaining); }
+            None            => {
+                // Retry the waiting operations until no more progress can be made
+                loop {
+                    let waiting         = mem::take(&mut self.waiting);
+                    let num_waiting     = waiting.len();
+
+                    for operation in waiting {
+                        if let Some(remaining) = self.try_apply(operation) {
+                            self.waiting.push(remaining);
+                        }
+                    }
+
+                    if self.waiting.len() == num_waiting { break; }
+                }
+            }
+        }
+    }
+
+    ///
+    /// Replaces a range of cells locally, generating the operations to send to the other replicas
+    ///
+    fn local_replace(&mut self, range: Range<usize>, new_cells: Vec<BaseRope::Cell>, attribute: Option<BaseRope::Attribute>) {
+        let len         = self.rope.len();
+        let range       = range.start.min(len)..range.end.min(len).max(range.start.min(len));
+        let num_cells   = new_cells.len();
+
+        // Apply to the base rope, which decides the attributes for the new cells if none are specified
+        match attribute {
+            Some(attribute) => self.rope.replace_attributes(range.clone(), new_cells.clone(), attribute),
+            None            => self.rope.replace(range.clone(), new_cells.clone())
+        }
+
+        // Delete the elements in the range
+        let deleted_indexes = self.element_indexes(range.clone());
+        let deleted_ids     = deleted_indexes.iter().map(|idx| self.elements[*idx].id).collect::<Vec<_>>();
+        deleted_indexes.iter().for_each(|idx| self.elements[*idx].deleted = true);
+
+        if deleted_ids.len() > 0 {
+            self.outgoing.push(CrdtOperation::Delete { ids: deleted_ids });
+        }
+
+        // Insert the new cells after the visible cell before the range
+        if num_cells > 0 {
+            let after       = if range.start == 0 { None } else { self.id_at(range.start-1) };
+            let attribute   = self.rope.read_attributes(range.start).0.clone();
+            let id          = self.next_id(num_cells as u64);
+            let pos         = after.and_then(|after| self.element_index(after)).map(|idx| idx+1).unwrap_or(0);
+
+            // Our IDs are later than any we've seen, so the cells go immediately after the preceding cell
+            self.elements.splice(pos..pos, (0..num_cells).map(|offset| CrdtElement {
+                id:                     CrdtId { counter: id.counter + offset as u64, replica: id.replica },
+                attribute_timestamp:    id,
+                deleted:                false
+            }));
+
+            self.outgoing.push(CrdtOperation::Insert { id, after, cells: new_cells, attribute });
+        }
+    }
+
+    ///
+    /// Sets the attributes for a range of cells locally, generating the operation to send to the other replicas
+    ///
+    fn local_set_attributes(&mut self, range: Range<usize>, attribute: BaseRope::Attribute) {
+        let indexes     = self.element_indexes(range.clone());
+        if indexes.len() == 0 { return; }
+
+        let timestamp   = self.next_id(1);
+        let ids         = indexes.iter().map(|idx| self.elements[*idx].id).collect::<Vec<_>>();
+
+        for idx in indexes {
+            self.elements[idx].attribute_timestamp  = timestamp;
+        }
+
+        self.rope.set_attributes(range, attribute.clone());
+        self.outgoing.push(CrdtOperation::SetAttributes { ids, attribute, timestamp });
+    }
+}
+
+impl<BaseRope> Rope for CrdtRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.rope.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        self.rope.read_cells(range)
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.read_chunks(range)
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.rope.read_attributes(pos)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.read_attribute_runs(range)
+    }
+}
+
+impl<BaseRope> RopeMut for CrdtRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Performs the specified editing action to this rope, generating the operations to send to the other replicas
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        match action {
+            RopeAction::Replace(range, cells)                       => self.local_replace(range, cells, None),
+            RopeAction::SetAttributes(range, attribute)             => self.local_set_attributes(range, attribute),
+            RopeAction::ReplaceAttributes(range, cells, attribute)  => self.local_replace(range, cells, Some(attribute))
+        }
+    }
+}
+
+impl<BaseRope> RopeSearch for CrdtRope<BaseRope>
+where
+BaseRope:       RopeMut,
+BaseRope::Cell: PartialEq { }
@@ -0,0 +1,138 @@
+use super::attributed_rope::*;
+use super::attributed_rope_iterator::*;
+
+use crate::api::*;
+
+use std::fmt;
+use std::sync::*;
+use std::ops::{Range};
+
+///
+/// A frozen rope is an immutable snapshot of an `AttributedRope`
+///
+/// Frozen ropes are cheap to create and to clone, as they share their cells with the rope they were created from.
+/// They can be sent to other threads (provided the cells and attributes can be), which makes them useful for
+/// performing background processing on a consistent version of a rope while the original continues to be edited.
+///
+#[derive(Clone)]
+pub struct FrozenRope<Cell, Attribute> {
+    /// The rope that this is a snapshot of
+    rope: Arc<AttributedRope<Cell, Attribute>>
+}
+
+impl<Cell, Attribute> FrozenRope<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Creates a new attributed rope from this snapshot, which can be edited
+    ///
+    pub fn thaw(&self) -> AttributedRope<Cell, Attribute> {
+        (*self.rope).clone()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    pub fn read_cells<'a>(&'a self, range: Range<usize>) -> AttributedRopeIterator<'a, Cell, Attribute> {
+        self.rope.read_cells(range)
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, one for each leaf node the range intersects
+    ///
+    pub fn read_chunks<'a>(&'a self, range: Range<usize>) -> AttributedRopeChunkIterator<'a, Cell, Attribute> {
+        self.rope.read_chunks(range)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope, in order
+    ///
+    pub fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> AttributedRopeRunIterator<'a, Cell, Attribute> {
+        self.rope.read_attribute_runs(range)
+    }
+}
+
+impl<Cell, Attribute> AttributedRope<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Creates an immutable snapshot of the current state of this rope
+    ///
+    pub fn freeze(&self) -> FrozenRope<Cell, Attribute> {
+        FrozenRope {
+            rope: Arc::new(self.clone())
+        }
+    }
+}
+
+impl<Cell, Attribute> From<AttributedRope<Cell, Attribute>> for FrozenRope<Cell, Attribute> {
+    fn from(rope: AttributedRope<Cell, Attribute>) -> FrozenRope<Cell, Attribute> {
+        FrozenRope {
+            rope: Arc::new(rope)
+        }
+    }
+}
+
+impl<Cell, Attribute> Rope for FrozenRope<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    type Cell       = Cell;
+    type Attribute  = Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.rope.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        Box::new(self.rope.read_cells(range))
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, one for each leaf node the range intersects
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        Rope::read_chunks(&*self.rope, range)
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.rope.read_attributes(pos)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        Box::new(self.rope.read_attribute_runs(range))
+    }
+}
+
+impl<Cell, Attribute> fmt::Debug for FrozenRope<Cell, Attribute>
+where
+Cell:       Clone+fmt::Debug,
+Attribute:  PartialEq+Clone+Default+fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.rope.fmt(f)
+    }
+}
+
+impl<Cell, Attribute> RopeSearch for FrozenRope<Cell, Attribute>
+where
+Cell:       Clone+PartialEq,
+Attribute:  PartialEq+Clone+Default { }
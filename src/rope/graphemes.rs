@@ -0,0 +1,277 @@
+use crate::api::*;
+
+use unicode_segmentation::{GraphemeCursor, GraphemeIncomplete};
+
+use std::str;
+use std::ops::{Range};
+
+/// The number of cells to read on either side of a position when first looking for a grapheme boundary
+const INITIAL_WINDOW: usize = 32;
+
+///
+/// A cell type that can be decoded as text, so that grapheme clusters can be found in a rope (available with the
+/// `unicode` feature)
+///
+/// This is implemented for `u8` (UTF-8 text, where invalid bytes are treated as replacement characters) and `char`.
+///
+pub trait GraphemeCell : Clone {
+    ///
+    /// True if this cell is the first cell of a character
+    ///
+    fn is_char_start(&self) -> bool;
+
+    ///
+    /// Decodes a set of cells (starting at a character) and appends them to a string, along with the offset in the string
+    /// and the position in the rope of the start of each character
+    ///
+    fn decode(cells: &[Self], first_pos: usize, text: &mut String, char_starts: &mut Vec<(usize, usize)>);
+}
+
+impl GraphemeCell for char {
+    #[inline]
+    fn is_char_start(&self) -> bool {
+        true
+    }
+
+    fn decode(cells: &[char], first_pos: usize, text: &mut String, char_starts: &mut Vec<(usize, usize)>) {
+        for (idx, chr) in cells.iter().enumerate() {
+            char_starts.push((text.len(), first_pos + idx));
+            text.push(*chr);
+        }
+    }
+}
+
+impl GraphemeCell for u8 {
+    #[inline]
+    fn is_char_start(&self) -> bool {
+        (*self & 0xc0) != 0x80
+    }
+
+    fn decode(cells: &[u8], first_pos: usize, text: &mut String, char_starts: &mut Vec<(usize, usize)>) {
+        let mut remaining   = cells;
+        let mut pos         = first_pos;
+
+        while !remaining.is_empty() {
+            let (valid, invalid_len) = match str::from_utf8(remaining) {
+                Ok(valid)   => (valid, 0),
+                Err(err)    => (str::from_utf8(&remaining[0..err.valid_up_to()]).unwrap(), err.error_len().unwrap_or(remaining.len() - err.valid_up_to()))
+            };
+
+            for (offset, chr) in valid.char_indices() {
+                char_starts.push((text.len(), pos + offset));
+                text.push(chr);
+            }
+
+            // Each invalid byte is treated as a separate replacement character
+            pos += valid.len();
+
+            for _ in 0..invalid_len {
+                char_starts.push((text.len(), pos));
+                text.push(char::REPLACEMENT_CHARACTER);
+                pos += 1;
+            }
+
+            remaining = &remaining[(valid.len() + invalid_len)..];
+        }
+    }
+}
+
+///
+/// A section of a rope decoded as a string
+///
+struct TextWindow {
+    /// The position in the rope of the first cell in the window
+    start: usize,
+
+    /// The position in the rope after the last cell in the window
+    end: usize,
+
+    /// The decoded text
+    text: String,
+
+    /// The offset in the text and position in the rope of the start of each character (followed by the end of the window)
+    char_starts: Vec<(usize, usize)>
+}
+
+impl TextWindow {
+    ///
+    /// Decodes a range of a rope, extending it so that it starts and ends at the start of a character
+    ///
+    fn read<R: ?Sized+Rope>(rope: &R, range: Range<usize>) -> TextWindow
+    where
+    R::Cell: GraphemeCell {
+        let len         = rope.len();
+        let is_start    = |pos: usize| rope.read_cells(pos..(pos+1)).next().map(|cell| cell.is_char_start()).unwrap_or(true);
+        let mut start   = range.start.min(len);
+        let mut end     = range.end.min(len);
+
+        while start > 0 && !is_start(start)     { start -= 1; }
+        while end < len && !is_start(end)       { end += 1; }
+
+        let cells           = rope.read_cells(start..end).cloned().collect::<Vec<_>>();
+        let mut text        = String::new();
+        let mut char_starts = vec![];
+
+        R::Cell::decode(&cells, start, &mut text, &mut char_starts);
+        char_starts.push((text.len(), end));
+
+        TextWindow { start, end, text, char_starts }
+    }
+
+    ///
+    /// Returns the offset in the text of the character containing a position in the rope, and whether or not the
+    /// position is at the start of that character
+    ///
+    fn offset_of(&self, pos: usize) -> (usize, bool) {
+        match self.char_starts.binary_search_by_key(&pos, |(_, char_pos)| *char_pos) {
+            Ok(idx)     => (self.char_starts[idx].0, true),
+            Err(idx)    => (self.char_starts[idx.saturating_sub(1)].0, false)
+        }
+    }
+
+    ///
+    /// Returns the position in the rope of a character boundary in the text
+    ///
+    fn pos_of(&self, offset: usize) -> usize {
+        match self.char_starts.binary_search_by_key(&offset, |(char_offset, _)| *char_offset) {
+            Ok(idx)     => self.char_starts[idx].1,
+            Err(idx)    => self.char_starts[idx.saturating_sub(1)].1
+        }
+    }
+}
+
+///
+/// Runs a query against a grapheme cursor at a position in a rope, reading more of the rope until the cursor has enough
+/// context to answer it
+///
+/// The query is passed the cursor, the window of text and the offset of the start of the window in the cursor's
+/// coordinates (which is 1 if there is text before the window, so the cursor knows to ask for it).
+///
+fn grapheme_query<R, T, QueryFn>(rope: &R, pos: usize, mut query: QueryFn) -> T
+where
+R:          ?Sized+Rope,
+R::Cell:    GraphemeCell,
+QueryFn:    FnMut(&mut GraphemeCursor, &TextWindow, usize) -> Result<T, GraphemeIncomplete> {
+    let len         = rope.len();
+    let mut before  = INITIAL_WINDOW;
+    let mut after   = INITIAL_WINDOW;
+
+    loop {
+        let window          = TextWindow::read(rope, pos.saturating_sub(before)..(pos + after));
+        let context         = if window.start > 0 { 1 } else { 0 };
+        let virtual_len     = context + window.text.len() + if window.end < len { 1 } else { 0 };
+        let (offset, _)     = window.offset_of(pos);
+        let mut cursor      = GraphemeCursor::new(context + offset, virtual_len, true);
+
+        match query(&mut cursor, &window, context) {
+            Ok(result)                                                  => { return result; }
+            Err(GraphemeIncomplete::PreContext(_))
+                | Err(GraphemeIncomplete::PrevChunk)                    => { before *= 2; }
+            Err(GraphemeIncomplete::NextChunk)                          => { after *= 2; }
+            Err(GraphemeIncomplete::InvalidOffset)                      => { panic!("Grapheme cursor offset is not at a character boundary"); }
+        }
+    }
+}
+
+///
+/// Finds the boundaries between the grapheme clusters (user-perceived characters) in a rope of text (available with
+/// the `unicode` feature)
+///
+/// Editors should use these to move the cursor and delete characters, so that emoji and characters with combining
+/// marks are treated as a single character. Only the text around the requested position is decoded.
+///
+pub trait RopeGraphemes : Rope
+where
+Self::Cell: GraphemeCell {
+    ///
+    /// True if the specified position is at the boundary between two grapheme clusters (the start and end of the rope
+    /// are always boundaries)
+    ///
+    fn is_grapheme_boundary(&self, pos: usize) -> bool {
+        if pos == 0 || pos >= self.len() { return true; }
+
+        grapheme_query(self, pos, |cursor, window, context| {
+            let (_, is_char_start) = window.offset_of(pos);
+            if !is_char_start { return Ok(false); }
+
+            cursor.is_boundary(&window.text, context)
+        })
+    }
+
+    ///
+    /// Returns the first grapheme boundary after the specified position, or `None` if the position is at the end of the rope
+    ///
+    fn next_grapheme_boundary(&self, pos: usize) -> Option<usize> {
+        if pos >= self.len() { return None; }
+
+        grapheme_query(self, pos, |cursor, window, context| {
+            Ok(cursor.next_boundary(&window.text, context)?
+                .map(|offset| window.pos_of(offset - context)))
+        })
+    }
+
+    ///
+    /// Returns the last grapheme boundary before the specified position, or `None` if the position is at the start of the rope
+    ///
+    fn prev_grapheme_boundary(&self, pos: usize) -> Option<usize> {
+        if pos == 0 { return None; }
+        let pos = pos.min(self.len());
+
+        grapheme_query(self, pos, |cursor, window, context| {
+            let (_, is_char_start) = window.offset_of(pos);
+
+            // Positions within a character start from the end of the character, so that the character itself is included
+            if !is_char_start { cursor.set_cursor(context + window.offset_of(pos).0); cursor.next_boundary(&window.text, context)?; }
+
+            Ok(cursor.prev_boundary(&window.text, context)?
+                .map(|offset| window.pos_of(offset - context)))
+        })
+    }
+
+    ///
+    /// Returns the ranges of the grapheme clusters in a range of the rope
+    ///
+    fn grapheme_ranges<'a>(&'a self, range: Range<usize>) -> RopeGraphemeRanges<'a, Self> {
+        RopeGraphemeRanges {
+            rope:   self,
+            pos:    range.start,
+            end:    range.end.min(self.len())
+        }
+    }
+}
+
+impl<R> RopeGraphemes for R
+where
+R:          ?Sized+Rope,
+R::Cell:    GraphemeCell { }
+
+///
+/// Iterator over the ranges of the grapheme clusters in a rope, returned by `RopeGraphemes::grapheme_ranges()`
+///
+pub struct RopeGraphemeRanges<'a, R: ?Sized> {
+    /// The rope containing the text
+    rope: &'a R,
+
+    /// The start of the next grapheme cluster
+    pos: usize,
+
+    /// The end of the range being iterated over
+    end: usize
+}
+
+impl<'a, R> Iterator for RopeGraphemeRanges<'a, R>
+where
+R:          ?Sized+Rope,
+R::Cell:    GraphemeCell {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        if self.pos >= self.end { return None; }
+
+        let start   = self.pos;
+        let end     = self.rope.next_grapheme_boundary(start).unwrap_or(self.end).min(self.end);
+        self.pos    = end;
+
+        Some(start..end)
+    }
+}
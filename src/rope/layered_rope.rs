@@ -0,0 +1,216 @@
+use super::attributed_rope::*;
+
+use crate::api::*;
+
+use std::iter;
+use std::hash::{Hash};
+use std::ops::{Range};
+use std::collections::{HashMap};
+
+///
+/// A layered rope stores a set of cells along with any number of independent attribute layers.
+///
+/// Each layer is identified by a key, and has its own set of attributes that can be set or read without
+/// affecting the other layers. For example, a text editor might store syntax colouring in one layer,
+/// diagnostics in another and the selection in a third.
+///
+/// Editing the cells in the rope updates every layer: as for `AttributedRope`, cells that are inserted
+/// take on the attributes of the first cell in the range they replace, separately for each layer.
+///
+#[derive(Clone)]
+pub struct LayeredRope<Cell, Layer, Attribute> 
+where
+Layer: Hash+Eq {
+    /// The cells in this rope
+    cells: AttributedRope<Cell, ()>,
+
+    /// The attributes for each layer (each layer has the same length as the cells rope)
+    layers: HashMap<Layer, AttributedRope<(), Attribute>>
+}
+
+impl<Cell, Layer, Attribute> LayeredRope<Cell, Layer, Attribute>
+where
+Cell:       Clone,
+Layer:      Hash+Eq,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Creates a new, empty layered rope
+    ///
+    pub fn new() -> LayeredRope<Cell, Layer, Attribute> {
+        LayeredRope {
+            cells:  AttributedRope::new(),
+            layers: HashMap::new()
+        }
+    }
+
+    ///
+    /// Creates a new layered rope containing the specified cells and no layers
+    ///
+    pub fn from<NewCells: IntoIterator<Item=Cell>>(cells: NewCells) -> LayeredRope<Cell, Layer, Attribute> {
+        LayeredRope {
+            cells:  AttributedRope::from(cells),
+            layers: HashMap::new()
+        }
+    }
+
+    ///
+    /// Returns the keys of the layers that have been created in this rope
+    ///
+    pub fn layers<'a>(&'a self) -> impl 'a+Iterator<Item=&'a Layer> {
+        self.layers.keys()
+    }
+
+    ///
+    /// Removes a layer and all of its attributes from this rope
+    ///
+    /// The layer is deleted rather than reset, so `read_layer_attributes()` will return `None` for it until its
+    /// attributes are set again.
+    ///
+    pub fn remove_layer(&mut self, layer: &Layer) {
+        self.layers.remove(layer);
+    }
+
+    ///
+    /// Returns the attributes set at the specified location in a layer and their extent
+    ///
+    /// If the layer has no attributes set, this returns `None`
+    ///
+    pub fn read_layer_attributes<'a>(&'a self, layer: &Layer, pos: usize) -> Option<(&'a Attribute, Range<usize>)> {
+        self.layers.get(layer)
+            .map(|layer| layer.read_attributes(pos))
+    }
+
+    ///
+    /// Reads the attribute runs in a layer that intersect a range of this rope
+    ///
+    /// If the layer has no attributes set, this returns an empty iterator
+    ///
+    pub fn read_layer_attribute_runs<'a>(&'a self, layer: &Layer, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Attribute, Range<usize>)>> {
+        match self.layers.get(layer) {
+            Some(layer) => Box::new(layer.read_attribute_runs(range)),
+            None        => Box::new(iter::empty())
+        }
+    }
+
+    ///
+    /// Sets the attributes for a range of cells in a layer, creating the layer if it doesn't already exist
+    ///
+    pub fn set_layer_attributes(&mut self, layer: Layer, range: Range<usize>, new_attributes: Attribute) {
+        let len = self.cells.len();
+
+        self.layers.entry(layer)
+            .or_insert_with(|| AttributedRope::from(iter::repeat_n((), len)))
+            .set_attributes(range, new_attributes);
+    }
+
+    ///
+    /// Resets the attributes for a range of cells in a layer to the default value
+    ///
+    pub fn clear_layer_attributes(&mut self, layer: &Layer, range: Range<usize>) {
+        if let Some(layer) = self.layers.get_mut(layer) {
+            layer.clear_attributes(range);
+        }
+    }
+}
+
+impl<Cell, Layer, Attribute> Rope for LayeredRope<Cell, Layer, Attribute>
+where
+Cell:       Clone,
+Layer:      Hash+Eq,
+Attribute:  PartialEq+Clone+Default {
+    type Cell       = Cell;
+
+    /// The layered rope only has attributes in its layers
+    type Attribute  = ();
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        Box::new(self.cells.read_cells(range))
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        Rope::read_chunks(&self.cells, range)
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.cells.read_attributes(pos)
+    }
+}
+
+impl<Cell, Layer, Attribute> RopeMut for LayeredRope<Cell, Layer, Attribute>
+where
+Cell:       Clone,
+Layer:      Hash+Eq,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Performs the specified editing action to this rope
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        match action {
+            RopeAction::Replace(range, cells)                   => self.replace(range, cells),
+            RopeAction::SetAttributes(_, ())                    => { }
+            RopeAction::ReplaceAttributes(range, cells, ())     => self.replace(range, cells)
+        }
+    }
+
+    ///
+    /// Replaces a range of cells, moving the attributes in all of the layers to match
+    ///
+    fn replace<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells) {
+        let new_cells   = new_cells.into_iter().collect::<Vec<_>>();
+        let new_len     = new_cells.len();
+
+        for layer in self.layers.values_mut() {
+            layer.replace(range.clone(), iter::repeat_n((), new_len));
+        }
+
+        self.cells.replace(range, new_cells);
+    }
+
+    ///
+    /// There are no attributes outside of the layers in a layered rope, so this has no effect
+    ///
+    fn set_attributes(&mut self, _range: Range<usize>, _new_attributes: ()) { }
+
+    ///
+    /// Replaces a range of cells (there are no attributes outside of the layers in a layered rope)
+    ///
+    fn replace_attributes<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells, _new_attributes: ()) {
+        self.replace(range, new_cells);
+    }
+}
+
+impl<Cell, Layer, Attribute> Default for LayeredRope<Cell, Layer, Attribute>
+where
+Cell:       Clone,
+Layer:      Hash+Eq,
+Attribute:  PartialEq+Clone+Default {
+    fn default() -> LayeredRope<Cell, Layer, Attribute> {
+        LayeredRope::new()
+    }
+}
+
+impl<Cell, Layer, Attribute> RopeSearch for LayeredRope<Cell, Layer, Attribute>
+where
+Cell:       Clone+PartialEq,
+Layer:      Hash+Eq,
+Attribute:  PartialEq+Clone+Default { }
@@ -0,0 +1,84 @@
+use super::node::*;
+use super::attributed_rope::*;
+
+use std::hash::{Hash};
+use std::collections::{HashSet};
+use std::sync::*;
+
+///
+/// A leaf interner stores a single copy of each distinct leaf found in the ropes that are deduplicated with it
+///
+/// Ropes with a lot of repeated content (padding, or repeated rows in a grid, for example) can use
+/// `AttributedRope::deduplicate_leaves()` to share the cells of identical leaves, so the memory used scales with the
+/// amount of unique content rather than the total length. The same interner can be used with several ropes to share
+/// content between them. Shared leaves are copied as normal if they're edited later on.
+///
+pub struct LeafInterner<Cell>
+where
+Cell: Hash+Eq {
+    /// The distinct leaves that have been found so far
+    leaves: HashSet<Arc<Vec<Cell>>>
+}
+
+impl<Cell> LeafInterner<Cell>
+where
+Cell: Hash+Eq {
+    ///
+    /// Creates a new, empty leaf interner
+    ///
+    pub fn new() -> LeafInterner<Cell> {
+        LeafInterner {
+            leaves: HashSet::new()
+        }
+    }
+
+    ///
+    /// The number of distinct leaves stored in this interner
+    ///
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    ///
+    /// Removes any leaves that are no longer used by any rope
+    ///
+    pub fn purge(&mut self) {
+        self.leaves.retain(|leaf| Arc::strong_count(leaf) > 1);
+    }
+
+    ///
+    /// Returns the shared copy of a leaf, adding it to the interner if it's not already there
+    ///
+    fn intern(&mut self, leaf: &Arc<Vec<Cell>>) -> Arc<Vec<Cell>> {
+        match self.leaves.get(leaf) {
+            Some(existing)  => Arc::clone(existing),
+            None            => {
+                self.leaves.insert(Arc::clone(leaf));
+                Arc::clone(leaf)
+            }
+        }
+    }
+}
+
+impl<Cell, Attribute> AttributedRope<Cell, Attribute>
+where
+Cell:       Clone+Hash+Eq,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Shares the cells of any leaves in this rope that are identical to a leaf that has already been seen by the interner
+    ///
+    /// This doesn't change the content of the rope, or the structure of its tree. Leaves are split on attribute boundaries
+    /// and as the rope is edited, so this is most effective for content that is written as whole repeated blocks.
+    ///
+    pub fn deduplicate_leaves(&mut self, interner: &mut LeafInterner<Cell>) {
+        for node in self.nodes.iter_mut() {
+            if let RopeNode::Leaf(_, cells, _) = node {
+                let shared = interner.intern(cells);
+
+                if !Arc::ptr_eq(&shared, cells) {
+                    *cells = shared;
+                }
+            }
+        }
+    }
+}
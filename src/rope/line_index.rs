@@ -0,0 +1,488 @@
+use super::lsp::*;
+
+use crate::api::*;
+
+use std::ops::{Range};
+
+///
+/// The characters that end a line
+///
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum LineEnding {
+    /// The last line in the text has no line ending
+    None,
+
+    /// `\n`
+    Lf,
+
+    /// `\r` on its own
+    Cr,
+
+    /// `\r\n`
+    CrLf
+}
+
+///
+/// A node in the tree of lines in a `LineIndex` (a treap ordered by line number)
+///
+#[derive(Clone)]
+struct LineNode {
+    /// The length of this line in bytes, including its line ending
+    len: usize,
+
+    /// The characters at the end of this line
+    ending: LineEnding,
+
+    /// The heap priority of this node, which keeps the tree balanced
+    priority: u64,
+
+    /// The lines before this one
+    left: Option<Box<LineNode>>,
+
+    /// The lines after this one
+    right: Option<Box<LineNode>>,
+
+    /// The number of lines in this subtree
+    num_lines: usize,
+
+    /// The number of bytes in this subtree
+    total_len: usize
+}
+
+///
+/// Tracks where the lines start in a rope containing UTF-8 text
+///
+/// The index is updated from the actions made to the rope rather than by reading it, so it can be kept up to date by
+/// passing it the actions from a `PushAfterRefRope` or the changes pulled from a `PullRope`. Finding the line containing
+/// an offset or the offset of a line takes O(log n) time, where n is the number of lines, so the index can also be used to
+/// convert language server protocol positions by reading only the line that contains them.
+///
+/// Lines are separated by `\n`, `\r\n` or `\r` as for the language server protocol, and the line ending counts as part of
+/// the line it ends. There is always at least one line, and the last line is the text after the final line ending (so it
+/// will be empty if the text ends with a line ending).
+///
+#[derive(Clone)]
+pub struct LineIndex {
+    /// The lines, in order
+    root: Option<Box<LineNode>>,
+
+    /// The state of the random number generator used for the node priorities
+    seed: u64
+}
+
+impl LineNode {
+    ///
+    /// Creates a node for a single line
+    ///
+    fn new(len: usize, ending: LineEnding, priority: u64) -> Box<LineNode> {
+        Box::new(LineNode {
+            len:        len,
+            ending:     ending,
+            priority:   priority,
+            left:       None,
+            right:      None,
+            num_lines:  1,
+            total_len:  len
+        })
+    }
+
+    ///
+    /// Recalculates the totals for this node from its children
+    ///
+    fn update(&mut self) {
+        self.num_lines  = 1 + num_lines(&self.left) + num_lines(&self.right);
+        self.total_len  = self.len + total_len(&self.left) + total_len(&self.right);
+    }
+}
+
+/// The number of lines in a subtree
+#[inline]
+fn num_lines(node: &Option<Box<LineNode>>) -> usize {
+    node.as_ref().map(|node| node.num_lines).unwrap_or(0)
+}
+
+/// The number of bytes in a subtree
+#[inline]
+fn total_len(node: &Option<Box<LineNode>>) -> usize {
+    node.as_ref().map(|node| node.total_len).unwrap_or(0)
+}
+
+///
+/// Joins two subtrees, where all the lines in the first come before the lines in the second
+///
+fn merge(first: Option<Box<LineNode>>, second: Option<Box<LineNode>>) -> Option<Box<LineNode>> {
+    match (first, second) {
+        (None, second)                      => second,
+        (first, None)                       => first,
+        (Some(mut first), Some(mut second)) => {
+            if first.priority > second.priority {
+                first.right = merge(first.right.take(), Some(second));
+                first.update();
+                Some(first)
+            } else {
+                second.left = merge(Some(first), second.left.take());
+                second.update();
+                Some(second)
+            }
+        }
+    }
+}
+
+///
+/// Splits a subtree into the first `count` lines and the remaining lines
+///
+fn split(node: Option<Box<LineNode>>, count: usize) -> (Option<Box<LineNode>>, Option<Box<LineNode>>) {
+    match node {
+        None            => (None, None),
+        Some(mut node)  => {
+            let left_lines = num_lines(&node.left);
+
+            if count <= left_lines {
+                let (first, second) = split(node.left.take(), count);
+                node.left           = second;
+                node.update();
+
+                (first, Some(node))
+            } else {
+                let (first, second) = split(node.right.take(), count - left_lines - 1);
+                node.right          = first;
+                node.update();
+
+                (Some(node), second)
+            }
+        }
+    }
+}
+
+impl LineEnding {
+    ///
+    /// The bytes that make up this line ending
+    ///
+    fn bytes(self) -> &'static [u8] {
+        match self {
+            LineEnding::None    => b"",
+            LineEnding::Lf      => b"\n",
+            LineEnding::Cr      => b"\r",
+            LineEnding::CrLf    => b"\r\n"
+        }
+    }
+}
+
+///
+/// Divides some text into lines, where the text can be described by its line endings and the length of the text between them
+///
+struct LineSplitter {
+    /// The lengths and endings of the lines that have been found so far
+    lines: Vec<(usize, LineEnding)>,
+
+    /// The length of the line that is being read
+    line_len: usize,
+
+    /// True if the last byte was a `\r`, which might be the start of a `\r\n` line ending
+    after_cr: bool
+}
+
+impl LineSplitter {
+    ///
+    /// Creates a splitter at the start of a line
+    ///
+    fn new() -> LineSplitter {
+        LineSplitter {
+            lines:      vec![],
+            line_len:   0,
+            after_cr:   false
+        }
+    }
+
+    ///
+    /// Adds some text that contains no `\r` or `\n` characters
+    ///
+    fn push_text(&mut self, len: usize) {
+        if len > 0 {
+            self.end_cr_line();
+            self.line_len += len;
+        }
+    }
+
+    ///
+    /// Adds a single byte of text
+    ///
+    fn push_byte(&mut self, byte: u8) {
+        match byte {
+            b'\r' => {
+                self.end_cr_line();
+                self.line_len   += 1;
+                self.after_cr   = true;
+            }
+
+            b'\n' => {
+                let ending      = if self.after_cr { LineEnding::CrLf } else { LineEnding::Lf };
+                self.line_len   += 1;
+                self.end_line(ending);
+            }
+
+            _ => self.push_text(1)
+        }
+    }
+
+    ///
+    /// Ends the current line if the last byte was a `\r` (which isn't followed by a `\n`)
+    ///
+    fn end_cr_line(&mut self) {
+        if self.after_cr {
+            self.end_line(LineEnding::Cr);
+        }
+    }
+
+    ///
+    /// Adds the current line to the list
+    ///
+    fn end_line(&mut self, ending: LineEnding) {
+        self.lines.push((self.line_len, ending));
+        self.line_len   = 0;
+        self.after_cr   = false;
+    }
+
+    ///
+    /// Returns the lines that were found. The text after the last line ending is only a line if it's at the end of the rope
+    ///
+    fn finish(mut self, at_end_of_rope: bool) -> Vec<(usize, LineEnding)> {
+        self.end_cr_line();
+
+        if at_end_of_rope {
+            self.lines.push((self.line_len, LineEnding::None));
+        }
+
+        self.lines
+    }
+}
+
+impl LineIndex {
+    ///
+    /// Creates the line index for an empty rope
+    ///
+    pub fn new() -> LineIndex {
+        let mut index = LineIndex {
+            root:   None,
+            seed:   0x2545f4914f6cdd1d
+        };
+
+        index.root = Some(LineNode::new(0, LineEnding::None, index.next_priority()));
+        index
+    }
+
+    ///
+    /// Creates the line index for the text in a rope
+    ///
+    pub fn from_rope<R: ?Sized+Rope<Cell=u8>>(rope: &R) -> LineIndex {
+        let mut index = LineIndex::new();
+
+        for chunk in rope.read_chunks(0..rope.len()) {
+            let end = index.len();
+            index.replace(end..end, chunk);
+        }
+
+        index
+    }
+
+    ///
+    /// The number of bytes in the text
+    ///
+    pub fn len(&self) -> usize {
+        total_len(&self.root)
+    }
+
+    ///
+    /// The number of lines in the text (this is always at least 1)
+    ///
+    pub fn line_count(&self) -> usize {
+        num_lines(&self.root)
+    }
+
+    ///
+    /// Returns the offset of the first byte of a line (or the length of the text if the line is beyond the end)
+    ///
+    pub fn line_to_offset(&self, line: usize) -> usize {
+        let mut node        = &self.root;
+        let mut remaining   = line;
+        let mut offset      = 0;
+
+        while let Some(current) = node {
+            let left_lines = num_lines(&current.left);
+
+            if remaining <= left_lines {
+                node        = &current.left;
+            } else {
+                offset      += total_len(&current.left) + current.len;
+                remaining   -= left_lines + 1;
+                node        = &current.right;
+            }
+        }
+
+        offset
+    }
+
+    ///
+    /// Returns the line containing the byte at the specified offset (offsets beyond the end are on the last line)
+    ///
+    pub fn offset_to_line(&self, offset: usize) -> usize {
+        let mut node        = &self.root;
+        let mut remaining   = offset;
+        let mut line        = 0;
+
+        while let Some(current) = node {
+            let left_len = total_len(&current.left);
+
+            if remaining < left_len {
+                node        = &current.left;
+            } else if remaining < left_len + current.len {
+                return line + num_lines(&current.left);
+            } else {
+                remaining   -= left_len + current.len;
+                line        += num_lines(&current.left) + 1;
+                node        = &current.right;
+            }
+        }
+
+        self.line_count() - 1
+    }
+
+    ///
+    /// Returns the range of bytes covered by a line, including its newline character
+    ///
+    pub fn line_range(&self, line: usize) -> Range<usize> {
+        self.line_to_offset(line)..self.line_to_offset(line+1)
+    }
+
+    ///
+    /// Returns the byte offset in a UTF-8 rope corresponding to a language server protocol position
+    ///
+    /// This gives the same result as `lsp_position_to_offset()`, but only needs to read the line containing the position.
+    /// The index must be up to date with the rope.
+    ///
+    pub fn lsp_position_to_offset<R: ?Sized+Rope<Cell=u8>>(&self, rope: &R, position: LspPosition) -> usize {
+        let line_start  = self.line_to_offset(position.line as usize);
+        let mut scanner = LspScanner::at(rope.read_cells(line_start..rope.len()).copied(), line_start, position.line);
+        scanner.seek_position(position);
+
+        scanner.offset()
+    }
+
+    ///
+    /// Returns the language server protocol position for a byte offset in a UTF-8 rope
+    ///
+    /// This gives the same result as `offset_to_lsp_position()`, but only needs to read the line containing the offset.
+    /// The index must be up to date with the rope.
+    ///
+    pub fn offset_to_lsp_position<R: ?Sized+Rope<Cell=u8>>(&self, rope: &R, offset: usize) -> LspPosition {
+        let line        = self.offset_to_line(offset);
+        let line_start  = self.line_to_offset(line);
+        let mut scanner = LspScanner::at(rope.read_cells(line_start..rope.len()).copied(), line_start, line as u32);
+        scanner.seek_offset(offset);
+
+        scanner.position()
+    }
+
+    ///
+    /// Returns the node for a line (which must be in the index)
+    ///
+    fn line<'a>(&'a self, line: usize) -> &'a LineNode {
+        let mut node        = self.root.as_ref().unwrap();
+        let mut remaining   = line;
+
+        loop {
+            let left_lines = num_lines(&node.left);
+
+            if remaining < left_lines {
+                node        = node.left.as_ref().unwrap();
+            } else if remaining == left_lines {
+                return node;
+            } else {
+                remaining   -= left_lines + 1;
+                node        = node.right.as_ref().unwrap();
+            }
+        }
+    }
+
+    ///
+    /// Updates the index after an action has been applied to the rope
+    ///
+    pub fn edit<'a, Attribute: 'a, Action: Into<RopeActionRef<'a, u8, Attribute>>>(&mut self, action: Action) {
+        match action.into() {
+            RopeActionRef::Replace(range, cells)                => self.replace(range, cells),
+            RopeActionRef::ReplaceAttributes(range, cells, _)   => self.replace(range, cells),
+            RopeActionRef::SetAttributes(_, _)                  => { }
+        }
+    }
+
+    ///
+    /// Updates the index after a range of bytes has been replaced
+    ///
+    pub fn replace(&mut self, range: Range<usize>, new_cells: &[u8]) {
+        let len         = self.len();
+        let start       = range.start.min(len);
+        let end         = range.end.min(len).max(start);
+
+        // Find the lines that the range covers
+        let mut first_line  = self.offset_to_line(start);
+        let last_line       = self.offset_to_line(end);
+
+        // The line before the range is also replaced if it ends with a '\r', as a '\n' after the range will join its line ending
+        if first_line > 0 && start == self.line_to_offset(first_line) && self.line(first_line-1).ending == LineEnding::Cr {
+            first_line -= 1;
+        }
+
+        let first_start = self.line_to_offset(first_line);
+        let last_start  = self.line_to_offset(last_line);
+        let first       = self.line(first_line);
+        let last        = self.line(last_line);
+        let first_text  = first.len - first.ending.bytes().len();
+        let last_text   = last.len - last.ending.bytes().len();
+
+        // Work out the lengths of the lines that replace them: the text around the range never contains a line ending
+        // except at the end of the line, so we only need to know where the line endings are
+        let mut splitter    = LineSplitter::new();
+        let before_len      = start - first_start;
+        let after_start     = end - last_start;
+
+        splitter.push_text(before_len.min(first_text));
+        first.ending.bytes()[0..before_len.saturating_sub(first_text)].iter().for_each(|byte| splitter.push_byte(*byte));
+
+        new_cells.iter().for_each(|byte| splitter.push_byte(*byte));
+
+        splitter.push_text(last_text.saturating_sub(after_start));
+        last.ending.bytes()[after_start.saturating_sub(last_text)..].iter().for_each(|byte| splitter.push_byte(*byte));
+
+        let new_lines = splitter.finish(last_line+1 == self.line_count());
+
+        // Replace the lines in the tree
+        let (before, rest)  = split(self.root.take(), first_line);
+        let (_, after)      = split(rest, last_line - first_line + 1);
+        let mut replacement = None;
+
+        for (line_len, ending) in new_lines {
+            let priority    = self.next_priority();
+            replacement     = merge(replacement, Some(LineNode::new(line_len, ending, priority)));
+        }
+
+        self.root = merge(merge(before, replacement), after);
+    }
+
+    ///
+    /// Generates the priority for a new node
+    ///
+    fn next_priority(&mut self) -> u64 {
+        // xorshift64
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+
+        self.seed
+    }
+}
+
+impl Default for LineIndex {
+    fn default() -> Self {
+        LineIndex::new()
+    }
+}
@@ -0,0 +1,247 @@
+use crate::api::*;
+
+use std::iter::{Peekable};
+
+///
+/// A position in a document, in the form used by the language server protocol: a zero-based line number
+/// and a zero-based offset within that line in UTF-16 code units
+///
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct LspPosition {
+    pub line:       u32,
+    pub character:  u32
+}
+
+///
+/// A range in a document, in the form used by the language server protocol
+///
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct LspRange {
+    pub start:  LspPosition,
+    pub end:    LspPosition
+}
+
+///
+/// A change to a text document, in the form used by the `TextDocumentContentChangeEvent` in the language server protocol
+///
+/// If the range is `None`, the text replaces the entire document.
+///
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct LspTextChange {
+    pub range:  Option<LspRange>,
+    pub text:   String
+}
+
+///
+/// The number of UTF-16 code units needed for the character that starts with a UTF-8 byte (0 for continuation bytes)
+///
+/// Conversions to UTF-16 all use this to measure characters, and treat an offset in the middle of a character (including
+/// one in the middle of a surrogate pair) as being at the end of that character.
+///
+#[inline]
+pub (super) fn utf16_units(byte: u8) -> usize {
+    if (byte & 0xc0) == 0x80 {
+        0
+    } else if byte >= 0xf0 {
+        2
+    } else {
+        1
+    }
+}
+
+///
+/// Tracks the LSP position while scanning through the bytes of a UTF-8 rope
+///
+pub (super) struct LspScanner<Cells: Iterator<Item=u8>> {
+    /// The remaining cells in the rope
+    cells: Peekable<Cells>,
+
+    /// The byte offset of the next cell
+    offset: usize,
+
+    /// The line that the next cell is on
+    line: u32,
+
+    /// The UTF-16 offset of the next cell within its line
+    character: u32
+}
+
+impl<Cells: Iterator<Item=u8>> LspScanner<Cells> {
+    ///
+    /// Creates a scanner at the start of a set of cells
+    ///
+    fn new(cells: Cells) -> LspScanner<Cells> {
+        Self::at(cells, 0, 0)
+    }
+
+    ///
+    /// Creates a scanner for the cells starting at the beginning of a line
+    ///
+    pub (super) fn at(cells: Cells, offset: usize, line: u32) -> LspScanner<Cells> {
+        LspScanner {
+            cells:      cells.peekable(),
+            offset:     offset,
+            line:       line,
+            character:  0
+        }
+    }
+
+    ///
+    /// The current position of the scanner
+    ///
+    pub (super) fn position(&self) -> LspPosition {
+        LspPosition { line: self.line, character: self.character }
+    }
+
+    ///
+    /// The byte offset of the scanner
+    ///
+    pub (super) fn offset(&self) -> usize {
+        self.offset
+    }
+
+    ///
+    /// Moves past the next character or line ending, returning false if the end of the rope was reached
+    ///
+    fn advance(&mut self) -> bool {
+        let next = match self.cells.next() {
+            Some(next)  => next,
+            None        => { return false; }
+        };
+
+        self.offset += 1;
+
+        match next {
+            b'\r' => {
+                // '\r\n' and '\r' are both line endings
+                if self.cells.peek() == Some(&b'\n') {
+                    self.cells.next();
+                    self.offset += 1;
+                }
+
+                self.line       += 1;
+                self.character  = 0;
+            }
+
+            b'\n' => {
+                self.line       += 1;
+                self.character  = 0;
+            }
+
+            byte => {
+                self.character += utf16_units(byte) as u32;
+
+                // Skip the rest of the character if we're in the middle of a multi-byte sequence
+                while let Some(byte) = self.cells.peek() {
+                    if (byte & 0xc0) != 0x80 { break; }
+
+                    self.cells.next();
+                    self.offset += 1;
+                }
+            }
+        }
+
+        true
+    }
+
+    ///
+    /// Moves the scanner forward to the specified byte offset (or to the end of the character containing that offset)
+    ///
+    pub (super) fn seek_offset(&mut self, offset: usize) {
+        while self.offset < offset {
+            if !self.advance() { break; }
+        }
+    }
+
+    ///
+    /// Moves the scanner forward to the specified position. Positions beyond the end of a line are moved to the end of that line.
+    ///
+    pub (super) fn seek_position(&mut self, position: LspPosition) {
+        // Move to the start of the line
+        while self.line < position.line {
+            if !self.advance() { return; }
+        }
+
+        // Move to the character within the line
+        while self.line == position.line && self.character < position.character {
+            match self.cells.peek() {
+                None | Some(b'\r') | Some(b'\n')    => { break; }
+                _                                   => { self.advance(); }
+            }
+        }
+    }
+}
+
+///
+/// Returns the byte offset in a UTF-8 rope corresponding to a language server protocol position
+///
+/// Positions beyond the end of a line are treated as being at the end of that line, and lines beyond the
+/// end of the rope are treated as being at the end of the rope.
+///
+pub fn lsp_position_to_offset<TRope: ?Sized+Rope<Cell=u8>>(rope: &TRope, position: LspPosition) -> usize {
+    let mut scanner = LspScanner::new(rope.read_cells(0..rope.len()).copied());
+    scanner.seek_position(position);
+
+    scanner.offset
+}
+
+///
+/// Returns the language server protocol position for a byte offset in a UTF-8 rope
+///
+pub fn offset_to_lsp_position<TRope: ?Sized+Rope<Cell=u8>>(rope: &TRope, offset: usize) -> LspPosition {
+    let mut scanner = LspScanner::new(rope.read_cells(0..rope.len()).copied());
+    scanner.seek_offset(offset);
+
+    scanner.position()
+}
+
+///
+/// Converts a language server protocol change into the action that will apply it to a rope
+///
+/// The rope should be in the state before the change is applied: when applying a list of changes, each
+/// change must be converted after the previous one has been applied.
+///
+pub fn lsp_change_to_action<TRope: ?Sized+Rope<Cell=u8>>(rope: &TRope, change: &LspTextChange) -> RopeAction<u8, TRope::Attribute> {
+    let new_cells = change.text.bytes().collect();
+
+    match change.range {
+        None        => RopeAction::Replace(0..rope.len(), new_cells),
+        Some(range) => {
+            // Find the start and end offsets in a single pass through the rope
+            let mut scanner = LspScanner::new(rope.read_cells(0..rope.len()).copied());
+
+            scanner.seek_position(range.start);
+            let start = scanner.offset;
+            scanner.seek_position(range.end);
+            let end = scanner.offset;
+
+            RopeAction::Replace(start..end, new_cells)
+        }
+    }
+}
+
+///
+/// Converts an action into the language server protocol change that describes it, or `None` if the action does not change the text
+///
+/// The rope should be in the state before the action is applied.
+///
+pub fn action_to_lsp_change<TRope: ?Sized+Rope<Cell=u8>>(rope: &TRope, action: &RopeAction<u8, TRope::Attribute>) -> Option<LspTextChange> {
+    let (range, new_cells) = match action {
+        RopeAction::Replace(range, new_cells)               => (range, new_cells),
+        RopeAction::ReplaceAttributes(range, new_cells, _)  => (range, new_cells),
+        RopeAction::SetAttributes(_, _)                     => { return None; }
+    };
+
+    // Find the start and end positions in a single pass through the rope
+    let mut scanner = LspScanner::new(rope.read_cells(0..rope.len()).copied());
+
+    scanner.seek_offset(range.start);
+    let start = scanner.position();
+    scanner.seek_offset(range.end);
+    let end = scanner.position();
+
+    Some(LspTextChange {
+        range:  Some(LspRange { start, end }),
+        text:   String::from_utf8_lossy(new_cells).into()
+    })
+}
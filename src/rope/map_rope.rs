@@ -0,0 +1,136 @@
+use crate::api::*;
+
+use std::marker::{PhantomData};
+use std::ops::{Range};
+
+///
+/// A map rope is a read-only view of a base rope where each cell is passed through a function as it's read
+///
+/// This can be used to display a transformed version of a rope (for example, masking a password field) without storing
+/// a transformed copy of it. The cells are read by reference, so the map function returns a reference to the new cell:
+/// this is usually a constant or an entry in a static table:
+///
+/// ```
+/// # use flo_rope::*;
+/// let password    = AttributedRope::<u8, ()>::from("hunter2".bytes());
+/// let masked      = MapRope::from(password, |_cell: &u8| &b'*');
+///
+/// assert!(masked.read_cells(0..7).cloned().collect::<Vec<_>>() == b"*******".to_vec());
+/// ```
+///
+/// Every cell is mapped to exactly one new cell, so the attributes and the ranges of any changes to the base rope are
+/// the same in the map rope. `map_action()` converts an action on the base rope (such as one pulled from a `PullRope`)
+/// into the corresponding action on the map rope.
+///
+#[derive(Clone)]
+pub struct MapRope<BaseRope, MapFn, NewCell> {
+    /// The rope whose cells are mapped
+    rope: BaseRope,
+
+    /// The function that maps the cells in the base rope
+    map_fn: MapFn,
+
+    /// The type of the mapped cells
+    new_cell: PhantomData<fn() -> NewCell>
+}
+
+impl<BaseRope, MapFn, NewCell> MapRope<BaseRope, MapFn, NewCell>
+where
+BaseRope:   Rope,
+NewCell:    Clone,
+MapFn:      for<'a> Fn(&'a BaseRope::Cell) -> &'a NewCell {
+    ///
+    /// Creates a view of a rope where each cell is mapped through a function
+    ///
+    pub fn from(rope: BaseRope, map_fn: MapFn) -> MapRope<BaseRope, MapFn, NewCell> {
+        MapRope {
+            rope:       rope,
+            map_fn:     map_fn,
+            new_cell:   PhantomData
+        }
+    }
+
+    ///
+    /// Returns the rope that this is a view of
+    ///
+    pub fn into_inner(self) -> BaseRope {
+        self.rope
+    }
+
+    ///
+    /// Borrows the rope that this is a view of
+    ///
+    pub fn base(&self) -> &BaseRope {
+        &self.rope
+    }
+
+    ///
+    /// Borrows the rope that this is a view of for editing (the map rope reflects any changes that are made to it)
+    ///
+    pub fn base_mut(&mut self) -> &mut BaseRope {
+        &mut self.rope
+    }
+
+    ///
+    /// Converts an action on the base rope into the same action on this rope
+    ///
+    pub fn map_action(&self, action: &RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> RopeAction<NewCell, BaseRope::Attribute> {
+        let map_cells = |cells: &Vec<BaseRope::Cell>| cells.iter().map(|cell| (self.map_fn)(cell).clone()).collect();
+
+        match action {
+            RopeAction::Replace(range, cells)                       => RopeAction::Replace(range.clone(), map_cells(cells)),
+            RopeAction::SetAttributes(range, attribute)             => RopeAction::SetAttributes(range.clone(), attribute.clone()),
+            RopeAction::ReplaceAttributes(range, cells, attribute)  => RopeAction::ReplaceAttributes(range.clone(), map_cells(cells), attribute.clone())
+        }
+    }
+}
+
+impl<BaseRope, MapFn, NewCell> Rope for MapRope<BaseRope, MapFn, NewCell>
+where
+BaseRope:   Rope,
+NewCell:    Clone,
+MapFn:      for<'a> Fn(&'a BaseRope::Cell) -> &'a NewCell {
+    /// The cells in this rope are the result of the map function
+    type Cell = NewCell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.rope.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope, mapping them through the function
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        Box::new(self.rope.read_cells(range).map(move |cell| (self.map_fn)(cell)))
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.rope.read_attributes(pos)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.read_attribute_runs(range)
+    }
+}
+
+impl<BaseRope, MapFn, NewCell> RopeSearch for MapRope<BaseRope, MapFn, NewCell>
+where
+BaseRope:   Rope,
+NewCell:    Clone+PartialEq,
+MapFn:      for<'a> Fn(&'a BaseRope::Cell) -> &'a NewCell { }
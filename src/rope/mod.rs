@@ -1,12 +1,68 @@
 mod node;
 mod branch;
 mod utf8_rope;
+mod char_rope;
+mod reader_diff;
+mod rope_io;
+#[cfg(feature = "bytes")] mod bytes_rope;
+#[cfg(feature = "icu_collator")] mod utf8_collation;
+#[cfg(feature = "regex")] mod utf8_regex;
+#[cfg(feature = "unicode")] mod graphemes;
 mod rope_extensions;
 mod attributed_rope;
 mod attributed_rope_iterator;
+mod rope_leaf;
+mod attribute_registry;
+mod attribute_table;
+mod buffer_rope;
+mod combining_rope;
+mod layered_rope;
+mod lsp;
+mod line_index;
+mod rope_anchors;
+mod rope_handles;
+mod rope_stress;
+mod frozen_rope;
+mod crdt_rope;
+mod sparse_rope;
+mod tombstone_rope;
+mod suggestion_rope;
+mod progressive_rope;
+mod rope_integrity;
+mod leaf_interner;
+mod rope_stats;
+mod rope_compact;
+mod map_rope;
+#[cfg(feature = "node_api")] pub mod node_api;
 #[cfg(test)] mod tests;
 
 pub use self::utf8_rope::*;
-pub use self::attributed_rope::*;
+pub use self::char_rope::*;
+pub use self::reader_diff::*;
+pub use self::rope_io::*;
+#[cfg(feature = "bytes")] pub use self::bytes_rope::*;
+#[cfg(feature = "icu_collator")] pub use self::utf8_collation::*;
+#[cfg(feature = "regex")] pub use self::utf8_regex::*;
+#[cfg(feature = "unicode")] pub use self::graphemes::*;
 pub use self::attributed_rope::*;
 pub use self::attributed_rope_iterator::*;
+pub use self::rope_leaf::*;
+pub use self::attribute_registry::*;
+pub use self::attribute_table::*;
+pub use self::combining_rope::*;
+pub use self::layered_rope::*;
+pub use self::lsp::*;
+pub use self::line_index::*;
+pub use self::rope_anchors::*;
+pub use self::rope_handles::*;
+pub use self::rope_stress::*;
+pub use self::frozen_rope::*;
+pub use self::crdt_rope::*;
+pub use self::sparse_rope::*;
+pub use self::tombstone_rope::*;
+pub use self::suggestion_rope::*;
+pub use self::progressive_rope::*;
+pub use self::rope_integrity::*;
+pub use self::leaf_interner::*;
+pub use self::rope_stats::*;
+pub use self::map_rope::*;
@@ -15,13 +15,32 @@ impl RopeNodeIndex {
     }
 }
 
-impl Into<usize> for RopeNodeIndex {
-    fn into(self) -> usize {
-        let RopeNodeIndex(idx) = self;
+impl From<RopeNodeIndex> for usize {
+    fn from(node_idx: RopeNodeIndex) -> usize {
+        let RopeNodeIndex(idx) = node_idx;
         idx
     }
 }
 
+///
+/// Decides which attribute a leaf has after it has been joined to the leaf on its right
+///
+/// The editing operations only join leaves in ways that don't change any attributes: the other policies are available
+/// through the `node_api` feature.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(not(feature = "node_api"), allow(dead_code))]
+pub enum JoinPolicy {
+    /// The joined leaf has the attribute of the left-hand leaf
+    KeepLeft,
+
+    /// The joined leaf has the attribute of the right-hand leaf
+    KeepRight,
+
+    /// The leaves are only joined if they have the same attribute
+    MustMatch
+}
+
 ///
 /// A node in a rope
 ///
@@ -31,7 +50,9 @@ pub enum RopeNode<Cell, Attribute> {
     Empty,
 
     /// A leaf node represents a substring of cells. The node index indicates the parent node
-    Leaf(Option<RopeNodeIndex>, Vec<Cell>, Arc<Attribute>),
+    ///
+    /// The cells are shared between clones of a rope, and are copied when a clone edits them
+    Leaf(Option<RopeNodeIndex>, Arc<Vec<Cell>>, Arc<Attribute>),
 
     /// A rope branch represents a point where a rope is split into two substrings
     Branch(RopeBranch)
@@ -68,4 +89,15 @@ impl<Cell, Attribute> RopeNode<Cell, Attribute> {
             RopeNode::Branch(branch)        => branch.parent
         }
     }
+
+    ///
+    /// Updates the parent for this node
+    ///
+    pub fn set_parent(&mut self, new_parent: Option<RopeNodeIndex>) {
+        match self {
+            RopeNode::Empty                 => { }
+            RopeNode::Leaf(parent, _, _)    => { *parent = new_parent; }
+            RopeNode::Branch(branch)        => { branch.parent = new_parent; }
+        }
+    }
 }
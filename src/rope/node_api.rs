@@ -0,0 +1,322 @@
+//!
+//! Low-level access to the nodes that make up an `AttributedRope`
+//!
+//! This module is only available when the `node_api` feature is enabled. It's intended for experimenting
+//! with alternative balancing and chunking policies, and is not covered by the crate's semver guarantees:
+//! the node representation may change between any two releases.
+//!
+//! The primitives here all preserve the content of the rope: rotations and splits change the shape of
+//! the tree but not the cells or attributes that it contains, and detaching or attaching a subtree fixes up
+//! the lengths of all of the branches above it.
+//!
+
+pub use super::node::{RopeNode, RopeNodeIndex, JoinPolicy};
+pub use super::branch::{RopeBranch};
+
+use super::attributed_rope::*;
+use crate::api::*;
+
+use std::sync::*;
+
+///
+/// Which side of an existing node a subtree should be attached to
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AttachSide {
+    /// The subtree's cells will appear before the existing node
+    Left,
+
+    /// The subtree's cells will appear after the existing node
+    Right
+}
+
+impl<Cell, Attribute> AttributedRope<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Returns the index of the root node of this rope
+    ///
+    pub fn root_node_index(&self) -> RopeNodeIndex {
+        self.root_node_idx
+    }
+
+    ///
+    /// Retrieves the node at the specified index
+    ///
+    pub fn node(&self, node_idx: RopeNodeIndex) -> &RopeNode<Cell, Attribute> {
+        &self.nodes[node_idx.idx()]
+    }
+
+    ///
+    /// Splits a leaf node in two at the specified offset, returning the index of the left-hand leaf
+    ///
+    /// The leaf at `leaf_node_idx` is replaced with a branch node that contains the two new leaves.
+    ///
+    pub fn split_leaf(&mut self, leaf_node_idx: RopeNodeIndex, split_index: usize) -> RopeNodeIndex {
+        match &self.nodes[leaf_node_idx.idx()] {
+            RopeNode::Leaf(_, cells, _) => { assert!(split_index <= cells.len(), "Split index beyond the end of the leaf node"); }
+            _                           => { panic!("Tried to split non-leaf nodes"); }
+        }
+
+        self.split(leaf_node_idx, split_index)
+    }
+
+    ///
+    /// Joins a leaf node to the leaf immediately to its right, returning true if the leaves were joined
+    ///
+    /// The policy decides which attribute the joined cells will take: with `JoinPolicy::MustMatch`, the leaves are
+    /// left unchanged if their attributes are different. The index of the leaf node is no longer valid after the
+    /// leaves have been joined.
+    ///
+    pub fn join_leaf_to_right(&mut self, leaf_node_idx: RopeNodeIndex, policy: JoinPolicy) -> bool {
+        match &self.nodes[leaf_node_idx.idx()] {
+            RopeNode::Leaf(_, _, _) => { }
+            _                       => { panic!("Tried to join a non-leaf node"); }
+        }
+
+        self.join_to_right(leaf_node_idx, policy)
+    }
+
+    ///
+    /// Performs a left rotation on a branch node whose right-hand side is also a branch
+    ///
+    /// `(a, (b, c))` becomes `((a, b), c)`. The node at `branch_node_idx` remains the root of the rotated subtree.
+    ///
+    pub fn rotate_left(&mut self, branch_node_idx: RopeNodeIndex) {
+        let outer = match &self.nodes[branch_node_idx.idx()] { RopeNode::Branch(branch) => *branch, _ => panic!("Can only rotate branch nodes") };
+        let inner = match &self.nodes[outer.right.idx()] { RopeNode::Branch(branch) => *branch, _ => panic!("Left rotation requires a branch on the right-hand side") };
+
+        let (a, b, c)   = (outer.left, inner.left, inner.right);
+        let inner_idx   = outer.right;
+        let inner_len   = self.nodes[a.idx()].len() + self.nodes[b.idx()].len();
+
+        // The inner node becomes the left-hand side of the outer node
+        self.nodes[inner_idx.idx()] = RopeNode::Branch(RopeBranch {
+            left:       a,
+            right:      b,
+            length:     inner_len,
+            single_run: false,
+            parent:     Some(branch_node_idx)
+        });
+        self.nodes[branch_node_idx.idx()] = RopeNode::Branch(RopeBranch {
+            left:       inner_idx,
+            right:      c,
+            length:     outer.length,
+            single_run: false,
+            parent:     outer.parent
+        });
+
+        self.nodes[a.idx()].set_parent(Some(inner_idx));
+        self.nodes[c.idx()].set_parent(Some(branch_node_idx));
+        self.correct_run_summary(inner_idx);
+
+        self.verify_tree("post-rotate-left");
+    }
+
+    ///
+    /// Performs a right rotation on a branch node whose left-hand side is also a branch
+    ///
+    /// `((a, b), c)` becomes `(a, (b, c))`. The node at `branch_node_idx` remains the root of the rotated subtree.
+    ///
+    pub fn rotate_right(&mut self, branch_node_idx: RopeNodeIndex) {
+        let outer = match &self.nodes[branch_node_idx.idx()] { RopeNode::Branch(branch) => *branch, _ => panic!("Can only rotate branch nodes") };
+        let inner = match &self.nodes[outer.left.idx()] { RopeNode::Branch(branch) => *branch, _ => panic!("Right rotation requires a branch on the left-hand side") };
+
+        let (a, b, c)   = (inner.left, inner.right, outer.right);
+        let inner_idx   = outer.left;
+        let inner_len   = self.nodes[b.idx()].len() + self.nodes[c.idx()].len();
+
+        // The inner node becomes the right-hand side of the outer node
+        self.nodes[inner_idx.idx()] = RopeNode::Branch(RopeBranch {
+            left:       b,
+            right:      c,
+            length:     inner_len,
+            single_run: false,
+            parent:     Some(branch_node_idx)
+        });
+        self.nodes[branch_node_idx.idx()] = RopeNode::Branch(RopeBranch {
+            left:       a,
+            right:      inner_idx,
+            length:     outer.length,
+            single_run: false,
+            parent:     outer.parent
+        });
+
+        self.nodes[a.idx()].set_parent(Some(branch_node_idx));
+        self.nodes[c.idx()].set_parent(Some(inner_idx));
+        self.correct_run_summary(inner_idx);
+
+        self.verify_tree("post-rotate-right");
+    }
+
+    ///
+    /// Removes the subtree at the specified node from this rope, returning it as a new rope
+    ///
+    /// The sibling of the detached node takes the place of its parent branch. Detaching the root node leaves
+    /// this rope empty.
+    ///
+    pub fn detach_subtree(&mut self, node_idx: RopeNodeIndex) -> AttributedRope<Cell, Attribute> {
+        if node_idx == self.root_node_idx {
+            return std::mem::take(self);
+        }
+
+        // Find the parent and sibling of the node that's being detached
+        let parent_idx  = self.nodes[node_idx.idx()].parent().expect("Non-root node must have a parent");
+        let parent      = match &self.nodes[parent_idx.idx()] { RopeNode::Branch(branch) => *branch, _ => panic!("Parent node must be a branch node") };
+        let sibling_idx = if parent.left == node_idx { parent.right } else { parent.left };
+
+        // Move the nodes into a new rope
+        let mut detached        = AttributedRope { nodes: vec![], root_node_idx: RopeNodeIndex(0), free_nodes: vec![], leaf_ids: vec![], next_leaf_id: 0 };
+        detached.root_node_idx  = move_subtree(self, node_idx, &mut detached, None);
+
+        // The sibling replaces the parent node
+        match parent.parent {
+            Some(grandparent_idx) => {
+                match &mut self.nodes[grandparent_idx.idx()] {
+                    RopeNode::Branch(grandparent) => {
+                        if grandparent.left == parent_idx {
+                            grandparent.left = sibling_idx;
+                        } else {
+                            grandparent.right = sibling_idx;
+                        }
+                    }
+
+                    _ => panic!("Grandparent node must be a branch node")
+                }
+            }
+
+            None => { self.root_node_idx = sibling_idx; }
+        }
+
+        self.nodes[sibling_idx.idx()].set_parent(parent.parent);
+        self.nodes[parent_idx.idx()] = RopeNode::Empty;
+        self.free_nodes.push(parent_idx.idx());
+
+        parent.parent.map(|grandparent_idx| self.correct_branch_length(grandparent_idx));
+        parent.parent.map(|grandparent_idx| self.correct_run_summary(grandparent_idx));
+
+        self.verify_tree("post-detach");
+        detached.verify_tree("detached");
+
+        detached
+    }
+
+    ///
+    /// Attaches the nodes of another rope beside an existing node in this rope
+    ///
+    /// A new branch node is created in the place of `node_idx`, with the existing node on one side and the subtree on
+    /// the other. The index of the new branch node is returned.
+    ///
+    pub fn attach_subtree(&mut self, node_idx: RopeNodeIndex, side: AttachSide, mut subtree: AttributedRope<Cell, Attribute>) -> RopeNodeIndex {
+        let parent_idx  = self.nodes[node_idx.idx()].parent();
+        let subtree_len = subtree.len();
+
+        // Create the branch that will hold the two subtrees (the children are filled in once the subtree is moved)
+        let branch_idx  = self.store_new_node(RopeNode::Branch(RopeBranch {
+            left:       node_idx,
+            right:      node_idx,
+            length:     self.nodes[node_idx.idx()].len() + subtree_len,
+            single_run: false,
+            parent:     parent_idx
+        }));
+
+        let subtree_root    = subtree.root_node_idx;
+        let subtree_idx     = move_subtree(&mut subtree, subtree_root, self, Some(branch_idx));
+
+        match &mut self.nodes[branch_idx.idx()] {
+            RopeNode::Branch(branch) => {
+                match side {
+                    AttachSide::Left    => { branch.left = subtree_idx; }
+                    AttachSide::Right   => { branch.right = subtree_idx; }
+                }
+            }
+
+            _ => unreachable!()
+        }
+
+        // Replace the existing node with the branch
+        match parent_idx {
+            Some(parent_idx) => {
+                match &mut self.nodes[parent_idx.idx()] {
+                    RopeNode::Branch(parent) => {
+                        if parent.left == node_idx {
+                            parent.left = branch_idx;
+                        } else {
+                            parent.right = branch_idx;
+                        }
+                    }
+
+                    _ => panic!("Parent node must be a branch node")
+                }
+
+                self.correct_branch_length(parent_idx);
+            }
+
+            None => { self.root_node_idx = branch_idx; }
+        }
+
+        self.nodes[node_idx.idx()].set_parent(Some(branch_idx));
+        self.correct_run_summary(branch_idx);
+
+        self.verify_tree("post-attach");
+
+        branch_idx
+    }
+}
+
+///
+/// Moves the nodes in a subtree of one rope into another rope, returning the index of the subtree's root in the target
+///
+fn move_subtree<Cell, Attribute>(source: &mut AttributedRope<Cell, Attribute>, source_idx: RopeNodeIndex, target: &mut AttributedRope<Cell, Attribute>, parent: Option<RopeNodeIndex>) -> RopeNodeIndex
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    // Trees aren't necessarily balanced, so this uses a stack rather than recursion (the bool indicates which side of the parent to update)
+    let mut root_idx    = None;
+    let mut to_move     = vec![(source_idx, parent, false)];
+
+    while let Some((source_idx, parent, is_left)) = to_move.pop() {
+        // Take the node from the source
+        let node = source.nodes[source_idx.idx()].take();
+        source.free_nodes.push(source_idx.idx());
+
+        let target_idx = match node {
+            RopeNode::Leaf(_, cells, attribute) => {
+                target.store_new_node(RopeNode::Leaf(parent, cells, attribute))
+            }
+
+            RopeNode::Branch(branch) => {
+                let target_idx = target.store_new_node(RopeNode::Branch(RopeBranch {
+                    left:       branch.left,
+                    right:      branch.right,
+                    length:     branch.length,
+                    single_run: branch.single_run,
+                    parent:     parent
+                }));
+
+                to_move.push((branch.left, Some(target_idx), true));
+                to_move.push((branch.right, Some(target_idx), false));
+
+                target_idx
+            }
+
+            RopeNode::Empty => {
+                target.store_new_node(RopeNode::Leaf(parent, Arc::new(vec![]), Arc::new(Attribute::default())))
+            }
+        };
+
+        // Update the parent node to point at the new node
+        match (root_idx, parent) {
+            (None, _)               => { root_idx = Some(target_idx); }
+            (Some(_), Some(parent)) => {
+                if let RopeNode::Branch(branch) = &mut target.nodes[parent.idx()] {
+                    if is_left { branch.left = target_idx; } else { branch.right = target_idx; }
+                }
+            }
+            (Some(_), None)         => unreachable!()
+        }
+    }
+
+    root_idx.unwrap()
+}
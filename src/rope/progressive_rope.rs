@@ -0,0 +1,203 @@
+use super::attributed_rope::*;
+
+use crate::api::*;
+
+use std::fmt;
+use std::iter;
+use std::slice;
+use std::error::{Error};
+use std::ops::{Range};
+
+///
+/// Error returned when reading part of a `ProgressiveRope` that has not been loaded yet
+///
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RangeNotLoaded {
+    /// The part of the requested range that has not been loaded
+    pub unloaded: Range<usize>
+}
+
+impl fmt::Display for RangeNotLoaded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cells {}..{} have not been loaded yet", self.unloaded.start, self.unloaded.end)
+    }
+}
+
+impl Error for RangeNotLoaded { }
+
+///
+/// A progressive rope knows the eventual length of its content but only has a prefix of it loaded, so that (for example)
+/// a file can be displayed and scrolled while it's still being read
+///
+/// Reading from the part of the rope that hasn't been loaded yet returns placeholder cells with the default attribute,
+/// or `read_loaded_cells()` can be used to get an error instead. Each call to `load()` appends the next chunk of the
+/// content and returns the action that replaces the placeholders with it, which can be sent on to any ropes that are
+/// mirroring this one.
+///
+/// The eventual length is only an estimate: loading more cells than expected extends the rope, and `finish_loading()`
+/// removes any placeholders that are left when the content turns out to be shorter.
+///
+#[derive(Clone)]
+pub struct ProgressiveRope<Cell, Attribute> {
+    /// The cells that have been loaded so far
+    loaded: AttributedRope<Cell, Attribute>,
+
+    /// The total length of the rope, including the placeholders
+    len: usize,
+
+    /// The cell returned for the parts of the rope that haven't been loaded
+    placeholder: Cell,
+
+    /// The attribute of the placeholder cells
+    placeholder_attribute: Attribute
+}
+
+impl<Cell, Attribute> ProgressiveRope<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Creates a rope that will eventually contain `len` cells, and which fills the unloaded part of the rope with
+    /// a placeholder cell
+    ///
+    pub fn new(len: usize, placeholder: Cell) -> ProgressiveRope<Cell, Attribute> {
+        ProgressiveRope {
+            loaded:                 AttributedRope::new(),
+            len:                    len,
+            placeholder:            placeholder,
+            placeholder_attribute:  Attribute::default()
+        }
+    }
+
+    ///
+    /// The number of cells that have been loaded (from the start of the rope)
+    ///
+    pub fn loaded_len(&self) -> usize {
+        self.loaded.len()
+    }
+
+    ///
+    /// True if every cell in the rope has been loaded
+    ///
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.len() >= self.len
+    }
+
+    ///
+    /// True if the cells in the specified range have been loaded
+    ///
+    pub fn is_range_loaded(&self, range: Range<usize>) -> bool {
+        range.end.min(self.len) <= self.loaded.len()
+    }
+
+    ///
+    /// Reads a range of cells, or returns an error if any of them have not been loaded yet
+    ///
+    pub fn read_loaded_cells<'a>(&'a self, range: Range<usize>) -> Result<impl 'a+Iterator<Item=&'a Cell>, RangeNotLoaded> {
+        let end = range.end.min(self.len);
+
+        if end > self.loaded.len() {
+            Err(RangeNotLoaded { unloaded: range.start.max(self.loaded.len())..end })
+        } else {
+            Ok(self.loaded.read_cells(range))
+        }
+    }
+
+    ///
+    /// Appends the next chunk of the content to the loaded part of the rope, returning the action that replaces the
+    /// placeholders with the new cells
+    ///
+    pub fn load<NewCells: IntoIterator<Item=Cell>>(&mut self, cells: NewCells) -> RopeAction<Cell, Attribute> {
+        self.load_with_attributes(cells, Attribute::default())
+    }
+
+    ///
+    /// Appends the next chunk of the content with the specified attribute, returning the action that replaces the
+    /// placeholders with the new cells
+    ///
+    pub fn load_with_attributes<NewCells: IntoIterator<Item=Cell>>(&mut self, cells: NewCells, attribute: Attribute) -> RopeAction<Cell, Attribute> {
+        let cells       = cells.into_iter().collect::<Vec<_>>();
+        let start       = self.loaded.len();
+        let end         = (start + cells.len()).min(self.len);
+
+        self.loaded.replace_attributes(start..start, cells.iter().cloned(), attribute.clone());
+        self.len        = self.len.max(self.loaded.len());
+
+        RopeAction::ReplaceAttributes(start..end, cells, attribute)
+    }
+
+    ///
+    /// Indicates that all of the content has been loaded, returning an action that removes the remaining placeholders
+    /// if there are any
+    ///
+    pub fn finish_loading(&mut self) -> Option<RopeAction<Cell, Attribute>> {
+        let loaded_len = self.loaded.len();
+
+        if loaded_len < self.len {
+            let placeholders    = loaded_len..self.len;
+            self.len            = loaded_len;
+
+            Some(RopeAction::Replace(placeholders, vec![]))
+        } else {
+            None
+        }
+    }
+}
+
+impl<Cell, Attribute> Rope for ProgressiveRope<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    type Cell       = Cell;
+    type Attribute  = Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Cell>> {
+        let loaded_len      = self.loaded.len();
+        let end             = range.end.min(self.len);
+        let num_placeholder = end.saturating_sub(range.start.max(loaded_len));
+
+        Box::new(self.loaded.read_cells(range.start.min(loaded_len)..end.min(loaded_len))
+            .chain(iter::repeat_n(&self.placeholder, num_placeholder)))
+    }
+
+    ///
+    /// Reads the loaded cells in a range as slices, followed by the placeholders one at a time
+    ///
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Cell]>> {
+        let loaded_len      = self.loaded.len();
+        let end             = range.end.min(self.len);
+        let num_placeholder = end.saturating_sub(range.start.max(loaded_len));
+
+        Box::new(Rope::read_chunks(&self.loaded, range.start.min(loaded_len)..end.min(loaded_len))
+            .chain(iter::repeat_n(slice::from_ref(&self.placeholder), num_placeholder)))
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Attribute, Range<usize>) {
+        let loaded_len = self.loaded.len();
+
+        if pos < loaded_len || (loaded_len > 0 && loaded_len >= self.len) {
+            self.loaded.read_attributes(pos)
+        } else {
+            (&self.placeholder_attribute, loaded_len..self.len)
+        }
+    }
+}
+
+impl<Cell, Attribute> RopeSearch for ProgressiveRope<Cell, Attribute>
+where
+Cell:       Clone+PartialEq,
+Attribute:  PartialEq+Clone+Default { }
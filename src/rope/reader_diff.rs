@@ -0,0 +1,83 @@
+use crate::api::*;
+
+use std::io;
+use std::io::{Read};
+
+///
+/// Which content the actions generated by `diff_against_reader()` should update
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReaderDiffDirection {
+    /// Generates actions that update the rope so that it matches the content of the reader
+    UpdateRope,
+
+    /// Generates actions that, if applied to the content of the reader, would make it match the rope
+    UpdateReader
+}
+
+///
+/// Compares ropes of bytes against content read from a file or another stream
+///
+pub trait ReaderDiff : Rope<Cell=u8> {
+    ///
+    /// Reads the content of a reader and generates the actions needed to make it and this rope the same
+    ///
+    /// The reader is compared with the rope as it is read: only the part of it that follows the first difference is kept
+    /// in memory. The result is a single `Replace` action covering the region between the common start and end of the
+    /// two (or no actions if they are already the same), so the attributes of the unchanged parts of the rope are kept.
+    ///
+    fn diff_against_reader(&self, reader: impl Read, direction: ReaderDiffDirection) -> io::Result<Vec<RopeAction<u8, Self::Attribute>>>;
+}
+
+impl<R> ReaderDiff for R
+where
+R: Rope<Cell=u8> {
+    fn diff_against_reader(&self, mut reader: impl Read, direction: ReaderDiffDirection) -> io::Result<Vec<RopeAction<u8, Self::Attribute>>> {
+        let len             = self.len();
+        let mut rope_cells  = self.read_cells(0..len);
+        let mut chunk       = vec![0u8; 8192];
+        let mut prefix_len  = 0;
+        let mut matching    = true;
+        let mut remaining   = vec![];
+
+        // Find the common prefix while reading, then store the rest of the reader
+        loop {
+            let num_read = match reader.read(&mut chunk) {
+                Ok(0)                                                   => { break; }
+                Ok(num_read)                                            => num_read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted    => { continue; }
+                Err(err)                                                => { return Err(err); }
+            };
+
+            let mut pos = 0;
+            while matching && pos < num_read {
+                if rope_cells.next() == Some(&chunk[pos]) {
+                    pos         += 1;
+                    prefix_len  += 1;
+                } else {
+                    matching = false;
+                }
+            }
+
+            remaining.extend_from_slice(&chunk[pos..num_read]);
+        }
+
+        if prefix_len == len && remaining.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // Find the common suffix (the longest run of matching bytes at the end of the two remaining regions)
+        let max_suffix  = (len - prefix_len).min(remaining.len());
+        let rope_tail   = self.read_cells((len-max_suffix)..len);
+        let reader_tail = remaining[(remaining.len()-max_suffix)..].iter();
+        let suffix_len  = rope_tail.zip(reader_tail).fold(0, |suffix_len, (a, b)| if a == b { suffix_len + 1 } else { 0 });
+
+        remaining.truncate(remaining.len() - suffix_len);
+        let rope_range = prefix_len..(len - suffix_len);
+
+        match direction {
+            ReaderDiffDirection::UpdateRope     => Ok(vec![RopeAction::Replace(rope_range, remaining)]),
+            ReaderDiffDirection::UpdateReader   => Ok(vec![RopeAction::Replace(prefix_len..(prefix_len + remaining.len()), self.read_cells(rope_range).copied().collect())])
+        }
+    }
+}
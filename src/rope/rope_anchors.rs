@@ -0,0 +1,273 @@
+use crate::api::*;
+
+use std::ops::{Range};
+use std::collections::{HashMap};
+
+// `AnchorBias` is declared in the api module, but is still available as `rope::AnchorBias`
+pub use crate::api::{AnchorBias};
+
+///
+/// Identifies an anchor in a `RopeAnchors` collection
+///
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct AnchorId(pub usize);
+
+///
+/// An anchored range
+///
+#[derive(Clone, PartialEq, Debug)]
+struct Anchor {
+    /// Where the anchor is
+    range: Range<usize>,
+
+    /// The bias to use for the start of the range
+    start_bias: AnchorBias,
+
+    /// The bias to use for the end of the range
+    end_bias: AnchorBias
+}
+
+///
+/// A set of positions and ranges in a rope that are updated as the rope is edited. This can be used to
+/// track things like cursors, selections and breakpoints.
+///
+/// `RopeAnchors` doesn't refer to a rope directly: call `edit` with each action that is applied to the rope
+/// (or use an `AnchoredRope`, which does this automatically)
+///
+#[derive(Clone, Default)]
+pub struct RopeAnchors {
+    /// The anchors in this collection
+    anchors: HashMap<AnchorId, Anchor>,
+
+    /// The ID to assign to the next anchor
+    next_id: usize
+}
+
+impl RopeAnchors {
+    ///
+    /// Creates a new, empty set of anchors
+    ///
+    pub fn new() -> RopeAnchors {
+        RopeAnchors {
+            anchors: HashMap::new(),
+            next_id: 0
+        }
+    }
+
+    ///
+    /// Adds an anchor at a single position in the rope
+    ///
+    pub fn add_anchor(&mut self, pos: usize, bias: AnchorBias) -> AnchorId {
+        self.add_range(pos..pos, bias, bias)
+    }
+
+    ///
+    /// Adds an anchor that tracks a range in the rope
+    ///
+    pub fn add_range(&mut self, range: Range<usize>, start_bias: AnchorBias, end_bias: AnchorBias) -> AnchorId {
+        let id          = AnchorId(self.next_id);
+        self.next_id    += 1;
+
+        self.anchors.insert(id, Anchor { range, start_bias, end_bias });
+
+        id
+    }
+
+    ///
+    /// Removes an anchor from this collection
+    ///
+    pub fn remove(&mut self, id: AnchorId) {
+        self.anchors.remove(&id);
+    }
+
+    ///
+    /// Moves an anchor to a new range
+    ///
+    pub fn move_anchor(&mut self, id: AnchorId, range: Range<usize>) {
+        if let Some(anchor) = self.anchors.get_mut(&id) {
+            anchor.range = range;
+        }
+    }
+
+    ///
+    /// Returns the position of an anchor (the start of the range if the anchor is tracking a range)
+    ///
+    pub fn position(&self, id: AnchorId) -> Option<usize> {
+        self.anchors.get(&id).map(|anchor| anchor.range.start)
+    }
+
+    ///
+    /// Returns the range covered by an anchor
+    ///
+    pub fn range(&self, id: AnchorId) -> Option<Range<usize>> {
+        self.anchors.get(&id).map(|anchor| anchor.range.clone())
+    }
+
+    ///
+    /// Returns the IDs of the anchors in this collection
+    ///
+    pub fn ids<'a>(&'a self) -> impl 'a+Iterator<Item=AnchorId> {
+        self.anchors.keys().copied()
+    }
+
+    ///
+    /// Updates the anchors after the cells in a range have been replaced with `new_len` new cells
+    ///
+    pub fn replace(&mut self, range: Range<usize>, new_len: usize) {
+        for anchor in self.anchors.values_mut() {
+            let start       = map_position(anchor.range.start, anchor.start_bias, &range, new_len);
+            let end         = map_position(anchor.range.end, anchor.end_bias, &range, new_len);
+
+            anchor.range    = start..end.max(start);
+        }
+    }
+
+    ///
+    /// Updates the anchors for an action that has been applied to the rope
+    ///
+    pub fn edit<Cell, Attribute>(&mut self, action: &RopeAction<Cell, Attribute>) {
+        match action {
+            RopeAction::Replace(range, new_cells)               => self.replace(range.clone(), new_cells.len()),
+            RopeAction::ReplaceAttributes(range, new_cells, _)  => self.replace(range.clone(), new_cells.len()),
+            RopeAction::SetAttributes(_, _)                     => { }
+        }
+    }
+}
+
+///
+/// A rope that keeps a set of anchors up to date as it's edited
+///
+#[derive(Clone)]
+pub struct AnchoredRope<BaseRope> 
+where
+BaseRope: RopeMut {
+    /// The rope that's being edited
+    rope: BaseRope,
+
+    /// The anchors for the rope
+    anchors: RopeAnchors
+}
+
+impl<BaseRope> AnchoredRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Creates a new anchored rope with no anchors
+    ///
+    pub fn from(rope: BaseRope) -> AnchoredRope<BaseRope> {
+        AnchoredRope {
+            rope:       rope,
+            anchors:    RopeAnchors::new()
+        }
+    }
+
+    ///
+    /// Returns the anchors for this rope
+    ///
+    pub fn anchors(&self) -> &RopeAnchors {
+        &self.anchors
+    }
+
+    ///
+    /// Returns the anchors for this rope so they can be added, moved or removed
+    ///
+    pub fn anchors_mut(&mut self) -> &mut RopeAnchors {
+        &mut self.anchors
+    }
+}
+
+impl<BaseRope> Rope for AnchoredRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.rope.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        self.rope.read_cells(range)
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.read_chunks(range)
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.rope.read_attributes(pos)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.read_attribute_runs(range)
+    }
+}
+
+impl<BaseRope> RopeMut for AnchoredRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Performs the specified editing action to this rope
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        self.anchors.edit(&action);
+        self.rope.edit(action);
+    }
+
+    ///
+    /// Replaces a range of cells. The attributes applied to the new cells will be the same
+    /// as the attributes that were applied to the first cell in the replacement range
+    ///
+    fn replace<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells) {
+        let new_cells = new_cells.into_iter().collect::<Vec<_>>();
+
+        self.anchors.replace(range.clone(), new_cells.len());
+        self.rope.replace(range, new_cells);
+    }
+
+    ///
+    /// Sets the attributes for a range of cells
+    ///
+    #[inline]
+    fn set_attributes(&mut self, range: Range<usize>, new_attributes: Self::Attribute) {
+        self.rope.set_attributes(range, new_attributes);
+    }
+
+    ///
+    /// Replaces a range of cells and sets the attributes for them.
+    ///
+    fn replace_attributes<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells, new_attributes: Self::Attribute) {
+        let new_cells = new_cells.into_iter().collect::<Vec<_>>();
+
+        self.anchors.replace(range.clone(), new_cells.len());
+        self.rope.replace_attributes(range, new_cells, new_attributes);
+    }
+}
+
+impl<BaseRope> RopeSearch for AnchoredRope<BaseRope>
+where
+BaseRope:       RopeMut,
+BaseRope::Cell: PartialEq { }
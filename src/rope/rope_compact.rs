@@ -0,0 +1,117 @@
+use super::node::*;
+use super::branch::*;
+use super::rope_leaf::*;
+use super::attributed_rope::*;
+
+use std::sync::*;
+
+/// The maximum number of cells in a leaf created by `compact()`
+const COMPACT_LEAF_LENGTH: usize = 1024;
+
+impl<Cell, Attribute> AttributedRope<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Rebuilds this rope as a balanced tree, merging neighbouring leaves that have the same attributes and releasing
+    /// any unused nodes
+    ///
+    /// After a long editing session, a rope can end up with a lot of small leaves and free nodes. Compacting it
+    /// reduces the memory it uses and the depth of the tree. This doesn't change the content of the rope, but all of
+    /// the leaves will be given new IDs, so this is best called at a time when the rope is idle.
+    ///
+    pub fn compact(&mut self) {
+        rope_span!("compact", num_nodes = self.nodes.len(), num_free_nodes = self.free_nodes.len());
+
+        // Gather the runs of cells with the same attributes
+        let mut runs: Vec<(Vec<Cell>, Arc<Attribute>)> = vec![];
+        let mut node_idx = self.leftmost_leaf();
+
+        while let Some(leaf_idx) = node_idx {
+            if let RopeNode::Leaf(_, cells, attribute) = &self.nodes[leaf_idx.idx()] {
+                let is_empty = runs.len() == 0;
+
+                match runs.last_mut() {
+                    Some((run_cells, run_attribute)) if **run_attribute == **attribute  => { run_cells.extend(cells.iter().cloned()); }
+                    _ if cells.len() > 0 || is_empty                                    => { runs.push((cells.to_vec(), Arc::clone(attribute))); }
+                    _                                                                   => { }
+                }
+            }
+
+            node_idx = self.next_leaf_to_the_right(leaf_idx);
+        }
+
+        // An empty first run is only kept if the rope is empty
+        if runs.len() > 1 && runs[0].0.len() == 0 {
+            runs.remove(0);
+        }
+
+        // Divide the runs into leaves
+        let mut leaves = vec![];
+        for (cells, attribute) in runs {
+            if cells.len() <= COMPACT_LEAF_LENGTH {
+                leaves.push((cells, attribute));
+            } else {
+                leaves.extend(cells.chunks(COMPACT_LEAF_LENGTH).map(|chunk| (chunk.to_vec(), Arc::clone(&attribute))));
+            }
+        }
+
+        // Rebuild the tree from the leaves
+        let num_leaves      = leaves.len();
+        self.nodes          = Vec::with_capacity(num_leaves * 2 - 1);
+        self.leaf_ids       = Vec::with_capacity(num_leaves * 2 - 1);
+        self.free_nodes     = vec![];
+        self.root_node_idx  = self.build_balanced(&mut leaves.into_iter(), None, num_leaves);
+
+        rope_event!(num_nodes = self.nodes.len(), num_leaves = num_leaves, "compacted");
+        self.verify_tree("post-compact");
+    }
+
+    ///
+    /// Finds the leaf at the start of the rope
+    ///
+    fn leftmost_leaf(&self) -> Option<RopeNodeIndex> {
+        let mut node_idx = self.root_node_idx;
+
+        loop {
+            match &self.nodes[node_idx.idx()] {
+                RopeNode::Branch(branch)    => { node_idx = branch.left; }
+                RopeNode::Leaf(..)          => { return Some(node_idx); }
+                RopeNode::Empty             => { return None; }
+            }
+        }
+    }
+
+    ///
+    /// Builds a balanced tree from the next `num_leaves` leaves, returning the index of its root node
+    ///
+    fn build_balanced<Leaves: Iterator<Item=(Vec<Cell>, Arc<Attribute>)>>(&mut self, leaves: &mut Leaves, parent: Option<RopeNodeIndex>, num_leaves: usize) -> RopeNodeIndex {
+        if num_leaves <= 1 {
+            // Create a leaf node
+            let (cells, attribute)  = leaves.next().expect("Not enough leaves to build the tree");
+            let node_idx            = RopeNodeIndex(self.nodes.len());
+
+            self.nodes.push(RopeNode::Leaf(parent, Arc::new(cells), attribute));
+            self.leaf_ids.push((LeafId(self.next_leaf_id), None));
+            self.next_leaf_id += 1;
+
+            node_idx
+        } else {
+            // Create a branch, then fill in the two halves
+            let node_idx = RopeNodeIndex(self.nodes.len());
+
+            self.nodes.push(RopeNode::Branch(RopeBranch { left: node_idx, right: node_idx, length: 0, single_run: false, parent: parent }));
+            self.leaf_ids.push((LeafId(0), None));
+
+            let left_count  = num_leaves / 2;
+            let left        = self.build_balanced(leaves, Some(node_idx), left_count);
+            let right       = self.build_balanced(leaves, Some(node_idx), num_leaves - left_count);
+            let length      = self.nodes[left.idx()].len() + self.nodes[right.idx()].len();
+            let single_run  = self.branch_is_single_run(left, right);
+
+            self.nodes[node_idx.idx()] = RopeNode::Branch(RopeBranch { left: left, right: right, length: length, single_run: single_run, parent: parent });
+
+            node_idx
+        }
+    }
+}
@@ -2,8 +2,10 @@ use super::attributed_rope::*;
 
 use crate::api::*;
 
+use std::fmt;
 use std::iter;
-use std::ops::{AddAssign};
+use std::hash::{Hash, Hasher};
+use std::ops::{AddAssign, Index};
 
 // These extensions will work for any implementation of Rope, but Rust doesn't let us a provide a universal implementation
 
@@ -47,14 +49,24 @@ Attribute:  PartialEq+Clone+Default {
             return false;
         }
 
-        // Compare cells
-        let mut cells_a = self.read_cells(0..self.len());
-        let mut cells_b = other.read_cells(0..other.len());
+        // Compare cells a chunk at a time (the leaves of the two ropes may be split at different points, so compare the overlapping part of each pair of chunks)
+        let mut chunks_a    = self.read_chunks(0..self.len()).filter(|chunk| !chunk.is_empty());
+        let mut chunks_b    = other.read_chunks(0..other.len()).filter(|chunk| !chunk.is_empty());
+        let mut chunk_a     = chunks_a.next().unwrap_or(&[]);
+        let mut chunk_b     = chunks_b.next().unwrap_or(&[]);
 
-        while let (Some(a), Some(b)) = (cells_a.next(), cells_b.next()) {
-            if a != b {
+        while !chunk_a.is_empty() && !chunk_b.is_empty() {
+            let overlap = chunk_a.len().min(chunk_b.len());
+
+            if chunk_a[0..overlap] != chunk_b[0..overlap] {
                 return false;
             }
+
+            chunk_a = &chunk_a[overlap..];
+            chunk_b = &chunk_b[overlap..];
+
+            if chunk_a.is_empty() { chunk_a = chunks_a.next().unwrap_or(&[]); }
+            if chunk_b.is_empty() { chunk_b = chunks_b.next().unwrap_or(&[]); }
         }
 
         // Compare attributes (coverage of each attribute may vary between the two styles)
@@ -105,3 +117,149 @@ Attribute:  PartialEq+Clone+Default {
         return true;
     }
 }
+
+///
+/// True if the cells in a rope are the same as the cells in a slice (attributes are not compared)
+///
+fn cells_equal<Cell, Attribute>(rope: &AttributedRope<Cell, Attribute>, cells: &[Cell]) -> bool
+where
+Cell:       Clone+PartialEq,
+Attribute:  PartialEq+Clone+Default {
+    if rope.len() != cells.len() {
+        return false;
+    }
+
+    let mut pos = 0;
+    for chunk in rope.read_chunks(0..rope.len()) {
+        if chunk != &cells[pos..(pos + chunk.len())] {
+            return false;
+        }
+
+        pos += chunk.len();
+    }
+
+    true
+}
+
+impl<Cell, Attribute> PartialEq<[Cell]> for AttributedRope<Cell, Attribute>
+where
+Cell:       Clone+PartialEq,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Compares the cells in this rope with a slice, ignoring the attributes
+    ///
+    fn eq(&self, other: &[Cell]) -> bool {
+        cells_equal(self, other)
+    }
+}
+
+impl<'b, Cell, Attribute> PartialEq<&'b [Cell]> for AttributedRope<Cell, Attribute>
+where
+Cell:       Clone+PartialEq,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Compares the cells in this rope with a slice, ignoring the attributes
+    ///
+    fn eq(&self, other: &&'b [Cell]) -> bool {
+        cells_equal(self, other)
+    }
+}
+
+impl<Cell, Attribute> PartialEq<Vec<Cell>> for AttributedRope<Cell, Attribute>
+where
+Cell:       Clone+PartialEq,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Compares the cells in this rope with a vector, ignoring the attributes
+    ///
+    fn eq(&self, other: &Vec<Cell>) -> bool {
+        cells_equal(self, other)
+    }
+}
+
+impl<Attribute> PartialEq<str> for AttributedRope<u8, Attribute>
+where
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Compares the bytes in this rope with the UTF-8 bytes of a string, ignoring the attributes
+    ///
+    fn eq(&self, other: &str) -> bool {
+        cells_equal(self, other.as_bytes())
+    }
+}
+
+impl<'b, Attribute> PartialEq<&'b str> for AttributedRope<u8, Attribute>
+where
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Compares the bytes in this rope with the UTF-8 bytes of a string, ignoring the attributes
+    ///
+    fn eq(&self, other: &&'b str) -> bool {
+        cells_equal(self, other.as_bytes())
+    }
+}
+
+impl<Cell, Attribute> fmt::Debug for AttributedRope<Cell, Attribute>
+where
+Cell:       Clone+fmt::Debug,
+Attribute:  PartialEq+Clone+Default+fmt::Debug {
+    ///
+    /// Formats the rope as a list of its attribute runs, in the form `[([cells...], attribute), ...]`
+    ///
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.read_attribute_runs(0..self.len())
+                .map(|(attribute, range)| (self.read_cells(range).collect::<Vec<_>>(), attribute)))
+            .finish()
+    }
+}
+
+impl<Cell, Attribute> Hash for AttributedRope<Cell, Attribute>
+where
+Cell:       Clone+Hash,
+Attribute:  PartialEq+Clone+Default+Hash {
+    ///
+    /// Hashes the cells and the attribute runs of this rope
+    ///
+    /// Neighbouring runs with the same attribute are treated as a single run, so ropes that are equal have the same
+    /// hash regardless of how their leaves are arranged.
+    ///
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        self.read_cells(0..self.len()).for_each(|cell| cell.hash(state));
+
+        let mut current_run: Option<(&Attribute, usize)> = None;
+        for (attribute, range) in self.read_attribute_runs(0..self.len()) {
+            match &mut current_run {
+                Some((run_attribute, run_len)) if *run_attribute == attribute   => { *run_len += range.len(); }
+                _                                                               => {
+                    if let Some((run_attribute, run_len)) = current_run {
+                        run_len.hash(state);
+                        run_attribute.hash(state);
+                    }
+
+                    current_run = Some((attribute, range.len()));
+                }
+            }
+        }
+
+        if let Some((run_attribute, run_len)) = current_run {
+            run_len.hash(state);
+            run_attribute.hash(state);
+        }
+    }
+}
+
+impl<Cell, Attribute> Index<usize> for AttributedRope<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    type Output = Cell;
+
+    fn index(&self, pos: usize) -> &Cell {
+        match self.get(pos) {
+            Some(cell)  => cell,
+            None        => panic!("Index {} is out of bounds for a rope of length {}", pos, self.len())
+        }
+    }
+}
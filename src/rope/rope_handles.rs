@@ -0,0 +1,179 @@
+use super::rope_anchors::*;
+
+use crate::api::*;
+
+use std::ops::{Range};
+
+///
+/// A reference to a cell in a `HandleRope`, which follows the cell as the rope is edited
+///
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct CellHandle(AnchorId);
+
+///
+/// A rope that can hand out stable references to its cells
+///
+/// This is useful for ropes that are used as a list of objects: other parts of an application can refer to an item
+/// by its handle and find out where it is using `position_of()`, without needing to track the edits made to the rope.
+/// A handle remains valid until its cell is deleted. If the cell is replaced, the handle refers to the first of the
+/// cells that replaced it.
+///
+#[derive(Clone)]
+pub struct HandleRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// The rope that's being edited
+    rope: BaseRope,
+
+    /// The ranges covered by the cells that have handles
+    handles: RopeAnchors
+}
+
+impl<BaseRope> HandleRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Creates a new handle rope with no handles
+    ///
+    pub fn from(rope: BaseRope) -> HandleRope<BaseRope> {
+        HandleRope {
+            rope:       rope,
+            handles:    RopeAnchors::new()
+        }
+    }
+
+    ///
+    /// Returns a handle for the cell at the specified position, or `None` if the position is beyond the end of the rope
+    ///
+    pub fn handle_at(&mut self, pos: usize) -> Option<CellHandle> {
+        if pos >= self.rope.len() {
+            return None;
+        }
+
+        // The start of the range sticks to the cell, and the end stays before any cells inserted after it
+        Some(CellHandle(self.handles.add_range(pos..(pos+1), AnchorBias::Right, AnchorBias::Left)))
+    }
+
+    ///
+    /// Returns the position of the cell referred to by a handle, or `None` if the cell has been deleted
+    ///
+    pub fn position_of(&self, handle: CellHandle) -> Option<usize> {
+        self.handles.range(handle.0)
+            .filter(|range| range.start < range.end)
+            .map(|range| range.start)
+    }
+
+    ///
+    /// Stops tracking the cell referred to by a handle
+    ///
+    pub fn release(&mut self, handle: CellHandle) {
+        self.handles.remove(handle.0);
+    }
+
+    ///
+    /// Releases the handles for any cells that have been deleted
+    ///
+    pub fn release_deleted(&mut self) {
+        let deleted = self.handles.ids()
+            .filter(|id| self.handles.range(*id).map(|range| range.start >= range.end).unwrap_or(false))
+            .collect::<Vec<_>>();
+
+        deleted.into_iter().for_each(|id| self.handles.remove(id));
+    }
+}
+
+impl<BaseRope> Rope for HandleRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.rope.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        self.rope.read_cells(range)
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.read_chunks(range)
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.rope.read_attributes(pos)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.read_attribute_runs(range)
+    }
+}
+
+impl<BaseRope> RopeMut for HandleRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Performs the specified editing action to this rope
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        self.handles.edit(&action);
+        self.rope.edit(action);
+    }
+
+    ///
+    /// Replaces a range of cells. The attributes applied to the new cells will be the same
+    /// as the attributes that were applied to the first cell in the replacement range
+    ///
+    fn replace<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells) {
+        let new_cells = new_cells.into_iter().collect::<Vec<_>>();
+
+        self.handles.replace(range.clone(), new_cells.len());
+        self.rope.replace(range, new_cells);
+    }
+
+    ///
+    /// Sets the attributes for a range of cells
+    ///
+    #[inline]
+    fn set_attributes(&mut self, range: Range<usize>, new_attributes: Self::Attribute) {
+        self.rope.set_attributes(range, new_attributes);
+    }
+
+    ///
+    /// Replaces a range of cells and sets the attributes for them.
+    ///
+    fn replace_attributes<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells, new_attributes: Self::Attribute) {
+        let new_cells = new_cells.into_iter().collect::<Vec<_>>();
+
+        self.handles.replace(range.clone(), new_cells.len());
+        self.rope.replace_attributes(range, new_cells, new_attributes);
+    }
+}
+
+impl<BaseRope> RopeSearch for HandleRope<BaseRope>
+where
+BaseRope:       RopeMut,
+BaseRope::Cell: PartialEq { }
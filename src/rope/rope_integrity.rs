@@ -0,0 +1,144 @@
+use super::node::*;
+use super::attributed_rope::*;
+
+use std::fmt;
+use std::error::{Error};
+
+///
+/// Describes a problem found in the structure of an `AttributedRope` by `check_integrity()`
+///
+/// Node indexes refer to positions in the rope's internal list of nodes.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RopeIntegrityError {
+    /// A node index that is outside of the list of nodes was found
+    MissingNode { node: usize },
+
+    /// An empty node was found in the tree
+    UnexpectedEmptyNode { node: usize },
+
+    /// A node's parent is not the branch that refers to it
+    IncorrectParent { node: usize, expected: Option<usize>, actual: Option<usize> },
+
+    /// A node was found more than once while walking the tree
+    DuplicateNode { node: usize },
+
+    /// The length of a branch is not the sum of the lengths of its children
+    IncorrectBranchLength { node: usize, expected: usize, actual: usize },
+
+    /// A branch is marked as a single attribute run but its children are not all part of the same run
+    IncorrectRunSummary { node: usize },
+
+    /// A node is in the free list but is not empty
+    FreeNodeNotEmpty { node: usize },
+
+    /// A node is in the free list more than once
+    DuplicateFreeNode { node: usize },
+
+    /// A node is neither part of the tree nor in the free list
+    LeakedNode { node: usize }
+}
+
+impl fmt::Display for RopeIntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use RopeIntegrityError::*;
+
+        match self {
+            MissingNode { node }                             => write!(f, "node {} does not exist", node),
+            UnexpectedEmptyNode { node }                     => write!(f, "node {} is part of the tree but is empty", node),
+            IncorrectParent { node, expected, actual }       => write!(f, "node {} should have parent {:?} but has parent {:?}", node, expected, actual),
+            DuplicateNode { node }                           => write!(f, "node {} appears in the tree more than once", node),
+            IncorrectBranchLength { node, expected, actual } => write!(f, "branch {} should have length {} but has length {}", node, expected, actual),
+            IncorrectRunSummary { node }                     => write!(f, "branch {} is marked as a single attribute run but contains more than one", node),
+            FreeNodeNotEmpty { node }                        => write!(f, "node {} is in the free list but is not empty", node),
+            DuplicateFreeNode { node }                       => write!(f, "node {} is in the free list more than once", node),
+            LeakedNode { node }                              => write!(f, "node {} is not in the tree or the free list", node)
+        }
+    }
+}
+
+impl Error for RopeIntegrityError { }
+
+impl<Cell, Attribute> AttributedRope<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Checks that the internal structure of this rope is valid, returning a description of the first problem found if it is not
+    ///
+    /// This checks that the parent of every node is the branch that refers to it, that branch lengths are the sum of the
+    /// lengths of their children, that branches marked as single attribute runs really are, and that every node is either part of the tree or in the free list (and that free nodes
+    /// are empty). This is not needed in normal use but is useful for detecting bugs when testing or fuzzing code that
+    /// edits ropes.
+    ///
+    pub fn check_integrity(&self) -> Result<(), RopeIntegrityError> {
+        let num_nodes   = self.nodes.len();
+        let mut found   = vec![false; num_nodes];
+
+        // Walk the tree from the root node (using a stack, as the tree might not be balanced)
+        let root_idx = self.root_node_idx.idx();
+        if root_idx >= num_nodes { return Err(RopeIntegrityError::MissingNode { node: root_idx }); }
+        if let Some(parent) = self.nodes[root_idx].parent() { return Err(RopeIntegrityError::IncorrectParent { node: root_idx, expected: None, actual: Some(parent.idx()) }); }
+
+        let mut to_check = vec![root_idx];
+
+        while let Some(node_idx) = to_check.pop() {
+            if found[node_idx] { return Err(RopeIntegrityError::DuplicateNode { node: node_idx }); }
+            found[node_idx] = true;
+
+            match &self.nodes[node_idx] {
+                RopeNode::Empty     => { return Err(RopeIntegrityError::UnexpectedEmptyNode { node: node_idx }); }
+                RopeNode::Leaf(..)  => { }
+
+                RopeNode::Branch(branch) => {
+                    let mut length = 0;
+
+                    for child in [branch.left.idx(), branch.right.idx()] {
+                        if child >= num_nodes { return Err(RopeIntegrityError::MissingNode { node: child }); }
+
+                        let child_parent = self.nodes[child].parent().map(|parent| parent.idx());
+                        if child_parent != Some(node_idx) {
+                            return Err(RopeIntegrityError::IncorrectParent { node: child, expected: Some(node_idx), actual: child_parent });
+                        }
+
+                        length += self.nodes[child].len();
+                        to_check.push(child);
+                    }
+
+                    if length != branch.length {
+                        return Err(RopeIntegrityError::IncorrectBranchLength { node: node_idx, expected: length, actual: branch.length });
+                    }
+                }
+            }
+        }
+
+        // Branches marked as a single run must have children that are also single runs with the same attribute
+        for (node_idx, (node, in_tree)) in self.nodes.iter().zip(found.iter()).enumerate() {
+            if !in_tree { continue; }
+
+            if let RopeNode::Branch(branch) = node {
+                if branch.single_run && !self.branch_is_single_run(branch.left, branch.right) {
+                    return Err(RopeIntegrityError::IncorrectRunSummary { node: node_idx });
+                }
+            }
+        }
+
+        // Every free node must be empty and not part of the tree
+        let mut is_free = vec![false; num_nodes];
+
+        for free_idx in self.free_nodes.iter().copied() {
+            if free_idx >= num_nodes                            { return Err(RopeIntegrityError::MissingNode { node: free_idx }); }
+            if is_free[free_idx]                                { return Err(RopeIntegrityError::DuplicateFreeNode { node: free_idx }); }
+            if !matches!(self.nodes[free_idx], RopeNode::Empty) { return Err(RopeIntegrityError::FreeNodeNotEmpty { node: free_idx }); }
+
+            is_free[free_idx] = true;
+        }
+
+        // Every node must either be free or in the tree
+        if let Some(leaked) = (0..num_nodes).find(|idx| !found[*idx] && !is_free[*idx]) {
+            return Err(RopeIntegrityError::LeakedNode { node: leaked });
+        }
+
+        Ok(())
+    }
+}
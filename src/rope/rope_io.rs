@@ -0,0 +1,187 @@
+use crate::api::*;
+
+use std::io;
+use std::io::{Read, Write, Seek, SeekFrom};
+
+///
+/// Reads the bytes in a rope using the standard `io::Read` and `io::Seek` traits
+///
+/// The rope is read a chunk at a time, so this can be used to save a rope or to pass its contents to a parser without
+/// copying it first.
+///
+pub struct RopeReader<'a, R: ?Sized> {
+    /// The rope that is being read
+    rope: &'a R,
+
+    /// The position of the next byte to read
+    pos: usize
+}
+
+///
+/// Writes to a rope using the standard `io::Write` trait
+///
+/// Writers either insert the bytes that are written at their position (so `append()` adds to the end of the rope) or
+/// overwrite the bytes that follow their position, extending the rope if they reach the end.
+///
+pub struct RopeWriter<'a, R: ?Sized> {
+    /// The rope that is being written to
+    rope: &'a mut R,
+
+    /// The position where the next bytes will be written
+    pos: usize,
+
+    /// True if written bytes replace the existing bytes rather than being inserted before them
+    overwrite: bool
+}
+
+///
+/// Works out the position that a seek operation moves to
+///
+fn seek_position(pos: usize, len: usize, seek: SeekFrom) -> io::Result<usize> {
+    let new_pos = match seek {
+        SeekFrom::Start(offset)     => offset as i128,
+        SeekFrom::End(offset)       => len as i128 + offset as i128,
+        SeekFrom::Current(offset)   => pos as i128 + offset as i128
+    };
+
+    if new_pos >= 0 && new_pos <= usize::MAX as i128 {
+        Ok(new_pos as usize)
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative or overflowing position"))
+    }
+}
+
+impl<'a, R> RopeReader<'a, R>
+where
+R: ?Sized+Rope<Cell=u8> {
+    ///
+    /// Creates a reader that starts at the beginning of a rope
+    ///
+    pub fn new(rope: &'a R) -> RopeReader<'a, R> {
+        RopeReader {
+            rope:   rope,
+            pos:    0
+        }
+    }
+
+    ///
+    /// The position of the next byte that will be read
+    ///
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+}
+
+impl<'a, R> Read for RopeReader<'a, R>
+where
+R: ?Sized+Rope<Cell=u8> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.rope.len();
+        if self.pos >= len { return Ok(0); }
+
+        let end         = len.min(self.pos + buf.len());
+        let mut written = 0;
+
+        for chunk in self.rope.read_chunks(self.pos..end) {
+            buf[written..(written + chunk.len())].copy_from_slice(chunk);
+            written += chunk.len();
+        }
+
+        self.pos += written;
+        Ok(written)
+    }
+}
+
+impl<'a, R> Seek for RopeReader<'a, R>
+where
+R: ?Sized+Rope<Cell=u8> {
+    ///
+    /// Moves the reader to a new position (positions beyond the end of the rope are allowed, but nothing can be read there)
+    ///
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.pos = seek_position(self.pos, self.rope.len(), pos)?;
+
+        Ok(self.pos as u64)
+    }
+}
+
+impl<'a, R> RopeWriter<'a, R>
+where
+R: ?Sized+RopeMut<Cell=u8> {
+    ///
+    /// Creates a writer that adds bytes to the end of a rope
+    ///
+    pub fn append(rope: &'a mut R) -> RopeWriter<'a, R> {
+        let pos = rope.len();
+
+        RopeWriter {
+            rope:       rope,
+            pos:        pos,
+            overwrite:  false
+        }
+    }
+
+    ///
+    /// Creates a writer that inserts bytes into a rope at the specified position
+    ///
+    pub fn insert(rope: &'a mut R, pos: usize) -> RopeWriter<'a, R> {
+        let pos = pos.min(rope.len());
+
+        RopeWriter {
+            rope:       rope,
+            pos:        pos,
+            overwrite:  false
+        }
+    }
+
+    ///
+    /// Creates a writer that replaces the bytes in a rope, starting at the specified position
+    ///
+    pub fn overwrite(rope: &'a mut R, pos: usize) -> RopeWriter<'a, R> {
+        let pos = pos.min(rope.len());
+
+        RopeWriter {
+            rope:       rope,
+            pos:        pos,
+            overwrite:  true
+        }
+    }
+
+    ///
+    /// The position where the next bytes will be written
+    ///
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+}
+
+impl<'a, R> Write for RopeWriter<'a, R>
+where
+R: ?Sized+RopeMut<Cell=u8> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let end = if self.overwrite { self.rope.len().min(self.pos + buf.len()) } else { self.pos };
+
+        self.rope.replace(self.pos..end, buf.iter().copied());
+        self.pos += buf.len();
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a, R> Seek for RopeWriter<'a, R>
+where
+R: ?Sized+RopeMut<Cell=u8> {
+    ///
+    /// Moves the writer to a new position (positions beyond the end of the rope are moved to the end)
+    ///
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let len     = self.rope.len();
+        self.pos    = seek_position(self.pos, len, pos)?.min(len);
+
+        Ok(self.pos as u64)
+    }
+}
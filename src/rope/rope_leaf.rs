@@ -0,0 +1,38 @@
+use std::ops::{Range};
+
+///
+/// Identifies the contents of a leaf node in an `AttributedRope`
+///
+/// A leaf keeps its ID for as long as its cells and attribute are unchanged, even if the cells around it are edited
+/// and its position in the rope moves. Any edit to the leaf itself will give it a new ID, so the ID can be used as
+/// the key for a cache of data derived from the leaf's cells (shaped glyphs for a run of text, for example).
+///
+/// IDs are unique within a rope and its clones, but ropes created separately can re-use the same IDs.
+///
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct LeafId(pub u64);
+
+///
+/// Describes a leaf read from an `AttributedRope` by `read_leaves()`
+///
+#[derive(Clone, PartialEq, Debug)]
+pub struct RopeLeaf<'a, Cell, Attribute> {
+    /// The ID of the leaf that these cells are read from
+    pub id: LeafId,
+
+    /// If this leaf was created by splitting another leaf and hasn't been edited since, the ID of the original leaf
+    ///
+    /// The cells in this leaf will be a contiguous subset of the cells of the original
+    pub split_from: Option<LeafId>,
+
+    /// The range in the rope covered by the cells
+    pub range: Range<usize>,
+
+    /// The cells read from this leaf
+    ///
+    /// If the range being read starts or ends in the middle of a leaf, only the cells within the range are returned
+    pub cells: &'a [Cell],
+
+    /// The attribute applied to the cells in this leaf
+    pub attribute: &'a Attribute
+}
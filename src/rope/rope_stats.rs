@@ -0,0 +1,108 @@
+use super::node::*;
+use super::rope_leaf::*;
+use super::attributed_rope::*;
+
+use std::mem;
+use std::sync::*;
+use std::collections::{HashSet};
+
+///
+/// Statistics describing the internal structure of an `AttributedRope`, returned by `AttributedRope::stats()`
+///
+/// These are intended for tuning and diagnostics: a large number of free nodes or very small leaves relative to the
+/// length of the rope indicates that it has become fragmented.
+///
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RopeStats {
+    /// The number of cells in the rope
+    pub num_cells: usize,
+
+    /// The total number of node slots allocated (including free nodes)
+    pub num_nodes: usize,
+
+    /// The number of branch nodes in the tree
+    pub num_branches: usize,
+
+    /// The number of leaf nodes in the tree
+    pub num_leaves: usize,
+
+    /// The number of leaf nodes that contain no cells
+    pub num_empty_leaves: usize,
+
+    /// The number of node slots that are not currently in use
+    pub num_free_nodes: usize,
+
+    /// The number of nodes on the longest path from the root node to a leaf (a rope with a single leaf has depth 1)
+    pub depth: usize,
+
+    /// The number of leaves of each size. Entry 0 counts empty leaves, and entry `n` counts leaves with a length in
+    /// the range `2^(n-1)..2^n`
+    pub leaf_size_histogram: Vec<usize>,
+
+    /// Approximate number of bytes allocated on the heap by the rope. Cells that are shared with other ropes are
+    /// included in the count, but cells shared between leaves of this rope are only counted once.
+    pub heap_bytes: usize
+}
+
+impl<Cell, Attribute> AttributedRope<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Gathers statistics about the structure and memory usage of this rope
+    ///
+    pub fn stats(&self) -> RopeStats {
+        let mut stats = RopeStats {
+            num_nodes:      self.nodes.len(),
+            num_free_nodes: self.free_nodes.len(),
+            heap_bytes:     self.nodes.capacity() * mem::size_of::<RopeNode<Cell, Attribute>>()
+                            + self.free_nodes.capacity() * mem::size_of::<usize>()
+                            + self.leaf_ids.capacity() * mem::size_of::<(LeafId, Option<LeafId>)>(),
+            ..RopeStats::default()
+        };
+
+        // The Arc header is two reference counts
+        let arc_overhead    = 2 * mem::size_of::<usize>();
+        let mut seen_cells  = HashSet::new();
+        let mut seen_attrs  = HashSet::new();
+
+        // Walk the tree to find the depth and the leaves
+        let mut to_visit = vec![(self.root_node_idx, 1)];
+
+        while let Some((node_idx, depth)) = to_visit.pop() {
+            stats.depth = stats.depth.max(depth);
+
+            match &self.nodes[node_idx.idx()] {
+                RopeNode::Empty => { }
+
+                RopeNode::Branch(branch) => {
+                    stats.num_branches += 1;
+
+                    to_visit.push((branch.left, depth+1));
+                    to_visit.push((branch.right, depth+1));
+                }
+
+                RopeNode::Leaf(_, cells, attribute) => {
+                    stats.num_leaves    += 1;
+                    stats.num_cells     += cells.len();
+                    if cells.len() == 0 { stats.num_empty_leaves += 1; }
+
+                    // Bucket 0 is empty leaves, and bucket n is leaves with a length less than 2^n
+                    let bucket = (usize::BITS - cells.len().leading_zeros()) as usize;
+                    if stats.leaf_size_histogram.len() <= bucket { stats.leaf_size_histogram.resize(bucket+1, 0); }
+                    stats.leaf_size_histogram[bucket] += 1;
+
+                    // Count the cells and attributes (but only once if they're shared between leaves)
+                    if seen_cells.insert(Arc::as_ptr(cells)) {
+                        stats.heap_bytes += arc_overhead + mem::size_of::<Vec<Cell>>() + cells.capacity() * mem::size_of::<Cell>();
+                    }
+                    if seen_attrs.insert(Arc::as_ptr(attribute)) {
+                        stats.heap_bytes += arc_overhead + mem::size_of::<Attribute>();
+                    }
+                }
+            }
+        }
+
+        stats
+    }
+}
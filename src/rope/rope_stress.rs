@@ -0,0 +1,383 @@
+use super::attributed_rope::*;
+
+use crate::api::*;
+use crate::stream_rope::*;
+
+use std::iter;
+use std::ops::{Range};
+
+///
+/// A simple (and slow) rope that stores a vec of cells and a separate attribute for every cell
+///
+/// This is used as the reference implementation when stress testing other ropes: its behaviour is
+/// intended to be the same as `AttributedRope`, but it is simple enough to be obviously correct.
+///
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RopeOracle<Cell, Attribute> {
+    /// The cells in this rope
+    cells: Vec<Cell>,
+
+    /// The attribute for each cell in this rope
+    attributes: Vec<Attribute>,
+
+    /// The attribute reported for an empty rope
+    default_attribute: Attribute
+}
+
+impl<Cell, Attribute> RopeOracle<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Creates a new oracle with the same content as an existing rope
+    ///
+    pub fn from_rope<TRope: ?Sized+Rope<Cell=Cell, Attribute=Attribute>>(rope: &TRope) -> RopeOracle<Cell, Attribute> {
+        let len         = rope.len();
+        let cells       = rope.read_cells(0..len).cloned().collect();
+        let attributes  = rope.read_attribute_runs(0..len)
+            .flat_map(|(attribute, range)| iter::repeat_n(attribute.clone(), range.len()))
+            .collect();
+
+        RopeOracle {
+            cells:              cells,
+            attributes:         attributes,
+            default_attribute:  Attribute::default()
+        }
+    }
+
+    ///
+    /// Returns the attribute that should be applied to cells inserted at the specified position
+    ///
+    fn attribute_for_insertion(&self, pos: usize) -> Attribute {
+        if self.attributes.len() == 0 {
+            // Rope is empty (the attributes used by other ropes in this case depend on their history, so this is only an approximation)
+            self.default_attribute.clone()
+        } else if pos == 0 {
+            // The new cells get the attributes of the first cell
+            self.attributes[0].clone()
+        } else {
+            // New cells get the attributes of the cell to their left
+            self.attributes[(pos-1).min(self.attributes.len()-1)].clone()
+        }
+    }
+
+    ///
+    /// Limits a range to the cells in this rope
+    ///
+    fn clip_range(&self, range: Range<usize>) -> Range<usize> {
+        let len     = self.cells.len();
+        let start   = range.start.min(len);
+        let end     = range.end.min(len).max(start);
+
+        start..end
+    }
+
+    ///
+    /// Replaces a range of cells and gives them the specified attribute
+    ///
+    fn replace_with_attribute(&mut self, range: Range<usize>, new_cells: Vec<Cell>, attribute: Attribute) {
+        let range   = self.clip_range(range);
+        let new_len = new_cells.len();
+
+        self.cells.splice(range.clone(), new_cells);
+        self.attributes.splice(range, iter::repeat_n(attribute, new_len));
+    }
+}
+
+impl<Cell, Attribute> Rope for RopeOracle<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    type Cell       = Cell;
+    type Attribute  = Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        Box::new(self.cells[self.clip_range(range)].iter())
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        if self.attributes.len() == 0 {
+            return (&self.default_attribute, 0..0);
+        }
+
+        let pos         = pos.min(self.attributes.len()-1);
+        let attribute   = &self.attributes[pos];
+
+        // Search for the extent of the attribute
+        let mut start   = pos;
+        let mut end     = pos+1;
+
+        while start > 0 && self.attributes[start-1] == *attribute                   { start -= 1; }
+        while end < self.attributes.len() && self.attributes[end] == *attribute     { end += 1; }
+
+        (attribute, start..end)
+    }
+}
+
+impl<Cell, Attribute> RopeMut for RopeOracle<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Performs the specified editing action to this rope
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        match action {
+            RopeAction::Replace(range, new_cells) => {
+                let attribute = self.attribute_for_insertion(range.start.min(self.cells.len()));
+                self.replace_with_attribute(range, new_cells, attribute);
+            }
+
+            RopeAction::SetAttributes(range, attribute) => {
+                let range = self.clip_range(range);
+                self.attributes[range].iter_mut().for_each(|old_attribute| *old_attribute = attribute.clone());
+            }
+
+            RopeAction::ReplaceAttributes(range, new_cells, attribute) => {
+                self.replace_with_attribute(range, new_cells, attribute);
+            }
+        }
+    }
+}
+
+///
+/// A deterministic pseudo-random number generator (xorshift64*) used to generate stress test actions
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StressRandom {
+    state: u64
+}
+
+impl StressRandom {
+    ///
+    /// Creates a new random number generator from a seed. The same seed will always produce the same sequence of numbers.
+    ///
+    pub fn new(seed: u64) -> StressRandom {
+        // The state must never be 0
+        StressRandom { state: seed ^ 0x2545_f491_4f6c_dd1d }
+    }
+
+    ///
+    /// Returns the next random number in the sequence
+    ///
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    ///
+    /// Returns a random number in the specified range (which must not be empty)
+    ///
+    pub fn next_range(&mut self, range: Range<usize>) -> usize {
+        range.start + (self.next_u64() % (range.len() as u64)) as usize
+    }
+}
+
+///
+/// Describes the point where a stress test found a difference between a rope and the oracle
+///
+#[derive(Clone, PartialEq, Debug)]
+pub struct RopeStressFailure {
+    /// The step where the failure was found
+    pub step: usize,
+
+    /// The action that was applied just before the failure was found
+    pub action: Option<RopeAction<u8, u8>>,
+
+    /// A description of the failure
+    pub description: String
+}
+
+///
+/// Generates random sequences of actions and checks that a rope produces the same results as a `RopeOracle`
+///
+/// The ropes tested have `u8` cells and `u8` attributes. The same seed will always produce the same sequence
+/// of actions, so failures can be reproduced.
+///
+#[derive(Clone, Debug)]
+pub struct RopeStressTest {
+    /// The random number generator for this test
+    random: StressRandom,
+
+    /// The length that the test will try not to grow the rope beyond
+    max_length: usize,
+
+    /// The number of distinct attributes to use
+    num_attributes: u8
+}
+
+impl RopeStressTest {
+    ///
+    /// Creates a new stress test with the specified seed
+    ///
+    pub fn new(seed: u64) -> RopeStressTest {
+        RopeStressTest {
+            random:         StressRandom::new(seed),
+            max_length:     1024,
+            num_attributes: 4
+        }
+    }
+
+    ///
+    /// Sets the length that the test will try to avoid growing the rope beyond
+    ///
+    pub fn with_max_length(mut self, max_length: usize) -> RopeStressTest {
+        self.max_length = max_length;
+        self
+    }
+
+    ///
+    /// Sets the number of distinct attribute values to use (fewer values makes it more likely that leaves will be coalesced)
+    ///
+    pub fn with_num_attributes(mut self, num_attributes: u8) -> RopeStressTest {
+        self.num_attributes = num_attributes.max(1);
+        self
+    }
+
+    ///
+    /// Generates a random range in a rope of the specified length
+    ///
+    fn random_range(&mut self, len: usize, max_range_len: usize) -> Range<usize> {
+        let start   = self.random.next_range(0..(len+1));
+        let end     = (start + self.random.next_range(0..(max_range_len+1))).min(len);
+
+        start..end
+    }
+
+    ///
+    /// Generates some random cells
+    ///
+    fn random_cells(&mut self, max_cells: usize) -> Vec<u8> {
+        let num_cells = self.random.next_range(0..(max_cells+1));
+
+        (0..num_cells).map(|_| b'a' + self.random.next_range(0..26) as u8).collect()
+    }
+
+    ///
+    /// Generates a random action for a rope of the specified length
+    ///
+    pub fn random_action(&mut self, len: usize) -> RopeAction<u8, u8> {
+        // Occasionally make large edits so that leaves get split, and delete more if the rope is getting too long
+        let max_cells       = if self.random.next_range(0..16) == 0 { 100 } else { 8 };
+        let max_range_len   = if len > self.max_length { 200 } else { 8 };
+        let max_cells       = if len > self.max_length { 0 } else { max_cells };
+
+        // The attributes of cells added to an empty rope by `Replace` aren't well-defined, so always set them explicitly
+        let action_type = if len == 0 { 9 } else { self.random.next_range(0..10) };
+
+        match action_type {
+            0..=5   => {
+                let range   = self.random_range(len, max_range_len);
+                let cells   = self.random_cells(max_cells);
+
+                RopeAction::Replace(range, cells)
+            }
+
+            6..=7   => {
+                let range       = self.random_range(len, max_range_len.max(32));
+                let attribute   = self.random.next_range(0..(self.num_attributes as usize)) as u8;
+
+                RopeAction::SetAttributes(range, attribute)
+            }
+
+            _       => {
+                let range       = self.random_range(len, max_range_len);
+                let cells       = self.random_cells(max_cells);
+                let attribute   = self.random.next_range(0..(self.num_attributes as usize)) as u8;
+
+                RopeAction::ReplaceAttributes(range, cells, attribute)
+            }
+        }
+    }
+
+    ///
+    /// Checks that a rope has the same cells and attributes as an oracle
+    ///
+    fn compare<TRope: ?Sized+Rope<Cell=u8, Attribute=u8>>(rope: &TRope, oracle: &RopeOracle<u8, u8>, compare_attributes: bool) -> Result<(), String> {
+        if rope.len() != oracle.len() {
+            return Err(format!("Length is {} but should be {}", rope.len(), oracle.len()));
+        }
+
+        let len = oracle.len();
+
+        if !rope.read_cells(0..len).eq(oracle.read_cells(0..len)) {
+            return Err(format!("Cells are {:?} but should be {:?}", rope.read_cells(0..len).collect::<Vec<_>>(), oracle.cells));
+        }
+
+        if compare_attributes {
+            let rope_runs   = rope.read_attribute_runs(0..len).collect::<Vec<_>>();
+            let oracle_runs = oracle.read_attribute_runs(0..len).collect::<Vec<_>>();
+
+            if rope_runs != oracle_runs {
+                return Err(format!("Attribute runs are {:?} but should be {:?}", rope_runs, oracle_runs));
+            }
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Applies a number of random actions to a rope, checking that it produces the same results as the oracle after every step
+    ///
+    pub fn run<TRope: RopeMut<Cell=u8, Attribute=u8>>(&mut self, rope: &mut TRope, steps: usize) -> Result<(), RopeStressFailure> {
+        let mut oracle = RopeOracle::from_rope(rope);
+
+        for step in 0..steps {
+            let action = self.random_action(oracle.len());
+
+            rope.edit(action.clone());
+            oracle.edit(action.clone());
+
+            Self::compare(rope, &oracle, true)
+                .map_err(|description| RopeStressFailure { step, action: Some(action), description })?;
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Applies random actions to a `PullRope`, occasionally pulling the changes and applying them to a mirror, which is
+    /// checked against the rope's cells. The rope itself is checked against the oracle after every step.
+    ///
+    pub fn run_pull(&mut self, steps: usize) -> Result<(), RopeStressFailure> {
+        let mut rope    = PullRope::from(AttributedRope::<u8, u8>::new(), || { });
+        let mut oracle  = RopeOracle::from_rope(&rope);
+        let mut mirror  = RopeOracle::from_rope(&rope);
+
+        for step in 0..steps {
+            let action = self.random_action(oracle.len());
+
+            rope.edit(action.clone());
+            oracle.edit(action.clone());
+
+            Self::compare(&rope, &oracle, true)
+                .map_err(|description| RopeStressFailure { step, action: Some(action), description })?;
+
+            // Pull roughly every 4 steps
+            if self.random.next_range(0..4) == 0 {
+                rope.pull_changes().for_each(|change| mirror.edit(change));
+
+                Self::compare(&mirror, &oracle, false)
+                    .map_err(|description| RopeStressFailure { step, action: None, description: format!("Pulled changes are incorrect: {}", description) })?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,339 @@
+use super::attributed_rope::*;
+
+use crate::api::*;
+
+use std::iter;
+use std::slice;
+use std::ops::{Range};
+
+///
+/// A sparse rope represents a sequence where most of the cells are a 'default' value, such as a timeline of
+/// keyframes. Only the cells that have been explicitly written are stored in the underlying rope: the gaps between
+/// them are read as the default cell with the default attribute, without taking up any storage.
+///
+/// Writing cells to the rope always stores them (even if they're the same as the default cell). Gaps can be
+/// created with `insert_gap()` or `clear()`, and converted back into stored cells with `fill_gaps()`.
+/// Setting the attributes of a range will fill any gaps in that range, as gaps always have the default attribute.
+///
+#[derive(Clone)]
+pub struct SparseRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// The rope containing the cells that have been stored
+    rope: BaseRope,
+
+    /// The layout of the rope: this is true where cells are stored in the base rope, and false where there are gaps
+    layout: AttributedRope<(), bool>,
+
+    /// The value read from the cells in a gap
+    default_cell: BaseRope::Cell,
+
+    /// The attribute of the cells in a gap
+    default_attribute: BaseRope::Attribute
+}
+
+impl<BaseRope> SparseRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Creates a new sparse rope. All of the cells in the base rope are treated as stored cells, and gaps will be
+    /// read as `default_cell`.
+    ///
+    pub fn from(rope: BaseRope, default_cell: BaseRope::Cell) -> SparseRope<BaseRope> {
+        let mut layout = AttributedRope::new();
+        layout.replace_attributes(0..0, iter::repeat_n((), rope.len()), true);
+
+        SparseRope {
+            rope:               rope,
+            layout:             layout,
+            default_cell:       default_cell,
+            default_attribute:  BaseRope::Attribute::default()
+        }
+    }
+
+    ///
+    /// Retrieves the number of cells that are actually stored in this rope
+    ///
+    pub fn stored_len(&self) -> usize {
+        self.rope.len()
+    }
+
+    ///
+    /// Returns the ranges that are gaps (containing no stored cells) within a range of this rope
+    ///
+    pub fn gaps<'a>(&'a self, range: Range<usize>) -> impl 'a+Iterator<Item=Range<usize>> {
+        self.layout.read_attribute_runs(range)
+            .filter(|(is_stored, _)| !**is_stored)
+            .map(|(_, gap_range)| gap_range)
+    }
+
+    ///
+    /// Returns the ranges that contain stored cells within a range of this rope
+    ///
+    pub fn stored_ranges<'a>(&'a self, range: Range<usize>) -> impl 'a+Iterator<Item=Range<usize>> {
+        self.layout.read_attribute_runs(range)
+            .filter(|(is_stored, _)| **is_stored)
+            .map(|(_, stored_range)| stored_range)
+    }
+
+    ///
+    /// True if there are no gaps in the specified range
+    ///
+    pub fn is_dense(&self, range: Range<usize>) -> bool {
+        self.gaps(range).next().is_none()
+    }
+
+    ///
+    /// Inserts a gap of `length` default cells at the specified position
+    ///
+    pub fn insert_gap(&mut self, pos: usize, length: usize) {
+        if length == 0 { return; }
+
+        self.layout.replace_attributes(pos..pos, iter::repeat_n((), length), false);
+    }
+
+    ///
+    /// Turns a range of cells into a gap, removing any cells stored there from the underlying rope
+    ///
+    pub fn clear(&mut self, range: Range<usize>) {
+        let range = range.start.min(self.len())..range.end.min(self.len());
+        if range.start >= range.end { return; }
+
+        let stored_range = self.stored_index(range.start)..self.stored_index(range.end);
+        if stored_range.start < stored_range.end {
+            self.rope.replace(stored_range, iter::empty());
+        }
+
+        self.layout.set_attributes(range, false);
+    }
+
+    ///
+    /// Stores default cells in any gaps in the specified range, so they can be edited or have attributes set on them
+    ///
+    pub fn fill_gaps(&mut self, range: Range<usize>) {
+        let gaps = self.gaps(range).collect::<Vec<_>>();
+
+        for gap in gaps {
+            let stored_pos = self.stored_index(gap.start);
+
+            self.rope.replace_attributes(stored_pos..stored_pos, iter::repeat_n(self.default_cell.clone(), gap.len()), self.default_attribute.clone());
+            self.layout.set_attributes(gap, true);
+        }
+    }
+
+    ///
+    /// Divides a range of this rope into runs, returning the range of the stored rope for each stored run (or `None` for
+    /// a gap) along with the length of the run
+    ///
+    fn stored_runs(&self, range: Range<usize>) -> Vec<(Option<Range<usize>>, usize)> {
+        let mut stored_pos = self.stored_index(range.start);
+
+        self.layout.read_attribute_runs(range)
+            .map(|(is_stored, run_range)| {
+                if *is_stored {
+                    let stored_range    = stored_pos..(stored_pos + run_range.len());
+                    stored_pos          = stored_range.end;
+
+                    (Some(stored_range), run_range.len())
+                } else {
+                    (None, run_range.len())
+                }
+            })
+            .collect()
+    }
+
+    ///
+    /// Finds the index in the base rope of the first stored cell at or after the specified position
+    ///
+    fn stored_index(&self, pos: usize) -> usize {
+        if pos == 0 { return 0; }
+
+        self.layout.read_attribute_runs(0..pos)
+            .filter(|(is_stored, _)| **is_stored)
+            .map(|(_, stored_range)| stored_range.len())
+            .sum()
+    }
+}
+
+impl<BaseRope> SparseRope<BaseRope>
+where
+BaseRope:       RopeMut,
+BaseRope::Cell: PartialEq {
+    ///
+    /// Converts any stored cells in a range that are the same as the default cell and have the default attribute back into gaps
+    ///
+    pub fn detect_gaps(&mut self, range: Range<usize>) {
+        // Find the runs of default cells within the stored ranges
+        let mut default_runs: Vec<Range<usize>> = vec![];
+
+        for stored_range in self.stored_ranges(range) {
+            let stored_start = self.stored_index(stored_range.start);
+
+            for (attribute, attribute_range) in self.rope.read_attribute_runs(stored_start..(stored_start + stored_range.len())) {
+                if *attribute != self.default_attribute { continue; }
+
+                // Convert back to a position in this rope
+                let run_start = attribute_range.start - stored_start + stored_range.start;
+
+                for (offset, cell) in self.rope.read_cells(attribute_range).enumerate() {
+                    if *cell != self.default_cell { continue; }
+
+                    let pos = run_start + offset;
+                    match default_runs.last_mut() {
+                        Some(last_run) if last_run.end == pos   => { last_run.end = pos + 1; }
+                        _                                       => { default_runs.push(pos..(pos+1)); }
+                    }
+                }
+            }
+        }
+
+        // Clear the runs, starting at the end so the earlier runs aren't moved
+        for default_run in default_runs.into_iter().rev() {
+            self.clear(default_run);
+        }
+    }
+}
+
+impl<BaseRope> Rope for SparseRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.layout.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        let runs = self.stored_runs(range);
+
+        Box::new(runs.into_iter()
+            .flat_map(move |(stored_range, len)| -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+                match stored_range {
+                    Some(stored_range)  => self.rope.read_cells(stored_range),
+                    None                => Box::new(iter::repeat_n(&self.default_cell, len))
+                }
+            }))
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order (each cell in a gap is a separate slice)
+    ///
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        let runs = self.stored_runs(range);
+
+        Box::new(runs.into_iter()
+            .flat_map(move |(stored_range, len)| -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+                match stored_range {
+                    Some(stored_range)  => self.rope.read_chunks(stored_range),
+                    None                => Box::new(iter::repeat_n(slice::from_ref(&self.default_cell), len))
+                }
+            }))
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        if self.layout.len() == 0 { return (&self.default_attribute, 0..0); }
+
+        let (is_stored, layout_range) = self.layout.read_attributes(pos);
+
+        if !*is_stored {
+            // Gaps always have the default attribute
+            (&self.default_attribute, layout_range)
+        } else {
+            // Read from the stored cells, limiting the range to the stored cells in this run
+            let stored_start            = self.stored_index(layout_range.start);
+            let stored_pos              = stored_start + pos.min(layout_range.end-1) - layout_range.start;
+            let (attribute, stored_run) = self.rope.read_attributes(stored_pos);
+
+            let start   = stored_run.start.max(stored_start) - stored_start + layout_range.start;
+            let end     = stored_run.end.min(stored_start + layout_range.len()) - stored_start + layout_range.start;
+
+            (attribute, start..end)
+        }
+    }
+}
+
+impl<BaseRope> RopeMut for SparseRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Performs the specified editing action to this rope
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        match action {
+            RopeAction::Replace(range, cells)                           => self.replace(range, cells),
+            RopeAction::SetAttributes(range, attribute)                 => self.set_attributes(range, attribute),
+            RopeAction::ReplaceAttributes(range, cells, attribute)      => self.replace_attributes(range, cells, attribute)
+        }
+    }
+
+    ///
+    /// Replaces a range of cells. The new cells are always stored, and take their attributes from the stored cells
+    /// before them in the underlying rope.
+    ///
+    fn replace<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells) {
+        let range           = range.start.min(self.len())..range.end.min(self.len());
+        let new_cells       = new_cells.into_iter().collect::<Vec<_>>();
+        let num_cells       = new_cells.len();
+        let stored_range    = self.stored_index(range.start)..self.stored_index(range.end);
+
+        self.rope.replace(stored_range, new_cells);
+        self.layout.replace_attributes(range, iter::repeat_n((), num_cells), true);
+    }
+
+    ///
+    /// Sets the attributes for a range of cells, filling in any gaps in the range
+    ///
+    fn set_attributes(&mut self, range: Range<usize>, new_attributes: Self::Attribute) {
+        let range = range.start.min(self.len())..range.end.min(self.len());
+        if range.start >= range.end { return; }
+
+        self.fill_gaps(range.clone());
+
+        let stored_range = self.stored_index(range.start)..self.stored_index(range.end);
+        self.rope.set_attributes(stored_range, new_attributes);
+    }
+
+    ///
+    /// Resets the attributes for a range of cells to the default attribute (gaps already have the default attribute, so are left as they are)
+    ///
+    fn clear_attributes(&mut self, range: Range<usize>) {
+        let stored_ranges = self.stored_ranges(range).collect::<Vec<_>>();
+
+        for stored_range in stored_ranges {
+            let stored_start = self.stored_index(stored_range.start);
+            self.rope.clear_attributes(stored_start..(stored_start + stored_range.len()));
+        }
+    }
+
+    ///
+    /// Replaces a range of cells and sets the attributes for them.
+    ///
+    fn replace_attributes<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells, new_attributes: Self::Attribute) {
+        let range           = range.start.min(self.len())..range.end.min(self.len());
+        let new_cells       = new_cells.into_iter().collect::<Vec<_>>();
+        let num_cells       = new_cells.len();
+        let stored_range    = self.stored_index(range.start)..self.stored_index(range.end);
+
+        self.rope.replace_attributes(stored_range, new_cells, new_attributes);
+        self.layout.replace_attributes(range, iter::repeat_n((), num_cells), true);
+    }
+}
+
+impl<BaseRope> RopeSearch for SparseRope<BaseRope>
+where
+BaseRope:       RopeMut,
+BaseRope::Cell: PartialEq { }
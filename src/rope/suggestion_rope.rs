@@ -0,0 +1,313 @@
+use super::layered_rope::*;
+use super::tombstone_rope::*;
+
+use crate::api::*;
+
+use std::iter;
+use std::ops::{Range};
+
+///
+/// Identifies a suggested change in a `SuggestionRope`
+///
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct SuggestionId(pub u64);
+
+///
+/// The type of change suggested for a range of cells
+///
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum SuggestionKind {
+    /// The cells are suggested for insertion into the document
+    Insertion,
+
+    /// The cells are suggested for deletion from the document
+    Deletion
+}
+
+///
+/// Key for the layer in the layout of a `SuggestionRope` that stores the suggestion that each cell belongs to
+///
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+struct SuggestionLayer;
+
+///
+/// A suggestion rope records the edits made to it as suggestions that can be accepted or rejected later on (often called
+/// 'tracked changes')
+///
+/// Both the suggested insertions and the suggested deletions are visible when reading from this rope, so it's a view of
+/// a document that is being reviewed. Use `suggestions()` to find out which cells are part of a suggestion (to display
+/// them differently, for example). Accepting a suggestion returns the edits needed to apply it to the document without
+/// any suggestions in it, so the 'real' document can be kept in another rope.
+///
+/// Each edit creates a new suggestion: replacing a range creates a suggestion that deletes the original cells and
+/// inserts the new cells after them. Deleting cells that are part of a suggested insertion removes them immediately.
+/// Setting attributes is not tracked, and only changes the cells that are not suggested for deletion.
+///
+/// Suggested deletions are stored as tombstones in a `TombstoneRope`, whose stored rope is the content of this rope,
+/// and whose visible cells are the document with all of the suggestions accepted.
+///
+#[derive(Clone)]
+pub struct SuggestionRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// The document and all of the suggested changes (suggested deletions are tombstones)
+    rope: TombstoneRope<BaseRope>,
+
+    /// A layer containing the suggestion that each cell in the rope belongs to (or `None` for cells that are part of the document)
+    layout: LayeredRope<(), SuggestionLayer, Option<SuggestionId>>,
+
+    /// The ID to assign to the next suggestion
+    next_id: u64
+}
+
+impl<BaseRope> SuggestionRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Creates a new suggestion rope for reviewing the content of a rope
+    ///
+    pub fn from(rope: BaseRope) -> SuggestionRope<BaseRope> {
+        let mut layout = LayeredRope::from(iter::repeat_n((), rope.len()));
+        layout.set_layer_attributes(SuggestionLayer, 0..0, None);
+
+        SuggestionRope {
+            rope:       TombstoneRope::from(rope),
+            layout:     layout,
+            next_id:    0
+        }
+    }
+
+    ///
+    /// Returns the suggestions in this rope, along with the ranges of cells that they cover
+    ///
+    /// A suggestion that replaces some cells will appear twice, once for the deletion and once for the insertion.
+    ///
+    pub fn suggestions(&self) -> impl Iterator<Item=(SuggestionId, SuggestionKind, Range<usize>)> {
+        self.suggestion_runs(0..self.len()).into_iter()
+            .filter_map(|(suggestion, range)| suggestion.map(|(id, kind)| (id, kind, range)))
+    }
+
+    ///
+    /// Accepts a suggestion, returning the edits that will make the same change to the document without the suggestions
+    ///
+    /// The edits are relative to the document with all of the suggestions that are still pending rejected, and should be
+    /// applied in order.
+    ///
+    pub fn accept(&mut self, id: SuggestionId) -> Vec<RopeAction<BaseRope::Cell, BaseRope::Attribute>> {
+        let mut actions = vec![];
+
+        while let Some((kind, range)) = self.find_suggestion(id) {
+            // Positions in the document are the cells that aren't suggested insertions
+            let doc_start = self.suggestion_runs(0..range.start).into_iter()
+                .filter(|(suggestion, _)| !matches!(suggestion, Some((_, SuggestionKind::Insertion))))
+                .map(|(_, range)| range.len())
+                .sum::<usize>();
+
+            match kind {
+                SuggestionKind::Deletion => {
+                    actions.push(RopeAction::Replace(doc_start..(doc_start + range.len()), vec![]));
+
+                    self.rope.purge_range(range.clone());
+                    self.layout.replace(range, iter::empty());
+                }
+
+                SuggestionKind::Insertion => {
+                    let mut doc_pos = doc_start;
+
+                    for (attribute, attribute_range) in self.rope.stored_rope().read_attribute_runs(range.clone()) {
+                        let cells = self.rope.stored_rope().read_cells(attribute_range).cloned().collect::<Vec<_>>();
+                        let len   = cells.len();
+
+                        actions.push(RopeAction::ReplaceAttributes(doc_pos..doc_pos, cells, attribute.clone()));
+                        doc_pos += len;
+                    }
+
+                    self.layout.clear_layer_attributes(&SuggestionLayer, range);
+                }
+            }
+        }
+
+        actions
+    }
+
+    ///
+    /// Rejects a suggestion, removing any cells it inserted and restoring any cells it deleted
+    ///
+    pub fn reject(&mut self, id: SuggestionId) {
+        while let Some((kind, range)) = self.find_suggestion(id) {
+            match kind {
+                SuggestionKind::Deletion => {
+                    self.rope.restore(range.clone());
+                    self.layout.clear_layer_attributes(&SuggestionLayer, range);
+                }
+
+                SuggestionKind::Insertion => {
+                    self.remove_insertion(range);
+                }
+            }
+        }
+    }
+
+    ///
+    /// Finds the first range of cells belonging to a suggestion
+    ///
+    fn find_suggestion(&self, id: SuggestionId) -> Option<(SuggestionKind, Range<usize>)> {
+        self.suggestions()
+            .find(|(suggestion_id, _, _)| *suggestion_id == id)
+            .map(|(_, kind, range)| (kind, range))
+    }
+
+    ///
+    /// Divides a range of this rope into runs of cells that belong to the same suggestion (or to no suggestion)
+    ///
+    /// Tombstones in the underlying rope are suggested deletions, and visible cells that belong to a suggestion are
+    /// suggested insertions.
+    ///
+    #[allow(clippy::type_complexity)]
+    fn suggestion_runs(&self, range: Range<usize>) -> Vec<(Option<(SuggestionId, SuggestionKind)>, Range<usize>)> {
+        self.rope.stored_runs(range)
+            .flat_map(|(is_visible, stored_range)| {
+                let kind = if is_visible { SuggestionKind::Insertion } else { SuggestionKind::Deletion };
+
+                self.layout.read_layer_attribute_runs(&SuggestionLayer, stored_range)
+                    .map(move |(suggestion, run_range)| (suggestion.map(|id| (id, kind)), run_range))
+            })
+            .collect()
+    }
+
+    ///
+    /// Removes a range of cells that are suggested for insertion from the rope
+    ///
+    fn remove_insertion(&mut self, range: Range<usize>) {
+        let visible_range = self.rope.visible_index(range.start)..self.rope.visible_index(range.end);
+
+        self.rope.replace(visible_range, iter::empty());
+        self.rope.purge_range(range.clone());
+        self.layout.replace(range, iter::empty());
+    }
+
+    ///
+    /// Records a replacement as a new suggestion
+    ///
+    fn suggest_replacement(&mut self, range: Range<usize>, new_cells: Vec<BaseRope::Cell>, new_attributes: Option<BaseRope::Attribute>) {
+        let start       = range.start.min(self.len());
+        let range       = start..range.end.min(self.len()).max(start);
+        let num_cells   = new_cells.len();
+        if range.is_empty() && num_cells == 0 { return; }
+
+        let id          = SuggestionId(self.next_id);
+        self.next_id    += 1;
+
+        // Cells replacing a range have the attributes of the first cell in that range
+        let new_attributes = new_attributes.or_else(|| if range.start < range.end { Some(self.read_attributes(range.start).0.clone()) } else { None });
+
+        // Suggest deleting the cells in the document, and remove any cells that were only suggested for insertion
+        let runs        = self.suggestion_runs(range.clone());
+        let mut removed = 0;
+
+        for (suggestion, run_range) in runs.into_iter().rev() {
+            match suggestion {
+                None => {
+                    let visible_range = self.rope.visible_index(run_range.start)..self.rope.visible_index(run_range.end);
+
+                    self.rope.replace(visible_range, iter::empty());
+                    self.layout.set_layer_attributes(SuggestionLayer, run_range, Some(id));
+                }
+
+                Some((_, SuggestionKind::Deletion))     => { }
+                Some((_, SuggestionKind::Insertion))    => {
+                    removed += run_range.len();
+                    self.remove_insertion(run_range);
+                }
+            }
+        }
+
+        // Suggest inserting the new cells after the deleted cells (the tombstone rope stores them after any following tombstones)
+        if num_cells > 0 {
+            let visible_pos = self.rope.visible_index(range.end - removed);
+            let insert_pos  = self.rope.stored_index(visible_pos);
+
+            match new_attributes {
+                Some(new_attributes)    => self.rope.replace_attributes(visible_pos..visible_pos, new_cells, new_attributes),
+                None                    => self.rope.replace(visible_pos..visible_pos, new_cells)
+            }
+
+            self.layout.replace(insert_pos..insert_pos, iter::repeat_n((), num_cells));
+            self.layout.set_layer_attributes(SuggestionLayer, insert_pos..(insert_pos + num_cells), Some(id));
+        }
+    }
+}
+
+impl<BaseRope> Rope for SuggestionRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.rope.stored_len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        self.rope.stored_rope().read_cells(range)
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.stored_rope().read_chunks(range)
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.rope.stored_rope().read_attributes(pos)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.stored_rope().read_attribute_runs(range)
+    }
+}
+
+impl<BaseRope> RopeMut for SuggestionRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Performs the specified editing action to this rope
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        match action {
+            RopeAction::Replace(range, cells)                       => self.suggest_replacement(range, cells, None),
+            RopeAction::ReplaceAttributes(range, cells, attribute)  => self.suggest_replacement(range, cells, Some(attribute)),
+            RopeAction::SetAttributes(range, attribute)             => {
+                // Cells that are suggested for deletion are tombstones, so keep their attributes
+                let visible_range = self.rope.visible_index(range.start)..self.rope.visible_index(range.end);
+                self.rope.set_attributes(visible_range, attribute);
+            }
+        }
+    }
+}
+
+impl<BaseRope> RopeSearch for SuggestionRope<BaseRope>
+where
+BaseRope:       RopeMut,
+BaseRope::Cell: PartialEq { }
@@ -1,5 +1,9 @@
 use crate::*;
 use crate::rope::*;
+use super::node::*;
+
+use std::sync::*;
+use std::iter;
 
 #[test]
 fn read_single_node() {
@@ -46,7 +50,7 @@ fn set_attributes_on_neighboring_ranges() {
     assert!(rope.read_attributes(0) == (&0, 0..1));
     assert!(rope.read_attributes(1) == (&1, 1..3));
     assert!(rope.read_attributes(2) != (&0, 2..3));
-    assert!(rope.read_attributes(2) == (&1, 2..3));
+    assert!(rope.read_attributes(2) == (&1, 1..3));
 }
 
 #[test]
@@ -62,6 +66,21 @@ fn set_attributes_overlapping_ranges() {
     assert!(rope.read_attributes(4) == (&0, 4..6));
 }
 
+#[test]
+fn read_attributes_extends_to_the_left() {
+    let mut rope = AttributedRope::<_, i64>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+    rope.set_attributes(1..3, 1);
+    rope.set_attributes(3..5, 1);
+    rope.set_attributes(5..7, 1);
+
+    assert!(rope.read_attributes(0) == (&0, 0..1));
+    assert!(rope.read_attributes(1) == (&1, 1..7));
+    assert!(rope.read_attributes(4) == (&1, 1..7));
+    assert!(rope.read_attributes(6) == (&1, 1..7));
+    assert!(rope.read_attributes(7) == (&0, 7..8));
+}
+
 #[test]
 fn replace_attributes() {
     let mut rope = AttributedRope::<_, i64>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
@@ -111,17 +130,34 @@ fn read_after_full_split_and_join() {
     rope.split_at(6);
     rope.split_at(7);
 
-    rope.join_at(2);
-    rope.join_at(1);
-    rope.join_at(4);
-    rope.join_at(6);
-    rope.join_at(5);
-    rope.join_at(7);
+    rope.join_at(2, JoinPolicy::KeepRight);
+    rope.join_at(1, JoinPolicy::KeepRight);
+    rope.join_at(4, JoinPolicy::KeepRight);
+    rope.join_at(6, JoinPolicy::KeepRight);
+    rope.join_at(5, JoinPolicy::KeepRight);
+    rope.join_at(7, JoinPolicy::KeepRight);
 
     assert!(rope.len() == 8);
     assert!(rope.read_cells(0..8).cloned().collect::<Vec<_>>() == vec![1, 2, 3, 4, 5, 6, 7, 8]);
 }
 
+#[test]
+fn join_policy_chooses_attribute() {
+    let mut rope = AttributedRope::<_, i64>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    rope.set_attributes(0..4, 1);
+
+    assert!(!rope.join_at(0, JoinPolicy::MustMatch));
+    assert!(rope.read_attributes(0) == (&1, 0..4));
+
+    let mut keep_right = rope.clone();
+    assert!(keep_right.join_at(0, JoinPolicy::KeepRight));
+    assert!(keep_right.read_attributes(0) == (&0, 0..8));
+
+    assert!(rope.join_at(0, JoinPolicy::KeepLeft));
+    assert!(rope.read_attributes(0) == (&1, 0..8));
+    assert!(rope.read_cells(0..8).cloned().collect::<Vec<_>>() == vec![1, 2, 3, 4, 5, 6, 7, 8]);
+}
+
 #[test]
 fn read_attributes_after_full_split() {
     let mut rope = AttributedRope::<_, ()>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
@@ -317,6 +353,42 @@ fn equal_attributes_ranges() {
     assert!(rope2 == rope1);
 }
 
+#[test]
+fn equal_cells_with_different_leaves() {
+    let cells       = (0..200).collect::<Vec<_>>();
+    let rope1       = AttributedRope::<_, ()>::from(cells.clone());
+    let mut rope2   = AttributedRope::<_, ()>::new();
+
+    for start in (0..200).step_by(7) {
+        let end = (start+7).min(200);
+        rope2.replace(start..start, cells[start..end].iter().cloned());
+    }
+
+    let mut rope3 = AttributedRope::<_, ()>::from(cells.clone());
+    rope3.replace(150..151, iter::once(0));
+
+    assert!(rope1 == rope2);
+    assert!(rope2 == rope1);
+    assert!(rope1 != rope3);
+    assert!(rope3 != rope2);
+}
+
+#[test]
+fn compare_with_slices_and_strings() {
+    let mut rope = AttributedRope::<u8, i64>::new();
+    rope.replace(0..0, "hello".bytes());
+    rope.replace(5..5, " world".bytes());
+    rope.set_attributes(0..5, 1);
+
+    assert!(rope == "hello world");
+    assert!(rope != "hello");
+    assert!(rope != "hello there");
+    assert!(rope == *"hello world");
+    assert!(rope == "hello world".bytes().collect::<Vec<_>>());
+    assert!(rope == "hello world".as_bytes());
+    assert!(rope == *"hello world".as_bytes());
+}
+
 #[test]
 fn unequal_cells() {
     let rope1 = AttributedRope::<_, ()>::from(vec![1, 2, 3]);
@@ -358,3 +430,1526 @@ fn unequal_attribute_values() {
     assert!(rope1 != rope2);
     assert!(rope2 != rope1);
 }
+
+#[test]
+fn read_chunks_single_node() {
+    let rope = AttributedRope::<_, ()>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+    assert!(rope.read_chunks(2..6).collect::<Vec<_>>() == vec![&[3, 4, 5, 6][..]]);
+}
+
+#[test]
+fn read_chunks_after_split() {
+    let mut rope = AttributedRope::<_, ()>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+    rope.split_at(4);
+    rope.split_at(2);
+    rope.split_at(6);
+
+    assert!(rope.read_chunks(0..8).collect::<Vec<_>>() == vec![&[1, 2][..], &[3, 4][..], &[5, 6][..], &[7, 8][..]]);
+    assert!(rope.read_chunks(2..7).collect::<Vec<_>>() == vec![&[3, 4][..], &[5, 6][..], &[7][..]]);
+    assert!(rope.read_chunks(3..3).count() == 0);
+    assert!(rope.read_chunks(0..8).flat_map(|chunk| chunk.iter()).cloned().collect::<Vec<_>>() == rope.read_cells(0..8).cloned().collect::<Vec<_>>());
+}
+
+#[test]
+fn read_cells_in_reverse() {
+    let rope = AttributedRope::<_, ()>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+    assert!(rope.read_cells(2..6).rev().cloned().collect::<Vec<_>>() == vec![6, 5, 4, 3]);
+}
+
+#[test]
+fn read_cells_in_reverse_after_full_split() {
+    let mut rope = AttributedRope::<_, ()>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+    rope.split_at(4);
+    rope.split_at(2);
+    rope.split_at(3);
+    rope.split_at(1);
+    rope.split_at(5);
+    rope.split_at(6);
+    rope.split_at(7);
+
+    assert!(rope.read_cells(0..8).rev().cloned().collect::<Vec<_>>() == vec![8, 7, 6, 5, 4, 3, 2, 1]);
+    assert!(rope.read_cells(1..7).rev().cloned().collect::<Vec<_>>() == vec![7, 6, 5, 4, 3, 2]);
+    assert!(rope.read_cells(0..20).rev().cloned().collect::<Vec<_>>() == vec![8, 7, 6, 5, 4, 3, 2, 1]);
+}
+
+#[test]
+fn read_cells_from_both_ends() {
+    let mut rope = AttributedRope::<_, ()>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+    rope.split_at(4);
+    rope.split_at(2);
+
+    let mut cells = rope.read_cells(1..6);
+
+    assert!(cells.next() == Some(&2));
+    assert!(cells.next_back() == Some(&6));
+    assert!(cells.next_back() == Some(&5));
+    assert!(cells.next() == Some(&3));
+    assert!(cells.next() == Some(&4));
+    assert!(cells.next().is_none());
+    assert!(cells.next_back().is_none());
+}
+
+#[test]
+fn read_attribute_runs() {
+    let mut rope = AttributedRope::<_, i64>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+    rope.set_attributes(2..4, 1);
+    rope.set_attributes(4..5, 1);
+    rope.set_attributes(5..7, 2);
+
+    assert!(rope.read_attribute_runs(0..8).collect::<Vec<_>>() == vec![(&0, 0..2), (&1, 2..5), (&2, 5..7), (&0, 7..8)]);
+    assert!(rope.read_attribute_runs(3..6).collect::<Vec<_>>() == vec![(&1, 3..5), (&2, 5..6)]);
+    assert!(rope.read_attribute_runs(2..2).count() == 0);
+}
+
+#[test]
+fn read_attribute_runs_default_implementation() {
+    let mut rope = AttributedRope::<_, i64>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+    rope.set_attributes(2..4, 1);
+    rope.set_attributes(4..5, 1);
+    rope.set_attributes(5..7, 2);
+
+    // A rope that only implements the required methods will use the default implementation of read_attribute_runs
+    struct MinimalRope(AttributedRope<u8, i64>);
+
+    impl Rope for MinimalRope {
+        type Cell       = u8;
+        type Attribute  = i64;
+
+        fn len(&self) -> usize { self.0.len() }
+        fn read_cells<'a>(&'a self, range: std::ops::Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a u8>> { Rope::read_cells(&self.0, range) }
+        fn read_attributes<'a>(&'a self, pos: usize) -> (&'a i64, std::ops::Range<usize>) { self.0.read_attributes(pos) }
+    }
+
+    let minimal = MinimalRope(rope);
+    assert!(Rope::read_attribute_runs(&minimal, 0..8).collect::<Vec<_>>() == vec![(&0, 0..2), (&1, 2..5), (&2, 5..7), (&0, 7..8)]);
+    assert!(Rope::read_attribute_runs(&minimal, 3..6).collect::<Vec<_>>() == vec![(&1, 3..5), (&2, 5..6)]);
+}
+
+#[test]
+fn read_attribute_runs_skips_single_run_branches() {
+    let mut rope = AttributedRope::<u32, u8>::from(0..10_000);
+
+    // All the leaves start with the same attribute, so the whole tree is one run
+    let is_single_run = |rope: &AttributedRope<u32, u8>| if let RopeNode::Branch(branch) = &rope.nodes[rope.root_node_idx.idx()] { branch.single_run } else { false };
+    assert!(is_single_run(&rope));
+    assert!(rope.read_attribute_runs(0..10_000).collect::<Vec<_>>() == vec![(&0, 0..10_000)]);
+    assert!(rope.read_attribute_runs(17..9_000).collect::<Vec<_>>() == vec![(&0, 17..9_000)]);
+
+    // Changing the attributes of part of the rope splits the run
+    rope.set_attributes(100..200, 1);
+    assert!(!is_single_run(&rope));
+    assert!(rope.nodes.iter().any(|node| matches!(node, RopeNode::Branch(branch) if branch.single_run)));
+    assert!(rope.read_attribute_runs(0..10_000).collect::<Vec<_>>() == vec![(&0, 0..100), (&1, 100..200), (&0, 200..10_000)]);
+    assert!(rope.read_attribute_runs(150..5_000).collect::<Vec<_>>() == vec![(&1, 150..200), (&0, 200..5_000)]);
+    assert!(rope.check_integrity() == Ok(()));
+
+    // Setting the attribute back joins the runs again
+    rope.set_attributes(100..200, 0);
+    assert!(rope.read_attribute_runs(0..10_000).collect::<Vec<_>>() == vec![(&0, 0..10_000)]);
+    assert!(rope.check_integrity() == Ok(()));
+
+    // Incorrectly marking a branch as a single run is detected
+    rope.set_attributes(5_000..5_001, 2);
+    if let RopeNode::Branch(branch) = &mut rope.nodes[rope.root_node_idx.idx()] { branch.single_run = true; }
+    assert!(matches!(rope.check_integrity(), Err(RopeIntegrityError::IncorrectRunSummary { .. })));
+}
+
+#[test]
+fn read_attribute_runs_after_random_edits() {
+    let mut rope    = AttributedRope::<u8, u8>::from(vec![0; 2000]);
+    let mut stress  = RopeStressTest::new(7).with_max_length(3000).with_num_attributes(2);
+    let mut random  = StressRandom::new(11);
+
+    for step in 0..2000 {
+        let action = stress.random_action(rope.len());
+        rope.edit(action);
+
+        if step % 50 == 0 {
+            let oracle  = RopeOracle::from_rope(&rope);
+            let start   = random.next_range(0..rope.len()+1);
+            let end     = start + random.next_range(0..(rope.len()-start)+1);
+
+            assert!(rope.read_attribute_runs(0..rope.len()).eq(Rope::read_attribute_runs(&oracle, 0..oracle.len())));
+            assert!(rope.read_attribute_runs(start..end).eq(Rope::read_attribute_runs(&oracle, start..end)));
+            assert!(rope.check_integrity() == Ok(()));
+        }
+    }
+}
+
+#[test]
+fn registry_shares_attributes() {
+    let mut registry    = AttributeRegistry::<String>::new();
+    let bold            = registry.register("bold".to_string());
+    let italic          = registry.register("italic".to_string());
+
+    assert!(registry.register("bold".to_string()) == bold);
+    assert!(registry.find(&String::new()) == Some(AttributeId::default()));
+    assert!(registry.len() == 3);
+
+    let mut rope1 = AttributedRope::<_, AttributeId>::from(vec![1, 2, 3, 4]);
+    let mut rope2 = AttributedRope::<_, AttributeId>::from(vec![5, 6, 7, 8]);
+
+    rope1.set_attributes(1..3, bold);
+    rope2.set_attributes(0..2, italic);
+    rope2.set_attributes(2..4, bold);
+
+    assert!(registry.read_attributes(&rope1, 1) == (&"bold".to_string(), 1..3));
+    assert!(registry.read_attribute_runs(&rope2, 0..4).collect::<Vec<_>>() == vec![(&"italic".to_string(), 0..2), (&"bold".to_string(), 2..4)]);
+    assert!(registry.resolve(*rope1.read_attributes(0).0).is_empty());
+}
+
+fn rope_contents<TRope: ?Sized+Rope>(rope: &TRope) -> Vec<TRope::Cell> {
+    rope.read_cells(0..rope.len()).cloned().collect()
+}
+
+#[test]
+fn vec_as_rope() {
+    let vec = vec![1, 2, 3, 4];
+
+    assert!(rope_contents(&vec) == vec![1, 2, 3, 4]);
+    assert!(Rope::read_cells(&vec, 1..10).cloned().collect::<Vec<_>>() == vec![2, 3, 4]);
+    assert!(Rope::read_attributes(&vec, 2) == (&(), 0..4));
+    assert!(rope_contents(&vec[1..3]) == vec![2, 3]);
+}
+
+#[test]
+fn str_as_rope() {
+    let string = "Hello";
+
+    assert!(rope_contents(string) == "Hello".bytes().collect::<Vec<_>>());
+    assert!(rope_contents(&string) == "Hello".bytes().collect::<Vec<_>>());
+    assert!(rope_contents(&string.to_string()) == "Hello".bytes().collect::<Vec<_>>());
+    assert!(Rope::read_attribute_runs(&string, 1..3).collect::<Vec<_>>() == vec![(&(), 1..3)]);
+}
+
+fn count_leaves<Cell, Attribute>(rope: &AttributedRope<Cell, Attribute>) -> usize {
+    rope.nodes.iter()
+        .filter(|node| if let RopeNode::Leaf(_, _, _) = node { true } else { false })
+        .count()
+}
+
+#[test]
+fn coalesce_after_set_attributes() {
+    let mut rope = AttributedRope::<_, i64>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+    rope.set_attributes(1..2, 1);
+    rope.set_attributes(2..3, 1);
+    rope.set_attributes(3..4, 1);
+
+    assert!(rope.read_attributes(2) == (&1, 1..4));
+    assert!(count_leaves(&rope) == 3);
+
+    rope.set_attributes(1..4, 0);
+
+    assert!(rope.read_attributes(2) == (&0, 0..8));
+    assert!(count_leaves(&rope) == 1);
+    assert!(rope.read_cells(0..8).cloned().collect::<Vec<_>>() == vec![1, 2, 3, 4, 5, 6, 7, 8]);
+}
+
+#[test]
+fn coalesce_after_full_split() {
+    let mut rope = AttributedRope::<_, ()>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+    rope.split_at(1);
+    rope.split_at(2);
+    rope.split_at(3);
+    rope.split_at(4);
+    rope.split_at(5);
+    rope.split_at(6);
+    rope.split_at(7);
+
+    rope.coalesce_range(2..5);
+    assert!(rope.read_cells(0..8).cloned().collect::<Vec<_>>() == vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    assert!(rope.read_chunks(0..8).collect::<Vec<_>>() == vec![&[1, 2, 3, 4, 5, 6][..], &[7][..], &[8][..]]);
+
+    rope.coalesce();
+    assert!(count_leaves(&rope) == 1);
+    assert!(rope.read_cells(0..8).cloned().collect::<Vec<_>>() == vec![1, 2, 3, 4, 5, 6, 7, 8]);
+}
+
+#[test]
+fn coalesce_respects_split_length() {
+    let mut rope = AttributedRope::<_, ()>::from(0..100);
+
+    rope.split_at(50);
+    rope.split_at(25);
+    rope.split_at(75);
+    rope.coalesce();
+
+    assert!(count_leaves(&rope) == 4);
+    assert!(rope.read_cells(0..100).cloned().collect::<Vec<_>>() == (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn combine_attributes() {
+    // Attributes are a bit set of styles
+    let mut rope = CombiningRope::from(AttributedRope::<_, u32>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]), |existing: &u32, new: &u32| existing | new);
+
+    rope.set_attributes(2..5, 1);
+    rope.set_attributes(4..7, 2);
+
+    assert!(rope.read_attribute_runs(0..8).collect::<Vec<_>>() == vec![(&0, 0..2), (&1, 2..4), (&3, 4..5), (&2, 5..7), (&0, 7..8)]);
+
+    rope.edit(RopeAction::SetAttributes(0..8, 4));
+
+    assert!(rope.read_attribute_runs(0..8).collect::<Vec<_>>() == vec![(&4, 0..2), (&5, 2..4), (&7, 4..5), (&6, 5..7), (&4, 7..8)]);
+}
+
+#[test]
+fn clear_attributes_merges_leaves() {
+    let mut rope = AttributedRope::<_, i64>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+    rope.set_attributes(1..3, 1);
+    rope.set_attributes(4..6, 2);
+    rope.set_attributes(6..7, 3);
+    assert!(count_leaves(&rope) == 6);
+
+    rope.clear_attributes(0..8);
+
+    assert!(count_leaves(&rope) == 1);
+    assert!(rope.read_attributes(4) == (&0, 0..8));
+    assert!(rope.read_cells(0..8).cloned().collect::<Vec<_>>() == vec![1, 2, 3, 4, 5, 6, 7, 8]);
+}
+
+#[test]
+fn clear_combined_attributes() {
+    let mut rope = CombiningRope::from(AttributedRope::<_, u32>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]), |existing: &u32, new: &u32| existing | new);
+
+    rope.set_attributes(2..6, 1);
+    rope.clear_attributes(3..5);
+
+    assert!(rope.read_attribute_runs(0..8).collect::<Vec<_>>() == vec![(&0, 0..2), (&1, 2..3), (&0, 3..5), (&1, 5..6), (&0, 6..8)]);
+}
+
+#[test]
+fn map_attributes() {
+    let mut rope = AttributedRope::<_, u32>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+    rope.set_attributes(2..4, 1);
+    rope.set_attributes(4..6, 3);
+
+    // Toggle the '2' flag
+    rope.map_attributes(1..7, |attr| attr ^ 2);
+
+    assert!(rope.read_attribute_runs(0..8).collect::<Vec<_>>() == vec![(&0, 0..1), (&2, 1..2), (&3, 2..4), (&1, 4..6), (&2, 6..7), (&0, 7..8)]);
+
+    // Runs that map to the same value are merged
+    rope.map_attributes(0..8, |_| 4);
+    assert!(rope.read_attribute_runs(0..8).collect::<Vec<_>>() == vec![(&4, 0..8)]);
+}
+
+#[test]
+fn layers_are_independent() {
+    let mut rope = LayeredRope::<_, &str, i64>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+    rope.set_layer_attributes("syntax", 0..4, 1);
+    rope.set_layer_attributes("selection", 2..6, 2);
+
+    assert!(rope.read_layer_attributes(&"syntax", 2) == Some((&1, 0..4)));
+    assert!(rope.read_layer_attributes(&"selection", 2) == Some((&2, 2..6)));
+    assert!(rope.read_layer_attributes(&"diagnostics", 2).is_none());
+    assert!(rope.read_layer_attribute_runs(&"selection", 0..8).collect::<Vec<_>>() == vec![(&0, 0..2), (&2, 2..6), (&0, 6..8)]);
+
+    rope.clear_layer_attributes(&"syntax", 0..8);
+    assert!(rope.read_layer_attributes(&"syntax", 2) == Some((&0, 0..8)));
+    assert!(rope.read_layer_attributes(&"selection", 2) == Some((&2, 2..6)));
+}
+
+#[test]
+fn layers_move_with_edits() {
+    let mut rope = LayeredRope::<_, &str, i64>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+    rope.set_layer_attributes("syntax", 4..6, 1);
+    rope.set_layer_attributes("selection", 6..8, 2);
+
+    // Delete two cells before both ranges
+    rope.replace(0..2, vec![]);
+
+    assert!(rope.len() == 6);
+    assert!(rope.read_layer_attributes(&"syntax", 2) == Some((&1, 2..4)));
+    assert!(rope.read_layer_attributes(&"selection", 4) == Some((&2, 4..6)));
+
+    // Insert into the middle of the syntax range
+    rope.replace(3..3, vec![9, 9]);
+
+    assert!(rope.read_cells(0..8).cloned().collect::<Vec<_>>() == vec![3, 4, 5, 9, 9, 6, 7, 8]);
+    assert!(rope.read_layer_attributes(&"syntax", 2) == Some((&1, 2..6)));
+    assert!(rope.read_layer_attributes(&"selection", 6) == Some((&2, 6..8)));
+}
+
+#[test]
+fn lsp_positions() {
+    // '€' is 3 bytes in UTF-8 and 1 UTF-16 code unit, '𝄞' is 4 bytes and 2 UTF-16 code units
+    let rope = AttributedRope::<u8, ()>::from_str("ab\r\n€x\n𝄞y");
+
+    assert!(offset_to_lsp_position(&rope, 0) == LspPosition { line: 0, character: 0 });
+    assert!(offset_to_lsp_position(&rope, 2) == LspPosition { line: 0, character: 2 });
+    assert!(offset_to_lsp_position(&rope, 4) == LspPosition { line: 1, character: 0 });
+    assert!(offset_to_lsp_position(&rope, 7) == LspPosition { line: 1, character: 1 });
+    assert!(offset_to_lsp_position(&rope, 13) == LspPosition { line: 2, character: 2 });
+
+    assert!(lsp_position_to_offset(&rope, LspPosition { line: 1, character: 1 }) == 7);
+    assert!(lsp_position_to_offset(&rope, LspPosition { line: 1, character: 100 }) == 8);
+    assert!(lsp_position_to_offset(&rope, LspPosition { line: 2, character: 2 }) == 13);
+    assert!(lsp_position_to_offset(&rope, LspPosition { line: 7, character: 0 }) == rope.len());
+}
+
+#[test]
+fn utf16_offsets_across_chunks() {
+    let mut rope = AttributedRope::<u8, i64>::from_str("ab\r\n€x\n𝄞y\r");
+    (0..rope.len()).step_by(2).for_each(|pos| rope.set_attributes(pos..(pos+1), 1));
+
+    assert!(rope.utf8_to_utf16_offset(4) == 4);
+    assert!(rope.utf8_to_utf16_offset(7) == 5);
+    assert!(rope.utf8_to_utf16_offset(5) == 5);
+    assert!(rope.utf8_to_utf16_offset(13) == 9);
+    assert!(rope.utf8_to_utf16_offset(100) == 11);
+
+    assert!(rope.utf16_to_utf8_offset(5) == 7);
+    assert!(rope.utf16_to_utf8_offset(8) == 13);
+    assert!(rope.utf16_to_utf8_offset(9) == 13);
+    assert!(rope.utf16_to_utf8_offset(100) == rope.len());
+
+    // The line/column versions should agree with the LSP functions
+    for offset in [0, 2, 4, 7, 8, 9, 13, 14, 15] {
+        assert!(rope.utf8_offset_to_utf16_position(offset) == offset_to_lsp_position(&rope, offset), "{}", offset);
+    }
+
+    for (line, character) in [(0, 0), (0, 5), (1, 1), (1, 100), (2, 2), (3, 0), (7, 0)] {
+        let position = LspPosition { line, character };
+        assert!(rope.utf16_position_to_utf8_offset(position) == lsp_position_to_offset(&rope, position), "{:?}", position);
+    }
+}
+
+#[test]
+fn utf16_positions_in_surrogate_pairs() {
+    // Positions in the middle of a surrogate pair are moved to the end of the character by all of the conversions
+    for (text, position, expected) in [("😀😀\n\n", LspPosition { line: 0, character: 1 }, 4), ("a\r\nb€c\n😀x\r\ry", LspPosition { line: 2, character: 1 }, 13)] {
+        let rope    = AttributedRope::<u8, ()>::from_str(text);
+        let index   = LineIndex::from_rope(&rope);
+
+        assert!(lsp_position_to_offset(&rope, position) == expected);
+        assert!(rope.utf16_position_to_utf8_offset(position) == expected);
+        assert!(index.lsp_position_to_offset(&rope, position) == expected);
+
+        for offset in 0..=rope.len()+1 {
+            let position = offset_to_lsp_position(&rope, offset);
+
+            assert!(rope.utf8_offset_to_utf16_position(offset) == position, "{}", offset);
+            assert!(index.offset_to_lsp_position(&rope, offset) == position, "{}", offset);
+        }
+    }
+
+    let rope = AttributedRope::<u8, ()>::from_str("😀😀\n\n");
+    assert!(rope.utf16_to_utf8_offset(1) == 4);
+    assert!(rope.utf16_to_utf8_offset(3) == 8);
+}
+
+#[test]
+fn chars_decode_across_chunks() {
+    let mut bytes = "a€b𝄞c é".as_bytes().to_vec();
+    bytes.extend([0xe2, 0x82, b'x', 0xff, b'y', 0xf0, 0x9d]);
+
+    let mut rope = AttributedRope::<u8, i64>::from(bytes.clone());
+    (0..rope.len()).for_each(|pos| rope.set_attributes(pos..(pos+1), (pos % 2) as i64));
+    assert!(rope.read_chunks(0..rope.len()).count() == rope.len());
+
+    let expected = String::from_utf8_lossy(&bytes);
+    assert!(rope.chars().collect::<String>() == expected);
+    assert!(rope.char_indices().take(6).collect::<Vec<_>>() == vec![(0, 'a'), (1, '€'), (4, 'b'), (5, '𝄞'), (9, 'c'), (10, ' ')]);
+    assert!(rope.char_indices().skip(7).collect::<Vec<_>>() == vec![(13, '\u{fffd}'), (15, 'x'), (16, '\u{fffd}'), (17, 'y'), (18, '\u{fffd}')]);
+}
+
+#[test]
+fn lsp_change_round_trip() {
+    let mut rope    = AttributedRope::<u8, ()>::from_str("fn main() {\n    println!(\"€\");\n}\n");
+    let change      = LspTextChange {
+        range:  Some(LspRange { start: LspPosition { line: 1, character: 14 }, end: LspPosition { line: 1, character: 15 } }),
+        text:   "Hello".to_string()
+    };
+
+    let action = lsp_change_to_action(&rope, &change);
+    assert!(action == RopeAction::Replace(26..29, "Hello".bytes().collect()));
+    assert!(action_to_lsp_change(&rope, &action) == Some(change));
+
+    rope.edit(action);
+    assert!(rope.to_string_lossy() == "fn main() {\n    println!(\"Hello\");\n}\n");
+
+    let replace_all = lsp_change_to_action(&rope, &LspTextChange { range: None, text: "x".to_string() });
+    rope.edit(replace_all);
+    assert!(rope.to_string_lossy() == "x");
+}
+
+#[test]
+fn anchor_bias() {
+    let mut anchors = RopeAnchors::new();
+    let left        = anchors.add_anchor(4, AnchorBias::Left);
+    let right       = anchors.add_anchor(4, AnchorBias::Right);
+    let before      = anchors.add_anchor(2, AnchorBias::Right);
+
+    // Insert at the anchor position
+    anchors.edit(&RopeAction::<u8, ()>::Replace(4..4, vec![1, 2, 3]));
+
+    assert!(anchors.position(left) == Some(4));
+    assert!(anchors.position(right) == Some(7));
+    assert!(anchors.position(before) == Some(2));
+
+    // Delete a range containing the 'right' anchor
+    anchors.edit(&RopeAction::<u8, ()>::Replace(5..8, vec![]));
+
+    assert!(anchors.position(left) == Some(4));
+    assert!(anchors.position(right) == Some(5));
+}
+
+#[test]
+fn anchored_rope_tracks_selection() {
+    let mut rope    = AnchoredRope::from(AttributedRope::<_, ()>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]));
+    let selection   = rope.anchors_mut().add_range(2..5, AnchorBias::Right, AnchorBias::Left);
+
+    // Typing at the edges of the selection doesn't extend it
+    rope.replace(2..2, vec![9]);
+    rope.replace(6..6, vec![9]);
+    assert!(rope.anchors().range(selection) == Some(3..6));
+
+    // Editing before the selection moves it
+    rope.replace(0..2, vec![]);
+    assert!(rope.anchors().range(selection) == Some(1..4));
+
+    // Replacing the whole selection collapses it
+    rope.edit(RopeAction::Replace(1..4, vec![0]));
+    assert!(rope.anchors().range(selection) == Some(1..2));
+
+    rope.anchors_mut().remove(selection);
+    assert!(rope.anchors().range(selection).is_none());
+}
+
+#[test]
+fn stress_attributed_rope() {
+    for seed in 0..10 {
+        let mut rope = AttributedRope::<u8, u8>::new();
+
+        RopeStressTest::new(seed).run(&mut rope, 500).unwrap();
+    }
+}
+
+#[test]
+fn stress_attributed_rope_few_attributes() {
+    for seed in 0..10 {
+        let mut rope = AttributedRope::<u8, u8>::new();
+
+        RopeStressTest::new(seed).with_num_attributes(2).with_max_length(200).run(&mut rope, 500).unwrap();
+    }
+}
+
+#[test]
+fn stress_pull_rope() {
+    for seed in 0..10 {
+        RopeStressTest::new(seed).run_pull(500).unwrap();
+    }
+}
+
+#[test]
+fn clone_shares_leaves() {
+    let mut rope = AttributedRope::<_, i64>::from(0..200);
+    rope.set_attributes(50..100, 1);
+    rope.set_attributes(150..160, 2);
+
+    let mut cloned = rope.clone();
+
+    // All of the leaves should be shared to start with
+    let shared_leaves = |a: &AttributedRope<i32, i64>, b: &AttributedRope<i32, i64>| {
+        a.nodes.iter().zip(b.nodes.iter())
+            .filter(|(a, b)| match (a, b) { (RopeNode::Leaf(_, a, _), RopeNode::Leaf(_, b, _)) => Arc::ptr_eq(a, b), _ => false })
+            .count()
+    };
+    assert!(shared_leaves(&rope, &cloned) == count_leaves(&rope));
+
+    // Editing the clone should only copy the leaf that was changed
+    cloned.replace(10..12, vec![1000]);
+    assert!(shared_leaves(&rope, &cloned) == count_leaves(&rope)-1);
+
+    assert!(rope.read_cells(0..200).cloned().collect::<Vec<_>>() == (0..200).collect::<Vec<_>>());
+    assert!(cloned.read_cells(9..12).cloned().collect::<Vec<_>>() == vec![9, 1000, 12]);
+    assert!(cloned.read_attributes(160) == (&0, 159..199));
+}
+
+#[test]
+fn frozen_rope_on_other_thread() {
+    let mut rope    = AttributedRope::<_, i64>::from(0..100);
+    rope.set_attributes(10..20, 1);
+
+    let frozen      = rope.freeze();
+    let background  = frozen.clone();
+
+    // Editing the original rope doesn't affect the snapshot
+    rope.replace(0..50, vec![]);
+
+    let sum = std::thread::spawn(move || background.read_cells(0..100).sum::<i32>()).join().unwrap();
+
+    assert!(sum == (0..100).sum::<i32>());
+    assert!(frozen.read_attributes(15) == (&1, 10..20));
+    assert!(rope.len() == 50);
+
+    // Thawing the rope produces an editable copy
+    let mut thawed = frozen.thaw();
+    thawed.replace(0..10, vec![]);
+    assert!(thawed.len() == 90 && frozen.len() == 100);
+}
+
+#[test]
+fn uppercase_preserves_attributes() {
+    let mut rope = AttributedRope::<u8, i64>::from_str("\u{fb01}ne, café");
+    let len      = rope.len();
+
+    // The 'fi' ligature is 3 bytes, but becomes 'FI' (2 bytes) in upper case, so the first run shrinks by a byte
+    rope.set_attributes(0..5, 1);
+    rope.set_attributes(5..len, 2);
+
+    rope.make_uppercase(0..len);
+
+    assert!(rope.to_string_lossy() == "FINE, CAFÉ");
+    assert!(rope.read_attribute_runs(0..rope.len()).collect::<Vec<_>>() == vec![(&1, 0..4), (&2, 4..11)]);
+
+    rope.make_lowercase(0..2);
+    assert!(rope.to_string_lossy() == "fiNE, CAFÉ");
+}
+
+#[test]
+fn case_conversion_only_edits_changed_regions() {
+    let actions     = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+    let push_to     = std::rc::Rc::clone(&actions);
+    let mut rope    = PushAfterRope::from(AttributedRope::<u8, ()>::from_str("abc 123 DEF"), move |action| push_to.borrow_mut().push(action));
+
+    rope.make_uppercase(0..11);
+
+    assert!(*actions.borrow() == vec![RopeAction::ReplaceAttributes(0..3, b"ABC".to_vec(), ())]);
+}
+
+#[test]
+fn crdt_concurrent_edits_converge() {
+    let mut replica_a = CrdtRope::new(1, AttributedRope::<u8, i64>::from_str("Hello world"));
+    let mut replica_b = CrdtRope::new(2, AttributedRope::<u8, i64>::from_str("Hello world"));
+
+    // Concurrent edits
+    replica_a.replace(5..5, b",".iter().cloned());
+    replica_a.set_attributes(0..5, 1);
+    replica_b.replace(6..11, b"there".iter().cloned());
+    replica_b.replace(11..11, b"!".iter().cloned());
+
+    // Exchange the operations
+    let ops_a = replica_a.take_operations();
+    let ops_b = replica_b.take_operations();
+
+    ops_b.into_iter().for_each(|op| replica_a.apply_operation(op));
+    ops_a.into_iter().for_each(|op| replica_b.apply_operation(op));
+
+    assert!(rope_contents(&replica_a) == b"Hello, there!");
+    assert!(rope_contents(&replica_b) == b"Hello, there!");
+    assert!(replica_a.read_attribute_runs(0..13).collect::<Vec<_>>() == replica_b.read_attribute_runs(0..13).collect::<Vec<_>>());
+    assert!(replica_b.read_attributes(0) == (&1, 0..5));
+}
+
+#[test]
+fn crdt_operations_out_of_order() {
+    let mut replica_a = CrdtRope::new(1, AttributedRope::<u8, ()>::new());
+    let mut replica_b = CrdtRope::new(2, AttributedRope::<u8, ()>::new());
+
+    replica_a.replace(0..0, b"abc".iter().cloned());
+    replica_a.replace(3..3, b"def".iter().cloned());
+    replica_a.replace(1..2, vec![]);
+
+    // Deliver the operations in reverse, and twice
+    let ops = replica_a.take_operations();
+    ops.iter().rev().cloned().for_each(|op| replica_b.apply_operation(op));
+    assert!(!replica_b.has_waiting_operations());
+    ops.iter().cloned().for_each(|op| replica_b.apply_operation(op));
+
+    assert!(rope_contents(&replica_b) == b"acdef");
+}
+
+#[test]
+fn crdt_random_edits_converge() {
+    for seed in 0..10 {
+        let mut stress      = RopeStressTest::new(seed).with_max_length(64);
+        let mut replicas    = (1..4).map(|replica| CrdtRope::new(replica, AttributedRope::<u8, u8>::from_str("initial"))).collect::<Vec<_>>();
+
+        for _round in 0..5 {
+            // Each replica makes some edits independently
+            for replica in replicas.iter_mut() {
+                for _ in 0..10 {
+                    let action = stress.random_action(replica.len());
+                    replica.edit(action);
+                }
+            }
+
+            // Send every operation to every other replica
+            let operations = replicas.iter_mut().map(|replica| (replica.replica(), replica.take_operations())).collect::<Vec<_>>();
+
+            for (source, ops) in operations {
+                for replica in replicas.iter_mut().filter(|replica| replica.replica() != source) {
+                    ops.iter().cloned().for_each(|op| replica.apply_operation(op));
+                }
+            }
+
+            let expected = RopeOracle::from_rope(&replicas[0]);
+            assert!(replicas.iter().all(|replica| RopeOracle::from_rope(replica) == expected));
+        }
+    }
+}
+
+#[cfg(feature = "node_api")]
+#[test]
+fn node_api_rotate_preserves_contents() {
+    use crate::rope::node_api::*;
+
+    let mut rope = AttributedRope::<u8, u8>::from_str("abcdefgh");
+    rope.split_at(4);
+    rope.split_at(6);
+
+    // Root is (abcd, (ef, gh))
+    let root = rope.root_node_index();
+    rope.rotate_left(root);
+
+    match rope.node(root) {
+        RopeNode::Branch(branch)    => { assert!(rope.node(branch.left).len() == 6); assert!(rope.node(branch.right).len() == 2); }
+        _                           => { panic!("Root should be a branch"); }
+    }
+    assert!(rope_contents(&rope) == b"abcdefgh");
+
+    rope.rotate_right(root);
+    assert!(rope_contents(&rope) == b"abcdefgh");
+    assert!(rope.node(root).len() == 8);
+
+    rope.set_attributes(6..8, 1);
+    assert!(rope.read_attributes(7) == (&1, 6..8));
+}
+
+#[cfg(feature = "node_api")]
+#[test]
+fn node_api_detach_and_attach() {
+    use crate::rope::node_api::*;
+
+    let mut rope = AttributedRope::<u8, ()>::from_str("abcdefgh");
+    let left_leaf = rope.split_leaf(rope.root_node_index(), 3);
+
+    // Move 'abc' to the end of the rope
+    let detached = rope.detach_subtree(left_leaf);
+    assert!(rope_contents(&detached) == b"abc");
+    assert!(rope_contents(&rope) == b"defgh");
+
+    let root = rope.root_node_index();
+    rope.attach_subtree(root, AttachSide::Right, detached);
+    assert!(rope_contents(&rope) == b"defghabc");
+    assert!(rope.len() == 8);
+
+    rope.replace(7..8, vec![b'x']);
+    assert!(rope_contents(&rope) == b"defghabx");
+}
+
+#[test]
+fn leaf_ids_survive_edits_elsewhere() {
+    let mut rope = AttributedRope::<u8, u8>::from_str("hello world");
+    rope.set_attributes(6..11, 1);
+
+    let leaves      = rope.read_leaves(0..rope.len()).map(|leaf| (leaf.id, leaf.range, leaf.cells.to_vec())).collect::<Vec<_>>();
+    let world_id    = leaves[1].0;
+    assert!(leaves.len() == 2);
+    assert!(leaves[1].1 == (6..11));
+    assert!(leaves[1].2 == b"world");
+
+    // Editing the first leaf moves the second leaf but doesn't change it
+    rope.replace(0..5, "goodbye".bytes());
+    let leaves = rope.read_leaves(0..rope.len()).collect::<Vec<_>>();
+    assert!(leaves[1].id == world_id);
+    assert!(leaves[1].range == (8..13));
+    assert!(leaves[0].id != world_id);
+
+    // Editing the second leaf changes its ID
+    rope.replace(13..13, "!".bytes());
+    let leaves = rope.read_leaves(0..rope.len()).collect::<Vec<_>>();
+    assert!(leaves[1].id != world_id);
+    assert!(leaves[1].cells == b"world!");
+}
+
+#[test]
+fn leaf_ids_record_split_origin() {
+    let mut rope    = AttributedRope::<u8, u8>::from_str("abcdefgh");
+    let original_id = rope.read_leaves(0..8).next().unwrap().id;
+
+    rope.set_attributes(2..4, 1);
+
+    let leaves = rope.read_leaves(0..8).collect::<Vec<_>>();
+    assert!(leaves.len() == 3);
+    assert!(leaves[0].split_from == Some(original_id));
+    assert!(leaves[2].split_from == Some(original_id));
+    assert!(leaves[1].split_from.is_none());
+    assert!(leaves.iter().all(|leaf| leaf.id != original_id));
+
+    // Ranges that start in the middle of a leaf only return the cells in the range
+    let partial = rope.read_leaves(3..5).map(|leaf| leaf.cells.to_vec()).collect::<Vec<_>>();
+    assert!(partial == vec![b"d".to_vec(), b"e".to_vec()]);
+}
+
+#[test]
+fn sparse_rope_reads_gaps_as_default() {
+    let mut rope = SparseRope::from(AttributedRope::<u32, u8>::from(vec![1, 2]), 0);
+
+    rope.insert_gap(1, 1_000_000);
+    assert!(rope.len() == 1_000_002);
+    assert!(rope.stored_len() == 2);
+    let gaps = rope.gaps(0..rope.len()).collect::<Vec<_>>();
+    assert!(gaps.len() == 1 && gaps[0] == (1..1_000_001));
+    assert!(rope.read_cells(999_999..1_000_002).cloned().collect::<Vec<_>>() == vec![0, 0, 2]);
+    assert!(rope.read_attributes(500) == (&0, 1..1_000_001));
+}
+
+#[test]
+fn sparse_rope_edits_through_to_stored_cells() {
+    let mut rope = SparseRope::from(AttributedRope::<u32, u8>::new(), 0);
+
+    rope.insert_gap(0, 100);
+    rope.replace(50..51, vec![7, 8]);
+    assert!(rope.len() == 101);
+    assert!(rope.stored_len() == 2);
+    assert!(rope_contents(&rope)[49..53] == [0, 7, 8, 0]);
+
+    // Setting attributes fills in the gaps in the range
+    rope.set_attributes(48..51, 3);
+    assert!(rope.stored_len() == 4);
+    assert!(rope.read_attributes(49) == (&3, 48..51));
+    assert!(rope.read_attributes(51) == (&0, 51..52));
+
+    // Clearing a range turns it back into a gap
+    rope.clear(48..52);
+    assert!(rope.stored_len() == 0);
+    assert!(rope.is_dense(0..0));
+    assert!(!rope.is_dense(0..101));
+    assert!(rope_contents(&rope) == vec![0; 101]);
+}
+
+#[test]
+fn sparse_rope_detect_gaps() {
+    let mut rope = SparseRope::from(AttributedRope::<u32, u8>::from(vec![0, 0, 1, 0, 0, 0, 2]), 0);
+
+    rope.set_attributes(3..4, 1);
+    rope.detect_gaps(0..7);
+
+    assert!(rope.stored_len() == 3);
+    assert!(rope.stored_ranges(0..7).collect::<Vec<_>>() == vec![2..4, 6..7]);
+    assert!(rope_contents(&rope) == vec![0, 0, 1, 0, 0, 0, 2]);
+    assert!(rope.read_attributes(3) == (&1, 3..4));
+}
+
+#[test]
+fn check_integrity_after_edits() {
+    let mut rope = AttributedRope::<u8, u8>::from_str("hello world");
+    rope.set_attributes(2..5, 1);
+    rope.replace(3..8, "xyz".bytes());
+    rope.set_attributes(0..11, 2);
+    rope.replace(0..rope.len(), vec![]);
+
+    assert!(rope.check_integrity() == Ok(()));
+}
+
+#[test]
+fn check_integrity_detects_corruption() {
+    let mut rope = AttributedRope::<u8, u8>::from_str("hello world");
+    rope.set_attributes(2..5, 1);
+
+    let branch_idx = rope.nodes.iter().position(|node| if let RopeNode::Branch(_) = node { true } else { false }).unwrap();
+    if let RopeNode::Branch(branch) = &mut rope.nodes[branch_idx] { branch.length += 1; }
+
+    assert!(matches!(rope.check_integrity(), Err(RopeIntegrityError::IncorrectBranchLength { .. })));
+
+    // Free nodes that are still in use are also detected
+    if let RopeNode::Branch(branch) = &mut rope.nodes[branch_idx] { branch.length -= 1; }
+    rope.free_nodes.push(branch_idx);
+    assert!(rope.check_integrity() == Err(RopeIntegrityError::FreeNodeNotEmpty { node: branch_idx }));
+}
+
+#[test]
+fn deduplicate_repeated_leaves() {
+    let mut interner    = LeafInterner::new();
+    let mut rope        = AttributedRope::<u8, u8>::new();
+
+    // Write the same padding with alternating attributes so each block is a separate leaf
+    for idx in 0..10 {
+        let len = rope.len();
+        rope.replace_attributes(len..len, iter::repeat_n(b' ', 16), (idx % 2) as u8);
+    }
+
+    rope.deduplicate_leaves(&mut interner);
+    assert!(interner.len() == 1);
+
+    let leaves = rope.nodes.iter().filter_map(|node| if let RopeNode::Leaf(_, cells, _) = node { Some(cells) } else { None }).collect::<Vec<_>>();
+    assert!(leaves.len() == 10);
+    assert!(leaves.iter().all(|cells| Arc::ptr_eq(cells, leaves[0])));
+
+    // Editing a shared leaf only changes that leaf
+    rope.replace(0..1, vec![b'x']);
+    assert!(rope.read_cells(0..17).cloned().collect::<Vec<_>>() == [b"x".to_vec(), vec![b' '; 16]].concat());
+
+    // Leaves no longer used by the rope can be removed from the interner
+    drop(rope);
+    interner.purge();
+    assert!(interner.len() == 0);
+}
+
+#[test]
+fn from_creates_balanced_leaves() {
+    let rope    = AttributedRope::<u32, ()>::from(0..10_000);
+    let stats   = rope.stats();
+
+    assert!(stats.num_leaves == 313);
+    assert!(stats.depth == 10);
+    assert!(rope.read_chunks(0..rope.len()).all(|chunk| chunk.len() <= 32));
+    assert!(rope.read_cells(0..rope.len()).cloned().eq(0..10_000));
+
+    // Loading text into an empty rope also creates balanced leaves
+    let mut text = AttributedRope::<u8, i64>::new();
+    text.replace(0..0, "x".repeat(1000).bytes());
+    assert!(text.stats().num_leaves == 32);
+    assert!(AttributedRope::<u8, ()>::from_str(&"x".repeat(1000)).stats().depth == 6);
+
+    // The first edit only needs to change a single leaf
+    let mut edited = rope.clone();
+    edited.replace(5000..5001, vec![42]);
+    assert!(edited.stats().num_leaves <= 315);
+    assert!(edited.read_cells(4999..5002).cloned().collect::<Vec<_>>() == vec![4999, 42, 5001]);
+}
+
+#[test]
+fn rope_stats() {
+    let mut rope = AttributedRope::<u8, u8>::from(vec![0; 100]);
+    let stats    = rope.stats();
+    assert!(stats.num_cells == 100);
+    assert!(stats.num_leaves == 4);
+    assert!(stats.depth == 3);
+    assert!(stats.leaf_size_histogram == vec![0, 0, 0, 1, 0, 0, 3]);
+
+    rope.set_attributes(10..20, 1);
+    rope.replace(50..60, vec![]);
+    let stats = rope.stats();
+    assert!(stats.num_cells == 90);
+    assert!(stats.num_leaves == stats.num_branches + 1);
+    assert!(stats.num_nodes == stats.num_leaves + stats.num_branches + stats.num_free_nodes);
+    assert!(stats.leaf_size_histogram.iter().sum::<usize>() == stats.num_leaves);
+    assert!(stats.depth >= 2);
+    assert!(stats.heap_bytes >= 90);
+}
+
+#[test]
+fn compact_fragmented_rope() {
+    let mut rope    = AttributedRope::<u8, u8>::new();
+    let mut stress  = RopeStressTest::new(42).with_max_length(3000).with_num_attributes(2);
+
+    for _ in 0..2000 {
+        let action = stress.random_action(rope.len());
+        rope.edit(action);
+    }
+
+    let before          = RopeOracle::from_rope(&rope);
+    let stats_before    = rope.stats();
+    rope.compact();
+    let stats_after     = rope.stats();
+
+    assert!(RopeOracle::from_rope(&rope) == before);
+    assert!(rope.check_integrity() == Ok(()));
+    assert!(stats_after.num_free_nodes == 0);
+    assert!(stats_after.num_leaves <= stats_before.num_leaves);
+    assert!(stats_after.depth <= stats_before.depth);
+
+    // The rope can still be edited after compacting
+    rope.replace(0..0, vec![1, 2, 3]);
+    assert!(rope.read_cells(0..3).cloned().collect::<Vec<_>>() == vec![1, 2, 3]);
+}
+
+#[test]
+fn compact_empty_rope() {
+    let mut rope = AttributedRope::<u8, u8>::from(vec![1, 2, 3]);
+    rope.set_attributes(1..2, 1);
+    rope.replace(0..3, vec![]);
+
+    rope.compact();
+    assert!(rope.len() == 0);
+    assert!(rope.stats().num_nodes == 1);
+}
+
+#[cfg(feature = "icu_collator")]
+#[test]
+fn find_collated_ignores_accents_and_case() {
+    use icu_collator::*;
+
+    let mut options     = CollatorOptions::new();
+    options.strength    = Some(Strength::Primary);
+    let collator        = Collator::try_new(&Default::default(), options).unwrap();
+
+    let rope = AttributedRope::<u8, ()>::from_str("Le CAFÉ est cafe\u{301}");
+
+    assert!(rope.find_collated("café", &collator, 0..rope.len()) == Some(3..8));
+    assert!(rope.find_collated("cafe", &collator, 8..rope.len()) == Some(13..19));
+    assert!(rope.find_collated("thé", &collator, 0..rope.len()).is_none());
+    assert!(rope.collation_cmp(&AttributedRope::<u8, ()>::from_str("le cafe est CAFE"), &collator) == std::cmp::Ordering::Equal);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn regex_find_iter_matches_regex_crate() {
+    use regex::*;
+
+    let text        = "The quick brown fox\njumps over the lazy dog, café quickly";
+    let mut rope    = AttributedRope::<u8, i64>::from_str(text);
+    (0..rope.len()).step_by(3).for_each(|pos| rope.set_attributes(pos..(pos+1), (pos % 2) as i64));
+    assert!(rope.read_chunks(0..rope.len()).count() > 10);
+
+    for pattern in ["qu\\w+", "o", "x*", "^The", "(?m)^\\w+", "(?m)\\w+$", "\\bquick\\b", "caf\\w", "\\bcafé\\b", "(?i)THE", "cat"] {
+        let regex       = Regex::new(pattern).unwrap();
+        let expected    = regex.find_iter(text).map(|found| found.range()).collect::<Vec<_>>();
+        let actual      = rope.regex_find_iter(&regex).collect::<Vec<_>>();
+
+        assert!(actual == expected, "{}: {:?} != {:?}", pattern, actual, expected);
+    }
+}
+
+#[cfg(feature = "unicode")]
+#[test]
+fn grapheme_boundaries_for_emoji_and_combining_marks() {
+    // 'e' + combining acute accent, a family emoji (joined with ZWJs) and a flag (two regional indicators)
+    let text        = "ae\u{301}b\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}c\u{1F1EC}\u{1F1E7}d";
+    let padding     = "x".repeat(100);
+    let text        = format!("{}{}", padding, text);
+    let mut bytes   = AttributedRope::<u8, i64>::from_str(&text);
+    let chars: CharRope<()> = text.as_str().into();
+    (0..bytes.len()).step_by(3).for_each(|pos| bytes.set_attributes(pos..(pos+1), (pos % 2) as i64));
+
+    let expected_chars  = (0..padding.len()).map(|pos| pos..(pos+1)).chain([100..101, 101..103, 103..104, 104..109, 109..110, 110..112, 112..113]).collect::<Vec<_>>();
+    let expected_bytes  = (0..padding.len()).map(|pos| pos..(pos+1)).chain([100..101, 101..104, 104..105, 105..123, 123..124, 124..132, 132..133]).collect::<Vec<_>>();
+
+    assert!(chars.grapheme_ranges(0..chars.len()).collect::<Vec<_>>() == expected_chars);
+    assert!(bytes.grapheme_ranges(0..bytes.len()).collect::<Vec<_>>() == expected_bytes);
+
+    assert!(bytes.is_grapheme_boundary(105));
+    assert!(!bytes.is_grapheme_boundary(109));
+    assert!(!bytes.is_grapheme_boundary(106));
+    assert!(!chars.is_grapheme_boundary(102));
+    assert!(bytes.next_grapheme_boundary(105) == Some(123));
+    assert!(bytes.prev_grapheme_boundary(123) == Some(105));
+    assert!(bytes.prev_grapheme_boundary(110) == Some(105));
+    assert!(chars.prev_grapheme_boundary(112) == Some(110));
+    assert!(chars.next_grapheme_boundary(chars.len()).is_none());
+    assert!(chars.prev_grapheme_boundary(0).is_none());
+
+    // Pairs of regional indicators are counted from the start of the run, which is outside the initial window
+    let flags = AttributedRope::<u8, ()>::from_str(&format!("a{}", "\u{1F1EC}\u{1F1E7}".repeat(20)));
+    assert!(flags.is_grapheme_boundary(153));
+    assert!(!flags.is_grapheme_boundary(149));
+    assert!(flags.prev_grapheme_boundary(152) == Some(145));
+    assert!(flags.next_grapheme_boundary(149) == Some(153));
+}
+
+#[test]
+fn read_and_write_with_io_traits() {
+    use std::io::{Read, Write, Seek, SeekFrom};
+
+    let mut rope = AttributedRope::<u8, i64>::new();
+
+    {
+        let mut writer = RopeWriter::append(&mut rope);
+        write!(writer, "Hello, {}", 42).unwrap();
+        writer.write_all(b"!\n").unwrap();
+    }
+
+    RopeWriter::overwrite(&mut rope, 7).write_all(b"there, everyone").unwrap();
+    assert!(rope.to_string_lossy() == "Hello, there, everyone");
+    RopeWriter::insert(&mut rope, 5).write_all(b" again").unwrap();
+    rope.set_attributes(0..5, 1);
+    assert!(rope.to_string_lossy() == "Hello again, there, everyone");
+
+    let mut reader  = RopeReader::new(&rope);
+    let mut text    = String::new();
+    reader.read_to_string(&mut text).unwrap();
+    assert!(text == "Hello again, there, everyone");
+
+    let mut word    = [0u8; 5];
+    assert!(reader.seek(SeekFrom::End(-8)).unwrap() == 20);
+    reader.read_exact(&mut word).unwrap();
+    assert!(&word == b"every");
+    assert!(reader.seek(SeekFrom::Current(-11)).unwrap() == 14);
+    assert!(reader.seek(SeekFrom::Current(-20)).is_err());
+    assert!(reader.seek(SeekFrom::Start(100)).unwrap() == 100);
+    assert!(reader.read(&mut word).unwrap() == 0);
+}
+
+#[test]
+fn diff_against_reader_updates_rope() {
+    let mut rope    = AttributedRope::<u8, ()>::from_str("The quick brown fox jumps over the lazy dog");
+    let on_disk     = "The quick red fox jumps over the lazy dog";
+
+    let actions     = rope.diff_against_reader(on_disk.as_bytes(), ReaderDiffDirection::UpdateRope).unwrap();
+    assert!(actions == vec![RopeAction::Replace(10..15, b"red".to_vec())]);
+
+    actions.into_iter().for_each(|action| rope.edit(action));
+    assert!(rope.to_string_lossy() == on_disk);
+}
+
+#[test]
+fn diff_against_reader_updates_reader() {
+    let rope        = AttributedRope::<u8, ()>::from_str("abcabc");
+    let on_disk     = "abc";
+
+    let actions     = rope.diff_against_reader(on_disk.as_bytes(), ReaderDiffDirection::UpdateReader).unwrap();
+    assert!(actions == vec![RopeAction::Replace(3..3, b"abc".to_vec())]);
+
+    let mut disk_rope = AttributedRope::<u8, ()>::from_str(on_disk);
+    actions.into_iter().for_each(|action| disk_rope.edit(action));
+    assert!(disk_rope.to_string_lossy() == "abcabc");
+}
+
+#[test]
+fn diff_against_identical_reader() {
+    let rope = AttributedRope::<u8, ()>::from_str("Unchanged");
+    assert!(rope.diff_against_reader("Unchanged".as_bytes(), ReaderDiffDirection::UpdateRope).unwrap() == vec![]);
+}
+
+#[test]
+#[cfg(feature = "bytes")]
+fn bytes_rope_edits_without_copying() {
+    use bytes::Bytes;
+
+    let buffer      = Bytes::from_static(b"Hello, world");
+    let mut rope    = BytesRope::<i64>::from(buffer.clone());
+
+    rope.replace(5..6, b" there,".iter().copied());
+    rope.set_attributes(0..5, 1);
+
+    assert!(rope.to_string_lossy() == "Hello there, world");
+    assert!(rope.read_attributes(2) == (&1, 0..5));
+    assert!(rope.read_attributes(8) == (&0, 5..18));
+
+    let chunks = rope.read_chunks(0..rope.len()).collect::<Vec<_>>();
+    assert!(chunks.len() == 3);
+    assert!(chunks[0].as_ptr() == buffer.as_ptr());
+    assert!(chunks[2] == Bytes::from_static(b" world"));
+    assert!(chunks[2].as_ptr() == buffer[6..].as_ptr());
+}
+
+#[test]
+fn apply_attribute_spans_matches_set_attributes() {
+    let cells               = (0..2000).map(|idx| idx % 251).collect::<Vec<_>>();
+    let mut spans_rope      = AttributedRope::<_, i64>::from(cells.clone());
+    let mut expected_rope   = AttributedRope::<_, i64>::from(cells);
+
+    // Sorted spans, with some gaps and overlaps, followed by one that's out of order
+    let mut spans = (0..100).map(|idx| ((idx*20)..(idx*20 + 10 + (idx % 15)), (idx % 4) as i64)).collect::<Vec<_>>();
+    spans.push((5..1500, 7));
+
+    for (range, attribute) in spans.iter().cloned() {
+        expected_rope.set_attributes(range, attribute);
+    }
+    spans_rope.apply_attribute_spans(spans);
+
+    assert!(spans_rope == expected_rope);
+    assert!(spans_rope.check_integrity().is_ok());
+}
+
+#[test]
+fn copy_attributes_between_ropes() {
+    let mut template = AttributedRope::<u8, i64>::from_str("Title: body text");
+    template.set_attributes(0..6, 1);
+    template.set_attributes(7..11, 2);
+
+    let mut rope = AttributedRope::<u8, i64>::from_str("> Other: some words");
+    rope.copy_attributes_from(&template, 0..11, 2);
+
+    assert!(rope.to_string_lossy() == "> Other: some words");
+    assert!(rope.read_attribute_runs(0..rope.len()).map(|(attr, range)| (*attr, range)).collect::<Vec<_>>()
+        == vec![(0, 0..2), (1, 2..8), (0, 8..9), (2, 9..13), (0, 13..19)]);
+}
+
+#[test]
+fn string_conversions() {
+    let rope: AttributedRope<u8, ()>    = "Hello".into();
+    let owned: AttributedRope<u8, ()>   = String::from("World").into();
+
+    assert_eq!(String::from(&rope), "Hello");
+    assert_eq!(String::from(owned), "World");
+}
+
+#[test]
+fn display_and_debug() {
+    let mut rope = AttributedRope::<u8, i64>::from_str("Hello");
+    rope.set_attributes(1..3, 2);
+
+    assert!(format!("{}", rope) == "Hello");
+
+    let numbers = AttributedRope::<u32, i64>::from(vec![1, 2, 3]);
+    assert!(format!("{:?}", numbers) == "[([1, 2, 3], 0)]");
+    assert!(format!("{:?}", rope) == "[([72], 0), ([101, 108], 2), ([108, 111], 0)]");
+}
+
+#[test]
+fn hash_ignores_tree_shape() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of(rope: &AttributedRope<u8, i64>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        rope.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // Build the same content in one go and as a series of edits
+    let mut whole = AttributedRope::<u8, i64>::from_str("The quick brown fox");
+    whole.set_attributes(4..9, 1);
+
+    let mut edited = AttributedRope::<u8, i64>::new();
+    for (idx, chr) in "The quick brown fox".bytes().enumerate() {
+        edited.replace_attributes(idx..idx, iter::once(chr), if (4..9).contains(&idx) { 1 } else { 0 });
+    }
+
+    assert!(whole == edited);
+    assert!(hash_of(&whole) == hash_of(&edited));
+
+    edited.set_attributes(0..1, 2);
+    assert!(hash_of(&whole) != hash_of(&edited));
+}
+
+#[test]
+fn cell_handles_follow_edits() {
+    let mut rope    = HandleRope::from(AttributedRope::<_, ()>::from(vec![10, 20, 30, 40]));
+    let twenty      = rope.handle_at(1).unwrap();
+    let thirty      = rope.handle_at(2).unwrap();
+
+    assert!(rope.handle_at(4).is_none());
+
+    rope.replace(0..0, vec![1, 2, 3]);
+    rope.replace(5..5, vec![25]);
+    assert!(rope.position_of(twenty) == Some(4));
+    assert!(rope.position_of(thirty) == Some(6));
+
+    rope.replace(6..7, vec![]);
+    assert!(rope.position_of(twenty) == Some(4));
+    assert!(rope.position_of(thirty).is_none());
+    assert!(rope.read_cells(0..rope.len()).cloned().collect::<Vec<_>>() == vec![1, 2, 3, 10, 20, 25, 40]);
+}
+
+#[test]
+fn get_and_index_cells() {
+    let mut rope = AttributedRope::<_, i64>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    rope.set_attributes(2..5, 1);
+    rope.replace(4..4, vec![]);
+
+    assert!((0..8).map(|pos| rope[pos]).collect::<Vec<_>>() == vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    assert!(rope.get(4) == Some(&5));
+    assert!(rope.get(8).is_none());
+}
+
+#[test]
+fn tombstones_hide_deleted_cells() {
+    fn text<R: Rope<Cell=u8>>(rope: &R) -> String {
+        String::from_utf8_lossy(&rope.read_cells(0..rope.len()).copied().collect::<Vec<_>>()).into()
+    }
+
+    let mut rope = TombstoneRope::from(AttributedRope::<u8, i64>::from_str("The quick brown fox"));
+
+    rope.replace(4..10, "slow ".bytes());
+    rope.set_attributes(0..9, 1);
+
+    assert!(text(&rope) == "The slow brown fox");
+    assert!(rope.stored_rope().to_string_lossy() == "The quick slow brown fox");
+    assert!(rope.tombstones().eq(iter::once(4..10)));
+    assert!(rope.read_attributes(2) == (&1, 0..9));
+    assert!(rope.read_attributes(10) == (&0, 9..18));
+
+    // Rejecting the change restores the deleted cells
+    let mut rejected = rope.clone();
+    rejected.restore(4..10);
+    assert!(text(&rejected) == "The quick slow brown fox");
+
+    // Accepting it removes them
+    rope.purge();
+    assert!(rope.stored_len() == 18);
+    assert!(text(&rope) == "The slow brown fox");
+}
+
+#[test]
+fn accept_and_reject_suggestions() {
+    let mut document    = AttributedRope::<u8, ()>::from_str("The quick brown fox");
+    let mut review      = SuggestionRope::from(document.clone());
+
+    review.replace(4..9, "slow".bytes());
+    review.replace(review.len()..review.len(), " jumps".bytes());
+
+    assert!(review.read_cells(0..review.len()).copied().collect::<Vec<_>>() == b"The quickslow brown fox jumps".to_vec());
+    let suggestions = review.suggestions().collect::<Vec<_>>();
+    assert!(suggestions == vec![
+        (SuggestionId(0), SuggestionKind::Deletion, 4..9),
+        (SuggestionId(0), SuggestionKind::Insertion, 9..13),
+        (SuggestionId(1), SuggestionKind::Insertion, 23..29)
+    ]);
+
+    // Accept the second suggestion and reject the first
+    review.accept(SuggestionId(1)).into_iter().for_each(|action| document.edit(action));
+    review.reject(SuggestionId(0));
+
+    assert!(document.to_string_lossy() == "The quick brown fox jumps");
+    assert!(review.read_cells(0..review.len()).copied().collect::<Vec<_>>() == b"The quick brown fox jumps".to_vec());
+    assert!(review.suggestions().next().is_none());
+
+    // Deleting a suggested insertion removes it straight away
+    review.replace(4..10, "slow ".bytes());
+    review.replace(10..15, iter::empty());
+    assert!(review.read_cells(0..review.len()).copied().collect::<Vec<_>>() == b"The quick brown fox jumps".to_vec());
+
+    review.accept(SuggestionId(2)).into_iter().for_each(|action| document.edit(action));
+
+    assert!(document.to_string_lossy() == "The brown fox jumps");
+}
+
+#[test]
+fn suggestions_are_tombstones_in_review() {
+    let mut document    = AttributedRope::<u8, i64>::from_str("abcdef");
+    let mut review      = SuggestionRope::from(document.clone());
+
+    // Delete 'cd', then replace 'b' (the insertion is stored after the deletion that follows it)
+    review.replace(2..4, iter::empty());
+    review.replace(1..2, "X".bytes());
+    review.set_attributes(0..review.len(), 1);
+
+    assert!(review.read_cells(0..review.len()).copied().collect::<Vec<_>>() == b"abcdXef".to_vec());
+    assert!(review.suggestions().collect::<Vec<_>>() == vec![
+        (SuggestionId(1), SuggestionKind::Deletion, 1..2),
+        (SuggestionId(0), SuggestionKind::Deletion, 2..4),
+        (SuggestionId(1), SuggestionKind::Insertion, 4..5)
+    ]);
+
+    // Suggested deletions keep their attributes
+    assert!(review.read_attribute_runs(0..review.len()).collect::<Vec<_>>() == vec![(&1, 0..1), (&0, 1..4), (&1, 4..7)]);
+
+    // Accepting the suggestions applies them to the document
+    review.accept(SuggestionId(0)).into_iter().for_each(|action| document.edit(action));
+    review.accept(SuggestionId(1)).into_iter().for_each(|action| document.edit(action));
+
+    assert!(document.to_string_lossy() == "aXef");
+    assert!(review.read_cells(0..review.len()).copied().collect::<Vec<_>>() == b"aXef".to_vec());
+    assert!(review.suggestions().next().is_none());
+}
+
+#[test]
+fn attribute_table_deduplicates_attributes() {
+    let mut rope = AttributedRope::<u8, i64>::from("The quick brown fox".bytes());
+    rope.set_attributes(4..9, 1);
+    rope.set_attributes(10..15, 2);
+    rope.set_attributes(16..19, 1);
+
+    let table = rope.attribute_table();
+
+    assert!(table.attributes == vec![0, 1, 2]);
+    assert!(table.runs == vec![(0..4, 0), (4..9, 1), (9..10, 0), (10..15, 2), (15..16, 0), (16..19, 1)]);
+    assert!(table.run_attribute(3) == Some(&2));
+    assert!(table.run_attribute(6).is_none());
+}
+
+#[test]
+fn progressive_rope_loads_over_placeholders() {
+    let mut rope    = ProgressiveRope::<u8, i64>::new(15, b'?');
+    let mut mirror  = AttributedRope::<u8, i64>::from(iter::repeat_n(b'?', 15));
+
+    assert!(rope.read_cells(0..rope.len()).copied().collect::<Vec<_>>() == b"???????????????".to_vec());
+    assert!(rope.read_loaded_cells(2..4).err() == Some(RangeNotLoaded { unloaded: 2..4 }));
+
+    mirror.edit(rope.load("The quick".bytes()));
+    mirror.edit(rope.load_with_attributes(" brown".bytes(), 1));
+
+    assert!(rope.read_cells(0..rope.len()).copied().collect::<Vec<_>>() == b"The quick brown".to_vec());
+    assert!(rope.read_loaded_cells(4..9).is_ok());
+    assert!(rope.is_loaded());
+    assert!(rope.read_attributes(12) == (&1, 9..15));
+
+    // Loading more than expected extends the rope
+    mirror.edit(rope.load(" fox".bytes()));
+    assert!(rope.len() == 19);
+    assert!(mirror.to_string_lossy() == "The quick brown fox");
+    assert!(rope.finish_loading().is_none());
+
+    // Finishing early removes the placeholders
+    let mut rope    = ProgressiveRope::<u8, i64>::new(10, b'?');
+    let mut mirror  = AttributedRope::<u8, i64>::from(iter::repeat_n(b'?', 10));
+
+    mirror.edit(rope.load("The".bytes()));
+    assert!(mirror.to_string_lossy() == "The???????");
+    assert!(rope.read_attributes(5) == (&0, 3..10));
+
+    mirror.edit(rope.finish_loading().unwrap());
+    assert!(mirror.to_string_lossy() == "The");
+    assert!(rope.len() == 3);
+}
+
+#[test]
+fn line_index_follows_edits() {
+    let mut rope    = AttributedRope::<u8, ()>::from_str("one\ntwo\nthree");
+    let index       = Arc::new(Mutex::new(LineIndex::from_rope(&rope)));
+
+    {
+        let index = index.lock().unwrap();
+        assert!(index.line_count() == 3);
+        assert!(index.line_to_offset(2) == 8);
+        assert!(index.offset_to_line(3) == 0);
+        assert!(index.offset_to_line(4) == 1);
+        assert!(index.line_range(1) == (4..8));
+    }
+
+    // Keep the index up to date as the rope is edited
+    let update_index    = Arc::clone(&index);
+    let mut text        = PushAfterRefRope::from(rope.clone(), move |action: RopeActionRef<u8, ()>| update_index.lock().unwrap().edit(action));
+
+    for (range, new_text) in [(5..9, "a\nb\nc"), (0..4, ""), (10..10, "\n")] {
+        text.replace(range.clone(), new_text.bytes());
+        rope.replace(range, new_text.bytes());
+    }
+
+    let index = index.lock().unwrap();
+    let lines = (0..index.line_count()).map(|line| index.line_range(line)).collect::<Vec<_>>();
+
+    assert!(rope.to_string_lossy() == "ta\nb\nchree\n");
+    assert!(lines == vec![0..3, 3..5, 5..11, 11..11]);
+    assert!(index.offset_to_line(100) == 3);
+    assert!(index.line_to_offset(100) == 11);
+}
+
+#[test]
+fn line_index_many_lines() {
+    let text        = (0..1000).map(|line| format!("line {}\n", line)).collect::<String>();
+    let mut index   = LineIndex::new();
+    index.replace(0..0, text.as_bytes());
+
+    assert!(index.line_count() == 1001);
+
+    for (line, line_start) in text.match_indices('\n').map(|(pos, _)| pos+1).enumerate() {
+        assert!(index.line_to_offset(line+1) == line_start);
+        assert!(index.offset_to_line(line_start) == line+1);
+        assert!(index.offset_to_line(line_start-1) == line);
+    }
+}
+
+#[test]
+fn line_index_lsp_line_endings() {
+    let pieces      = ["a", "\r", "\n", "\r\n", "€", "😀"];
+    let mut random  = StressRandom::new(3);
+    let mut rope    = AttributedRope::<u8, ()>::new();
+    let mut index   = LineIndex::new();
+
+    for _ in 0..500 {
+        // Replace a random range with some random pieces of text
+        let len         = rope.len();
+        let start       = random.next_range(0..len+1);
+        let end         = start + random.next_range(0..(len-start).min(4)+1);
+        let num_pieces  = random.next_range(0..4);
+        let text        = (0..num_pieces).map(|_| pieces[random.next_range(0..pieces.len())]).collect::<String>();
+
+        rope.replace(start..end, text.bytes());
+        index.replace(start..end, text.as_bytes());
+
+        // '\n', '\r\n' and '\r' all end lines
+        let bytes       = rope.read_cells(0..rope.len()).copied().collect::<Vec<_>>();
+        let line_starts = iter::once(0)
+            .chain((0..bytes.len()).filter(|pos| bytes[*pos] == b'\n' || (bytes[*pos] == b'\r' && bytes.get(pos+1) != Some(&b'\n'))).map(|pos| pos+1))
+            .collect::<Vec<_>>();
+
+        assert!(index.line_count() == line_starts.len(), "{:?}", String::from_utf8_lossy(&bytes));
+        assert!((0..line_starts.len()).all(|line| index.line_to_offset(line) == line_starts[line]), "{:?}", String::from_utf8_lossy(&bytes));
+
+        // The LSP conversions should agree with the ones that scan the whole rope
+        for offset in 0..=rope.len() {
+            assert!(index.offset_to_lsp_position(&rope, offset) == offset_to_lsp_position(&rope, offset));
+        }
+
+        for line in 0..=(line_starts.len() as u32) {
+            for character in [0, 1, 2, 3, 100] {
+                let position = LspPosition { line, character };
+                assert!(index.lsp_position_to_offset(&rope, position) == lsp_position_to_offset(&rope, position));
+            }
+        }
+    }
+}
+
+#[test]
+fn char_rope_mirrors_utf8_rope() {
+    let mut chars: CharRope<i64>    = "café crème".into();
+    let mut bytes                   = AttributedRope::<u8, i64>::from_str("café crème");
+
+    assert!(chars.len() == 10);
+    assert!(chars.to_string() == "café crème");
+
+    // Edits to the char rope can't split a character, and can be sent to the UTF-8 rope
+    let actions = vec![
+        RopeAction::Replace(3..4, vec!['e', '\u{301}']),
+        RopeAction::SetAttributes(6..11, 1),
+        RopeAction::ReplaceAttributes(11..11, vec!['!'], 2)
+    ];
+
+    for action in actions {
+        bytes.edit(char_action_to_utf8(&chars, &action));
+        chars.edit(action);
+    }
+
+    assert!(chars.to_string() == "cafe\u{301} crème!");
+    assert!(bytes.to_string_lossy() == "cafe\u{301} crème!");
+    assert!(bytes.read_attributes(8) == (&1, 7..13));
+
+    // Edits to the UTF-8 rope can go the other way, provided they don't split a character
+    let split_action = RopeAction::Replace(10..11, vec![]);
+    assert!(utf8_action_to_char(&bytes, &split_action).is_none());
+
+    let action = RopeAction::Replace(7..13, b"cr\xc3\xa9me".to_vec());
+    chars.edit(utf8_action_to_char(&bytes, &action).unwrap());
+    bytes.edit(action);
+
+    assert!(chars.to_string() == bytes.to_string_lossy());
+}
+
+#[test]
+fn map_rope_transforms_cells_on_read() {
+    static UPPERCASE: [u8; 256] = {
+        let mut table = [0u8; 256];
+        let mut idx = 0;
+        while idx < 256 { table[idx] = (idx as u8).to_ascii_uppercase(); idx += 1; }
+        table
+    };
+
+    let mut base = PullRope::from(AttributedRope::<u8, i64>::from("Hello, world".bytes()), || { });
+    base.set_attributes(0..5, 1);
+    base.pull_changes().for_each(|_| { });
+
+    let mut upper = MapRope::from(base, |cell: &u8| &UPPERCASE[*cell as usize]);
+    assert!(upper.read_cells(0..upper.len()).cloned().collect::<Vec<_>>() == b"HELLO, WORLD".to_vec());
+    assert!(upper.read_attributes(2) == (&1, 0..5));
+    assert!(upper.find(&b"WORLD"[..], 0) == Some(7));
+
+    // Changes to the base rope can be passed on through the map rope
+    upper.base_mut().replace(7..12, "there".bytes());
+
+    let changes = upper.base_mut().pull_changes().collect::<Vec<_>>();
+    let changes = changes.iter().map(|action| upper.map_action(action)).collect::<Vec<_>>();
+    assert!(changes == vec![RopeAction::Replace(7..12, b"THERE".to_vec())]);
+}
+
+#[test]
+fn buffers_and_sparse_ropes_read_chunks() {
+    let buffer = vec![1, 2, 3, 4];
+    assert!(Rope::read_chunks(&buffer, 1..3).collect::<Vec<_>>() == vec![&[2, 3][..]]);
+    assert!(Rope::read_chunks(&buffer, 4..4).count() == 0);
+    assert!(Rope::read_chunks("Hello", 0..5).collect::<Vec<_>>() == vec![&b"Hello"[..]]);
+
+    // Stored cells are read as chunks, and gaps as one chunk per default cell
+    let mut sparse = SparseRope::from(AttributedRope::<u32, u8>::from(vec![1, 2]), 0);
+    sparse.insert_gap(1, 2);
+    assert!(sparse.read_chunks(0..4).collect::<Vec<_>>() == vec![&[1][..], &[0][..], &[0][..], &[2][..]]);
+}
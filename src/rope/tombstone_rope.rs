@@ -0,0 +1,302 @@
+use super::attributed_rope::*;
+
+use crate::api::*;
+
+use std::iter;
+use std::ops::{Range};
+
+///
+/// A tombstone rope keeps the cells that are deleted from it in the underlying rope, marked as 'tombstones', until
+/// `purge()` is called
+///
+/// Tombstoned cells are hidden: they can't be read and they don't count towards the positions of the other cells, so
+/// this behaves in the same way as any other rope. However, the deleted cells are still available in the underlying
+/// rope, and can be found using `tombstones()` and brought back using `restore()`. This makes it possible to implement
+/// things like tracked changes (where deletions are shown until they are accepted or rejected) or to keep the
+/// deleted items that a CRDT needs to order concurrent edits.
+///
+/// Positions in the underlying rope (which include the tombstones) are referred to as 'stored' positions. New cells
+/// are stored after any tombstones at the position they're inserted.
+///
+#[derive(Clone)]
+pub struct TombstoneRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// The rope containing the visible cells and the tombstones
+    rope: BaseRope,
+
+    /// The layout of the base rope: this is true for visible cells and false for tombstones
+    layout: AttributedRope<(), bool>,
+
+    /// The number of visible cells
+    len: usize,
+
+    /// The attribute returned when reading from an empty rope
+    default_attribute: BaseRope::Attribute
+}
+
+impl<BaseRope> TombstoneRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Creates a new tombstone rope, where all of the cells in the base rope are visible
+    ///
+    pub fn from(rope: BaseRope) -> TombstoneRope<BaseRope> {
+        let len         = rope.len();
+        let mut layout  = AttributedRope::new();
+        layout.replace_attributes(0..0, iter::repeat_n((), len), true);
+
+        TombstoneRope {
+            rope:               rope,
+            layout:             layout,
+            len:                len,
+            default_attribute:  BaseRope::Attribute::default()
+        }
+    }
+
+    ///
+    /// Returns the underlying rope, which contains both the visible cells and the tombstones
+    ///
+    pub fn stored_rope(&self) -> &BaseRope {
+        &self.rope
+    }
+
+    ///
+    /// The number of cells stored in the underlying rope (including the tombstones)
+    ///
+    pub fn stored_len(&self) -> usize {
+        self.rope.len()
+    }
+
+    ///
+    /// Returns the stored ranges that contain tombstones
+    ///
+    pub fn tombstones<'a>(&'a self) -> impl 'a+Iterator<Item=Range<usize>> {
+        self.stored_runs(0..self.layout.len())
+            .filter(|(is_visible, _)| !*is_visible)
+            .map(|(_, stored_range)| stored_range)
+    }
+
+    ///
+    /// Divides a stored range into runs of visible cells (`true`) and tombstones (`false`)
+    ///
+    pub fn stored_runs<'a>(&'a self, stored_range: Range<usize>) -> impl 'a+Iterator<Item=(bool, Range<usize>)> {
+        self.layout.read_attribute_runs(stored_range)
+            .map(|(is_visible, stored_range)| (*is_visible, stored_range))
+    }
+
+    ///
+    /// Makes any tombstones in a stored range visible again
+    ///
+    pub fn restore(&mut self, stored_range: Range<usize>) {
+        let restored = self.layout.read_attribute_runs(stored_range.clone())
+            .filter(|(is_visible, _)| !**is_visible)
+            .map(|(_, range)| range.len())
+            .sum::<usize>();
+
+        self.layout.set_attributes(stored_range, true);
+        self.len += restored;
+    }
+
+    ///
+    /// Removes all of the tombstones from the underlying rope
+    ///
+    pub fn purge(&mut self) {
+        self.purge_range(0..self.layout.len());
+    }
+
+    ///
+    /// Removes the tombstones in a stored range from the underlying rope
+    ///
+    pub fn purge_range(&mut self, stored_range: Range<usize>) {
+        let tombstones = self.stored_runs(stored_range)
+            .filter(|(is_visible, _)| !*is_visible)
+            .map(|(_, tombstone)| tombstone)
+            .collect::<Vec<_>>();
+
+        // Remove from the end so that the earlier ranges don't move
+        for tombstone in tombstones.into_iter().rev() {
+            self.rope.replace(tombstone.clone(), iter::empty());
+            self.layout.replace(tombstone, iter::empty());
+        }
+    }
+
+    ///
+    /// Finds the stored position of the visible cell at the specified position (or the end of the stored rope if the
+    /// position is at or beyond the end of the visible cells)
+    ///
+    pub fn stored_index(&self, pos: usize) -> usize {
+        let mut remaining = pos;
+
+        for (is_visible, stored_range) in self.layout.read_attribute_runs(0..self.layout.len()) {
+            if !*is_visible { continue; }
+
+            if remaining < stored_range.len() {
+                return stored_range.start + remaining;
+            }
+
+            remaining -= stored_range.len();
+        }
+
+        self.layout.len()
+    }
+
+    ///
+    /// Finds the number of visible cells before a stored position
+    ///
+    pub fn visible_index(&self, stored_pos: usize) -> usize {
+        self.layout.read_attribute_runs(0..stored_pos)
+            .filter(|(is_visible, _)| **is_visible)
+            .map(|(_, stored_range)| stored_range.len())
+            .sum()
+    }
+
+    ///
+    /// Returns the stored ranges of the visible cells within a range of visible positions
+    ///
+    fn visible_stored_ranges(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        let range = range.start.min(self.len)..range.end.min(self.len);
+        if range.start >= range.end { return vec![]; }
+
+        let stored_range = self.stored_index(range.start)..(self.stored_index(range.end-1)+1);
+
+        self.layout.read_attribute_runs(stored_range)
+            .filter(|(is_visible, _)| **is_visible)
+            .map(|(_, stored_range)| stored_range)
+            .collect()
+    }
+
+    ///
+    /// Returns the attribute of the visible cell at the specified position, and the range of visible cells around it that
+    /// have the same attribute and are not separated by tombstones
+    ///
+    fn visible_attribute_run(&self, pos: usize) -> (&BaseRope::Attribute, Range<usize>) {
+        let stored_pos              = self.stored_index(pos);
+        let (_, visible_run)        = self.layout.read_attributes(stored_pos);
+        let (attribute, stored_run) = self.rope.read_attributes(stored_pos);
+
+        // Limit the attribute run to the visible cells and convert back to a visible position
+        let stored_start    = stored_run.start.max(visible_run.start);
+        let stored_end      = stored_run.end.min(visible_run.end);
+        let start           = self.visible_index(stored_start);
+
+        (attribute, start..(start + stored_end - stored_start))
+    }
+
+    ///
+    /// Replaces a range of visible cells, turning the existing cells into tombstones and storing the new cells after them
+    ///
+    fn replace_with_tombstones(&mut self, range: Range<usize>, new_cells: Vec<BaseRope::Cell>, new_attributes: Option<BaseRope::Attribute>) {
+        let start           = range.start.min(self.len);
+        let range           = start..range.end.min(self.len).max(start);
+        let stored_start    = self.stored_index(range.start);
+        let stored_end      = if range.start < range.end { self.stored_index(range.end-1)+1 } else { stored_start };
+        let num_cells       = new_cells.len();
+
+        // Cells replacing a range have the attributes of the first cell in that range
+        let new_attributes  = new_attributes.or_else(|| if range.start < range.end { Some(self.rope.read_attributes(stored_start).0.clone()) } else { None });
+
+        // Tombstone the existing cells
+        if stored_start < stored_end {
+            self.layout.set_attributes(stored_start..stored_end, false);
+        }
+
+        // Store the new cells after the tombstones
+        if num_cells > 0 {
+            match new_attributes {
+                Some(new_attributes)    => self.rope.replace_attributes(stored_end..stored_end, new_cells, new_attributes),
+                None                    => self.rope.replace(stored_end..stored_end, new_cells)
+            }
+
+            self.layout.replace_attributes(stored_end..stored_end, iter::repeat_n((), num_cells), true);
+        }
+
+        self.len = self.len - range.len() + num_cells;
+    }
+}
+
+impl<BaseRope> Rope for TombstoneRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        Box::new(self.visible_stored_ranges(range).into_iter()
+            .flat_map(move |stored_range| self.rope.read_cells(stored_range)))
+    }
+
+    ///
+    /// Reads the visible cells in a range of this rope as a series of slices, in order
+    ///
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        Box::new(self.visible_stored_ranges(range).into_iter()
+            .flat_map(move |stored_range| self.rope.read_chunks(stored_range)))
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        if self.len == 0 { return (&self.default_attribute, 0..0); }
+
+        // Extend the run over any neighbouring runs with the same attribute (these can be separated by tombstones in the base rope)
+        let (attribute, mut range) = self.visible_attribute_run(pos.min(self.len-1));
+
+        while range.end < self.len {
+            let (next_attribute, next_range) = self.visible_attribute_run(range.end);
+            if next_attribute != attribute { break; }
+
+            range.end = next_range.end;
+        }
+
+        while range.start > 0 {
+            let (prev_attribute, prev_range) = self.visible_attribute_run(range.start-1);
+            if prev_attribute != attribute { break; }
+
+            range.start = prev_range.start;
+        }
+
+        (attribute, range)
+    }
+}
+
+impl<BaseRope> RopeMut for TombstoneRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Performs the specified editing action to this rope
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        match action {
+            RopeAction::Replace(range, cells)                       => self.replace_with_tombstones(range, cells, None),
+            RopeAction::ReplaceAttributes(range, cells, attribute)  => self.replace_with_tombstones(range, cells, Some(attribute)),
+
+            RopeAction::SetAttributes(range, attribute)             => {
+                // Only the visible cells have their attributes set
+                for stored_range in self.visible_stored_ranges(range) {
+                    self.rope.set_attributes(stored_range, attribute.clone());
+                }
+            }
+        }
+    }
+}
+
+impl<BaseRope> RopeSearch for TombstoneRope<BaseRope>
+where
+BaseRope:       RopeMut,
+BaseRope::Cell: PartialEq { }
@@ -0,0 +1,144 @@
+use crate::api::*;
+
+use icu_collator::{Collator};
+
+use std::cmp::{Ordering};
+use std::collections::{VecDeque};
+use std::ops::{Range};
+
+///
+/// Locale-aware comparison and search for ropes containing UTF-8 text (available with the `icu_collator` feature)
+///
+/// These use an ICU collator to decide if two pieces of text are equal, so depending on the options used to create the
+/// collator, searches can ignore differences in case or accents in the way that a reader of the text would expect.
+///
+pub trait Utf8RopeCollation {
+    ///
+    /// Compares the text in this rope with the text in another rope using a collator
+    ///
+    /// The collator needs the whole of both strings, so this reads a copy of the text from both ropes.
+    ///
+    fn collation_cmp<OtherRope: Rope<Cell=u8>>(&self, other: &OtherRope, collator: &Collator) -> Ordering;
+
+    ///
+    /// Finds the first part of a range of this rope that the collator considers equal to the specified string, returning
+    /// the range of bytes that matched
+    ///
+    /// The rope is read a character at a time, so only a small window of the text is decoded at once. Where several
+    /// matches start at the same position, the longest one is returned (so that any combining marks that follow the
+    /// match are included in it).
+    ///
+    fn find_collated(&self, needle: &str, collator: &Collator, range: Range<usize>) -> Option<Range<usize>>;
+}
+
+///
+/// Decodes the characters in a sequence of UTF-8 bytes, along with the position of the first byte of each character.
+/// Invalid sequences are decoded as one replacement character per byte.
+///
+struct Utf8CharPositions<Bytes: Iterator<Item=u8>> {
+    /// The bytes that are being decoded
+    bytes: Bytes,
+
+    /// Bytes that have been read but not decoded yet
+    pending: VecDeque<u8>,
+
+    /// The position of the first pending byte (or the next byte to be read if there are no pending bytes)
+    pos: usize
+}
+
+impl<Bytes: Iterator<Item=u8>> Iterator for Utf8CharPositions<Bytes> {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<(usize, char)> {
+        // Read the first byte of the next character
+        if self.pending.is_empty() {
+            self.pending.push_back(self.bytes.next()?);
+        }
+
+        // Work out how long the character should be from its first byte
+        let first   = self.pending[0];
+        let len     = match first {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _           => 1
+        };
+
+        while self.pending.len() < len {
+            match self.bytes.next() {
+                Some(byte)  => self.pending.push_back(byte),
+                None        => break
+            }
+        }
+
+        // Decode the character, or generate a replacement character for the first byte if it's invalid
+        let pos         = self.pos;
+        let char_bytes  = self.pending.iter().take(len).copied().collect::<Vec<_>>();
+
+        match std::str::from_utf8(&char_bytes).ok().and_then(|chr| chr.chars().next()) {
+            Some(chr) if char_bytes.len() == len => {
+                self.pending.drain(0..len);
+                self.pos += len;
+                Some((pos, chr))
+            }
+
+            _ => {
+                self.pending.pop_front();
+                self.pos += 1;
+                Some((pos, char::REPLACEMENT_CHARACTER))
+            }
+        }
+    }
+}
+
+impl<R> Utf8RopeCollation for R
+where
+R: Rope<Cell=u8> {
+    fn collation_cmp<OtherRope: Rope<Cell=u8>>(&self, other: &OtherRope, collator: &Collator) -> Ordering {
+        let our_text    = self.read_cells(0..self.len()).copied().collect::<Vec<_>>();
+        let their_text  = other.read_cells(0..other.len()).copied().collect::<Vec<_>>();
+
+        collator.compare_utf8(&our_text, &their_text)
+    }
+
+    fn find_collated(&self, needle: &str, collator: &Collator, range: Range<usize>) -> Option<Range<usize>> {
+        let range       = range.start.min(self.len())..range.end.min(self.len());
+        let needle_len  = needle.chars().count();
+        if needle_len == 0 { return Some(range.start..range.start); }
+
+        // Matches can be longer than the needle (eg, if they contain combining characters), so this checks up to twice the number of characters in the needle
+        let max_window  = needle_len * 2 + 1;
+        let mut chars   = Utf8CharPositions { bytes: self.read_cells(range.clone()).copied(), pending: VecDeque::new(), pos: range.start };
+        let mut window  = VecDeque::with_capacity(max_window);
+        let mut text    = String::new();
+
+        loop {
+            // Fill the window with characters from the rope
+            while window.len() < max_window {
+                match chars.next() {
+                    Some(chr)   => window.push_back(chr),
+                    None        => break
+                }
+            }
+
+            if window.is_empty() { return None; }
+
+            // Try the possible matches starting at the start of the window, longest first
+            for match_len in (1..=window.len()).rev() {
+                text.clear();
+                text.extend(window.iter().take(match_len).map(|(_, chr)| *chr));
+
+                if collator.compare(&text, needle) == Ordering::Equal {
+                    let start   = window[0].0;
+                    let end     = window.get(match_len).map(|(pos, _)| *pos).unwrap_or(chars.pos);
+
+                    return Some(start..end);
+                }
+            }
+
+            // Move on to the next character
+            window.pop_front();
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use crate::api::*;
+
+use regex::{Regex};
+use regex_automata::{Anchored, MatchKind};
+use regex_automata::hybrid::dfa::{DFA, Cache};
+use regex_automata::nfa::thompson;
+use regex_automata::util::start;
+
+use std::ops::{Range};
+
+/// The number of bytes that are read at a time when searching backwards for the start of a match
+const REVERSE_WINDOW: usize = 64;
+
+///
+/// Regular expression search for ropes containing UTF-8 text (available with the `regex` feature)
+///
+pub trait Utf8RopeRegex : Rope<Cell=u8> {
+    ///
+    /// Returns an iterator over the ranges of the non-overlapping matches of a regular expression in this rope
+    ///
+    /// The rope is searched a chunk at a time using a lazy DFA built from the regular expression's pattern, so the text
+    /// doesn't need to be copied into a `String` first. As the DFA is built from `Regex::as_str()`, any options that
+    /// were set using a `RegexBuilder` will not apply (inline flags such as `(?i)` can be used instead). Patterns that
+    /// use Unicode word boundaries fall back to copying the text if they encounter a non-ASCII character.
+    ///
+    fn regex_find_iter<'a>(&'a self, regex: &Regex) -> RopeRegexMatches<'a, Self> {
+        RopeRegexMatches {
+            rope:           self,
+            pattern:        regex.as_str().to_string(),
+            dfas:           RegexDfas::new(regex.as_str()),
+            fallback:       None,
+            pos:            0,
+            last_match_end: None
+        }
+    }
+}
+
+impl<R> Utf8RopeRegex for R
+where
+R: Rope<Cell=u8> { }
+
+///
+/// The lazy DFAs used to search a rope for a regular expression
+///
+struct RegexDfas {
+    /// Finds the end of the leftmost match
+    forward: DFA,
+    forward_cache: Cache,
+
+    /// Searches backwards from the end of a match to find where it starts
+    reverse: DFA,
+    reverse_cache: Cache
+}
+
+///
+/// Iterator over the ranges that match a regular expression in a rope, returned by `Utf8RopeRegex::regex_find_iter()`
+///
+pub struct RopeRegexMatches<'a, R: ?Sized> {
+    /// The rope that is being searched
+    rope: &'a R,
+
+    /// The pattern that is being searched for
+    pattern: String,
+
+    /// The DFAs used for the search (or `None` if the search has fallen back to copying the text)
+    dfas: Option<RegexDfas>,
+
+    /// If the DFAs can't be used, a regular expression and a copy of the text to search
+    fallback: Option<(regex::bytes::Regex, Vec<u8>)>,
+
+    /// The position where the next search will start
+    pos: usize,
+
+    /// The end of the last match that was returned
+    last_match_end: Option<usize>
+}
+
+impl RegexDfas {
+    ///
+    /// Creates the DFAs for a pattern, or returns `None` if they can't be built
+    ///
+    fn new(pattern: &str) -> Option<RegexDfas> {
+        let forward = DFA::builder()
+            .configure(DFA::config().match_kind(MatchKind::LeftmostFirst).unicode_word_boundary(true))
+            .build(pattern)
+            .ok()?;
+        let reverse = DFA::builder()
+            .configure(DFA::config().match_kind(MatchKind::All).unicode_word_boundary(true))
+            .thompson(thompson::Config::new().reverse(true))
+            .build(pattern)
+            .ok()?;
+
+        Some(RegexDfas {
+            forward_cache:  forward.create_cache(),
+            reverse_cache:  reverse.create_cache(),
+            forward:        forward,
+            reverse:        reverse
+        })
+    }
+
+    ///
+    /// Finds the leftmost match at or after the specified position, or returns an error if the DFAs had to give up
+    ///
+    fn find_at<R: ?Sized+Rope<Cell=u8>>(&mut self, rope: &R, pos: usize) -> Result<Option<Range<usize>>, ()> {
+        match self.find_end(rope, pos)? {
+            Some(end)   => Ok(Some(self.find_start(rope, pos, end)?..end)),
+            None        => Ok(None)
+        }
+    }
+
+    ///
+    /// Finds the end of the leftmost match at or after the specified position
+    ///
+    fn find_end<R: ?Sized+Rope<Cell=u8>>(&mut self, rope: &R, pos: usize) -> Result<Option<usize>, ()> {
+        let len         = rope.len();
+        let start       = start::Config::new().anchored(Anchored::No).look_behind(byte_at(rope, pos, 1));
+        let mut state   = self.forward.start_state(&mut self.forward_cache, &start).map_err(|_| ())?;
+        let mut end     = None;
+        let mut at      = pos;
+
+        for chunk in rope.read_chunks(pos..len) {
+            for byte in chunk.iter() {
+                state = self.forward.next_state(&mut self.forward_cache, state, *byte).map_err(|_| ())?;
+
+                // Matches are reported one byte late, so a match state here means that a match ended before this byte
+                if state.is_match()     { end = Some(at); }
+                else if state.is_dead() { return Ok(end); }
+                else if state.is_quit() { return Err(()); }
+
+                at += 1;
+            }
+        }
+
+        state = self.forward.next_eoi_state(&mut self.forward_cache, state).map_err(|_| ())?;
+        if state.is_match() { end = Some(len); }
+
+        Ok(end)
+    }
+
+    ///
+    /// Finds the start of the match that ends at the specified position (the match starts at or after `pos`)
+    ///
+    fn find_start<R: ?Sized+Rope<Cell=u8>>(&mut self, rope: &R, pos: usize, end: usize) -> Result<usize, ()> {
+        let start_config    = start::Config::new().anchored(Anchored::Yes).look_behind(byte_at(rope, end, 0));
+        let mut state       = self.reverse.start_state(&mut self.reverse_cache, &start_config).map_err(|_| ())?;
+        let mut start       = None;
+        let mut window      = Vec::with_capacity(REVERSE_WINDOW);
+        let mut window_end  = end;
+
+        // Read the rope backwards a window at a time
+        while window_end > pos {
+            let window_start = window_end.saturating_sub(REVERSE_WINDOW).max(pos);
+
+            window.clear();
+            window.extend(rope.read_cells(window_start..window_end).copied());
+
+            for (offset, byte) in window.iter().enumerate().rev() {
+                state = self.reverse.next_state(&mut self.reverse_cache, state, *byte).map_err(|_| ())?;
+
+                // The earliest start seen so far is the start of the leftmost match
+                if state.is_match()     { start = Some(window_start + offset + 1); }
+                else if state.is_dead() { return start.ok_or(()); }
+                else if state.is_quit() { return Err(()); }
+            }
+
+            window_end = window_start;
+        }
+
+        // The byte before the search position (if there is one) is needed to check whether or not the match can start there
+        state = match byte_at(rope, pos, 1) {
+            Some(byte)  => self.reverse.next_state(&mut self.reverse_cache, state, byte),
+            None        => self.reverse.next_eoi_state(&mut self.reverse_cache, state)
+        }.map_err(|_| ())?;
+
+        if state.is_match() { start = Some(pos); }
+
+        start.ok_or(())
+    }
+}
+
+///
+/// Returns the byte at `pos - offset` in a rope, if there is one
+///
+fn byte_at<R: ?Sized+Rope<Cell=u8>>(rope: &R, pos: usize, offset: usize) -> Option<u8> {
+    if pos < offset { return None; }
+
+    let pos = pos - offset;
+    rope.read_cells(pos..(pos+1)).next().copied()
+}
+
+impl<'a, R> RopeRegexMatches<'a, R>
+where
+R: ?Sized+Rope<Cell=u8> {
+    ///
+    /// Finds the leftmost match at or after the specified position
+    ///
+    fn find_at(&mut self, pos: usize) -> Option<Range<usize>> {
+        if let Some(dfas) = &mut self.dfas {
+            match dfas.find_at(self.rope, pos) {
+                Ok(found)   => { return found; }
+                Err(())     => { self.dfas = None; }
+            }
+        }
+
+        // The DFAs have given up, so search a copy of the text instead
+        if self.fallback.is_none() {
+            let regex   = regex::bytes::Regex::new(&self.pattern).ok()?;
+            let text    = self.rope.read_cells(0..self.rope.len()).copied().collect();
+
+            self.fallback = Some((regex, text));
+        }
+
+        let (regex, text) = self.fallback.as_ref()?;
+        regex.find_at(text, pos).map(|found| found.range())
+    }
+}
+
+impl<'a, R> Iterator for RopeRegexMatches<'a, R>
+where
+R: ?Sized+Rope<Cell=u8> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        loop {
+            if self.pos > self.rope.len() { return None; }
+
+            let found = self.find_at(self.pos)?;
+
+            let is_char_boundary = byte_at(self.rope, found.end, 0).map(|byte| (byte & 0xc0) != 0x80).unwrap_or(true);
+
+            if found.is_empty() && (Some(found.end) == self.last_match_end || !is_char_boundary) {
+                // An empty match can't immediately follow another match, or split a character
+                self.pos = found.end + 1;
+            } else {
+                self.pos            = found.end;
+                self.last_match_end = Some(found.end);
+
+                return Some(found);
+            }
+        }
+    }
+}
@@ -1,5 +1,12 @@
+use super::attributed_rope::*;
+use super::lsp::*;
+
 use crate::api::*;
 
+use std::fmt;
+use std::str;
+use std::ops::{Range};
+
 ///
 /// Trait implemented by attributed ropes that can work as strings
 ///
@@ -13,6 +20,281 @@ pub trait Utf8Rope {
     /// Converts this rope to a string
     ///
     fn to_string_lossy(&self) -> String;
+
+    ///
+    /// Returns an iterator over the characters in this rope
+    ///
+    /// The text is decoded a chunk at a time, so this doesn't need to copy the whole rope. Invalid UTF-8 sequences are
+    /// returned as the replacement character, in the same way as for `to_string_lossy()`.
+    ///
+    fn chars<'a>(&'a self) -> RopeChars<'a>;
+
+    ///
+    /// Returns an iterator over the characters in this rope along with their byte offsets
+    ///
+    fn char_indices<'a>(&'a self) -> RopeCharIndices<'a>;
+
+    ///
+    /// Converts a byte offset in this rope to an offset in UTF-16 code units (offsets within a character are treated as
+    /// being at the end of that character)
+    ///
+    fn utf8_to_utf16_offset(&self, offset: usize) -> usize;
+
+    ///
+    /// Converts an offset in UTF-16 code units to a byte offset in this rope (offsets beyond the end are moved to the end
+    /// of the rope, and offsets in the middle of a surrogate pair are moved to the end of the character)
+    ///
+    fn utf16_to_utf8_offset(&self, utf16_offset: usize) -> usize;
+
+    ///
+    /// Converts a byte offset in this rope to a line number and an offset in UTF-16 code units within that line
+    ///
+    /// `\n`, `\r\n` and `\r` are all treated as line endings, as for the language server protocol. This is the same as
+    /// `offset_to_lsp_position()`, which scans the rope from the start: use a `LineIndex` to avoid this when converting
+    /// many positions in a large rope.
+    ///
+    fn utf8_offset_to_utf16_position(&self, offset: usize) -> LspPosition;
+
+    ///
+    /// Converts a line number and an offset in UTF-16 code units within that line to a byte offset in this rope
+    ///
+    /// Positions beyond the end of a line are moved to the end of that line, and lines beyond the end of the rope are
+    /// moved to the end of the rope. This is the same as `lsp_position_to_offset()`, which scans the rope from the start:
+    /// use a `LineIndex` to avoid this when converting many positions in a large rope.
+    ///
+    fn utf16_position_to_utf8_offset(&self, position: LspPosition) -> usize;
+}
+
+///
+/// Trait implemented by ropes containing UTF-8 text that can be edited
+///
+pub trait Utf8RopeMut {
+    ///
+    /// Converts the characters in a range of this rope to lower case, preserving their attributes
+    ///
+    fn make_lowercase(&mut self, range: Range<usize>);
+
+    ///
+    /// Converts the characters in a range of this rope to upper case, preserving their attributes
+    ///
+    fn make_uppercase(&mut self, range: Range<usize>);
+}
+
+///
+/// Converts the case of the characters in a range of a rope
+///
+/// Each attribute run is converted separately, so the attributes remain the same even if the converted characters have a different
+/// length. Only the regions containing characters that change are edited. Runs that do not contain valid UTF-8 are left unchanged.
+///
+fn convert_case<R, ConvertFn, ConvertIter>(rope: &mut R, range: Range<usize>, convert_char: ConvertFn)
+where
+R:              RopeMut<Cell=u8>,
+ConvertFn:      Fn(char) -> ConvertIter,
+ConvertIter:    Iterator<Item=char> {
+    let mut edits = vec![];
+
+    for (attribute, run_range) in rope.read_attribute_runs(range) {
+        let bytes   = rope.read_cells(run_range.clone()).copied().collect::<Vec<_>>();
+        let text    = match str::from_utf8(&bytes) { Ok(text) => text, Err(_) => { continue; } };
+
+        // Find the regions of characters that change when converted
+        let mut region: Option<(Range<usize>, String)> = None;
+
+        for (char_offset, chr) in text.char_indices() {
+            let char_range  = (run_range.start + char_offset)..(run_range.start + char_offset + chr.len_utf8());
+            let mut chars   = convert_char(chr);
+            let unchanged   = chars.next() == Some(chr) && chars.next().is_none();
+
+            if unchanged {
+                // Finish the current region
+                if let Some((region_range, converted)) = region.take() {
+                    edits.push((region_range, converted, attribute.clone()));
+                }
+            } else {
+                // Add to the current region
+                let (region_range, converted) = region.get_or_insert_with(|| (char_range.start..char_range.start, String::new()));
+
+                region_range.end = char_range.end;
+                converted.extend(convert_char(chr));
+            }
+        }
+
+        if let Some((region_range, converted)) = region.take() {
+            edits.push((region_range, converted, attribute.clone()));
+        }
+    }
+
+    // Apply the edits from the end of the rope so that the earlier ranges don't move
+    for (range, converted, attribute) in edits.into_iter().rev() {
+        rope.replace_attributes(range, converted.into_bytes(), attribute);
+    }
+}
+
+///
+/// Iterator over the characters in a UTF-8 rope along with their byte offsets, returned by `Utf8Rope::char_indices()`
+///
+pub struct RopeCharIndices<'a> {
+    /// The chunks of the rope that haven't been read yet
+    chunks: Box<dyn 'a+Iterator<Item=&'a [u8]>>,
+
+    /// The remaining bytes in the current chunk
+    chunk: &'a [u8],
+
+    /// Bytes that have been read from the chunks but not decoded yet (used for characters that are split between chunks)
+    pending: [u8; 4],
+
+    /// The number of bytes in `pending`
+    num_pending: usize,
+
+    /// The offset of the next byte to decode
+    pos: usize
+}
+
+///
+/// Iterator over the characters in a UTF-8 rope, returned by `Utf8Rope::chars()`
+///
+pub struct RopeChars<'a>(RopeCharIndices<'a>);
+
+impl<'a> RopeCharIndices<'a> {
+    ///
+    /// Creates an iterator that decodes the characters in a rope
+    ///
+    fn new<R: ?Sized+Rope<Cell=u8>>(rope: &'a R) -> RopeCharIndices<'a> {
+        RopeCharIndices {
+            chunks:         rope.read_chunks(0..rope.len()),
+            chunk:          &[],
+            pending:        [0; 4],
+            num_pending:    0,
+            pos:            0
+        }
+    }
+
+    ///
+    /// Moves bytes from the chunks to the pending buffer until it contains at least `len` bytes (or the rope runs out)
+    ///
+    fn fill_pending(&mut self, len: usize) {
+        while self.num_pending < len {
+            if let Some((byte, rest)) = self.chunk.split_first() {
+                self.pending[self.num_pending]  = *byte;
+                self.num_pending                += 1;
+                self.chunk                      = rest;
+            } else {
+                match self.chunks.next() {
+                    Some(chunk) => { self.chunk = chunk; }
+                    None        => { break; }
+                }
+            }
+        }
+    }
+
+    ///
+    /// Removes bytes from the start of the pending buffer
+    ///
+    fn consume_pending(&mut self, len: usize) {
+        self.pending.copy_within(len..self.num_pending, 0);
+        self.num_pending    -= len;
+        self.pos            += len;
+    }
+}
+
+impl<'a> Iterator for RopeCharIndices<'a> {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<(usize, char)> {
+        let pos = self.pos;
+
+        // ASCII characters can be read directly from the chunk
+        if self.num_pending == 0 {
+            if let Some((byte, rest)) = self.chunk.split_first() {
+                if *byte < 0x80 {
+                    self.chunk  = rest;
+                    self.pos    += 1;
+
+                    return Some((pos, *byte as char));
+                }
+            }
+        }
+
+        // Other characters are gathered into the pending buffer, which means they can be split across chunks
+        self.fill_pending(1);
+        if self.num_pending == 0 { return None; }
+
+        let char_len = match self.pending[0] {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _           => 1
+        };
+
+        self.fill_pending(char_len);
+        let available = char_len.min(self.num_pending);
+
+        match str::from_utf8(&self.pending[0..available]) {
+            Ok(text) if available == char_len => {
+                let chr = text.chars().next().unwrap();
+                self.consume_pending(char_len);
+
+                Some((pos, chr))
+            }
+
+            result => {
+                // Invalid sequences are replaced in the same way as String::from_utf8_lossy()
+                let invalid_len = result.err().and_then(|err| err.error_len()).unwrap_or(available);
+                self.consume_pending(invalid_len);
+
+                Some((pos, char::REPLACEMENT_CHARACTER))
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for RopeChars<'a> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        self.0.next().map(|(_, chr)| chr)
+    }
+}
+
+///
+/// Returns the number of UTF-16 code units needed for a range of a UTF-8 rope
+///
+fn utf16_len<R: ?Sized+Rope<Cell=u8>>(rope: &R, range: Range<usize>) -> usize {
+    rope.read_chunks(range)
+        .map(|chunk| chunk.iter().map(|byte| utf16_units(*byte)).sum::<usize>())
+        .sum()
+}
+
+///
+/// Finds the byte offset that is a number of UTF-16 code units after the start of a UTF-8 rope
+///
+fn utf16_advance<R: ?Sized+Rope<Cell=u8>>(rope: &R, utf16_offset: usize) -> usize {
+    let mut remaining   = utf16_offset;
+    let mut pos         = 0;
+
+    for chunk in rope.read_chunks(0..rope.len()) {
+        // Skip whole chunks when possible
+        let chunk_len = chunk.iter().map(|byte| utf16_units(*byte)).sum::<usize>();
+
+        if chunk_len <= remaining {
+            remaining   -= chunk_len;
+            pos         += chunk.len();
+            continue;
+        }
+
+        // Stop at the first character that starts after the offset, so an offset in a surrogate pair moves to the end of the character
+        for byte in chunk.iter() {
+            let units = utf16_units(*byte);
+            if remaining == 0 && units > 0 { return pos; }
+
+            remaining   = remaining.saturating_sub(units);
+            pos         += 1;
+        }
+    }
+
+    pos
 }
 
 impl<R: Default+RopeMut<Cell=u8>> Utf8Rope for R {
@@ -32,10 +314,123 @@ impl<R: Default+RopeMut<Cell=u8>> Utf8Rope for R {
     fn to_string_lossy(&self) -> String {
         // Generate a vec of all the bytes in this rope
         let bytes = self.read_cells(0..self.len())
-            .map(|byte| *byte)
+            .copied()
             .collect::<Vec<_>>();
 
         // Convert to string
         String::from_utf8_lossy(&bytes).into()
     }
-}
\ No newline at end of file
+
+    ///
+    /// Returns an iterator over the characters in this rope
+    ///
+    fn chars<'a>(&'a self) -> RopeChars<'a> {
+        RopeChars(RopeCharIndices::new(self))
+    }
+
+    ///
+    /// Returns an iterator over the characters in this rope along with their byte offsets
+    ///
+    fn char_indices<'a>(&'a self) -> RopeCharIndices<'a> {
+        RopeCharIndices::new(self)
+    }
+
+    ///
+    /// Converts a byte offset in this rope to an offset in UTF-16 code units
+    ///
+    fn utf8_to_utf16_offset(&self, offset: usize) -> usize {
+        utf16_len(self, 0..offset.min(self.len()))
+    }
+
+    ///
+    /// Converts an offset in UTF-16 code units to a byte offset in this rope
+    ///
+    fn utf16_to_utf8_offset(&self, utf16_offset: usize) -> usize {
+        utf16_advance(self, utf16_offset)
+    }
+
+    ///
+    /// Converts a byte offset in this rope to a line number and an offset in UTF-16 code units within that line
+    ///
+    fn utf8_offset_to_utf16_position(&self, offset: usize) -> LspPosition {
+        offset_to_lsp_position(self, offset)
+    }
+
+    ///
+    /// Converts a line number and an offset in UTF-16 code units within that line to a byte offset in this rope
+    ///
+    fn utf16_position_to_utf8_offset(&self, position: LspPosition) -> usize {
+        lsp_position_to_offset(self, position)
+    }
+}
+
+impl<R: RopeMut<Cell=u8>> Utf8RopeMut for R {
+    ///
+    /// Converts the characters in a range of this rope to lower case, preserving their attributes
+    ///
+    fn make_lowercase(&mut self, range: Range<usize>) {
+        convert_case(self, range, |chr| chr.to_lowercase());
+    }
+
+    ///
+    /// Converts the characters in a range of this rope to upper case, preserving their attributes
+    ///
+    fn make_uppercase(&mut self, range: Range<usize>) {
+        convert_case(self, range, |chr| chr.to_uppercase());
+    }
+}
+
+impl<'a, Attribute> From<&'a str> for AttributedRope<u8, Attribute>
+where
+Attribute: PartialEq+Clone+Default {
+    ///
+    /// Creates a rope containing the bytes of a string
+    ///
+    fn from(string: &'a str) -> Self {
+        AttributedRope::from(string.bytes())
+    }
+}
+
+impl<Attribute> From<String> for AttributedRope<u8, Attribute>
+where
+Attribute: PartialEq+Clone+Default {
+    ///
+    /// Creates a rope containing the bytes of a string
+    ///
+    fn from(string: String) -> Self {
+        AttributedRope::from(string.into_bytes())
+    }
+}
+
+impl<Attribute> From<AttributedRope<u8, Attribute>> for String
+where
+Attribute: PartialEq+Clone+Default {
+    ///
+    /// Converts a rope to a string, replacing any invalid UTF-8 sequences with the replacement character
+    ///
+    fn from(rope: AttributedRope<u8, Attribute>) -> String {
+        rope.to_string_lossy()
+    }
+}
+
+impl<'a, Attribute> From<&'a AttributedRope<u8, Attribute>> for String
+where
+Attribute: PartialEq+Clone+Default {
+    ///
+    /// Converts a rope to a string, replacing any invalid UTF-8 sequences with the replacement character
+    ///
+    fn from(rope: &'a AttributedRope<u8, Attribute>) -> String {
+        rope.to_string_lossy()
+    }
+}
+
+impl<Attribute> fmt::Display for AttributedRope<u8, Attribute>
+where
+Attribute: PartialEq+Clone+Default {
+    ///
+    /// Displays the text in this rope, replacing any invalid UTF-8 sequences with the replacement character
+    ///
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_string_lossy())
+    }
+}
@@ -1,6 +1,7 @@
 use crate::api::*;
 
 use std::marker::{PhantomData};
+use std::ops::{Range};
 
 ///
 /// Given two streams of actions that represent the changes to the two halfs of a single
@@ -71,13 +72,278 @@ impl<Cell, Attribute> RopeConcatenator<Cell, Attribute> {
         // Adjust all of the actions by the length of the RHS of the rope
         items.into_iter()
             .map(move |item| {
-                use RopeAction::*;
+                let range = item.range();
+                item.with_range((range.start+left_len)..(range.end+left_len))
+            })
+    }
+}
 
-                match item {
-                    Replace(range, cells)                       => Replace((range.start+left_len)..(range.end+left_len), cells),
-                    ReplaceAttributes(range, cells, attributes) => ReplaceAttributes((range.start+left_len)..(range.end+left_len), cells, attributes),
-                    SetAttributes(range, attributes)            => SetAttributes((range.start+left_len)..(range.end+left_len), attributes),
-                }
+///
+/// Given streams of actions for an ordered list of ropes, generates a single stream of actions representing the rope
+/// that's formed by concatenating them all together
+///
+/// This is a generalisation of `RopeConcatenator` to any number of segments. Segments can be inserted or removed at any
+/// point, and the actions for each segment are moved by the total length of the segments that come before it.
+///
+pub struct RopeSegments<Cell, Attribute> {
+    cell: PhantomData<Cell>,
+    attribute: PhantomData<Attribute>,
+
+    /// The length of each segment, in order
+    segment_lens: Vec<usize>
+}
+
+impl<Cell, Attribute> RopeSegments<Cell, Attribute> {
+    ///
+    /// Creates a new set of segments, initially containing no segments
+    ///
+    pub fn new() -> RopeSegments<Cell, Attribute> {
+        RopeSegments {
+            cell:           PhantomData,
+            attribute:      PhantomData,
+            segment_lens:   vec![]
+        }
+    }
+
+    ///
+    /// The number of segments
+    ///
+    pub fn num_segments(&self) -> usize {
+        self.segment_lens.len()
+    }
+
+    ///
+    /// The length of the concatenated rope
+    ///
+    pub fn len(&self) -> usize {
+        self.segment_lens.iter().sum()
+    }
+
+    ///
+    /// True if there are no cells in any of the segments
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///
+    /// The length of the segment with the specified index
+    ///
+    pub fn segment_len(&self, segment_idx: usize) -> usize {
+        self.segment_lens[segment_idx]
+    }
+
+    ///
+    /// The position in the concatenated rope where a segment starts
+    ///
+    pub fn segment_offset(&self, segment_idx: usize) -> usize {
+        self.segment_lens[0..segment_idx].iter().sum()
+    }
+
+    ///
+    /// Inserts a new, empty, segment before the segment with the specified index (or at the end if the index is the
+    /// number of segments)
+    ///
+    pub fn insert_segment(&mut self, segment_idx: usize) {
+        self.segment_lens.insert(segment_idx, 0);
+    }
+
+    ///
+    /// Removes a segment, returning the action that removes its cells from the concatenated rope (if it wasn't empty)
+    ///
+    pub fn remove_segment(&mut self, segment_idx: usize) -> Option<RopeAction<Cell, Attribute>> {
+        let offset  = self.segment_offset(segment_idx);
+        let len     = self.segment_lens.remove(segment_idx);
+
+        if len > 0 {
+            Some(RopeAction::Replace(offset..(offset+len), vec![]))
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// Processes actions intended for one of the segments, returning an iterator of the actions for the concatenated rope
+    ///
+    pub fn send_to<'a, ActionIter: 'a+IntoIterator<Item=RopeAction<Cell, Attribute>>>(&'a mut self, segment_idx: usize, items: ActionIter) -> impl 'a+Iterator<Item=RopeAction<Cell, Attribute>> {
+        let offset = self.segment_offset(segment_idx);
+
+        items.into_iter()
+            .map(move |item| {
+                // In debug builds, assert the actions are within the segment
+                let segment_len = &mut self.segment_lens[segment_idx];
+                debug_assert!(item.range().end <= *segment_len);
+
+                // Update the length of the segment
+                *segment_len = *segment_len + item.new_len() - item.range().len();
+
+                // Move the action to where the segment is in the concatenated rope
+                let range = item.range();
+                item.with_range((range.start+offset)..(range.end+offset))
             })
     }
 }
+
+///
+/// A rope made by joining two other ropes together, which can be read and edited as a single rope
+///
+/// The cells of the second rope follow the cells of the first. Edits are sent to the rope that contains the cells that
+/// they change, and an edit that changes cells in both ropes is divided between them: any new cells are added to the
+/// first rope. Cells inserted at the point where the two ropes meet are added to the start of the second rope (use
+/// `first_mut()` to add cells to the end of the first rope).
+///
+#[derive(Clone)]
+pub struct ConcatRope<FirstRope, SecondRope> {
+    /// The rope containing the first part of the cells
+    first: FirstRope,
+
+    /// The rope containing the cells after the first rope
+    second: SecondRope
+}
+
+impl<FirstRope, SecondRope> ConcatRope<FirstRope, SecondRope>
+where
+FirstRope:  RopeMut,
+SecondRope: RopeMut<Cell=FirstRope::Cell, Attribute=FirstRope::Attribute> {
+    ///
+    /// Creates a rope that concatenates two other ropes
+    ///
+    pub fn from(first: FirstRope, second: SecondRope) -> ConcatRope<FirstRope, SecondRope> {
+        ConcatRope {
+            first:  first,
+            second: second
+        }
+    }
+
+    ///
+    /// Returns the two ropes that this is made from
+    ///
+    pub fn into_inner(self) -> (FirstRope, SecondRope) {
+        (self.first, self.second)
+    }
+
+    ///
+    /// Borrows the first rope
+    ///
+    pub fn first(&self) -> &FirstRope {
+        &self.first
+    }
+
+    ///
+    /// Borrows the second rope
+    ///
+    pub fn second(&self) -> &SecondRope {
+        &self.second
+    }
+
+    ///
+    /// Borrows the first rope for editing
+    ///
+    pub fn first_mut(&mut self) -> &mut FirstRope {
+        &mut self.first
+    }
+
+    ///
+    /// Borrows the second rope for editing
+    ///
+    pub fn second_mut(&mut self) -> &mut SecondRope {
+        &mut self.second
+    }
+
+    ///
+    /// Divides a range of this rope into the ranges of the first and second ropes that it covers
+    ///
+    #[inline]
+    fn split_range(&self, range: Range<usize>) -> (Range<usize>, Range<usize>) {
+        let first_len       = self.first.len();
+        let start           = range.start;
+        let end             = range.end.max(start);
+
+        let first_range     = start.min(first_len)..end.min(first_len);
+        let second_range    = (start.max(first_len) - first_len)..(end.max(first_len) - first_len);
+
+        (first_range, second_range)
+    }
+}
+
+impl<FirstRope, SecondRope> Rope for ConcatRope<FirstRope, SecondRope>
+where
+FirstRope:  RopeMut,
+SecondRope: RopeMut<Cell=FirstRope::Cell, Attribute=FirstRope::Attribute> {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = FirstRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = FirstRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.first.len() + self.second.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        let (first_range, second_range) = self.split_range(range);
+
+        Box::new(self.first.read_cells(first_range).chain(self.second.read_cells(second_range)))
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        let (first_range, second_range) = self.split_range(range);
+
+        Box::new(self.first.read_chunks(first_range).chain(self.second.read_chunks(second_range)))
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    /// Attribute runs are not joined up across the two ropes, so a run never crosses from one rope into the other.
+    ///
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        let first_len = self.first.len();
+
+        if pos < first_len || self.second.len() == 0 {
+            let (attribute, range) = self.first.read_attributes(pos);
+            (attribute, range.start.min(first_len)..range.end.min(first_len))
+        } else {
+            let (attribute, range) = self.second.read_attributes(pos - first_len);
+            (attribute, (range.start + first_len)..(range.end + first_len))
+        }
+    }
+}
+
+impl<FirstRope, SecondRope> RopeMut for ConcatRope<FirstRope, SecondRope>
+where
+FirstRope:  RopeMut,
+SecondRope: RopeMut<Cell=FirstRope::Cell, Attribute=FirstRope::Attribute> {
+    ///
+    /// Applies an edit to the rope (or ropes) containing the cells that it changes
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        let first_len               = self.first.len();
+        let (first, second)         = action.split_at(first_len);
+
+        if let Some(second) = second {
+            let range = second.range();
+            self.second.edit(second.with_range((range.start-first_len)..(range.end-first_len)));
+        }
+
+        if let Some(first) = first {
+            self.first.edit(first);
+        }
+    }
+}
+
+impl<FirstRope, SecondRope> RopeSearch for ConcatRope<FirstRope, SecondRope>
+where
+FirstRope:          RopeMut,
+SecondRope:         RopeMut<Cell=FirstRope::Cell, Attribute=FirstRope::Attribute>,
+FirstRope::Cell:    PartialEq { }
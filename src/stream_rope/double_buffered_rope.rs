@@ -0,0 +1,111 @@
+use crate::api::*;
+use crate::rope::*;
+use crate::sync::{Mutex};
+
+use std::mem;
+use std::sync::{Arc};
+
+///
+/// A double-buffered rope keeps two mirrors of a rope: one that is read by (for example) a renderer and one that is
+/// updated with the changes pulled from another rope
+///
+/// When a batch of changes has been applied, the two mirrors are swapped over, so readers always see either the state
+/// before a batch or the state after it, never a partially applied batch. Readers can be on other threads: each call to
+/// `DoubleBufferedReader::read()` returns the most recent complete version of the rope, which remains valid for as long
+/// as the reader holds on to it.
+///
+/// Once the readers have released the old version, it is brought up to date and re-used for the next batch. If a reader
+/// is still holding on to it, the current version is copied instead (this is cheap as the cells are shared).
+///
+pub struct DoubleBufferedRope<Cell, Attribute> {
+    /// The version of the rope that is currently being read
+    front: Arc<Mutex<Arc<AttributedRope<Cell, Attribute>>>>,
+
+    /// The version of the rope that will be updated by the next batch
+    back: Option<Arc<AttributedRope<Cell, Attribute>>>,
+
+    /// The changes that have been applied to the front rope but not the back rope
+    back_changes: Vec<RopeAction<Cell, Attribute>>
+}
+
+///
+/// Reads the most recent complete version of a `DoubleBufferedRope`
+///
+#[derive(Clone)]
+pub struct DoubleBufferedReader<Cell, Attribute> {
+    /// The version of the rope that is currently being read
+    front: Arc<Mutex<Arc<AttributedRope<Cell, Attribute>>>>
+}
+
+impl<Cell, Attribute> DoubleBufferedRope<Cell, Attribute>
+where
+Cell:       Clone,
+Attribute:  PartialEq+Clone+Default {
+    ///
+    /// Creates a new double-buffered rope, initially containing the specified rope
+    ///
+    pub fn from(rope: AttributedRope<Cell, Attribute>) -> DoubleBufferedRope<Cell, Attribute> {
+        let back = Arc::new(rope.clone());
+
+        DoubleBufferedRope {
+            front:          Arc::new(Mutex::new(Arc::new(rope))),
+            back:           Some(back),
+            back_changes:   vec![]
+        }
+    }
+
+    ///
+    /// Creates a reader for this rope, which can be sent to another thread
+    ///
+    pub fn reader(&self) -> DoubleBufferedReader<Cell, Attribute> {
+        DoubleBufferedReader {
+            front: Arc::clone(&self.front)
+        }
+    }
+
+    ///
+    /// Returns the most recent complete version of this rope
+    ///
+    pub fn read(&self) -> Arc<AttributedRope<Cell, Attribute>> {
+        Arc::clone(&*self.front.lock())
+    }
+
+    ///
+    /// Applies a batch of changes (such as those returned by `PullRope::pull_changes()`) to the back rope, then swaps
+    /// it with the front rope so that readers see all of the changes at once
+    ///
+    pub fn apply_batch<Actions: IntoIterator<Item=RopeAction<Cell, Attribute>>>(&mut self, actions: Actions) {
+        let actions = actions.into_iter().collect::<Vec<_>>();
+
+        // Bring the back rope up to date with the front rope
+        let back        = self.back.take().expect("Back rope is always present between batches");
+        let mut back    = match Arc::try_unwrap(back) {
+            Ok(mut back) => {
+                self.back_changes.drain(..).for_each(|action| back.edit(action));
+                back
+            }
+
+            Err(_still_being_read) => {
+                // A reader is still using the old version, so start from a copy of the current version instead
+                self.back_changes.clear();
+                (*self.read()).clone()
+            }
+        };
+
+        // Apply the new changes and make this the version that's being read
+        actions.iter().cloned().for_each(|action| back.edit(action));
+
+        let old_front       = mem::replace(&mut *self.front.lock(), Arc::new(back));
+        self.back           = Some(old_front);
+        self.back_changes   = actions;
+    }
+}
+
+impl<Cell, Attribute> DoubleBufferedReader<Cell, Attribute> {
+    ///
+    /// Returns the most recent complete version of the rope
+    ///
+    pub fn read(&self) -> Arc<AttributedRope<Cell, Attribute>> {
+        Arc::clone(&*self.front.lock())
+    }
+}
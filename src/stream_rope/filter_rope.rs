@@ -0,0 +1,287 @@
+use crate::api::*;
+
+use std::ops::{Range};
+
+///
+/// A filter rope is a view of a base rope that hides the cells that don't match a predicate
+///
+/// The predicate is called with each cell and its attribute, so cells can be hidden based on their value (for example,
+/// control characters) or on their attribute (for example, a folded region). The filter rope keeps a mapping between
+/// the positions of the visible cells and their positions in the base rope, which is updated as the rope is edited.
+///
+/// Edits made to the filter rope are applied to the corresponding range of the base rope. A range covering several
+/// visible cells also covers any hidden cells between them, so deleting it deletes the hidden cells too. Cells inserted
+/// at a position are inserted just before the visible cell at that position (or just after the last visible cell).
+///
+#[derive(Clone)]
+pub struct FilterRope<BaseRope, FilterFn>
+where
+BaseRope: RopeMut,
+FilterFn: Fn(&BaseRope::Cell, &BaseRope::Attribute) -> bool {
+    /// The rope that this is a view of
+    rope: BaseRope,
+
+    /// Returns true for the cells that should be visible
+    filter_fn: FilterFn,
+
+    /// The ranges of the base rope containing visible cells, in order (neighbouring ranges are always merged)
+    visible: Vec<Range<usize>>,
+
+    /// The position in the filter rope of the start of each visible range
+    offsets: Vec<usize>
+}
+
+impl<BaseRope, FilterFn> FilterRope<BaseRope, FilterFn>
+where
+BaseRope: RopeMut,
+FilterFn: Fn(&BaseRope::Cell, &BaseRope::Attribute) -> bool {
+    ///
+    /// Creates a view of a rope containing only the cells that match a filter function
+    ///
+    pub fn from(rope: BaseRope, filter_fn: FilterFn) -> FilterRope<BaseRope, FilterFn> {
+        let mut filter_rope = FilterRope {
+            rope:       rope,
+            filter_fn:  filter_fn,
+            visible:    vec![],
+            offsets:    vec![]
+        };
+
+        filter_rope.visible = filter_rope.visible_ranges(0..filter_rope.rope.len());
+        filter_rope.update_offsets();
+
+        filter_rope
+    }
+
+    ///
+    /// Returns the rope that this is a view of
+    ///
+    pub fn into_inner(self) -> BaseRope {
+        self.rope
+    }
+
+    ///
+    /// Borrows the rope that this is a view of
+    ///
+    pub fn base(&self) -> &BaseRope {
+        &self.rope
+    }
+
+    ///
+    /// Applies an action to the base rope, updating which cells are visible in this rope
+    ///
+    pub fn edit_base(&mut self, action: RopeAction<BaseRope::Cell, BaseRope::Attribute>) {
+        let range   = action.range();
+        let new_len = action.new_len();
+
+        self.rope.edit(action);
+        self.update_visible(range, new_len);
+    }
+
+    ///
+    /// Returns the position in the base rope of the cell at a position in this rope
+    ///
+    /// Positions at or after the end of this rope map to the position just after the last visible cell.
+    ///
+    pub fn to_base_index(&self, pos: usize) -> usize {
+        if pos >= self.len() {
+            return self.visible.last().map(|range| range.end).unwrap_or(0);
+        }
+
+        let idx = self.offsets.partition_point(|offset| *offset <= pos) - 1;
+
+        self.visible[idx].start + (pos - self.offsets[idx])
+    }
+
+    ///
+    /// Returns the position in this rope of a position in the base rope
+    ///
+    /// Hidden cells map to the position of the next visible cell.
+    ///
+    pub fn to_filtered_index(&self, base_pos: usize) -> usize {
+        let idx = self.visible.partition_point(|range| range.start < base_pos);
+
+        if idx == 0 {
+            0
+        } else {
+            let range = &self.visible[idx-1];
+            self.offsets[idx-1] + base_pos.min(range.end) - range.start
+        }
+    }
+
+    ///
+    /// Finds the ranges of visible cells within a range of the base rope
+    ///
+    fn visible_ranges(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        let mut visible = vec![];
+        let mut current: Option<Range<usize>> = None;
+
+        for (attribute, run_range) in self.rope.read_attribute_runs(range) {
+            for (pos, cell) in run_range.clone().zip(self.rope.read_cells(run_range)) {
+                if (self.filter_fn)(cell, attribute) {
+                    match &mut current {
+                        Some(current)   => { current.end = pos + 1; }
+                        None            => { current = Some(pos..(pos + 1)); }
+                    }
+                } else if let Some(current) = current.take() {
+                    visible.push(current);
+                }
+            }
+        }
+
+        visible.extend(current);
+        visible
+    }
+
+    ///
+    /// Updates the visible ranges after a range of the base rope has been replaced with `new_len` cells
+    ///
+    fn update_visible(&mut self, replaced: Range<usize>, new_len: usize) {
+        let old_visible     = &self.visible;
+        let shift           = |range: Range<usize>| (range.start + new_len - replaced.len())..(range.end + new_len - replaced.len());
+
+        // The visible ranges before the replaced range are unchanged, and the ones after it move
+        let before          = old_visible.iter()
+            .filter(|range| range.start < replaced.start)
+            .map(|range| range.start..range.end.min(replaced.start));
+        let after           = old_visible.iter()
+            .filter(|range| range.end > replaced.end)
+            .map(|range| shift(range.start.max(replaced.end)..range.end));
+
+        // Check the cells in the replacement range
+        let replacement     = self.visible_ranges(replaced.start..(replaced.start + new_len));
+
+        // Merge any neighbouring ranges
+        let mut visible: Vec<Range<usize>> = vec![];
+        for range in before.chain(replacement).chain(after).collect::<Vec<_>>() {
+            match visible.last_mut() {
+                Some(last) if last.end == range.start   => { last.end = range.end; }
+                _                                       => { visible.push(range); }
+            }
+        }
+
+        self.visible = visible;
+        self.update_offsets();
+    }
+
+    ///
+    /// Recalculates the position of each visible range in this rope
+    ///
+    fn update_offsets(&mut self) {
+        let mut offset = 0;
+
+        self.offsets = self.visible.iter()
+            .map(|range| {
+                let start = offset;
+                offset += range.len();
+                start
+            })
+            .collect();
+    }
+
+    ///
+    /// Returns the ranges of the base rope that contain the visible cells in a range of this rope
+    ///
+    fn base_ranges(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        let end = range.end.min(self.len());
+        if range.start >= end { return vec![]; }
+
+        let first_idx = self.offsets.partition_point(|offset| *offset <= range.start) - 1;
+
+        (first_idx..self.visible.len())
+            .take_while(|idx| self.offsets[*idx] < end)
+            .map(|idx| {
+                let visible = &self.visible[idx];
+                let offset  = self.offsets[idx];
+                let start   = visible.start + range.start.max(offset) - offset;
+                let end     = visible.start + end.min(offset + visible.len()) - offset;
+
+                start..end
+            })
+            .collect()
+    }
+
+    ///
+    /// Converts a range in this rope to the range of the base rope that an edit should replace
+    ///
+    fn base_edit_range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.to_base_index(range.start);
+
+        if range.end > range.start && range.start < self.len() {
+            start..(self.to_base_index(range.end.min(self.len()) - 1) + 1)
+        } else {
+            start..start
+        }
+    }
+}
+
+impl<BaseRope, FilterFn> Rope for FilterRope<BaseRope, FilterFn>
+where
+BaseRope: RopeMut,
+FilterFn: Fn(&BaseRope::Cell, &BaseRope::Attribute) -> bool {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of visible cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        match (self.offsets.last(), self.visible.last()) {
+            (Some(offset), Some(range)) => offset + range.len(),
+            _                           => 0
+        }
+    }
+
+    ///
+    /// Reads the visible cells in a range of this rope
+    ///
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        Box::new(self.base_ranges(range).into_iter()
+            .flat_map(move |base_range| self.rope.read_cells(base_range)))
+    }
+
+    ///
+    /// Reads the visible cells in a range of this rope as a series of slices, in order
+    ///
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        Box::new(self.base_ranges(range).into_iter()
+            .flat_map(move |base_range| self.rope.read_chunks(base_range)))
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        if pos >= self.len() {
+            let len = self.len();
+            return (self.rope.read_attributes(self.to_base_index(pos)).0, len..len);
+        }
+
+        let (attribute, extent) = self.rope.read_attributes(self.to_base_index(pos));
+
+        (attribute, self.to_filtered_index(extent.start)..self.to_filtered_index(extent.end))
+    }
+}
+
+impl<BaseRope, FilterFn> RopeMut for FilterRope<BaseRope, FilterFn>
+where
+BaseRope: RopeMut,
+FilterFn: Fn(&BaseRope::Cell, &BaseRope::Attribute) -> bool {
+    ///
+    /// Applies an edit to the corresponding range of the base rope
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        let range = self.base_edit_range(action.range());
+
+        self.edit_base(action.with_range(range));
+    }
+}
+
+impl<BaseRope, FilterFn> RopeSearch for FilterRope<BaseRope, FilterFn>
+where
+BaseRope:       RopeMut,
+BaseRope::Cell: PartialEq,
+FilterFn:       Fn(&BaseRope::Cell, &BaseRope::Attribute) -> bool { }
@@ -0,0 +1,365 @@
+use crate::api::*;
+
+use std::mem;
+use std::ops::{Range};
+
+///
+/// An action that was applied to a rope, along with the actions that will undo it
+///
+#[derive(Clone)]
+struct HistoryStep<Cell, Attribute> {
+    /// The action that was applied
+    action: RopeAction<Cell, Attribute>,
+
+    /// The actions that will restore the rope to its state before the action was applied
+    inverse: Vec<RopeAction<Cell, Attribute>>
+}
+
+///
+/// A group of steps that are undone or redone as a single unit
+///
+#[derive(Clone)]
+struct HistoryGroup<Cell, Attribute> {
+    /// Identifies the state of the rope after this group has been applied
+    generation: u64,
+
+    /// The steps in this group, in the order they were applied
+    steps: Vec<HistoryStep<Cell, Attribute>>
+}
+
+///
+/// A history rope records the actions that are applied to a base rope so that they can be undone and redone
+///
+/// Each edit is normally undone individually. Edits made between a call to `begin_group()` and the corresponding
+/// call to `end_group()` are undone and redone as a single unit. Groups can be nested, in which case the outermost
+/// group determines the unit.
+///
+/// Undoing or redoing an edit applies the actions to the base rope, so if that is a `PullRope` or one of the push
+/// ropes, the changes will be reported in the same way as any other edit.
+///
+/// `mark_saved()` records the current state of the rope, and `is_modified()` will report false whenever undo or
+/// redo return the rope to that state.
+///
+#[derive(Clone)]
+pub struct HistoryRope<BaseRope> 
+where
+BaseRope: RopeMut {
+    /// The rope that the actions are applied to
+    rope: BaseRope,
+
+    /// The groups of actions that can be undone (most recent last)
+    undo_stack: Vec<HistoryGroup<BaseRope::Cell, BaseRope::Attribute>>,
+
+    /// The groups of actions that can be redone (most recently undone last)
+    redo_stack: Vec<HistoryGroup<BaseRope::Cell, BaseRope::Attribute>>,
+
+    /// The group that is currently being recorded, if begin_group() has been called
+    current_group: Vec<HistoryStep<BaseRope::Cell, BaseRope::Attribute>>,
+
+    /// The number of times that begin_group() has been called without a corresponding end_group()
+    group_depth: usize,
+
+    /// The most recent generation assigned to a group of edits
+    generation: u64,
+
+    /// The generation of the rope when there is nothing to undo (changes when the history is cleared)
+    base_generation: u64,
+
+    /// The depth of the undo stack and the generation of the rope when mark_saved() was last called
+    saved_state: (usize, u64)
+}
+
+impl<BaseRope> HistoryRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Creates a new history rope with no history
+    ///
+    pub fn from(rope: BaseRope) -> HistoryRope<BaseRope> {
+        HistoryRope {
+            rope:           rope,
+            undo_stack:     vec![],
+            redo_stack:     vec![],
+            current_group:      vec![],
+            group_depth:        0,
+            generation:         0,
+            base_generation:    0,
+            saved_state:        (0, 0)
+        }
+    }
+
+    ///
+    /// Records the current state of the rope as the saved state
+    ///
+    pub fn mark_saved(&mut self) {
+        self.saved_state = self.current_state();
+    }
+
+    ///
+    /// True if the rope has been edited since `mark_saved()` was last called (or since it was created, if it has never
+    /// been saved)
+    ///
+    /// Undoing or redoing back to the saved state will mark the rope as unmodified again.
+    ///
+    pub fn is_modified(&self) -> bool {
+        self.current_group.len() > 0 || self.current_state() != self.saved_state
+    }
+
+    ///
+    /// Returns the depth of the undo stack and the generation of the edit at the top of it
+    ///
+    fn current_state(&self) -> (usize, u64) {
+        let generation = self.undo_stack.last().map(|group| group.generation).unwrap_or(self.base_generation);
+
+        (self.undo_stack.len(), generation)
+    }
+
+    ///
+    /// Adds a newly recorded group of steps to the undo stack
+    ///
+    fn push_undo_group(&mut self, steps: Vec<HistoryStep<BaseRope::Cell, BaseRope::Attribute>>) {
+        self.generation += 1;
+        self.undo_stack.push(HistoryGroup { generation: self.generation, steps: steps });
+    }
+
+    ///
+    /// Starts a group of edits that will be undone as a single unit
+    ///
+    pub fn begin_group(&mut self) {
+        self.group_depth += 1;
+    }
+
+    ///
+    /// Finishes a group of edits started by `begin_group()`
+    ///
+    pub fn end_group(&mut self) {
+        if self.group_depth == 0 { return; }
+
+        self.group_depth -= 1;
+
+        if self.group_depth == 0 && self.current_group.len() > 0 {
+            let group = mem::take(&mut self.current_group);
+            self.push_undo_group(group);
+        }
+    }
+
+    ///
+    /// True if there are any edits that can be undone
+    ///
+    pub fn can_undo(&self) -> bool {
+        self.undo_stack.len() > 0 || self.current_group.len() > 0
+    }
+
+    ///
+    /// True if there are any edits that can be redone
+    ///
+    pub fn can_redo(&self) -> bool {
+        self.redo_stack.len() > 0
+    }
+
+    ///
+    /// Discards all of the undo and redo history for this rope
+    ///
+    /// The rope keeps its saved state if it was unmodified when the history was cleared.
+    ///
+    pub fn clear_history(&mut self) {
+        let was_modified = self.is_modified();
+
+        self.generation         += 1;
+        self.base_generation    = self.generation;
+        if !was_modified { self.saved_state = (0, self.base_generation); }
+
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.current_group.clear();
+    }
+
+    ///
+    /// Undoes the most recent edit or group of edits, returning false if there was nothing to undo
+    ///
+    /// Any group that is being recorded is ended before the undo takes place.
+    ///
+    pub fn undo(&mut self) -> bool {
+        // Finish any group in progress
+        if self.current_group.len() > 0 {
+            let group = mem::take(&mut self.current_group);
+            self.push_undo_group(group);
+        }
+        self.group_depth = 0;
+
+        // Apply the inverse actions in reverse order
+        let group = match self.undo_stack.pop() {
+            Some(group) => group,
+            None        => { return false; }
+        };
+
+        for step in group.steps.iter().rev() {
+            for inverse in step.inverse.iter() {
+                self.rope.edit(inverse.clone());
+            }
+        }
+
+        self.redo_stack.push(group);
+
+        true
+    }
+
+    ///
+    /// Redoes the most recently undone edit or group of edits, returning false if there was nothing to redo
+    ///
+    pub fn redo(&mut self) -> bool {
+        let group = match self.redo_stack.pop() {
+            Some(group) => group,
+            None        => { return false; }
+        };
+
+        // The rope is in the same state as when these actions were originally applied
+        for step in group.steps.iter() {
+            self.rope.edit(step.action.clone());
+        }
+
+        self.undo_stack.push(group);
+
+        true
+    }
+
+    ///
+    /// Reads the cells and attributes in a range of the base rope as a list of actions that will insert them at the specified position
+    ///
+    fn restore_actions(&self, range: Range<usize>, replace_range: Range<usize>) -> Vec<RopeAction<BaseRope::Cell, BaseRope::Attribute>> {
+        let mut actions = vec![];
+        let mut pos     = replace_range.start;
+
+        for (attribute, run_range) in self.rope.read_attribute_runs(range) {
+            let cells       = self.rope.read_cells(run_range).cloned().collect::<Vec<_>>();
+            let num_cells   = cells.len();
+
+            if actions.len() == 0 {
+                // The first run replaces the whole range
+                actions.push(RopeAction::ReplaceAttributes(replace_range.clone(), cells, attribute.clone()));
+            } else {
+                // Subsequent runs are inserted after the previous one
+                actions.push(RopeAction::ReplaceAttributes(pos..pos, cells, attribute.clone()));
+            }
+
+            pos += num_cells;
+        }
+
+        if actions.len() == 0 && replace_range.len() > 0 {
+            // Nothing to restore, so the inverse just deletes the new cells
+            actions.push(RopeAction::Replace(replace_range, vec![]));
+        }
+
+        actions
+    }
+
+    ///
+    /// Generates the actions that will undo an action that's about to be applied to the base rope
+    ///
+    fn inverse_actions(&self, action: &RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> Vec<RopeAction<BaseRope::Cell, BaseRope::Attribute>> {
+        let len     = self.rope.len();
+        let clip    = |range: &Range<usize>| { let start = range.start.min(len); start..range.end.min(len).max(start) };
+
+        match action {
+            RopeAction::Replace(range, new_cells) | RopeAction::ReplaceAttributes(range, new_cells, _) => {
+                let range = clip(range);
+                self.restore_actions(range.clone(), range.start..(range.start+new_cells.len()))
+            }
+
+            RopeAction::SetAttributes(range, _) => {
+                self.rope.read_attribute_runs(clip(range))
+                    .map(|(attribute, run_range)| RopeAction::SetAttributes(run_range, attribute.clone()))
+                    .collect()
+            }
+        }
+    }
+}
+
+impl<BaseRope> Rope for HistoryRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.rope.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        self.rope.read_cells(range)
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.read_chunks(range)
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.rope.read_attributes(pos)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.read_attribute_runs(range)
+    }
+}
+
+impl<BaseRope> RopeMut for HistoryRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Performs the specified editing action to this rope, recording it in the history
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        // Work out how to undo the action before applying it
+        let inverse = self.inverse_actions(&action);
+
+        self.rope.edit(action.clone());
+
+        // Any new edit means the redo history is no longer valid
+        self.redo_stack.clear();
+
+        let step = HistoryStep { action, inverse };
+
+        if self.group_depth > 0 {
+            self.current_group.push(step);
+        } else {
+            self.push_undo_group(vec![step]);
+        }
+    }
+
+    ///
+    /// Performs a series of edits to this rope as a single transaction, which will be undone as a single unit
+    ///
+    fn transaction<TransactionFn: FnOnce(&mut Self) -> ()>(&mut self, transaction_fn: TransactionFn) {
+        self.begin_group();
+        transaction_fn(self);
+        self.end_group();
+    }
+}
+
+impl<BaseRope> RopeSearch for HistoryRope<BaseRope>
+where
+BaseRope:       RopeMut,
+BaseRope::Cell: PartialEq { }
@@ -0,0 +1,196 @@
+use crate::api::*;
+use crate::sync::{Mutex};
+
+use std::mem;
+use std::sync::{Arc};
+
+///
+/// Identifies one of the two ropes in a `MirrorPair`
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MirrorSide {
+    Left,
+    Right
+}
+
+impl MirrorSide {
+    ///
+    /// Returns the side that mirrors this one
+    ///
+    pub fn other(self) -> MirrorSide {
+        match self {
+            MirrorSide::Left    => MirrorSide::Right,
+            MirrorSide::Right   => MirrorSide::Left
+        }
+    }
+
+    ///
+    /// The index of this side in the arrays used by the mirror core
+    ///
+    #[inline]
+    fn index(self) -> usize {
+        match self {
+            MirrorSide::Left    => 0,
+            MirrorSide::Right   => 1
+        }
+    }
+}
+
+/// A function that applies an action to one of the ropes in a mirror pair (shared so it can be called without locking the core)
+type ApplyFn<Cell, Attribute> = Arc<Mutex<Box<dyn Send+FnMut(RopeAction<Cell, Attribute>) -> ()>>>;
+
+///
+/// The shared state of a mirror pair
+///
+struct MirrorCore<Cell, Attribute> {
+    /// The functions that apply actions to the left and right ropes (`None` if a side is not connected)
+    apply_fns: [Option<ApplyFn<Cell, Attribute>>; 2],
+
+    /// The action that's being applied to each side, which will be echoed back by that side's push function
+    echoes: [Option<RopeAction<Cell, Attribute>>; 2],
+
+    /// True while actions are being applied (actions sent while this is happening are delivered afterwards)
+    is_delivering: bool,
+
+    /// The actions waiting to be applied, along with the side that they should be applied to
+    pending: Vec<(MirrorSide, RopeAction<Cell, Attribute>)>
+}
+
+///
+/// A mirror pair keeps two ropes in sync by sending the actions applied to either of them to the other one
+///
+/// Connecting two ropes with a pair of `PushAfterRope`s directly will loop forever: an action applied to the left rope
+/// is applied to the right rope, which sends it back to the left rope, and so on. The mirror pair remembers the action
+/// it's applying to each side, and filters it out when it's echoed back by that side's push function, so only edits
+/// that originate from a rope are sent to the other one.
+///
+/// Each rope is wrapped in a `PushAfterRope` using the function returned by `push_fn()`, and `connect()` supplies the
+/// function that applies an action to each rope. The apply function must apply the action immediately, as echoes are
+/// only recognised while it's running. Any other edits that a rope makes in response to an action (for example,
+/// reformatting some text) are sent to the other rope as normal.
+///
+/// Clones of a mirror pair all refer to the same pair of ropes.
+///
+pub struct MirrorPair<Cell, Attribute> {
+    /// The state shared between the clones of this mirror and the push functions
+    core: Arc<Mutex<MirrorCore<Cell, Attribute>>>
+}
+
+impl<Cell, Attribute> Clone for MirrorPair<Cell, Attribute> {
+    fn clone(&self) -> Self {
+        MirrorPair {
+            core: Arc::clone(&self.core)
+        }
+    }
+}
+
+impl<Cell, Attribute> MirrorPair<Cell, Attribute>
+where
+Cell:       Clone+PartialEq,
+Attribute:  Clone+PartialEq {
+    ///
+    /// Creates a new mirror pair with neither side connected
+    ///
+    pub fn new() -> MirrorPair<Cell, Attribute> {
+        MirrorPair {
+            core: Arc::new(Mutex::new(MirrorCore {
+                apply_fns:      [None, None],
+                echoes:         [None, None],
+                is_delivering:  false,
+                pending:        vec![]
+            }))
+        }
+    }
+
+    ///
+    /// Sets the function that applies actions from the other side to the rope on the specified side
+    ///
+    /// Actions sent while a side is not connected are discarded.
+    ///
+    pub fn connect<ApplyActionFn: 'static+Send+FnMut(RopeAction<Cell, Attribute>) -> ()>(&self, side: MirrorSide, apply_fn: ApplyActionFn) {
+        self.core.lock().apply_fns[side.index()] = Some(Arc::new(Mutex::new(Box::new(apply_fn))));
+    }
+
+    ///
+    /// Removes the apply function for one side of the mirror (the apply function usually refers to the rope, which
+    /// refers to this mirror, so disconnecting is necessary to free the ropes)
+    ///
+    pub fn disconnect(&self, side: MirrorSide) {
+        let apply_fn = self.core.lock().apply_fns[side.index()].take();
+
+        // The apply function is dropped after the lock is released, as it might refer back to this mirror
+        mem::drop(apply_fn);
+    }
+
+    ///
+    /// Returns a function that sends the actions applied to the rope on the specified side to the other side
+    ///
+    /// This is intended to be used with `PushAfterRope::from()`
+    ///
+    pub fn push_fn(&self, side: MirrorSide) -> impl Send+FnMut(RopeAction<Cell, Attribute>) -> ()
+    where
+    Cell:       Send,
+    Attribute:  Send {
+        let mirror = self.clone();
+
+        move |action| mirror.send(side, action)
+    }
+
+    ///
+    /// Sends an action that has been applied to the rope on the specified side to the other side
+    ///
+    /// If the action is the echo of an action that this mirror is applying to that side, it's ignored.
+    ///
+    pub fn send(&self, side: MirrorSide, action: RopeAction<Cell, Attribute>) {
+        let is_delivering = {
+            let mut core    = self.core.lock();
+            let echo        = &mut core.echoes[side.index()];
+
+            if echo.as_ref() == Some(&action) {
+                // This is the action that we're applying to this side
+                *echo = None;
+                return;
+            }
+
+            core.pending.push((side.other(), action));
+            core.is_delivering
+        };
+
+        if !is_delivering {
+            self.deliver();
+        }
+    }
+
+    ///
+    /// Applies any pending actions to their ropes
+    ///
+    fn deliver(&self) {
+        loop {
+            // Take the next action and the function to apply it with (the lock isn't held while the apply function runs, as it will call back into this mirror)
+            let (side, action, apply_fn) = {
+                let mut core = self.core.lock();
+                if core.pending.len() == 0 {
+                    core.is_delivering = false;
+                    return;
+                }
+
+                let (side, action)  = core.pending.remove(0);
+                let apply_fn        = core.apply_fns[side.index()].clone();
+
+                core.is_delivering  = true;
+                if apply_fn.is_some() {
+                    core.echoes[side.index()] = Some(action.clone());
+                }
+
+                (side, action, apply_fn)
+            };
+
+            if let Some(apply_fn) = apply_fn {
+                (apply_fn.lock())(action);
+
+                // Only the action applied by the function is treated as an echo
+                self.core.lock().echoes[side.index()] = None;
+            }
+        }
+    }
+}
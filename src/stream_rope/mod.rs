@@ -10,8 +10,40 @@
 mod push_rope;
 mod pull_rope;
 mod concat_rope;
+mod rope_splitter;
+mod ring_rope;
+mod history_rope;
+mod remote_rope;
+mod rope_metrics;
+mod rope_broadcast;
+mod rope_edit_queue;
+mod double_buffered_rope;
+mod rope_builder_chain;
+mod rope_observer;
+mod slice_rope;
+mod filter_rope;
+mod recording_rope;
+mod throttle_rope;
+mod mirror_pair;
+mod rope_sync;
 #[cfg(test)] mod tests;
 
 pub use self::push_rope::*;
 pub use self::pull_rope::*;
 pub use self::concat_rope::*;
+pub use self::rope_splitter::*;
+pub use self::ring_rope::*;
+pub use self::history_rope::*;
+pub use self::remote_rope::*;
+pub use self::rope_metrics::*;
+pub use self::rope_broadcast::*;
+pub use self::rope_edit_queue::*;
+pub use self::double_buffered_rope::*;
+pub use self::rope_builder_chain::*;
+pub use self::rope_observer::*;
+pub use self::slice_rope::*;
+pub use self::filter_rope::*;
+pub use self::recording_rope::*;
+pub use self::throttle_rope::*;
+pub use self::mirror_pair::*;
+pub use self::rope_sync::*;
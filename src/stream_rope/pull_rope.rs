@@ -1,20 +1,139 @@
+use super::rope_metrics::*;
+
+use crate::adapter::*;
+
 use crate::api::*;
 
 use std::mem;
-use std::ops::{Range};
+use std::ops::{Range, Deref};
+use std::collections::{BTreeMap};
+
+///
+/// A change pulled from a rope, with the new cells grouped by their attributes
+///
+#[derive(Clone, PartialEq, Debug)]
+pub struct PulledChange<Cell, Attribute> {
+    /// The range of cells that this replaces, in the rope as it was before the changes were applied
+    pub range: Range<usize>,
+
+    /// The cells that replace the range along with their attributes, in order (empty if the range was deleted)
+    pub spans: Vec<(Vec<Cell>, Attribute)>,
+
+    /// True if the attributes of any of the cells in the range have changed
+    pub changed_attributes: bool
+}
+
+impl<Cell, Attribute> PulledChange<Cell, Attribute> {
+    ///
+    /// The total number of cells in the spans for this change
+    ///
+    pub fn new_len(&self) -> usize {
+        self.spans.iter().map(|(cells, _)| cells.len()).sum()
+    }
+}
+
+///
+/// Identifies a consumer that was added to a `PullRope` with `add_consumer()`
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PullConsumerId(usize);
+
+///
+/// A function that is called when changes become available for a consumer
+///
+type ConsumerFn = Box<dyn Send+Sync+FnMut() -> ()>;
 
 ///
-/// Indicates a range of values that has been updated since the last pull from a rope
+/// An extra consumer of the changes made to a `PullRope`, with its own set of pending changes
 ///
-struct RopePendingChange {
-    /// Where these values were originally in the rope
-    original_range: Range<usize>,
+struct PullConsumer {
+    /// The ID of this consumer
+    id: PullConsumerId,
 
-    /// Where the replacement values appear in the updated rope
-    new_range: Range<usize>,
+    /// The changes that have been made since this consumer last pulled from the rope
+    changes: RopeChangeSet,
 
-    /// True if the attributes for this range have changed
-    changed_attributes: bool
+    /// The function to call when changes become available
+    pull_fn: ConsumerFn,
+
+    /// True if the pull function has been called since the last time this consumer pulled from the rope
+    notified: bool
+}
+
+///
+/// The cells that were in a rope before the pending changes were made, used to work out minimal diffs
+///
+struct OriginalCells<Cell> {
+    /// Runs of cells that have been replaced since the last pull, indexed by their position in the original rope
+    runs: BTreeMap<usize, Vec<Cell>>,
+
+    /// Compares two cells
+    cells_equal: fn(&Cell, &Cell) -> bool
+}
+
+impl<Cell> OriginalCells<Cell>
+where
+Cell: Clone {
+    ///
+    /// Reads the original cells in a range, if they were all captured
+    ///
+    fn read(&self, range: Range<usize>) -> Option<Vec<Cell>> {
+        // Find the runs that overlap the range (the runs never overlap each other)
+        let mut runs = self.runs.range(..range.end)
+            .rev()
+            .take_while(|(start, cells)| *start + cells.len() > range.start)
+            .collect::<Vec<_>>();
+        runs.reverse();
+
+        // The runs must cover the whole range
+        let mut cells   = Vec::with_capacity(range.len());
+        let mut pos     = range.start;
+
+        for (start, run) in runs {
+            if *start > pos { return None; }
+
+            let run_end = (*start + run.len()).min(range.end);
+            cells.extend(run[(pos - *start)..(run_end - *start)].iter().cloned());
+            pos = run_end;
+        }
+
+        if pos == range.end {
+            Some(cells)
+        } else {
+            None
+        }
+    }
+}
+
+///
+/// Reads the action for a pending change that replaces cells, leaving out the cells at the start and end that are the same
+/// as they were originally
+///
+fn read_minimal_change<R>(rope: &R, change: PendingChange, original_cells: &OriginalCells<R::Cell>) -> Vec<RopeAction<R::Cell, R::Attribute>>
+where
+R: Rope {
+    // Changes where the original cells weren't captured (eg, made before minimal diffs were enabled) are sent in full
+    let original = if let Some(original) = original_cells.read(change.original_range.clone()) { original } else { return read_pending_change(rope, change); };
+    let new      = rope.read_cells(change.new_range.clone()).cloned().collect::<Vec<_>>();
+
+    // Find the cells at the start and end that are unchanged
+    let cells_equal = original_cells.cells_equal;
+    let prefix_len  = original.iter().zip(new.iter())
+        .take_while(|(a, b)| cells_equal(a, b))
+        .count();
+    let suffix_len  = original[prefix_len..].iter().rev().zip(new[prefix_len..].iter().rev())
+        .take_while(|(a, b)| cells_equal(a, b))
+        .count();
+
+    if prefix_len + suffix_len == original.len() && original.len() == new.len() {
+        // Nothing has changed
+        vec![]
+    } else {
+        let original_range  = (change.original_range.start + prefix_len)..(change.original_range.end - suffix_len);
+        let new_cells       = new[prefix_len..(new.len() - suffix_len)].to_vec();
+
+        vec![RopeAction::Replace(original_range, new_cells)]
+    }
 }
 
 ///
@@ -27,7 +146,7 @@ struct RopePendingChange {
 pub struct PullRope<BaseRope, PullFn> 
 where 
 BaseRope:   RopeMut, 
-PullFn:     Fn() -> () {
+PullFn:     FnMut() -> () {
     /// The rope that this will pull changes from
     rope: BaseRope,
 
@@ -35,13 +154,37 @@ PullFn:     Fn() -> () {
     pull_fn: PullFn,
 
     /// The changes that have ocurred since the last time this rope was pulled from (kept in ascending order)
-    changes: Vec<RopePendingChange>
+    changes: RopeChangeSet,
+
+    /// The number of transactions in progress (notifications are not sent while this is non-zero)
+    transaction_depth: usize,
+
+    /// True if the rope has been edited during the current transaction
+    edited_in_transaction: bool,
+
+    /// The number of batches of edits that have been made to this rope
+    version: u64,
+
+    /// The version of the rope when the changes were last pulled
+    pulled_version: u64,
+
+    /// If enabled, statistics about the changes made to this rope
+    metrics: Option<RopeMetrics>,
+
+    /// If minimal diffs are enabled, the original cells for the pending changes
+    original_cells: Option<OriginalCells<BaseRope::Cell>>,
+
+    /// The extra consumers that are pulling changes from this rope
+    consumers: Vec<PullConsumer>,
+
+    /// The ID to assign to the next consumer
+    next_consumer_id: usize
 }
 
 impl<BaseRope, PullFn> PullRope<BaseRope, PullFn>
 where 
 BaseRope:   RopeMut, 
-PullFn:     Fn() -> () {
+PullFn:     FnMut() -> () {
     ///
     /// Creates a new pull rope from a base rope and a pull function
     /// 
@@ -50,234 +193,259 @@ PullFn:     Fn() -> () {
     ///
     pub fn from(rope: BaseRope, pull_fn: PullFn) -> PullRope<BaseRope, PullFn> {
         PullRope {
-            rope:       rope,
-            pull_fn:    pull_fn,
-            changes:    vec![]
+            rope:                   rope,
+            pull_fn:                pull_fn,
+            changes:                RopeChangeSet::new(),
+            transaction_depth:      0,
+            edited_in_transaction:  false,
+            version:                0,
+            pulled_version:         0,
+            metrics:                None,
+            original_cells:         None,
+            consumers:              vec![],
+            next_consumer_id:       0
         }
     }
 
     ///
-    /// Returns the index in the changes list that is either before or just after the specified position,
-    /// along with the difference in position from the original at that point
+    /// Returns the rope that this wraps
+    ///
+    pub fn into_inner(self) -> BaseRope {
+        self.rope
+    }
+
+    ///
+    /// Borrows the rope that this wraps
     ///
-    fn find_change(&self, pos: usize) -> (usize, i64) {
-        let mut diff = 0;
+    pub fn base(&self) -> &BaseRope {
+        &self.rope
+    }
 
-        // Changes can only replace or insert, they can't move things around: this means that both
-        // the 'old' and 'new' ranges will always be in order.
-        for idx in 0..self.changes.len() {
-            let change = &self.changes[idx];
+    ///
+    /// Borrows the rope that this wraps for editing
+    ///
+    /// Edits made through this reference bypass this rope, so the changes are not recorded and won't be returned by `pull_changes()`. Anything that's mirroring this rope using
+    /// those actions will no longer match it.
+    ///
+    pub fn base_mut(&mut self) -> &mut BaseRope {
+        &mut self.rope
+    }
 
-            if change.new_range.start <= pos && change.new_range.end > pos {
-                // Position is in the range of this change
-                return (idx, diff);
-            } else if change.new_range.start > pos {
-                // We've passed the change
-                return (idx, diff);
+    ///
+    /// Starts collecting metrics for this rope (or resets the metrics if they're already being collected)
+    ///
+    pub fn enable_metrics(&mut self) {
+        self.metrics = Some(RopeMetrics::new());
+    }
+
+    ///
+    /// Stops collecting metrics for this rope
+    ///
+    pub fn disable_metrics(&mut self) {
+        self.metrics = None;
+    }
+
+    ///
+    /// Returns the metrics for this rope, if they've been enabled
+    ///
+    pub fn metrics(&self) -> Option<&RopeMetrics> {
+        self.metrics.as_ref()
+    }
+
+    ///
+    /// Stops pulling minimal diffs from this rope (see `enable_minimal_diffs()`)
+    ///
+    pub fn disable_minimal_diffs(&mut self) {
+        self.original_cells = None;
+    }
+
+    ///
+    /// Remembers the cells that are about to be replaced by an edit to a range, if they're not already part of a pending change
+    ///
+    fn capture_original_cells(&mut self, range: Range<usize>) {
+        let original_cells = if let Some(original_cells) = &mut self.original_cells { original_cells } else { return; };
+
+        let mut pos     = range.start;
+        let mut diff    = 0i64;
+
+        for change in self.changes.changes() {
+            if pos >= range.end { break; }
+
+            // The cells between the previous change and this one are in their original state
+            if change.new_range.start > pos {
+                let end     = change.new_range.start.min(range.end);
+                let cells   = self.rope.read_cells(pos..end).cloned().collect();
+                original_cells.runs.insert(((pos as i64) + diff) as usize, cells);
+
+                pos = end;
             }
 
-            // Update the difference in position from this point
-            let old_len = change.original_range.len() as i64;
-            let new_len = change.new_range.len() as i64;
+            // The cells in this change have already been replaced
+            pos     = pos.max(change.new_range.end);
+            diff    += change.original_range.len() as i64 - change.new_range.len() as i64;
+        }
 
-            diff += old_len - new_len;
+        if pos < range.end {
+            let cells = self.rope.read_cells(pos..range.end).cloned().collect();
+            original_cells.runs.insert(((pos as i64) + diff) as usize, cells);
         }
+    }
+
+    ///
+    /// The version of this rope
+    ///
+    /// This starts at 0 and is increased by one for each batch of edits (an edit made outside of a transaction, or a
+    /// transaction that edits the rope). Pulling changes does not change the version.
+    ///
+    pub fn version(&self) -> u64 {
+        self.version
+    }
 
-        // Change not found: must be beyond the end of the change range
-        (self.changes.len(), diff)
+    ///
+    /// The version of this rope when its changes were last pulled
+    ///
+    pub fn pulled_version(&self) -> u64 {
+        self.pulled_version
     }
 
-    #[cfg(test)]
-    fn check_integrity(&self) {
-        for change_idx in 1..self.changes.len() {
-            let last_change = &self.changes[change_idx-1];
-            let this_change = &self.changes[change_idx];
+    ///
+    /// Returns the ranges of the rope that have changed since the changes were last pulled, without clearing them
+    ///
+    /// The changes are in ascending order, and each one describes where the changed range was before the changes were
+    /// made and where it is in the rope now. Use `pull_changes()` to read the new cells and clear the changes.
+    ///
+    pub fn peek_changes(&self) -> &[PendingChange] {
+        self.changes.changes()
+    }
 
-            assert!(last_change.original_range.end <= this_change.original_range.start);
-            assert!(last_change.new_range.end <= this_change.new_range.start);
+    ///
+    /// True if there are changes waiting to be pulled from this rope
+    ///
+    pub fn has_pending_changes(&self) -> bool {
+        !self.changes.is_empty()
+    }
+
+    ///
+    /// The number of separate ranges that have changed since the changes were last pulled
+    ///
+    pub fn pending_change_count(&self) -> usize {
+        self.changes.len()
+    }
+
+    ///
+    /// Takes the original cells for the pending changes, leaving an empty set of cells if minimal diffs are enabled
+    ///
+    fn take_original_cells(&mut self) -> Option<OriginalCells<BaseRope::Cell>> {
+        self.original_cells.as_mut()
+            .map(|original_cells| OriginalCells {
+                runs:           mem::take(&mut original_cells.runs),
+                cells_equal:    original_cells.cells_equal
+            })
+    }
+
+    ///
+    /// Increases the version number after an edit (or marks the current transaction as having made an edit)
+    ///
+    fn finish_edit(&mut self) {
+        if self.transaction_depth == 0 {
+            self.version += 1;
+            self.notify_consumers();
+        } else {
+            self.edited_in_transaction = true;
         }
     }
 
-    #[cfg(not(test))]
-    #[inline]
-    fn check_integrity(&self) {
+    ///
+    /// Adds a consumer that gathers its own set of changes from this rope
+    ///
+    /// The changes for each consumer are kept separately from the changes returned by `pull_changes()` and from the
+    /// changes for every other consumer, so they can be pulled at different times. The function is called whenever the
+    /// consumer goes from having no changes to having changes waiting to be pulled with `pull_consumer_changes()`.
+    ///
+    pub fn add_consumer<ConsumerPullFn>(&mut self, pull_fn: ConsumerPullFn) -> PullConsumerId
+    where
+    ConsumerPullFn: 'static+Send+Sync+FnMut() -> () {
+        let id = PullConsumerId(self.next_consumer_id);
+        self.next_consumer_id += 1;
+
+        self.consumers.push(PullConsumer {
+            id:         id,
+            changes:    RopeChangeSet::new(),
+            pull_fn:    Box::new(pull_fn),
+            notified:   false
+        });
+
+        id
+    }
+
+    ///
+    /// Stops gathering changes for a consumer
+    ///
+    pub fn remove_consumer(&mut self, consumer: PullConsumerId) {
+        self.consumers.retain(|existing| existing.id != consumer);
+    }
+
+    ///
+    /// True if there are changes waiting to be pulled by a consumer
+    ///
+    pub fn consumer_has_pending_changes(&self, consumer: PullConsumerId) -> bool {
+        self.consumers.iter()
+            .any(|existing| existing.id == consumer && !existing.changes.is_empty())
+    }
+
+    ///
+    /// Pulls the pending changes for a consumer that was added with `add_consumer()`
+    ///
+    /// The changes are returned in reverse order in the same way as `pull_changes()`. The changes for the other consumers
+    /// are left in place. An unknown consumer has no changes.
+    ///
+    pub fn pull_consumer_changes<'a>(&'a mut self, consumer: PullConsumerId) -> impl 'a+Iterator<Item=RopeAction<BaseRope::Cell, BaseRope::Attribute>> {
+        let pending_changes = self.consumers.iter_mut()
+            .find(|existing| existing.id == consumer)
+            .map(|consumer| {
+                consumer.notified = false;
+                consumer.changes.take_changes()
+            })
+            .unwrap_or_default();
+
+        rope_event!(num_changes = pending_changes.len(), "pull");
+
+        pending_changes.into_iter()
+            .rev()
+            .flat_map(move |change| read_pending_change(&self.rope, change))
+    }
 
+    ///
+    /// Calls the pull function for any consumer that has just gained some pending changes
+    ///
+    fn notify_consumers(&mut self) {
+        for consumer in self.consumers.iter_mut() {
+            if !consumer.notified && !consumer.changes.is_empty() {
+                consumer.notified = true;
+                (consumer.pull_fn)();
+            }
+        }
     }
 
     ///
     /// Marks a region as changed for the next pull request
     ///
     fn mark_change(&mut self, original_range: Range<usize>, new_length: usize, attribute_change: bool) {
-        // Find the existing change corresponding to the start of the range
-        let (mut change_idx, mut diff)  = self.find_change(original_range.start);
-        let mut remaining_range         = original_range;
-        let mut remaining_length        = new_length;
-
-        loop {
-            self.check_integrity();
-
-            // If the index is beyond the end of the existing changes, then just add the edit range to the end
-            if change_idx >= self.changes.len() {
-                // Adjust the original range to match the new range
-                let original_start  = (remaining_range.start as i64) + diff;
-                let original_end    = (remaining_range.end as i64) + diff;
-                let original_start  = original_start as usize;
-                let original_end    = original_end as usize;
-
-                self.changes.push(RopePendingChange {
-                    original_range:     original_start..original_end,
-                    new_range:          remaining_range.start..(remaining_range.start+remaining_length),
-                    changed_attributes: attribute_change
-                });
-
-                break;
-            } else if self.changes[change_idx].new_range.start <= remaining_range.start {
-                // We overlap with an existing range
-                self.changes[change_idx].changed_attributes = self.changes[change_idx].changed_attributes || attribute_change;
-                let change = &self.changes[change_idx];
-
-                if remaining_range.end < change.new_range.end {
-                    // New change is entirely within the existing change
-                    let max_diff        = change.new_range.len() as i64;
-                    let length_diff     = (remaining_range.len() as i64) - (remaining_length as i64);
-                    let length_change   = max_diff.min(length_diff);
-
-                    if length_diff != 0 {
-                        // Adjust the length of the changed range
-                        self.changes[change_idx].new_range.end = (self.changes[change_idx].new_range.end as i64 - length_change) as usize;
-
-                        // Adjust the position of the following ranges
-                        for move_idx in (change_idx+1)..self.changes.len() {
-                            self.changes[move_idx].new_range.start  = (self.changes[move_idx].new_range.start as i64 - length_change) as usize;
-                            self.changes[move_idx].new_range.end    = (self.changes[move_idx].new_range.end as i64 - length_change) as usize;
-                        }
-                    }
-
-                    break;
-                } else {
-                    // Continue with the following range, by using all of the existing range
-                    let used_length         = change.new_range.end - remaining_range.start;
-                    remaining_range.start   += used_length;
-
-                    if remaining_length >= used_length {
-                        // The new items all fit within this range, so keep it as is
-                        remaining_length        -= used_length;
-                    } else {
-                        // The new range is shorter than this range, so shrink it by that much
-                        let length_diff = change.new_range.len() - remaining_length;
-                        self.changes[change_idx].new_range.end    = self.changes[change_idx].new_range.start + remaining_length;
-
-                        for move_idx in (change_idx+1)..self.changes.len() {
-                            self.changes[move_idx].new_range.start  = self.changes[move_idx].new_range.start - length_diff;
-                            self.changes[move_idx].new_range.end    = self.changes[move_idx].new_range.end - length_diff;
-                        }
-
-                        // The remaining range is moved by the same amount
-                        remaining_range.start   -= length_diff;
-                        remaining_range.end     -= length_diff;
-
-                        // The entire length is consumed
-                        remaining_length        = 0;
-
-                        if remaining_range.start == remaining_range.end {
-                            break;
-                        }
-                    }
-
-                    // New range will be overlapping or before the next change
-                    let change  = &self.changes[change_idx];
-                    let old_len = change.original_range.len() as i64;
-                    let new_len = change.new_range.len() as i64;
-
-                    diff        += old_len - new_len;
-                    change_idx  += 1;
-                }
-            } else {
-                // The range does not overlap an existing range
-                let next_range_start = if change_idx < self.changes.len() {
-                    self.changes[change_idx].new_range.start
-                } else {
-                    usize::MAX
-                };
-
-                if next_range_start >= remaining_range.end {
-                    // If the next range fits within the gap, then insert it and stop
-                    let original_start  = (remaining_range.start as i64) + diff;
-                    let original_end    = (remaining_range.end as i64) + diff;
-                    let original_start  = original_start as usize;
-                    let original_end    = original_end as usize;
-
-                    self.changes.insert(change_idx, RopePendingChange {
-                        original_range:     original_start..original_end,
-                        new_range:          remaining_range.start..(remaining_range.start+remaining_length),
-                        changed_attributes: false
-                    });
-
-                    // New change is entirely within the existing gap
-                    let length_diff = (remaining_range.len() as i64) - (remaining_length as i64);
-
-                    if length_diff != 0 {
-                        // Adjust the position of the following ranges
-                        for move_idx in (change_idx+1)..self.changes.len() {
-                            self.changes[move_idx].new_range.start  = (self.changes[move_idx].new_range.start as i64 - length_diff) as usize;
-                            self.changes[move_idx].new_range.end    = (self.changes[move_idx].new_range.end as i64 - length_diff) as usize;
-                        }
-                    }
-
-                    break;
-                } else {
-                    // Fill in as much as possible from the gap and continue from here
-                    let gap_length      = next_range_start - remaining_range.start;
-                    let original_start  = (remaining_range.start as i64) + diff;
-                    let original_start  = original_start as usize;
-                    let gap_end         = original_start + gap_length;
-
-                    if gap_length <= remaining_length {
-                        // The new range covers the entire gap
-                        self.changes.insert(change_idx, RopePendingChange {
-                            original_range:     original_start..gap_end,
-                            new_range:          remaining_range.start..(remaining_range.start+gap_length),
-                            changed_attributes: attribute_change
-                        });
-
-                        remaining_range.start   += gap_length;
-                        remaining_length        -= gap_length;
-                        change_idx              += 1;
-                    } else {
-                        // The new range needs to shrink the gap
-                        self.changes.insert(change_idx, RopePendingChange {
-                            original_range:     original_start..gap_end,
-                            new_range:          remaining_range.start..(remaining_range.start+remaining_length),
-                            changed_attributes: attribute_change
-                        });
-
-                        // Shrink the future changes
-                        let length_diff = gap_length - remaining_length;
-
-                        // Adjust the position of the following ranges
-                        for move_idx in (change_idx+1)..self.changes.len() {
-                            self.changes[move_idx].new_range.start  = self.changes[move_idx].new_range.start - length_diff;
-                            self.changes[move_idx].new_range.end    = self.changes[move_idx].new_range.end - length_diff;
-                        }
-
-                        remaining_range.start   += remaining_length;
-                        remaining_range.end     -= gap_length-remaining_length;
-                        remaining_length        = 0;
-
-                        // Move to the next range
-                        let change              = &self.changes[change_idx];
-
-                        let old_len             = change.original_range.len() as i64;
-                        let new_len             = change.new_range.len() as i64;
-
-                        diff                    += old_len - new_len;
-                        change_idx              += 1;
-                    }
-                }
-            }
+        rope_event!(start = original_range.start, end = original_range.end, new_length = new_length, attribute_change = attribute_change, "change");
+
+        if let Some(metrics) = &mut self.metrics {
+            metrics.record_edit(new_length);
+        }
+
+        for consumer in self.consumers.iter_mut() {
+            consumer.changes.mark(original_range.clone(), new_length, attribute_change);
         }
 
-        self.check_integrity();
+        self.changes.mark(original_range, new_length, attribute_change);
+
+        if let Some(metrics) = &mut self.metrics {
+            metrics.record_pending(self.changes.len());
+        }
     }
 
     ///
@@ -287,59 +455,190 @@ PullFn:     Fn() -> () {
     ///
     pub fn pull_changes<'a>(&'a mut self) -> impl 'a+Iterator<Item=RopeAction<BaseRope::Cell, BaseRope::Attribute>> {
         // Remove the pending changes from the rope
-        let mut pending_changes = vec![];
-        mem::swap(&mut self.changes, &mut pending_changes);
+        let pending_changes = self.changes.take_changes();
+        let original_cells  = self.take_original_cells();
+        self.pulled_version = self.version;
+
+        rope_event!(num_changes = pending_changes.len(), "pull");
+
+        if let Some(metrics) = &mut self.metrics {
+            metrics.record_pull();
+        }
 
         // Create an iterator to return the actions for these changes
         // Changes are returned in reverse so these edits can be applied directly to another rope in the original state
         pending_changes.into_iter()
             .rev()
-            .filter(|change| change.original_range.len() > 0 || change.new_range.len() > 0)
             .flat_map(move |change| {
-                // Read the cells for this change
-                if change.changed_attributes && change.new_range.len() > 0 {
-                    // Replace the cells and attributes in this range
+                match &original_cells {
+                    Some(original_cells) if !change.changed_attributes  => read_minimal_change(&self.rope, change, original_cells),
+                    _                                                   => read_pending_change(&self.rope, change)
+                }
+            })
+    }
 
-                    // Usually the attribute will cover the whole range but it's possible to create multiple attributes in a range via several updates: we work backwards until we've covered the entire range
-                    let mut original_range  = change.original_range;
-                    let new_range           = change.new_range;
-                    let mut end_pos         = new_range.end;
-                    let mut changes         = vec![];
+    ///
+    /// Pulls the pending changes from this rope, provided that the last pull was made at the specified version
+    ///
+    /// A consumer that mirrors this rope can store `version()` whenever it pulls changes, and pass it in here on the
+    /// next pull. If something else has pulled the changes in the meantime, the changes since that version are no longer
+    /// available: this returns `None` and leaves any pending changes in place. The consumer should re-read the whole rope
+    /// instead, discarding the pending changes with `pull_changes()`.
+    ///
+    pub fn pull_changes_since<'a>(&'a mut self, version: u64) -> Option<impl 'a+Iterator<Item=RopeAction<BaseRope::Cell, BaseRope::Attribute>>> {
+        if version == self.pulled_version {
+            Some(self.pull_changes())
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// Pulls the ranges that have changed in this rope without reading any of the new cells
+    ///
+    /// Each item is `(original_range, new_range, attributes_changed)`, where `original_range` is the range in the rope as
+    /// it was at the last pull and `new_range` is where the replacement cells are now. Unlike `pull_changes()`, the ranges
+    /// are returned in ascending order. There will be no pending changes after this function returns.
+    ///
+    pub fn pull_dirty_ranges(&mut self) -> impl Iterator<Item=(Range<usize>, Range<usize>, bool)> {
+        // Remove the pending changes from the rope
+        let pending_changes = self.changes.take_changes();
+        self.take_original_cells();
+        self.pulled_version = self.version;
 
-                    loop {
-                        // Read the attributes at the current end position
-                        let (attribute, attribute_range)    = self.rope.read_attributes(end_pos-1);
-                        let start_pos                       = new_range.start.max(attribute_range.start);
-                        let valid_range                     = start_pos..end_pos;
-                        let new_cells                       = self.read_cells(valid_range).cloned().collect();
+        rope_event!(num_changes = pending_changes.len(), "pull");
+
+        if let Some(metrics) = &mut self.metrics {
+            metrics.record_pull();
+        }
+
+        pending_changes.into_iter()
+            .filter(|change| change.original_range.len() > 0 || change.new_range.len() > 0)
+            .map(|change| (change.original_range, change.new_range, change.changed_attributes))
+    }
+
+    ///
+    /// Pulls the pending changes from this rope, passing them to a function one at a time
+    ///
+    /// This produces the same actions as `pull_changes()`, but they're passed as borrowed `RopeActionRef`s and the buffer
+    /// used for the cells in each action is re-used for the next one, so the memory needed doesn't grow with the number
+    /// of changes. This suits consumers that write the changes out somewhere, such as a journal. There will be no pending
+    /// changes after this function returns.
+    ///
+    pub fn pull_changes_for_each<ActionFn>(&mut self, mut action_fn: ActionFn)
+    where
+    ActionFn: FnMut(RopeActionRef<'_, BaseRope::Cell, BaseRope::Attribute>) -> () {
+        // Remove the pending changes from the rope
+        let pending_changes = self.changes.take_changes();
+        self.take_original_cells();
+        self.pulled_version = self.version;
+
+        rope_event!(num_changes = pending_changes.len(), "pull");
+
+        if let Some(metrics) = &mut self.metrics {
+            metrics.record_pull();
+        }
 
-                        changes.push(RopeAction::ReplaceAttributes(original_range.clone(), new_cells, attribute.clone()));
+        // The cells for each action are read into this buffer
+        let mut cells = vec![];
 
-                        // Stop once we reach the start of the changed range
-                        if start_pos <= new_range.start { break; }
+        // Changes are sent in reverse so these edits can be applied directly to another rope in the original state
+        for change in pending_changes.into_iter().rev() {
+            if change.original_range.is_empty() && change.new_range.is_empty() { continue; }
 
-                        // Make the next change an insertion at the beginning of the range
-                        original_range                      = original_range.start..original_range.start;
+            if change.changed_attributes && !change.new_range.is_empty() {
+                // Work backwards through the attribute runs in the changed range, in the same way as pull_changes()
+                let mut original_range  = change.original_range;
+                let new_range           = change.new_range;
+                let mut end_pos         = new_range.end;
 
-                        // Continue searching for attributes from the point we reached
-                        end_pos                             = start_pos;
-                    }
+                loop {
+                    let (attribute, attribute_range)    = self.rope.read_attributes(end_pos-1);
+                    let start_pos                       = new_range.start.max(attribute_range.start);
 
-                    changes
-                } else {
-                    // Just replace the cells in this range
-                    let new_cells = self.rope.read_cells(change.new_range.clone()).cloned().collect::<Vec<_>>();
+                    cells.extend(self.rope.read_cells(start_pos..end_pos).cloned());
 
-                    vec![RopeAction::Replace(change.original_range, new_cells)]
+                    action_fn(RopeActionRef::ReplaceAttributes(original_range.clone(), &cells, attribute));
+                    cells.clear();
+
+                    if start_pos <= new_range.start { break; }
+
+                    original_range  = original_range.start..original_range.start;
+                    end_pos         = start_pos;
+                }
+            } else {
+                // Just replace the cells in this range
+                cells.extend(self.rope.read_cells(change.new_range).cloned());
+
+                action_fn(RopeActionRef::Replace(change.original_range, &cells));
+                cells.clear();
+            }
+        }
+    }
+
+    ///
+    /// Pulls the pending changes from this rope, grouping the new cells for each change by attribute
+    ///
+    /// Like `pull_changes()`, the changes are returned in reverse order so that they can be applied directly to another
+    /// rope in the original state. There will be no pending changes after this function returns.
+    ///
+    pub fn pull_grouped_changes<'a>(&'a mut self) -> impl 'a+Iterator<Item=PulledChange<BaseRope::Cell, BaseRope::Attribute>> {
+        // Remove the pending changes from the rope
+        let pending_changes = self.changes.take_changes();
+        self.take_original_cells();
+        self.pulled_version = self.version;
+
+        rope_event!(num_changes = pending_changes.len(), "pull");
+
+        if let Some(metrics) = &mut self.metrics {
+            metrics.record_pull();
+        }
+
+        pending_changes.into_iter()
+            .rev()
+            .filter(|change| change.original_range.len() > 0 || change.new_range.len() > 0)
+            .map(move |change| {
+                // Read the cells for each attribute run in the new range
+                let spans = self.rope.read_attribute_runs(change.new_range.clone())
+                    .map(|(attribute, run_range)| (self.rope.read_cells(run_range).cloned().collect(), attribute.clone()))
+                    .collect();
+
+                PulledChange {
+                    range:              change.original_range,
+                    spans:              spans,
+                    changed_attributes: change.changed_attributes
                 }
             })
     }
 }
 
+impl<BaseRope, PullFn> PullRope<BaseRope, PullFn>
+where
+BaseRope:       RopeMut,
+PullFn:         FnMut() -> (),
+BaseRope::Cell: PartialEq {
+    ///
+    /// Narrows the changes returned by `pull_changes()` to the cells that are actually different
+    ///
+    /// Normally a change covers every cell that has been edited since the last pull, even if it has been set back to its
+    /// original value: in this mode, the rope remembers the cells that each edit replaces and drops the parts at the
+    /// start and end of each change that are the same as before. Changes that set attributes are not narrowed. This
+    /// takes effect for the edits made after it's called.
+    ///
+    pub fn enable_minimal_diffs(&mut self) {
+        if self.original_cells.is_none() {
+            self.original_cells = Some(OriginalCells {
+                runs:           BTreeMap::new(),
+                cells_equal:    |a, b| a == b
+            });
+        }
+    }
+}
+
 impl<BaseRope, PullFn> Rope for PullRope<BaseRope, PullFn>
 where 
 BaseRope:   RopeMut, 
-PullFn:     Fn() -> () {
+PullFn:     FnMut() -> () {
     /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
     type Cell = BaseRope::Cell;
 
@@ -358,10 +657,18 @@ PullFn:     Fn() -> () {
     /// Reads the cell values for a range in this rope
     ///
     #[inline]
-    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&Self::Cell>> {
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
         self.rope.read_cells(range)
     }
 
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.read_chunks(range)
+    }
+
     ///
     /// Returns the attributes set at the specified location and their extent
     ///
@@ -369,12 +676,20 @@ PullFn:     Fn() -> () {
     fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
         self.rope.read_attributes(pos)
     }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.read_attribute_runs(range)
+    }
 }
 
 impl<BaseRope, PullFn> RopeMut for PullRope<BaseRope, PullFn>
 where 
 BaseRope:   RopeMut, 
-PullFn:     Fn() -> () {
+PullFn:     FnMut() -> () {
     ///
     /// Performs the specified editing action to this rope
     ///
@@ -382,6 +697,7 @@ PullFn:     Fn() -> () {
         let need_pull = self.changes.len() == 0;
 
         // Store the change
+        self.capture_original_cells(action.range());
         match &action {
             RopeAction::Replace(range, new_values)                  => self.mark_change(range.clone(), new_values.len(), false),
             RopeAction::SetAttributes(range, _attr)                 => self.mark_change(range.clone(), range.len(), true),
@@ -390,9 +706,10 @@ PullFn:     Fn() -> () {
 
         // Pass on to the base rope
         self.rope.edit(action);
+        self.finish_edit();
 
         // Indicate that there are pending changes
-        if need_pull && self.changes.len() > 0 {
+        if need_pull && self.changes.len() > 0 && self.transaction_depth == 0 {
             (self.pull_fn)();
         }
     }
@@ -406,11 +723,13 @@ PullFn:     Fn() -> () {
 
         let new_cells = new_cells.into_iter().collect::<Vec<_>>();
 
+        self.capture_original_cells(range.clone());
         self.mark_change(range.clone(), new_cells.len(), false);
         self.rope.replace(range, new_cells);
+        self.finish_edit();
 
         // Indicate that there are pending changes
-        if need_pull && self.changes.len() > 0 {
+        if need_pull && self.changes.len() > 0 && self.transaction_depth == 0 {
             (self.pull_fn)();
         }
     }
@@ -421,11 +740,13 @@ PullFn:     Fn() -> () {
     fn set_attributes(&mut self, range: Range<usize>, new_attributes: Self::Attribute) {
         let need_pull = self.changes.len() == 0;
 
+        self.capture_original_cells(range.clone());
         self.mark_change(range.clone(), range.len(), true);
         self.rope.set_attributes(range, new_attributes);
+        self.finish_edit();
 
         // Indicate that there are pending changes
-        if need_pull && self.changes.len() > 0 {
+        if need_pull && self.changes.len() > 0 && self.transaction_depth == 0 {
             (self.pull_fn)();
         }
     }
@@ -438,16 +759,63 @@ PullFn:     Fn() -> () {
 
         let new_cells = new_cells.into_iter().collect::<Vec<_>>();
 
+        self.capture_original_cells(range.clone());
         self.mark_change(range.clone(), new_cells.len(), true);
         self.rope.replace_attributes(range, new_cells, new_attributes);
+        self.finish_edit();
 
         // Indicate that there are pending changes
-        if need_pull && self.changes.len() > 0 {
+        if need_pull && self.changes.len() > 0 && self.transaction_depth == 0 {
+            (self.pull_fn)();
+        }
+    }
+
+    ///
+    /// Performs a series of edits to this rope as a single transaction. The pull function is called at most once, after
+    /// the transaction has completed.
+    ///
+    fn transaction<TransactionFn: FnOnce(&mut Self) -> ()>(&mut self, transaction_fn: TransactionFn) {
+        let need_pull = self.changes.len() == 0;
+
+        self.transaction_depth += 1;
+        transaction_fn(self);
+        self.transaction_depth -= 1;
+
+        // The whole transaction is a single batch of edits
+        if self.transaction_depth == 0 && self.edited_in_transaction {
+            self.edited_in_transaction  = false;
+            self.version                += 1;
+
+            self.notify_consumers();
+        }
+
+        // Indicate that there are pending changes
+        if need_pull && self.changes.len() > 0 && self.transaction_depth == 0 {
             (self.pull_fn)();
         }
     }
 }
 
+impl<BaseRope, PullFn> Deref for PullRope<BaseRope, PullFn>
+where
+BaseRope:   RopeMut,
+PullFn:     FnMut() -> () {
+    type Target = BaseRope;
+
+    ///
+    /// Allows the methods of the base rope that only read from it to be called directly on this rope
+    ///
+    fn deref(&self) -> &BaseRope {
+        &self.rope
+    }
+}
+
+impl<BaseRope, PullFn> RopeSearch for PullRope<BaseRope, PullFn>
+where
+BaseRope:       RopeMut,
+PullFn:         FnMut() -> (),
+BaseRope::Cell: PartialEq { }
+
 #[cfg(test)]
 mod test {
     use crate::*;
@@ -814,7 +1182,7 @@ mod test {
 
         assert!(rope.changes[0].original_range == (0..0));
         assert!(rope.changes[0].new_range == (0..3));
-        assert!(rope.find_change(0) == (0, 0));
+        assert!(rope.changes.find_change(0) == (0, 0));
 
         rope.mark_change(1..2, 3, false);
 
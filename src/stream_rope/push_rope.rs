@@ -1,6 +1,8 @@
+use super::rope_edit_queue::*;
+
 use crate::api::*;
 
-use std::ops::{Range};
+use std::ops::{Range, Deref};
 
 ///
 /// A push rope is a rope with a callback function where updates will be sent.
@@ -10,22 +12,28 @@ use std::ops::{Range};
 /// In order to reduce the amount of data that's copied, the PushBeforeRope
 /// will only send updates before they're applied to the rope.
 ///
+/// Large actions can be divided into smaller ones before they're sent by calling
+/// `set_granularity()`: each of the smaller actions is sent just before it's applied.
+///
 #[derive(Clone)]
 pub struct PushBeforeRope<BaseRope, PushFn> 
 where 
 BaseRope:   RopeMut, 
-PushFn:     Fn(&RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
+PushFn:     FnMut(&RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
     /// The rope that this will push updates for
     rope:       BaseRope,
 
     /// The function that updates will be pushed to
-    push_fn:    PushFn
+    push_fn:    PushFn,
+
+    /// How actions are divided up before they're pushed
+    granularity: ActionGranularity<BaseRope::Cell>
 }
 
 impl<BaseRope, PushFn> Rope for PushBeforeRope<BaseRope, PushFn>
 where 
 BaseRope:   RopeMut, 
-PushFn:     Fn(&RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
+PushFn:     FnMut(&RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
     /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
     type Cell = BaseRope::Cell;
 
@@ -44,10 +52,18 @@ PushFn:     Fn(&RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
     /// Reads the cell values for a range in this rope
     ///
     #[inline]
-    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&Self::Cell>> {
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
         self.rope.read_cells(range)
     }
 
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.read_chunks(range)
+    }
+
     ///
     /// Returns the attributes set at the specified location and their extent
     ///
@@ -55,19 +71,33 @@ PushFn:     Fn(&RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
     fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
         self.rope.read_attributes(pos)
     }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.read_attribute_runs(range)
+    }
 }
 
 impl<BaseRope, PushFn> RopeMut for PushBeforeRope<BaseRope, PushFn>
 where 
 BaseRope:   RopeMut, 
-PushFn:     Fn(&RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
+PushFn:     FnMut(&RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
     ///
     /// Performs the specified editing action to this rope
     ///
-    #[inline]
     fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
-        (self.push_fn)(&action);
-        self.rope.edit(action);
+        if let ActionGranularity::Whole = self.granularity {
+            (self.push_fn)(&action);
+            self.rope.edit(action);
+        } else {
+            for action in split_action(action, self.granularity) {
+                (self.push_fn)(&action);
+                self.rope.edit(action);
+            }
+        }
     }
 }
 
@@ -80,22 +110,42 @@ PushFn:     Fn(&RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
 /// which can be more convenient but does require cloning the data that's
 /// passed in.
 ///
+/// Consumers whose cost depends on the size of the largest action (such as
+/// renderers that redraw everything an action touches) can call `set_granularity()`
+/// to have large actions divided into smaller ones before they're pushed.
+///
+/// The push function can't edit the rope directly. Instead, it can add edits to a
+/// `RopeEditQueue` attached with `set_edit_queue()`: these are applied (and pushed)
+/// in order once the current edit has completed.
+///
 #[derive(Clone)]
 pub struct PushAfterRope<BaseRope, PushFn> 
 where 
 BaseRope:   RopeMut, 
-PushFn:     Fn(RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
+PushFn:     FnMut(RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
     /// The rope that this will push updates for
     rope:       BaseRope,
 
     /// The function that updates will be pushed to
-    push_fn:    PushFn
+    push_fn:    PushFn,
+
+    /// While a transaction is in progress, the actions that will be pushed when it completes
+    deferred:   Option<Vec<RopeAction<BaseRope::Cell, BaseRope::Attribute>>>,
+
+    /// How actions are divided up before they're pushed
+    granularity: ActionGranularity<BaseRope::Cell>,
+
+    /// Edits to apply after the current edit has completed
+    edit_queue: Option<RopeEditQueue<BaseRope::Cell, BaseRope::Attribute>>,
+
+    /// True while the edit queue is being applied
+    is_applying_queue: bool
 }
 
 impl<BaseRope, PushFn> Rope for PushAfterRope<BaseRope, PushFn>
 where 
 BaseRope:   RopeMut, 
-PushFn:     Fn(RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
+PushFn:     FnMut(RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
     /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
     type Cell = BaseRope::Cell;
 
@@ -114,10 +164,18 @@ PushFn:     Fn(RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
     /// Reads the cell values for a range in this rope
     ///
     #[inline]
-    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&Self::Cell>> {
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
         self.rope.read_cells(range)
     }
 
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.read_chunks(range)
+    }
+
     ///
     /// Returns the attributes set at the specified location and their extent
     ///
@@ -125,19 +183,28 @@ PushFn:     Fn(RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
     fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
         self.rope.read_attributes(pos)
     }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.read_attribute_runs(range)
+    }
 }
 
 impl<BaseRope, PushFn> RopeMut for PushAfterRope<BaseRope, PushFn>
 where 
 BaseRope:   RopeMut, 
-PushFn:     Fn(RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
+PushFn:     FnMut(RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
     ///
     /// Performs the specified editing action to this rope
     ///
     #[inline]
     fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
         self.rope.edit(action.clone());
-        (self.push_fn)(action);
+        self.push(action);
+        self.apply_edit_queue();
     }
 
     ///
@@ -148,7 +215,8 @@ PushFn:     Fn(RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
         let new_cells = new_cells.into_iter().collect::<Vec<_>>();
 
         self.rope.replace(range.clone(), new_cells.clone());
-        (self.push_fn)(RopeAction::Replace(range, new_cells));
+        self.push(RopeAction::Replace(range, new_cells));
+        self.apply_edit_queue();
     }
 
     ///
@@ -156,7 +224,8 @@ PushFn:     Fn(RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
     ///
     fn set_attributes(&mut self, range: Range<usize>, new_attributes: Self::Attribute) {
         self.rope.set_attributes(range.clone(), new_attributes.clone());
-        (self.push_fn)(RopeAction::SetAttributes(range, new_attributes));
+        self.push(RopeAction::SetAttributes(range, new_attributes));
+        self.apply_edit_queue();
     }
 
     ///
@@ -166,36 +235,408 @@ PushFn:     Fn(RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
         let new_cells = new_cells.into_iter().collect::<Vec<_>>();
 
         self.rope.replace_attributes(range.clone(), new_cells.clone(), new_attributes.clone());
-        (self.push_fn)(RopeAction::ReplaceAttributes(range, new_cells, new_attributes));
+        self.push(RopeAction::ReplaceAttributes(range, new_cells, new_attributes));
+        self.apply_edit_queue();
+    }
+
+    ///
+    /// Performs a series of edits to this rope as a single transaction
+    ///
+    /// The actions are pushed together once the transaction has completed, after combining any that edit the same
+    /// region of the rope (see `compact_actions()`)
+    ///
+    fn transaction<TransactionFn: FnOnce(&mut Self) -> ()>(&mut self, transaction_fn: TransactionFn) {
+        if self.deferred.is_some() {
+            // Already in a transaction
+            transaction_fn(self);
+        } else {
+            // Collect the actions generated by the transaction
+            self.deferred = Some(vec![]);
+            transaction_fn(self);
+            let actions = self.deferred.take().unwrap_or_default();
+
+            // Push them once the transaction is complete
+            for action in compact_actions(actions) {
+                self.push(action);
+            }
+
+            self.apply_edit_queue();
+        }
+    }
+}
+
+///
+/// A push rope is a rope with a callback function where updates will be sent.
+/// It can be used as an event source for cases where updates need to be
+/// immediately reflected somewhere else.
+///
+/// The PushAfterRefRope generates its events after the base has been modified,
+/// like the PushAfterRope, but passes a borrowed version of the action to the
+/// callback function. This avoids copying the cells for observers that only
+/// need to inspect the changes.
+///
+#[derive(Clone)]
+pub struct PushAfterRefRope<BaseRope, PushFn> 
+where 
+BaseRope:   RopeMut, 
+PushFn:     FnMut(RopeActionRef<BaseRope::Cell, BaseRope::Attribute>) -> () {
+    /// The rope that this will push updates for
+    rope:       BaseRope,
+
+    /// The function that updates will be pushed to
+    push_fn:    PushFn
+}
+
+impl<BaseRope, PushFn> Rope for PushAfterRefRope<BaseRope, PushFn>
+where 
+BaseRope:   RopeMut, 
+PushFn:     FnMut(RopeActionRef<BaseRope::Cell, BaseRope::Attribute>) -> () {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.rope.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        self.rope.read_cells(range)
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.read_chunks(range)
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.rope.read_attributes(pos)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.read_attribute_runs(range)
+    }
+}
+
+impl<BaseRope, PushFn> RopeMut for PushAfterRefRope<BaseRope, PushFn>
+where 
+BaseRope:   RopeMut, 
+PushFn:     FnMut(RopeActionRef<BaseRope::Cell, BaseRope::Attribute>) -> () {
+    ///
+    /// Performs the specified editing action to this rope
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        match action {
+            RopeAction::Replace(range, new_cells)                           => self.replace(range, new_cells),
+            RopeAction::SetAttributes(range, new_attributes)                => self.set_attributes(range, new_attributes),
+            RopeAction::ReplaceAttributes(range, new_cells, new_attributes) => self.replace_attributes(range, new_cells, new_attributes)
+        }
+    }
+
+    ///
+    /// Replaces a range of cells. The attributes applied to the new cells will be the same
+    /// as the attributes that were applied to the first cell in the replacement range
+    ///
+    fn replace<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells) {
+        let new_cells   = new_cells.into_iter().collect::<Vec<_>>();
+        let written     = range.start..(range.start + new_cells.len());
+
+        self.rope.replace(range.clone(), new_cells);
+
+        let push_fn = &mut self.push_fn;
+        with_written_cells(&self.rope, written, |new_cells| push_fn(RopeActionRef::Replace(range, new_cells)));
+    }
+
+    ///
+    /// Sets the attributes for a range of cells
+    ///
+    fn set_attributes(&mut self, range: Range<usize>, new_attributes: Self::Attribute) {
+        self.rope.set_attributes(range.clone(), new_attributes.clone());
+        (self.push_fn)(RopeActionRef::SetAttributes(range, &new_attributes));
+    }
+
+    ///
+    /// Replaces a range of cells and sets the attributes for them.
+    ///
+    fn replace_attributes<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells, new_attributes: Self::Attribute) {
+        let new_cells   = new_cells.into_iter().collect::<Vec<_>>();
+        let written     = range.start..(range.start + new_cells.len());
+
+        self.rope.replace_attributes(range.clone(), new_cells, new_attributes.clone());
+
+        let push_fn = &mut self.push_fn;
+        with_written_cells(&self.rope, written, |new_cells| push_fn(RopeActionRef::ReplaceAttributes(range, new_cells, &new_attributes)));
+    }
+}
+
+///
+/// Calls a function with the cells that an edit wrote to a rope, read back from the rope itself
+///
+/// The cells are moved into the rope by the edit, so this only needs to copy them if the rope has stored them in more than
+/// one chunk.
+///
+fn with_written_cells<BaseRope: ?Sized+Rope>(rope: &BaseRope, written: Range<usize>, action_fn: impl FnOnce(&[BaseRope::Cell])) {
+    let mut chunks = rope.read_chunks(written.clone());
+
+    match (chunks.next(), chunks.next()) {
+        (None, _)           => action_fn(&[]),
+        (Some(chunk), None) => action_fn(chunk),
+        _                   => {
+            let cells = rope.read_cells(written).cloned().collect::<Vec<_>>();
+            action_fn(&cells)
+        }
     }
 }
 
 impl<BaseRope, PushFn> PushBeforeRope<BaseRope, PushFn>
 where 
 BaseRope:   RopeMut, 
-PushFn:     Fn(&RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
+PushFn:     FnMut(&RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
     ///
     /// Creates a new push rope from a base rope and function
     ///
     pub fn from(rope: BaseRope, update_fn: PushFn) -> PushBeforeRope<BaseRope, PushFn> {
         PushBeforeRope {
-            rope:       rope,
-            push_fn:    update_fn
+            rope:           rope,
+            push_fn:        update_fn,
+            granularity:    ActionGranularity::Whole
         }
     }
+
+    ///
+    /// Sets how large actions are divided up before they're sent to the push function
+    ///
+    pub fn set_granularity(&mut self, granularity: ActionGranularity<BaseRope::Cell>) {
+        self.granularity = granularity;
+    }
+
+    ///
+    /// Returns the rope that this wraps
+    ///
+    pub fn into_inner(self) -> BaseRope {
+        self.rope
+    }
+
+    ///
+    /// Borrows the rope that this wraps
+    ///
+    pub fn base(&self) -> &BaseRope {
+        &self.rope
+    }
+
+    ///
+    /// Borrows the rope that this wraps for editing
+    ///
+    /// Edits made through this reference bypass this rope, so they're not sent to the push function. Anything that's mirroring this rope using
+    /// those actions will no longer match it.
+    ///
+    pub fn base_mut(&mut self) -> &mut BaseRope {
+        &mut self.rope
+    }
 }
 
 impl<BaseRope, PushFn> PushAfterRope<BaseRope, PushFn>
 where 
 BaseRope:   RopeMut, 
-PushFn:     Fn(RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
+PushFn:     FnMut(RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
     ///
     /// Creates a new push rope from a base rope and function
     ///
     pub fn from(rope: BaseRope, update_fn: PushFn) -> PushAfterRope<BaseRope, PushFn> {
         PushAfterRope {
+            rope:               rope,
+            push_fn:            update_fn,
+            deferred:           None,
+            granularity:        ActionGranularity::Whole,
+            edit_queue:         None,
+            is_applying_queue:  false
+        }
+    }
+
+    ///
+    /// Attaches a queue of edits to this rope. Edits added to the queue by the push function are applied once the
+    /// edit that triggered it has completed.
+    ///
+    pub fn set_edit_queue(&mut self, edit_queue: RopeEditQueue<BaseRope::Cell, BaseRope::Attribute>) {
+        self.edit_queue = Some(edit_queue);
+        self.apply_edit_queue();
+    }
+
+    ///
+    /// Applies and pushes the actions in the edit queue, in order
+    ///
+    fn apply_edit_queue(&mut self) {
+        // Queued edits are applied after the transaction or the edit that's already applying the queue has finished
+        if self.deferred.is_some() || self.is_applying_queue { return; }
+
+        let edit_queue = match &self.edit_queue { Some(queue) => queue.clone(), None => { return; } };
+
+        self.is_applying_queue = true;
+        while let Some(action) = edit_queue.pop() {
+            self.rope.edit(action.clone());
+            self.push(action);
+        }
+        self.is_applying_queue = false;
+    }
+
+    ///
+    /// Sets how large actions are divided up before they're sent to the push function
+    ///
+    pub fn set_granularity(&mut self, granularity: ActionGranularity<BaseRope::Cell>) {
+        self.granularity = granularity;
+    }
+
+    ///
+    /// Returns the rope that this wraps
+    ///
+    pub fn into_inner(self) -> BaseRope {
+        self.rope
+    }
+
+    ///
+    /// Borrows the rope that this wraps
+    ///
+    pub fn base(&self) -> &BaseRope {
+        &self.rope
+    }
+
+    ///
+    /// Borrows the rope that this wraps for editing
+    ///
+    /// Edits made through this reference bypass this rope, so they're not sent to the push function. Anything that's mirroring this rope using
+    /// those actions will no longer match it.
+    ///
+    pub fn base_mut(&mut self) -> &mut BaseRope {
+        &mut self.rope
+    }
+
+    ///
+    /// Sends an action to the push function, or defers it until the end of the current transaction
+    ///
+    fn push(&mut self, action: RopeAction<BaseRope::Cell, BaseRope::Attribute>) {
+        match (&mut self.deferred, self.granularity) {
+            (Some(deferred), _)                 => deferred.push(action),
+            (None, ActionGranularity::Whole)    => (self.push_fn)(action),
+            (None, granularity)                 => split_action(action, granularity).into_iter().for_each(|action| (self.push_fn)(action))
+        }
+    }
+}
+
+impl<BaseRope, PushFn> PushAfterRefRope<BaseRope, PushFn>
+where 
+BaseRope:   RopeMut, 
+PushFn:     FnMut(RopeActionRef<BaseRope::Cell, BaseRope::Attribute>) -> () {
+    ///
+    /// Creates a new push rope from a base rope and function
+    ///
+    pub fn from(rope: BaseRope, update_fn: PushFn) -> PushAfterRefRope<BaseRope, PushFn> {
+        PushAfterRefRope {
             rope:       rope,
             push_fn:    update_fn
         }
     }
+
+    ///
+    /// Returns the rope that this wraps
+    ///
+    pub fn into_inner(self) -> BaseRope {
+        self.rope
+    }
+
+    ///
+    /// Borrows the rope that this wraps
+    ///
+    pub fn base(&self) -> &BaseRope {
+        &self.rope
+    }
+
+    ///
+    /// Borrows the rope that this wraps for editing
+    ///
+    /// Edits made through this reference bypass this rope, so they're not sent to the push function. Anything that's mirroring this rope using
+    /// those actions will no longer match it.
+    ///
+    pub fn base_mut(&mut self) -> &mut BaseRope {
+        &mut self.rope
+    }
+}
+
+impl<BaseRope, PushFn> RopeSearch for PushBeforeRope<BaseRope, PushFn>
+where
+BaseRope:       RopeMut,
+PushFn:         FnMut(&RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> (),
+BaseRope::Cell: PartialEq { }
+
+impl<BaseRope, PushFn> RopeSearch for PushAfterRope<BaseRope, PushFn>
+where
+BaseRope:       RopeMut,
+PushFn:         FnMut(RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> (),
+BaseRope::Cell: PartialEq { }
+
+impl<BaseRope, PushFn> RopeSearch for PushAfterRefRope<BaseRope, PushFn>
+where
+BaseRope:       RopeMut,
+PushFn:         FnMut(RopeActionRef<BaseRope::Cell, BaseRope::Attribute>) -> (),
+BaseRope::Cell: PartialEq { }
+
+impl<BaseRope, PushFn> Deref for PushBeforeRope<BaseRope, PushFn>
+where
+BaseRope:   RopeMut,
+PushFn:     FnMut(&RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
+    type Target = BaseRope;
+
+    ///
+    /// Allows the methods of the base rope that only read from it to be called directly on this rope
+    ///
+    fn deref(&self) -> &BaseRope {
+        &self.rope
+    }
+}
+
+impl<BaseRope, PushFn> Deref for PushAfterRope<BaseRope, PushFn>
+where
+BaseRope:   RopeMut,
+PushFn:     FnMut(RopeAction<BaseRope::Cell, BaseRope::Attribute>) -> () {
+    type Target = BaseRope;
+
+    ///
+    /// Allows the methods of the base rope that only read from it to be called directly on this rope
+    ///
+    fn deref(&self) -> &BaseRope {
+        &self.rope
+    }
+}
+
+impl<BaseRope, PushFn> Deref for PushAfterRefRope<BaseRope, PushFn>
+where
+BaseRope:   RopeMut,
+PushFn:     FnMut(RopeActionRef<BaseRope::Cell, BaseRope::Attribute>) -> () {
+    type Target = BaseRope;
+
+    ///
+    /// Allows the methods of the base rope that only read from it to be called directly on this rope
+    ///
+    fn deref(&self) -> &BaseRope {
+        &self.rope
+    }
 }
@@ -0,0 +1,190 @@
+use crate::api::*;
+
+use std::mem;
+use std::time::{Duration, Instant};
+use std::ops::{Range};
+
+///
+/// An action that was recorded by a `RecordingRope`
+///
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordedAction<Cell, Attribute> {
+    /// The action that was applied to the rope
+    pub action: RopeAction<Cell, Attribute>,
+
+    /// If timestamps are enabled, the time since the recording started when the action was applied
+    pub timestamp: Option<Duration>
+}
+
+///
+/// A recording rope stores every action that's applied to a base rope, so that the edits can be replayed later on
+///
+/// Replaying a recording onto a copy of the base rope in its original state reproduces the edits exactly, which can be
+/// used to reproduce the steps that led to a bug, or to test something that mirrors a rope with a fixed series of edits.
+/// The recording can optionally store the time that each action was applied.
+///
+#[derive(Clone)]
+pub struct RecordingRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// The rope that the actions are applied to
+    rope: BaseRope,
+
+    /// The actions that have been recorded, in order
+    recording: Vec<RecordedAction<BaseRope::Cell, BaseRope::Attribute>>,
+
+    /// If timestamps are being recorded, the time that the recording started
+    start_time: Option<Instant>
+}
+
+///
+/// Applies the actions in a recording to a rope, in order
+///
+pub fn replay_recording<'a, TargetRope>(recording: impl IntoIterator<Item=&'a RecordedAction<TargetRope::Cell, TargetRope::Attribute>>, target: &mut TargetRope)
+where
+TargetRope:             RopeMut,
+TargetRope::Cell:       'a,
+TargetRope::Attribute:  'a {
+    for recorded in recording {
+        target.edit(recorded.action.clone());
+    }
+}
+
+impl<BaseRope> RecordingRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Creates a rope that records the actions applied to a base rope (without timestamps)
+    ///
+    pub fn from(rope: BaseRope) -> RecordingRope<BaseRope> {
+        RecordingRope {
+            rope:       rope,
+            recording:  vec![],
+            start_time: None
+        }
+    }
+
+    ///
+    /// Starts recording the time of each action, relative to the time that this is called
+    ///
+    pub fn enable_timestamps(&mut self) {
+        self.start_time = Some(Instant::now());
+    }
+
+    ///
+    /// Stops recording the time of each action
+    ///
+    pub fn disable_timestamps(&mut self) {
+        self.start_time = None;
+    }
+
+    ///
+    /// The actions that have been recorded so far, in the order they were applied
+    ///
+    pub fn recording(&self) -> &[RecordedAction<BaseRope::Cell, BaseRope::Attribute>] {
+        &self.recording
+    }
+
+    ///
+    /// Removes and returns the actions that have been recorded so far
+    ///
+    pub fn take_recording(&mut self) -> Vec<RecordedAction<BaseRope::Cell, BaseRope::Attribute>> {
+        mem::take(&mut self.recording)
+    }
+
+    ///
+    /// Applies the recorded actions to another rope, which should be in the state that the base rope was in when the
+    /// recording started
+    ///
+    pub fn replay_onto<TargetRope>(&self, target: &mut TargetRope)
+    where
+    TargetRope: RopeMut<Cell=BaseRope::Cell, Attribute=BaseRope::Attribute> {
+        replay_recording(&self.recording, target);
+    }
+
+    ///
+    /// Returns the rope that the actions were applied to
+    ///
+    pub fn into_inner(self) -> BaseRope {
+        self.rope
+    }
+
+    ///
+    /// Borrows the rope that the actions are applied to
+    ///
+    pub fn base(&self) -> &BaseRope {
+        &self.rope
+    }
+}
+
+impl<BaseRope> Rope for RecordingRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.rope.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        self.rope.read_cells(range)
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.read_chunks(range)
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.rope.read_attributes(pos)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.read_attribute_runs(range)
+    }
+}
+
+impl<BaseRope> RopeMut for RecordingRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Records an action and applies it to the base rope
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        self.recording.push(RecordedAction {
+            action:     action.clone(),
+            timestamp:  self.start_time.map(|start_time| start_time.elapsed())
+        });
+
+        self.rope.edit(action);
+    }
+}
+
+impl<BaseRope> RopeSearch for RecordingRope<BaseRope>
+where
+BaseRope:       RopeMut,
+BaseRope::Cell: PartialEq { }
@@ -0,0 +1,242 @@
+use crate::api::*;
+use crate::rope::*;
+
+use std::ops::{Range};
+
+///
+/// An action sent from a remote rope to its provider, with a sequence number so the provider can detect missing or
+/// reordered actions
+///
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RopeActionEnvelope<Cell, Attribute> {
+    /// The sequence number of this action (the first action sent by a rope has sequence number 0)
+    pub sequence: u64,
+
+    /// The action that was performed on the rope
+    pub action: RopeAction<Cell, Attribute>
+}
+
+///
+/// Trait implemented by sources of content for a `RemoteRope`
+///
+pub trait RopeContentProvider {
+    /// The type of a cell in the remote rope
+    type Cell: Clone;
+
+    /// The type of an attribute in the remote rope
+    type Attribute: PartialEq+Clone+Default;
+
+    ///
+    /// Returns the number of cells in the remote rope
+    ///
+    fn len(&self) -> usize;
+
+    ///
+    /// Fetches the cells in a range of the remote rope as a list of spans with their attributes
+    ///
+    /// Providers that fetch content asynchronously can return an empty list here and call `RemoteRope::receive_cells()`
+    /// once the content has arrived. If fewer cells than were requested are returned, only the returned cells will be
+    /// marked as loaded.
+    ///
+    fn fetch(&mut self, range: Range<usize>) -> Vec<(Vec<Self::Cell>, Self::Attribute)>;
+
+    ///
+    /// Sends an action that was applied to the local copy of the rope to the remote rope
+    ///
+    fn send_action(&mut self, action: RopeActionEnvelope<Self::Cell, Self::Attribute>);
+}
+
+///
+/// A remote rope is a local view of a rope that is stored elsewhere (on a server, for example)
+///
+/// Cells are loaded from the provider as they're needed by calling `ensure_loaded()`, and kept in a local cache.
+/// Cells that have not been loaded are gaps in the cache that take up no storage, and are read as a placeholder
+/// value with the default attribute. Edits are applied to the local cache and forwarded to the provider, and edits
+/// made elsewhere can be applied with `apply_remote()`.
+///
+pub struct RemoteRope<Provider>
+where
+Provider: RopeContentProvider {
+    /// The provider that will supply the content for this rope
+    provider: Provider,
+
+    /// The cells that have been loaded (cells that are not loaded are gaps, which are read as the placeholder)
+    cache: SparseRope<AttributedRope<Provider::Cell, Provider::Attribute>>,
+
+    /// The sequence number of the next action to send to the provider
+    next_sequence: u64
+}
+
+impl<Provider> RemoteRope<Provider>
+where
+Provider: RopeContentProvider {
+    ///
+    /// Creates a new remote rope. No cells are loaded initially: they will be read as `placeholder` until they're loaded
+    ///
+    pub fn new(provider: Provider, placeholder: Provider::Cell) -> RemoteRope<Provider> {
+        let mut cache = SparseRope::from(AttributedRope::new(), placeholder);
+        cache.insert_gap(0, provider.len());
+
+        RemoteRope {
+            cache:          cache,
+            provider:       provider,
+            next_sequence:  0
+        }
+    }
+
+    ///
+    /// Returns the provider for this rope
+    ///
+    pub fn provider(&self) -> &Provider {
+        &self.provider
+    }
+
+    ///
+    /// Returns the provider for this rope
+    ///
+    pub fn provider_mut(&mut self) -> &mut Provider {
+        &mut self.provider
+    }
+
+    ///
+    /// True if all of the cells in the specified range have been loaded
+    ///
+    pub fn is_loaded(&self, range: Range<usize>) -> bool {
+        self.cache.is_dense(range)
+    }
+
+    ///
+    /// Fetches any cells in the specified range that have not already been loaded from the provider
+    ///
+    pub fn ensure_loaded(&mut self, range: Range<usize>) {
+        let missing_ranges = self.cache.gaps(range).collect::<Vec<_>>();
+
+        for missing_range in missing_ranges {
+            let spans = self.provider.fetch(missing_range.clone());
+            self.receive_cells(missing_range.start, spans);
+        }
+    }
+
+    ///
+    /// Stores cells that have been received from the provider, starting at the specified position
+    ///
+    pub fn receive_cells(&mut self, pos: usize, spans: Vec<(Vec<Provider::Cell>, Provider::Attribute)>) {
+        let mut pos = pos;
+        let len     = self.cache.len();
+
+        for (cells, attribute) in spans {
+            // Ignore cells beyond the end of the rope
+            let end     = (pos + cells.len()).min(len);
+            if pos >= end { break; }
+
+            let cells   = cells.into_iter().take(end-pos);
+
+            self.cache.replace_attributes(pos..end, cells, attribute);
+
+            pos = end;
+        }
+    }
+
+    ///
+    /// Marks the cells in a range as needing to be fetched from the provider again
+    ///
+    pub fn invalidate(&mut self, range: Range<usize>) {
+        self.cache.clear(range);
+    }
+
+    ///
+    /// Applies an action that was performed on the remote rope to the local copy (without sending it back to the provider)
+    ///
+    pub fn apply_remote(&mut self, action: RopeAction<Provider::Cell, Provider::Attribute>) {
+        self.apply_to_cache(action);
+    }
+
+    ///
+    /// Applies an action to the cached cells
+    ///
+    /// Cells that are written to the rope are always loaded. Attributes are only set on the cells that are already
+    /// loaded: the attributes of the other cells will be read from the provider when they are loaded.
+    ///
+    fn apply_to_cache(&mut self, action: RopeAction<Provider::Cell, Provider::Attribute>) {
+        match action {
+            RopeAction::SetAttributes(range, attribute) => {
+                let loaded_ranges = self.cache.stored_ranges(range).collect::<Vec<_>>();
+
+                for loaded_range in loaded_ranges {
+                    self.cache.set_attributes(loaded_range, attribute.clone());
+                }
+            }
+
+            action => { self.cache.edit(action); }
+        }
+    }
+}
+
+impl<Provider> Rope for RemoteRope<Provider>
+where
+Provider: RopeContentProvider {
+    type Cell       = Provider::Cell;
+    type Attribute  = Provider::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope (cells that are not loaded are read as the placeholder value)
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        self.cache.read_cells(range)
+    }
+
+    ///
+    /// Reads the cached cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.cache.read_chunks(range)
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.cache.read_attributes(pos)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.cache.read_attribute_runs(range)
+    }
+}
+
+impl<Provider> RopeMut for RemoteRope<Provider>
+where
+Provider: RopeContentProvider {
+    ///
+    /// Performs the specified editing action to this rope, and sends it to the provider
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        self.apply_to_cache(action.clone());
+
+        let sequence        = self.next_sequence;
+        self.next_sequence  += 1;
+
+        self.provider.send_action(RopeActionEnvelope { sequence, action });
+    }
+}
+
+impl<Provider> RopeSearch for RemoteRope<Provider>
+where
+Provider:       RopeContentProvider,
+Provider::Cell: PartialEq { }
@@ -0,0 +1,159 @@
+use crate::api::*;
+
+use std::ops::{Range};
+
+///
+/// A ring rope is a rope with a maximum length. When an edit makes the rope longer than its maximum length,
+/// cells are removed from the front of the rope to bring it back to size, which makes it suitable for
+/// representing things like the scrollback buffer of a terminal or a log.
+///
+/// Cells are evicted by editing the underlying rope, so if this is wrapped around a rope such as
+/// `PullRope` or `PushAfterRope`, the eviction will show up in its stream of changes.
+///
+#[derive(Clone)]
+pub struct RingRope<BaseRope> 
+where
+BaseRope: RopeMut {
+    /// The rope that will store the cells
+    rope:       BaseRope,
+
+    /// The maximum number of cells that can be stored in the rope
+    max_length: usize
+}
+
+impl<BaseRope> RingRope<BaseRope> 
+where
+BaseRope: RopeMut {
+    ///
+    /// Creates a new ring rope with the specified maximum length. If the base rope is already longer
+    /// than this, cells will be evicted from its start
+    ///
+    pub fn from(rope: BaseRope, max_length: usize) -> RingRope<BaseRope> {
+        let mut ring_rope = RingRope {
+            rope:       rope,
+            max_length: max_length
+        };
+
+        ring_rope.evict();
+        ring_rope
+    }
+
+    ///
+    /// Returns the maximum number of cells that can be stored in this rope
+    ///
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    ///
+    /// Changes the maximum number of cells that can be stored in this rope, evicting cells from the
+    /// start of the rope if it's now too long
+    ///
+    pub fn set_max_length(&mut self, max_length: usize) {
+        self.max_length = max_length;
+        self.evict();
+    }
+
+    ///
+    /// Removes cells from the start of the rope until it fits within the maximum length
+    ///
+    fn evict(&mut self) {
+        let len = self.rope.len();
+
+        if len > self.max_length {
+            self.rope.replace(0..(len - self.max_length), vec![]);
+        }
+    }
+}
+
+impl<BaseRope> Rope for RingRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.rope.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        self.rope.read_cells(range)
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.read_chunks(range)
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.rope.read_attributes(pos)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.read_attribute_runs(range)
+    }
+}
+
+impl<BaseRope> RopeMut for RingRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Performs the specified editing action to this rope
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        self.rope.edit(action);
+        self.evict();
+    }
+
+    ///
+    /// Replaces a range of cells. The attributes applied to the new cells will be the same
+    /// as the attributes that were applied to the first cell in the replacement range
+    ///
+    fn replace<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells) {
+        self.rope.replace(range, new_cells);
+        self.evict();
+    }
+
+    ///
+    /// Sets the attributes for a range of cells
+    ///
+    #[inline]
+    fn set_attributes(&mut self, range: Range<usize>, new_attributes: Self::Attribute) {
+        self.rope.set_attributes(range, new_attributes);
+    }
+
+    ///
+    /// Replaces a range of cells and sets the attributes for them.
+    ///
+    fn replace_attributes<NewCells: IntoIterator<Item=Self::Cell>>(&mut self, range: Range<usize>, new_cells: NewCells, new_attributes: Self::Attribute) {
+        self.rope.replace_attributes(range, new_cells, new_attributes);
+        self.evict();
+    }
+}
+
+impl<BaseRope> RopeSearch for RingRope<BaseRope>
+where
+BaseRope:       RopeMut,
+BaseRope::Cell: PartialEq { }
@@ -0,0 +1,176 @@
+use crate::api::*;
+use crate::sync::{Mutex};
+
+use std::mem;
+use std::sync::{Arc};
+
+///
+/// A set of actions made to one or more ropes as part of a single logical operation
+///
+#[derive(Clone, PartialEq, Debug)]
+pub struct RopeBatch<RopeId, Cell, Attribute> {
+    /// An ID for the transaction that generated these actions (transaction IDs increase with each batch)
+    pub transaction_id: u64,
+
+    /// The actions that were made, in order, along with the ID of the rope that they were applied to
+    pub actions: Vec<(RopeId, RopeAction<Cell, Attribute>)>
+}
+
+/// A function that receives batches of actions from a broadcast
+type Subscriber<RopeId, Cell, Attribute> = Box<dyn Send+FnMut(&RopeBatch<RopeId, Cell, Attribute>) -> ()>;
+
+///
+/// The shared state of a rope broadcast
+///
+struct BroadcastCore<RopeId, Cell, Attribute> {
+    /// The ID of the next transaction
+    next_transaction_id: u64,
+
+    /// The number of batches that are in progress (actions are delivered once this reaches 0)
+    batch_depth: usize,
+
+    /// True while the subscribers are being called (actions sent by the subscribers are delivered afterwards)
+    is_delivering: bool,
+
+    /// The actions that have been received but not delivered yet
+    pending: Vec<(RopeId, RopeAction<Cell, Attribute>)>,
+
+    /// The functions that batches are delivered to
+    subscribers: Vec<Subscriber<RopeId, Cell, Attribute>>
+}
+
+///
+/// A rope broadcast collects the actions made to several ropes and delivers them to its subscribers as batches.
+///
+/// Ropes send their actions to the broadcast via a `PushAfterRope`, using the function returned by `push_fn()`.
+/// Any actions made to the ropes within a call to `batch()` are delivered together with a single transaction ID once
+/// the batch completes, so subscribers never see a state where only some of the ropes have been updated. Actions
+/// made outside of a batch are delivered immediately, each with their own transaction ID.
+///
+/// Clones of a broadcast all refer to the same set of subscribers.
+///
+pub struct RopeBroadcast<RopeId, Cell, Attribute> {
+    /// The state shared between the clones of this broadcast and the push functions
+    core: Arc<Mutex<BroadcastCore<RopeId, Cell, Attribute>>>
+}
+
+impl<RopeId, Cell, Attribute> Clone for RopeBroadcast<RopeId, Cell, Attribute> {
+    fn clone(&self) -> Self {
+        RopeBroadcast {
+            core: Arc::clone(&self.core)
+        }
+    }
+}
+
+impl<RopeId, Cell, Attribute> RopeBroadcast<RopeId, Cell, Attribute>
+where
+RopeId: Clone {
+    ///
+    /// Creates a new broadcast with no subscribers
+    ///
+    pub fn new() -> RopeBroadcast<RopeId, Cell, Attribute> {
+        RopeBroadcast {
+            core: Arc::new(Mutex::new(BroadcastCore {
+                next_transaction_id:    0,
+                batch_depth:            0,
+                is_delivering:          false,
+                pending:                vec![],
+                subscribers:            vec![]
+            }))
+        }
+    }
+
+    ///
+    /// Adds a function that will be called with each batch of actions sent to this broadcast
+    ///
+    pub fn subscribe<SubscribeFn: 'static+Send+FnMut(&RopeBatch<RopeId, Cell, Attribute>) -> ()>(&self, subscriber: SubscribeFn) {
+        self.core.lock().subscribers.push(Box::new(subscriber));
+    }
+
+    ///
+    /// Returns a function that sends actions to this broadcast for the rope with the specified ID
+    ///
+    /// This is intended to be used with `PushAfterRope::from()`
+    ///
+    pub fn push_fn(&self, rope_id: RopeId) -> impl Fn(RopeAction<Cell, Attribute>) -> () {
+        let broadcast = self.clone();
+
+        move |action| broadcast.send(rope_id.clone(), action)
+    }
+
+    ///
+    /// Sends an action that has been applied to a rope to this broadcast
+    ///
+    pub fn send(&self, rope_id: RopeId, action: RopeAction<Cell, Attribute>) {
+        let is_batching = {
+            let mut core = self.core.lock();
+            core.pending.push((rope_id, action));
+
+            core.batch_depth > 0 || core.is_delivering
+        };
+
+        if !is_batching {
+            self.deliver();
+        }
+    }
+
+    ///
+    /// Performs a set of edits to the ropes attached to this broadcast, delivering the actions as a single batch
+    /// once the function returns
+    ///
+    /// Batches can be nested: the actions are delivered when the outermost batch completes.
+    ///
+    pub fn batch<BatchFn: FnOnce() -> TResult, TResult>(&self, batch_fn: BatchFn) -> TResult {
+        self.core.lock().batch_depth += 1;
+        let result = batch_fn();
+
+        let is_complete = {
+            let mut core = self.core.lock();
+            core.batch_depth -= 1;
+
+            core.batch_depth == 0 && !core.is_delivering
+        };
+
+        if is_complete {
+            self.deliver();
+        }
+
+        result
+    }
+
+    ///
+    /// Sends any pending actions to the subscribers
+    ///
+    fn deliver(&self) {
+        loop {
+            // Take the pending actions and the subscribers (the lock isn't held while the subscribers run, so they can edit the ropes)
+            let (batch, mut subscribers) = {
+                let mut core = self.core.lock();
+                if core.pending.len() == 0 {
+                    core.is_delivering = false;
+                    return;
+                }
+
+                let batch = RopeBatch {
+                    transaction_id: core.next_transaction_id,
+                    actions:        mem::take(&mut core.pending)
+                };
+                core.next_transaction_id    += 1;
+                core.is_delivering          = true;
+
+                (batch, mem::take(&mut core.subscribers))
+            };
+
+            for subscriber in subscribers.iter_mut() {
+                subscriber(&batch);
+            }
+
+            // Restore the subscribers (keeping any that were added while the batch was being delivered)
+            let mut core = self.core.lock();
+            subscribers.append(&mut core.subscribers);
+            core.subscribers = subscribers;
+
+            // Continue to deliver anything that the subscribers generated in response to this batch
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use super::push_rope::*;
+use super::pull_rope::*;
+use super::rope_broadcast::*;
+use super::throttle_rope::*;
+
+use crate::api::*;
+
+use std::time::{Duration};
+
+///
+/// Composes the streaming wrappers around a rope without needing to nest them by hand
+///
+/// Each method wraps the rope built so far, so the wrappers are applied in the order that they're added:
+///
+/// ```
+/// # use flo_rope::*;
+/// let mut rope = RopeBuilderChain::new(AttributedRope::<u8, ()>::new())
+///     .push_after(|action| { println!("{:?}", action); })
+///     .pull(|| { /* Schedule a redraw */ })
+///     .build();
+///
+/// rope.replace(0..0, "Hello".bytes());
+/// ```
+///
+/// Edits pass through the wrappers from the last one added to the first, so functions that are called after an edit
+/// has been made (`pull`, `throttle`, `push_after`, `broadcast`) are called in the order they were added. Functions that
+/// are called before an edit is made (`push_before`) are called before any wrappers that were added earlier see the edit.
+///
+/// The result of `build()` is the fully composed rope, so methods like `pull_changes()` are available on it when the
+/// outermost wrapper is a `PullRope` or a `ThrottleRope`. For this reason, `pull` and `throttle` must be the last
+/// wrapper to be added: they return a `RopeBuilderChainEnd`, which can only be built.
+///
+pub struct RopeBuilderChain<TRope>
+where
+TRope: RopeMut {
+    /// The rope with all of the wrappers added so far
+    rope: TRope
+}
+
+///
+/// The end of a `RopeBuilderChain`, after a wrapper that changes must be pulled from has been added
+///
+/// No more wrappers can be added once the chain has ended:
+///
+/// ```compile_fail
+/// # use flo_rope::*;
+/// let rope = RopeBuilderChain::new(AttributedRope::<u8, ()>::new())
+///     .pull(|| { })
+///     .push_after(|_| { })
+///     .build();
+/// ```
+///
+pub struct RopeBuilderChainEnd<TRope>
+where
+TRope: RopeMut {
+    /// The rope with all of the wrappers added
+    rope: TRope
+}
+
+impl<TRope> RopeBuilderChain<TRope>
+where
+TRope: RopeMut {
+    ///
+    /// Starts building a rope from a base rope
+    ///
+    pub fn new(base: TRope) -> RopeBuilderChain<TRope> {
+        RopeBuilderChain {
+            rope: base
+        }
+    }
+
+    ///
+    /// Calls a function with each action before it's applied to the rope (see `PushBeforeRope`)
+    ///
+    pub fn push_before<PushFn>(self, push_fn: PushFn) -> RopeBuilderChain<PushBeforeRope<TRope, PushFn>>
+    where
+    PushFn: FnMut(&RopeAction<TRope::Cell, TRope::Attribute>) -> () {
+        RopeBuilderChain::new(PushBeforeRope::from(self.rope, push_fn))
+    }
+
+    ///
+    /// Calls a function with each action after it's applied to the rope (see `PushAfterRope`)
+    ///
+    pub fn push_after<PushFn>(self, push_fn: PushFn) -> RopeBuilderChain<PushAfterRope<TRope, PushFn>>
+    where
+    PushFn: FnMut(RopeAction<TRope::Cell, TRope::Attribute>) -> () {
+        RopeBuilderChain::new(PushAfterRope::from(self.rope, push_fn))
+    }
+
+    ///
+    /// Calls a function with a reference to each action after it's applied to the rope (see `PushAfterRefRope`)
+    ///
+    pub fn push_after_ref<PushFn>(self, push_fn: PushFn) -> RopeBuilderChain<PushAfterRefRope<TRope, PushFn>>
+    where
+    PushFn: FnMut(RopeActionRef<TRope::Cell, TRope::Attribute>) -> () {
+        RopeBuilderChain::new(PushAfterRefRope::from(self.rope, push_fn))
+    }
+
+    ///
+    /// Gathers the changes to the rope so they can be pulled later on, calling a function when changes become available
+    /// (see `PullRope`). This must be the last wrapper added to the rope.
+    ///
+    pub fn pull<PullFn>(self, pull_fn: PullFn) -> RopeBuilderChainEnd<PullRope<TRope, PullFn>>
+    where
+    PullFn: FnMut() -> () {
+        RopeBuilderChainEnd { rope: PullRope::from(self.rope, pull_fn) }
+    }
+
+    ///
+    /// Gathers the changes to the rope so they can be pulled later on, calling a function at most once per `interval`
+    /// (or after every `max_edits` edits) while changes are being made (see `ThrottleRope`). This must be the last wrapper
+    /// added to the rope.
+    ///
+    pub fn throttle<NotifyFn>(self, interval: Duration, max_edits: Option<usize>, notify_fn: NotifyFn) -> RopeBuilderChainEnd<ThrottleRope<TRope, NotifyFn>>
+    where
+    NotifyFn: FnMut() -> () {
+        RopeBuilderChainEnd { rope: ThrottleRope::from(self.rope, interval, max_edits, notify_fn) }
+    }
+
+    ///
+    /// Sends the actions applied to the rope to a broadcast, using the specified rope ID (see `RopeBroadcast`)
+    ///
+    #[allow(clippy::type_complexity)]
+    pub fn broadcast<RopeId>(self, broadcast: &RopeBroadcast<RopeId, TRope::Cell, TRope::Attribute>, rope_id: RopeId) -> RopeBuilderChain<PushAfterRope<TRope, impl Fn(RopeAction<TRope::Cell, TRope::Attribute>) -> ()>>
+    where
+    RopeId: Clone {
+        RopeBuilderChain::new(PushAfterRope::from(self.rope, broadcast.push_fn(rope_id)))
+    }
+
+    ///
+    /// Returns the rope with all of the wrappers applied
+    ///
+    pub fn build(self) -> TRope {
+        self.rope
+    }
+}
+
+impl<TRope> RopeBuilderChainEnd<TRope>
+where
+TRope: RopeMut {
+    ///
+    /// Returns the rope with all of the wrappers applied
+    ///
+    pub fn build(self) -> TRope {
+        self.rope
+    }
+}
@@ -0,0 +1,74 @@
+use crate::api::*;
+use crate::sync::{Mutex};
+
+use std::collections::{VecDeque};
+use std::ops::{Range};
+use std::sync::{Arc};
+
+///
+/// A queue of edits that will be made to a rope once its current edit has completed
+///
+/// A push callback can't edit the rope that's calling it, as the rope is already being edited. Instead, the callback
+/// can add its edits to a queue that has been attached to the rope with `PushAfterRope::set_edit_queue()`. The rope
+/// applies the queued edits in order after the current edit (and all of its notifications) has completed.
+///
+/// Clones of a queue all refer to the same list of edits.
+///
+pub struct RopeEditQueue<Cell, Attribute> {
+    /// The actions waiting to be applied
+    actions: Arc<Mutex<VecDeque<RopeAction<Cell, Attribute>>>>
+}
+
+impl<Cell, Attribute> Clone for RopeEditQueue<Cell, Attribute> {
+    fn clone(&self) -> Self {
+        RopeEditQueue {
+            actions: Arc::clone(&self.actions)
+        }
+    }
+}
+
+impl<Cell, Attribute> RopeEditQueue<Cell, Attribute> {
+    ///
+    /// Creates a new, empty edit queue
+    ///
+    pub fn new() -> RopeEditQueue<Cell, Attribute> {
+        RopeEditQueue {
+            actions: Arc::new(Mutex::new(VecDeque::new()))
+        }
+    }
+
+    ///
+    /// Adds an action to the end of the queue
+    ///
+    pub fn edit(&self, action: RopeAction<Cell, Attribute>) {
+        self.actions.lock().push_back(action);
+    }
+
+    ///
+    /// Queues an action that replaces a range of cells
+    ///
+    pub fn replace<NewCells: IntoIterator<Item=Cell>>(&self, range: Range<usize>, new_cells: NewCells) {
+        self.edit(RopeAction::Replace(range, new_cells.into_iter().collect()));
+    }
+
+    ///
+    /// Queues an action that sets the attributes for a range of cells
+    ///
+    pub fn set_attributes(&self, range: Range<usize>, new_attributes: Attribute) {
+        self.edit(RopeAction::SetAttributes(range, new_attributes));
+    }
+
+    ///
+    /// True if there are no edits waiting in this queue
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.actions.lock().is_empty()
+    }
+
+    ///
+    /// Removes the next action from the front of the queue
+    ///
+    pub fn pop(&self) -> Option<RopeAction<Cell, Attribute>> {
+        self.actions.lock().pop_front()
+    }
+}
@@ -0,0 +1,113 @@
+use std::time::{Instant, Duration};
+
+///
+/// Statistics about the changes passing through a streaming rope
+///
+/// These can be used to detect when the consumer of a rope is falling behind the changes being made to it: for
+/// example, a UI might decide to redraw everything rather than process individual changes when the pull latency
+/// or the number of pending changes becomes too high.
+///
+#[derive(Clone, Debug)]
+pub struct RopeMetrics {
+    /// When these metrics were started (or last reset)
+    started: Instant,
+
+    /// When the oldest change that has not been pulled was made
+    first_pending: Option<Instant>,
+
+    /// The number of edits that have been made to the rope
+    pub num_edits: u64,
+
+    /// The number of cells that have been written or had their attributes changed
+    pub num_cells: u64,
+
+    /// The number of times changes have been pulled from the rope
+    pub num_pulls: u64,
+
+    /// The largest number of separate changes that were waiting to be pulled at any one time
+    pub max_pending_changes: usize,
+
+    /// The time between the first change being made and the changes being pulled for the most recent pull
+    pub last_pull_latency: Option<Duration>,
+
+    /// The longest time between the first change being made and the changes being pulled
+    pub max_pull_latency: Duration
+}
+
+impl RopeMetrics {
+    ///
+    /// Creates a new set of metrics, starting now
+    ///
+    pub fn new() -> RopeMetrics {
+        RopeMetrics {
+            started:                Instant::now(),
+            first_pending:          None,
+            num_edits:              0,
+            num_cells:              0,
+            num_pulls:              0,
+            max_pending_changes:    0,
+            last_pull_latency:      None,
+            max_pull_latency:       Duration::from_secs(0)
+        }
+    }
+
+    ///
+    /// Records an edit that changed the specified number of cells
+    ///
+    pub fn record_edit(&mut self, num_cells: usize) {
+        self.num_edits += 1;
+        self.num_cells += num_cells as u64;
+
+        if self.first_pending.is_none() {
+            self.first_pending = Some(Instant::now());
+        }
+    }
+
+    ///
+    /// Records the number of changes that are currently waiting to be pulled
+    ///
+    pub fn record_pending(&mut self, num_pending: usize) {
+        self.max_pending_changes = self.max_pending_changes.max(num_pending);
+    }
+
+    ///
+    /// Records that the pending changes have been pulled
+    ///
+    pub fn record_pull(&mut self) {
+        self.num_pulls += 1;
+
+        if let Some(first_pending) = self.first_pending.take() {
+            let latency             = first_pending.elapsed();
+
+            self.last_pull_latency  = Some(latency);
+            self.max_pull_latency   = self.max_pull_latency.max(latency);
+        }
+    }
+
+    ///
+    /// The time since these metrics were started
+    ///
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    ///
+    /// The average number of edits per second since these metrics were started
+    ///
+    pub fn edits_per_second(&self) -> f64 {
+        (self.num_edits as f64) / self.elapsed().as_secs_f64().max(f64::EPSILON)
+    }
+
+    ///
+    /// The average number of cells changed per second since these metrics were started
+    ///
+    pub fn cells_per_second(&self) -> f64 {
+        (self.num_cells as f64) / self.elapsed().as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+impl Default for RopeMetrics {
+    fn default() -> RopeMetrics {
+        RopeMetrics::new()
+    }
+}
@@ -0,0 +1,113 @@
+use super::push_rope::*;
+use super::pull_rope::*;
+
+use crate::api::*;
+
+use std::sync::*;
+
+///
+/// Receives notifications about the changes made to a rope
+///
+/// This is an alternative to passing a closure to one of the push or pull ropes: it can be implemented on an ordinary
+/// struct, and observers of different types can be stored together as `Box<dyn RopeObserver<Cell, Attribute>>`. Both
+/// methods do nothing by default, so an observer only needs to implement the one it's interested in.
+///
+/// The methods take `&self` so that an observer can be shared: the same `Arc<dyn RopeObserver<Cell, Attribute>>` can
+/// be registered with several ropes, and kept to read the results. Observers that need to update their state when they
+/// are notified should use a `Mutex` or another type with interior mutability.
+///
+pub trait RopeObserver<Cell, Attribute> {
+    ///
+    /// Called with each action that's applied to a rope (by `PushBeforeRope` or `PushAfterRope`)
+    ///
+    fn action(&self, _action: &RopeAction<Cell, Attribute>) { }
+
+    ///
+    /// Called when a `PullRope` goes from having no changes to having changes waiting to be pulled
+    ///
+    fn changes_available(&self) { }
+}
+
+///
+/// The function used by a `PushBeforeRope` that was created from an observer
+///
+pub type ObserverPushBeforeFn<Cell, Attribute> = Box<dyn FnMut(&RopeAction<Cell, Attribute>) -> ()>;
+
+///
+/// The function used by a `PushAfterRope` that was created from an observer
+///
+pub type ObserverPushAfterFn<Cell, Attribute> = Box<dyn FnMut(RopeAction<Cell, Attribute>) -> ()>;
+
+///
+/// The function used by a `PullRope` that was created from an observer
+///
+pub type ObserverPullFn = Box<dyn FnMut() -> ()>;
+
+impl<Cell, Attribute, Observer> RopeObserver<Cell, Attribute> for Box<Observer>
+where
+Observer: ?Sized+RopeObserver<Cell, Attribute> {
+    fn action(&self, action: &RopeAction<Cell, Attribute>) {
+        (**self).action(action)
+    }
+
+    fn changes_available(&self) {
+        (**self).changes_available()
+    }
+}
+
+impl<Cell, Attribute, Observer> RopeObserver<Cell, Attribute> for Arc<Observer>
+where
+Observer: ?Sized+RopeObserver<Cell, Attribute> {
+    fn action(&self, action: &RopeAction<Cell, Attribute>) {
+        (**self).action(action)
+    }
+
+    fn changes_available(&self) {
+        (**self).changes_available()
+    }
+}
+
+impl<BaseRope> PushBeforeRope<BaseRope, ObserverPushBeforeFn<BaseRope::Cell, BaseRope::Attribute>>
+where
+BaseRope: RopeMut {
+    ///
+    /// Creates a rope that sends each action to an observer before it's applied
+    ///
+    /// The observer can be a `Box<dyn RopeObserver>`, or an `Arc<dyn RopeObserver>` if it's shared with other code.
+    ///
+    pub fn from_observer<Observer>(rope: BaseRope, observer: Observer) -> Self
+    where
+    Observer: 'static+RopeObserver<BaseRope::Cell, BaseRope::Attribute> {
+        PushBeforeRope::from(rope, Box::new(move |action: &RopeAction<_, _>| observer.action(action)))
+    }
+}
+
+impl<BaseRope> PushAfterRope<BaseRope, ObserverPushAfterFn<BaseRope::Cell, BaseRope::Attribute>>
+where
+BaseRope: RopeMut {
+    ///
+    /// Creates a rope that sends each action to an observer after it's applied
+    ///
+    /// The observer can be a `Box<dyn RopeObserver>`, or an `Arc<dyn RopeObserver>` if it's shared with other code.
+    ///
+    pub fn from_observer<Observer>(rope: BaseRope, observer: Observer) -> Self
+    where
+    Observer: 'static+RopeObserver<BaseRope::Cell, BaseRope::Attribute> {
+        PushAfterRope::from(rope, Box::new(move |action: RopeAction<_, _>| observer.action(&action)))
+    }
+}
+
+impl<BaseRope> PullRope<BaseRope, ObserverPullFn>
+where
+BaseRope: RopeMut {
+    ///
+    /// Creates a pull rope that tells an observer when changes are available to be pulled
+    ///
+    /// The observer can be a `Box<dyn RopeObserver>`, or an `Arc<dyn RopeObserver>` if it's shared with other code.
+    ///
+    pub fn from_observer<Observer>(rope: BaseRope, observer: Observer) -> Self
+    where
+    Observer: 'static+RopeObserver<BaseRope::Cell, BaseRope::Attribute> {
+        PullRope::from(rope, Box::new(move || observer.changes_available()))
+    }
+}
@@ -0,0 +1,78 @@
+use crate::api::*;
+
+use std::marker::{PhantomData};
+
+///
+/// An action generated by a `RopeSplitter`, for either the left-hand or the right-hand side of the split rope
+///
+#[derive(Clone, PartialEq, Debug)]
+pub enum SplitRopeAction<Cell, Attribute> {
+    /// An action to apply to the left-hand side of the rope
+    Left(RopeAction<Cell, Attribute>),
+
+    /// An action to apply to the right-hand side of the rope
+    Right(RopeAction<Cell, Attribute>)
+}
+
+///
+/// Given a stream of actions for a single rope, generates the actions for two ropes that contain the left and right-hand
+/// sides of it (this is the inverse of `RopeConcatenator`)
+///
+/// The split point is tracked as the actions are processed, so the left-hand side grows and shrinks as cells are added
+/// to or removed from it. Cells inserted at the split point are added to the start of the right-hand side. An action
+/// that replaces cells on both sides of the split point generates an action for each side, and any new cells are added
+/// to the end of the left-hand side.
+///
+pub struct RopeSplitter<Cell, Attribute> {
+    cell: PhantomData<Cell>,
+    attribute: PhantomData<Attribute>,
+
+    /// The length of the left-hand side of the split rope
+    left_len: usize
+}
+
+impl<Cell, Attribute> RopeSplitter<Cell, Attribute>
+where
+Attribute: Clone {
+    ///
+    /// Creates a new splitting rope, where the left-hand side initially contains `left_len` cells
+    ///
+    pub fn new(left_len: usize) -> RopeSplitter<Cell, Attribute> {
+        RopeSplitter {
+            cell:       PhantomData,
+            attribute:  PhantomData,
+            left_len:   left_len
+        }
+    }
+
+    ///
+    /// The number of cells currently in the left-hand side of the rope
+    ///
+    pub fn left_len(&self) -> usize {
+        self.left_len
+    }
+
+    ///
+    /// Processes actions intended for the combined rope, returning an iterator of the actions for each side
+    ///
+    pub fn send<'a, ActionIter: 'a+IntoIterator<Item=RopeAction<Cell, Attribute>>>(&'a mut self, items: ActionIter) -> impl 'a+Iterator<Item=SplitRopeAction<Cell, Attribute>> {
+        items.into_iter()
+            .flat_map(move |item| {
+                let (left, right) = item.split_at(self.left_len);
+
+                // The right-hand side is relative to the original split point
+                let right = right.map(|right| {
+                    let range = right.range();
+                    right.with_range((range.start-self.left_len)..(range.end-self.left_len))
+                });
+
+                // Update the length of the left-hand side of the rope
+                if let Some(left) = &left {
+                    self.left_len = self.left_len + left.new_len() - left.range().len();
+                }
+
+                left.map(SplitRopeAction::Left).into_iter()
+                    .chain(right.map(SplitRopeAction::Right))
+            })
+    }
+}
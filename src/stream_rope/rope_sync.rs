@@ -0,0 +1,300 @@
+use super::remote_rope::*;
+
+use crate::api::*;
+
+use std::mem;
+use std::ops::{Range};
+
+///
+/// A message sent from a `RopeSyncSource` to the ropes that are following it
+///
+/// The version of a rope is the number of actions that have been applied to it. A snapshot contains the whole rope
+/// at a particular version, and each delta contains the action that changes the rope from the version in its sequence
+/// number to the next one.
+///
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RopeSyncMessage<Cell, Attribute> {
+    /// The contents of the rope at a version, as a list of spans of cells and their attributes
+    Snapshot(u64, Vec<(Vec<Cell>, Attribute)>),
+
+    /// An action that was applied to the rope
+    Delta(RopeActionEnvelope<Cell, Attribute>)
+}
+
+///
+/// The state of a `RopeFollower` after it has received a message
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RopeSyncStatus {
+    /// The follower is up to date with the messages it has received
+    InSync,
+
+    /// The follower has missed some changes and needs a snapshot before it can apply any more deltas
+    NeedsSnapshot
+}
+
+///
+/// A rope that generates the messages needed to keep followers in sync with it
+///
+/// Every edit generates a delta message, which can be retrieved with `pull_messages()`. Followers that join part-way
+/// through or miss some messages can be brought back up to date with the message returned by `snapshot()`.
+///
+#[derive(Clone)]
+pub struct RopeSyncSource<BaseRope>
+where
+BaseRope: RopeMut {
+    /// The rope that the actions are applied to
+    rope: BaseRope,
+
+    /// The number of actions that have been applied to the rope
+    version: u64,
+
+    /// The delta messages that have not been pulled yet
+    messages: Vec<RopeSyncMessage<BaseRope::Cell, BaseRope::Attribute>>
+}
+
+///
+/// A rope that mirrors a `RopeSyncSource` by applying the messages that it generates
+///
+/// A follower can start receiving deltas at any point: it will ask for a snapshot if it doesn't have one yet, or if
+/// it finds that some deltas are missing. Deltas that were already included in the last snapshot are ignored.
+///
+#[derive(Clone)]
+pub struct RopeFollower<BaseRope>
+where
+BaseRope: RopeMut {
+    /// The rope containing the mirrored content
+    rope: BaseRope,
+
+    /// The version of the source rope that this follower matches (None if it needs a snapshot)
+    version: Option<u64>
+}
+
+impl<BaseRope> RopeSyncSource<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Creates a sync source for a rope. The rope starts at version 0.
+    ///
+    pub fn from(rope: BaseRope) -> RopeSyncSource<BaseRope> {
+        RopeSyncSource {
+            rope:       rope,
+            version:    0,
+            messages:   vec![]
+        }
+    }
+
+    ///
+    /// The number of actions that have been applied to this rope
+    ///
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    ///
+    /// Removes and returns the delta messages that have been generated since this was last called
+    ///
+    pub fn pull_messages(&mut self) -> Vec<RopeSyncMessage<BaseRope::Cell, BaseRope::Attribute>> {
+        mem::take(&mut self.messages)
+    }
+
+    ///
+    /// Creates a snapshot message for the current version of the rope
+    ///
+    pub fn snapshot(&self) -> RopeSyncMessage<BaseRope::Cell, BaseRope::Attribute> {
+        let spans = self.rope.read_attribute_runs(0..self.rope.len())
+            .map(|(attribute, run)| (self.rope.read_cells(run).cloned().collect(), attribute.clone()))
+            .collect();
+
+        RopeSyncMessage::Snapshot(self.version, spans)
+    }
+
+    ///
+    /// Returns the rope that the actions are applied to
+    ///
+    pub fn into_inner(self) -> BaseRope {
+        self.rope
+    }
+}
+
+impl<BaseRope> RopeFollower<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Creates a follower that mirrors a source into a rope. The follower needs a snapshot before it can apply any deltas.
+    ///
+    pub fn from(rope: BaseRope) -> RopeFollower<BaseRope> {
+        RopeFollower {
+            rope:       rope,
+            version:    None
+        }
+    }
+
+    ///
+    /// The version of the source that this follower matches, or None if it is waiting for a snapshot
+    ///
+    pub fn version(&self) -> Option<u64> {
+        self.version
+    }
+
+    ///
+    /// True if this follower needs a snapshot before it can apply any more deltas
+    ///
+    pub fn needs_snapshot(&self) -> bool {
+        self.version.is_none()
+    }
+
+    ///
+    /// Applies a message from the source to this follower
+    ///
+    /// Snapshots replace the whole content of the rope. Deltas are applied if they follow on from the current version,
+    /// ignored if they have already been applied, and otherwise cause the follower to wait for a new snapshot.
+    ///
+    pub fn receive(&mut self, message: RopeSyncMessage<BaseRope::Cell, BaseRope::Attribute>) -> RopeSyncStatus {
+        match message {
+            RopeSyncMessage::Snapshot(version, spans) => {
+                let len = self.rope.len();
+                self.rope.replace(0..len, vec![]);
+
+                for (cells, attribute) in spans {
+                    let pos = self.rope.len();
+                    self.rope.replace_attributes(pos..pos, cells, attribute);
+                }
+
+                self.version = Some(version);
+            }
+
+            RopeSyncMessage::Delta(envelope) => {
+                match self.version {
+                    Some(version) if envelope.sequence == version   => { self.rope.edit(envelope.action); self.version = Some(version + 1); }
+                    Some(version) if envelope.sequence < version    => { }
+                    _                                               => { self.version = None; }
+                }
+            }
+        }
+
+        if self.version.is_some() { RopeSyncStatus::InSync } else { RopeSyncStatus::NeedsSnapshot }
+    }
+
+    ///
+    /// Returns the rope containing the mirrored content
+    ///
+    pub fn into_inner(self) -> BaseRope {
+        self.rope
+    }
+}
+
+impl<BaseRope> Rope for RopeSyncSource<BaseRope>
+where
+BaseRope: RopeMut {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.rope.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        self.rope.read_cells(range)
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.read_chunks(range)
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.rope.read_attributes(pos)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.read_attribute_runs(range)
+    }
+}
+
+impl<BaseRope> RopeMut for RopeSyncSource<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Performs the specified editing action to this rope, generating a delta message for the followers
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        self.rope.edit(action.clone());
+
+        self.messages.push(RopeSyncMessage::Delta(RopeActionEnvelope { sequence: self.version, action }));
+        self.version += 1;
+    }
+}
+
+impl<BaseRope> Rope for RopeFollower<BaseRope>
+where
+BaseRope: RopeMut {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.rope.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        self.rope.read_cells(range)
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.read_chunks(range)
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.rope.read_attributes(pos)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.read_attribute_runs(range)
+    }
+}
@@ -0,0 +1,167 @@
+use crate::api::*;
+
+use std::ops::{Range};
+
+///
+/// A slice rope is a view of a section of a base rope, which can be read and edited as if it were a rope in its own right
+///
+/// Positions in the slice rope are relative to the start of the section, and edits are applied to the corresponding
+/// part of the base rope. The section grows and shrinks as cells are added to or removed from it. Edits made to the
+/// rest of the base rope by calling `edit_base()` move the section so that it continues to cover the same cells.
+///
+#[derive(Clone)]
+pub struct SliceRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// The rope that this is a slice of
+    rope: BaseRope,
+
+    /// The range of the base rope covered by this slice
+    range: Range<usize>
+}
+
+impl<BaseRope> SliceRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Creates a slice rope covering a range of a base rope (the range is limited to the length of the base rope)
+    ///
+    pub fn from(rope: BaseRope, range: Range<usize>) -> SliceRope<BaseRope> {
+        let len     = rope.len();
+        let range   = range.start.min(len)..range.end.min(len).max(range.start.min(len));
+
+        SliceRope {
+            rope:   rope,
+            range:  range
+        }
+    }
+
+    ///
+    /// The range of the base rope that's covered by this slice
+    ///
+    pub fn slice_range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    ///
+    /// Returns the rope that this is a slice of
+    ///
+    pub fn into_inner(self) -> BaseRope {
+        self.rope
+    }
+
+    ///
+    /// Borrows the rope that this is a slice of
+    ///
+    pub fn base(&self) -> &BaseRope {
+        &self.rope
+    }
+
+    ///
+    /// Applies an action to the base rope, moving the slice so that it still covers the same cells
+    ///
+    /// Cells inserted just before or just after the slice are not added to it. If the action replaces cells on both sides
+    /// of the start or the end of the slice, the replacement cells become part of the slice.
+    ///
+    pub fn edit_base(&mut self, action: RopeAction<BaseRope::Cell, BaseRope::Attribute>) {
+        let replaced    = action.range();
+        let new_len     = action.new_len();
+        let start       = self.range.start;
+        let end         = self.range.end;
+
+        // Cells replaced before the slice move it, and if the replaced range overlaps the start of the slice, the slice starts where the replacement starts
+        let new_start   = if replaced.end <= start { start + new_len - replaced.len() }
+            else if replaced.start >= start { start }
+            else { replaced.start };
+
+        // The end of the slice moves if any cells before it are replaced, and overlapping the end extends the slice to the end of the replacement
+        let new_end     = if replaced.start >= end { end }
+            else if replaced.end <= end { end + new_len - replaced.len() }
+            else { replaced.start + new_len };
+
+        self.rope.edit(action);
+        self.range = new_start..new_end.max(new_start);
+    }
+
+    ///
+    /// Converts a range in this slice into a range in the base rope
+    ///
+    #[inline]
+    fn base_range(&self, range: Range<usize>) -> Range<usize> {
+        let len     = self.range.len();
+        let start   = range.start.min(len);
+        let end     = range.end.min(len).max(start);
+
+        (self.range.start + start)..(self.range.start + end)
+    }
+}
+
+impl<BaseRope> Rope for SliceRope<BaseRope>
+where
+BaseRope: RopeMut {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this slice
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this slice
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        self.rope.read_cells(self.base_range(range))
+    }
+
+    ///
+    /// Reads the cells for a range in this slice as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.read_chunks(self.base_range(range))
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent (limited to the slice)
+    ///
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        let base_pos                = self.range.start + pos.min(self.range.len());
+        let (attribute, extent)     = self.rope.read_attributes(base_pos);
+        let start                   = extent.start.max(self.range.start).min(self.range.end);
+        let end                     = extent.end.min(self.range.end).max(start);
+
+        (attribute, (start - self.range.start)..(end - self.range.start))
+    }
+}
+
+impl<BaseRope> RopeMut for SliceRope<BaseRope>
+where
+BaseRope: RopeMut {
+    ///
+    /// Performs the specified editing action to this slice, applying it to the base rope
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        let range       = self.base_range(action.range());
+        let action      = action.with_range(range);
+
+        // The slice grows or shrinks by the change in length
+        let old_len     = action.range().len();
+        let new_len     = action.new_len();
+
+        self.rope.edit(action);
+        self.range.end = self.range.end + new_len - old_len;
+    }
+}
+
+impl<BaseRope> RopeSearch for SliceRope<BaseRope>
+where
+BaseRope:       RopeMut,
+BaseRope::Cell: PartialEq { }
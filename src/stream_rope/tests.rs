@@ -2,6 +2,8 @@ use crate::*;
 
 use std::rc::*;
 use std::cell::*;
+use std::sync::*;
+use std::time::{Duration};
 
 #[test]
 fn push_before_remove_middle() {
@@ -33,6 +35,53 @@ fn push_before_after_middle() {
     assert!(rope.len() == 2);
 }
 
+#[test]
+fn push_after_ref() {
+    let pushed      = Rc::new(RefCell::new(vec![]));
+    let push_to     = Rc::clone(&pushed);
+
+    let rope        = AttributedRope::<_, i64>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    let mut rope    = PushAfterRefRope::from(rope, move |action: RopeActionRef<_, _>| { push_to.borrow_mut().push(action.to_action()); });
+
+    rope.replace(1..7, vec![9]);
+    rope.set_attributes(0..1, 2);
+
+    assert!(*pushed.borrow() == vec![RopeAction::Replace(1..7, vec![9]), RopeAction::SetAttributes(0..1, 2)]);
+    assert!(rope.read_cells(0..3).cloned().collect::<Vec<_>>() == vec![1, 9, 8]);
+}
+
+#[test]
+fn push_after_ref_lends_cells_from_base_rope() {
+    let pushed      = Rc::new(RefCell::new(vec![]));
+    let push_to     = Rc::clone(&pushed);
+
+    let mut rope    = AttributedRope::<_, i64>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    rope.set_attributes(2..4, 1);
+    rope.set_attributes(5..6, 2);
+
+    let mut rope    = PushAfterRefRope::from(rope, move |action: RopeActionRef<_, _>| { push_to.borrow_mut().push(action.to_action()); });
+
+    rope.replace(3..6, vec![9, 10, 11, 12]);
+    rope.replace_attributes(0..2, vec![13, 14, 15], 3);
+    rope.replace(4..5, vec![]);
+
+    assert!(*pushed.borrow() == vec![
+        RopeAction::Replace(3..6, vec![9, 10, 11, 12]),
+        RopeAction::ReplaceAttributes(0..2, vec![13, 14, 15], 3),
+        RopeAction::Replace(4..5, vec![])
+    ]);
+    assert!(rope.read_cells(0..rope.len()).cloned().collect::<Vec<_>>() == vec![13, 14, 15, 3, 10, 11, 12, 7, 8]);
+}
+
+#[test]
+fn action_ref_round_trip() {
+    let action      = RopeAction::<u8, i64>::ReplaceAttributes(1..2, vec![1, 2, 3], 4);
+    let action_ref  = RopeActionRef::from(&action);
+
+    assert!(action_ref == RopeActionRef::ReplaceAttributes(1..2, &[1, 2, 3], &4));
+    assert!(RopeAction::from(action_ref) == action);
+}
+
 #[test]
 fn pull_basic_change() {
     let mut rope = PullRope::from(AttributedRope::<u8, ()>::new(), || {});
@@ -65,6 +114,21 @@ fn pull_overlapping_changes() {
     assert!(pulled == vec![RopeAction::Replace(0..0, vec![1, 1, 2, 3, 3])]);
 }
 
+#[test]
+fn pull_attribute_change_before_existing_change() {
+    let mut rope    = PullRope::from(AttributedRope::<u8, i64>::from_str("Hello, world"), || {});
+    let mut mirror  = AttributedRope::<u8, i64>::from_str("Hello, world");
+
+    // The attribute change fits in the gap before the first change
+    rope.replace(10..12, "LD".bytes());
+    rope.set_attributes(2..4, 1);
+
+    rope.pull_changes().for_each(|action| mirror.edit(action));
+
+    assert!(mirror.read_cells(0..12).cloned().collect::<Vec<_>>() == "Hello, worLD".bytes().collect::<Vec<_>>());
+    assert!(mirror.read_attributes(2) == (&1, 2..4));
+}
+
 #[test]
 fn notify_attribute_changes() {
     let mut rope = PullRope::from(AttributedRope::<u8, ()>::new(), || {});
@@ -76,6 +140,34 @@ fn notify_attribute_changes() {
     assert!(pulled == vec![RopeAction::ReplaceAttributes(0..0, vec![1, 1, 2, 3, 3], ())]);
 }
 
+#[test]
+fn pull_changes_for_each_matches_pull_changes() {
+    let edit = |rope: &mut PullRope<AttributedRope<u8, i64>, fn() -> ()>| {
+        rope.replace(0..0, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        rope.pull_changes().for_each(|_| {});
+
+        rope.replace(6..7, vec![9, 9]);
+        rope.replace_attributes(1..3, vec![10, 11, 12], 1);
+        rope.set_attributes(2..3, 2);
+    };
+
+    let mut rope        = PullRope::from(AttributedRope::<u8, i64>::new(), (|| {}) as fn() -> ());
+    let mut streamed    = PullRope::from(AttributedRope::<u8, i64>::new(), (|| {}) as fn() -> ());
+    edit(&mut rope);
+    edit(&mut streamed);
+
+    let pulled          = rope.pull_changes().collect::<Vec<_>>();
+    let mut for_each    = vec![];
+    streamed.pull_changes_for_each(|action| for_each.push(action.to_action()));
+
+    assert!(pulled.len() > 1);
+    assert!(for_each == pulled);
+
+    for_each.clear();
+    streamed.pull_changes_for_each(|action| for_each.push(action.to_action()));
+    assert!(for_each.is_empty());
+}
+
 #[test]
 fn concat_str() {
     // Two pull ropes to represent the left and right-hand side of the stream
@@ -124,3 +216,1091 @@ fn concat_str() {
 
     assert!(concatenated.to_string_lossy() == "Good day, Alfred");
 }
+
+#[test]
+fn ring_rope_evicts_from_front() {
+    let pushed      = Rc::new(RefCell::new(vec![]));
+    let push_to     = Rc::clone(&pushed);
+
+    let rope        = AttributedRope::<_, ()>::from(vec![1, 2, 3, 4]);
+    let rope        = PushAfterRope::from(rope, move |action| { push_to.borrow_mut().push(action); });
+    let mut rope    = RingRope::from(rope, 6);
+
+    rope.replace(4..4, vec![5, 6, 7, 8]);
+
+    assert!(rope.len() == 6);
+    assert!(rope.read_cells(0..6).cloned().collect::<Vec<_>>() == vec![3, 4, 5, 6, 7, 8]);
+    assert!(*pushed.borrow() == vec![RopeAction::Replace(4..4, vec![5, 6, 7, 8]), RopeAction::Replace(0..2, vec![])]);
+
+    rope.set_max_length(3);
+    assert!(rope.read_cells(0..6).cloned().collect::<Vec<_>>() == vec![6, 7, 8]);
+}
+
+#[test]
+fn pull_delete_end_of_insertion() {
+    let mut rope = PullRope::from(AttributedRope::<_, i64>::new(), || { });
+
+    rope.replace_attributes(0..0, vec![1, 2], 1);
+    rope.replace(1..2, vec![]);
+    rope.replace(1..1, vec![3, 4]);
+
+    let mut mirror = AttributedRope::<_, i64>::new();
+    rope.pull_changes().for_each(|change| mirror.edit(change));
+
+    assert!(mirror.read_cells(0..3).cloned().collect::<Vec<_>>() == vec![1, 3, 4]);
+}
+
+#[test]
+fn pull_grouped_changes() {
+    let mut rope = PullRope::from(AttributedRope::<_, i64>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]), || { });
+
+    rope.replace_attributes(1..3, vec![9, 9, 9], 1);
+    rope.replace_attributes(3..3, vec![10], 2);
+    rope.replace(7..8, vec![]);
+
+    let changes = rope.pull_grouped_changes().collect::<Vec<_>>();
+
+    assert!(changes == vec![
+        PulledChange { range: 5..6, spans: vec![], changed_attributes: false },
+        PulledChange { range: 1..3, spans: vec![(vec![9, 9], 1), (vec![10], 2), (vec![9], 1)], changed_attributes: true },
+    ]);
+    assert!(changes[1].new_len() == 4);
+    assert!(rope.pull_changes().next().is_none());
+}
+
+#[test]
+fn undo_redo() {
+    let mut rope = HistoryRope::from(AttributedRope::<_, i64>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]));
+
+    rope.set_attributes(2..4, 1);
+    rope.replace(1..5, vec![9]);
+
+    assert!(rope.read_cells(0..8).cloned().collect::<Vec<_>>() == vec![1, 9, 6, 7, 8]);
+
+    assert!(rope.undo());
+    assert!(rope.read_cells(0..8).cloned().collect::<Vec<_>>() == vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    assert!(rope.read_attribute_runs(0..8).collect::<Vec<_>>() == vec![(&0, 0..2), (&1, 2..4), (&0, 4..8)]);
+
+    assert!(rope.undo());
+    assert!(rope.read_attribute_runs(0..8).collect::<Vec<_>>() == vec![(&0, 0..8)]);
+    assert!(!rope.undo());
+
+    assert!(rope.redo());
+    assert!(rope.redo());
+    assert!(!rope.redo());
+    assert!(rope.read_cells(0..8).cloned().collect::<Vec<_>>() == vec![1, 9, 6, 7, 8]);
+}
+
+#[test]
+fn undo_group() {
+    let mut rope = HistoryRope::from(AttributedRope::<_, i64>::from(vec![1, 2, 3, 4]));
+
+    rope.begin_group();
+    rope.replace(0..0, vec![5]);
+    rope.begin_group();
+    rope.replace_attributes(5..5, vec![6], 2);
+    rope.end_group();
+    rope.replace(1..2, vec![]);
+    rope.end_group();
+
+    assert!(rope.read_cells(0..8).cloned().collect::<Vec<_>>() == vec![5, 2, 3, 4, 6]);
+
+    assert!(rope.undo());
+    assert!(!rope.can_undo());
+    assert!(rope.read_cells(0..8).cloned().collect::<Vec<_>>() == vec![1, 2, 3, 4]);
+
+    // A new edit discards the redo history
+    rope.replace(0..1, vec![]);
+    assert!(!rope.can_redo());
+}
+
+#[test]
+fn history_save_point() {
+    let mut rope = HistoryRope::from(AttributedRope::<_, i64>::from(vec![1, 2, 3, 4]));
+    assert!(!rope.is_modified());
+
+    rope.replace(0..1, vec![5]);
+    assert!(rope.is_modified());
+
+    rope.mark_saved();
+    assert!(!rope.is_modified());
+
+    // Undoing and redoing back to the saved point marks the rope as unmodified again
+    rope.set_attributes(1..3, 1);
+    assert!(rope.is_modified());
+    assert!(rope.undo());
+    assert!(!rope.is_modified());
+    assert!(rope.undo());
+    assert!(rope.is_modified());
+    assert!(rope.redo());
+    assert!(!rope.is_modified());
+
+    // Edits in a group that has not been finished yet count as modifications
+    rope.begin_group();
+    rope.replace(4..4, vec![6]);
+    assert!(rope.is_modified());
+    rope.end_group();
+    assert!(rope.undo());
+    assert!(!rope.is_modified());
+
+    // A different edit at the same depth as the saved point is still a modification
+    assert!(rope.undo());
+    rope.replace(0..1, vec![7]);
+    assert!(rope.is_modified());
+
+    // Clearing the history keeps the saved state only if the rope was unmodified
+    rope.clear_history();
+    assert!(rope.is_modified());
+    rope.mark_saved();
+    rope.clear_history();
+    assert!(!rope.is_modified());
+}
+
+#[test]
+fn undo_random_edits() {
+    for seed in 0..10 {
+        let mut stress  = RopeStressTest::new(seed);
+        let mut rope    = HistoryRope::from(AttributedRope::<u8, u8>::new());
+
+        rope.replace_attributes(0..0, b"initial text".iter().cloned(), 1);
+        rope.clear_history();
+
+        let original    = RopeOracle::from_rope(&rope);
+
+        for _ in 0..100 {
+            let action = stress.random_action(rope.len());
+            rope.edit(action);
+        }
+
+        let edited = RopeOracle::from_rope(&rope);
+
+        while rope.undo() { }
+        assert!(RopeOracle::from_rope(&rope) == original);
+
+        while rope.redo() { }
+        assert!(RopeOracle::from_rope(&rope) == edited);
+    }
+}
+
+///
+/// Content provider that reads from a local rope and counts the fetch requests
+///
+struct TestProvider {
+    rope:       AttributedRope<u8, i64>,
+    fetches:    Vec<std::ops::Range<usize>>,
+    actions:    Vec<RopeActionEnvelope<u8, i64>>
+}
+
+impl RopeContentProvider for TestProvider {
+    type Cell       = u8;
+    type Attribute  = i64;
+
+    fn len(&self) -> usize { self.rope.len() }
+
+    fn fetch(&mut self, range: std::ops::Range<usize>) -> Vec<(Vec<u8>, i64)> {
+        self.fetches.push(range.clone());
+
+        self.rope.read_attribute_runs(range)
+            .map(|(attr, run)| (self.rope.read_cells(run).cloned().collect(), *attr))
+            .collect()
+    }
+
+    fn send_action(&mut self, action: RopeActionEnvelope<u8, i64>) {
+        self.rope.edit(action.action.clone());
+        self.actions.push(action);
+    }
+}
+
+fn rope_string<TRope: Rope<Cell=u8>>(rope: &TRope) -> String {
+    String::from_utf8_lossy(&rope.read_cells(0..rope.len()).cloned().collect::<Vec<_>>()).into()
+}
+
+#[test]
+fn remote_rope_loads_on_demand() {
+    let mut remote_content = AttributedRope::from_str("Hello, world");
+    remote_content.set_attributes(0..5, 1);
+
+    let provider    = TestProvider { rope: remote_content, fetches: vec![], actions: vec![] };
+    let mut rope    = RemoteRope::new(provider, b'?');
+
+    assert!(rope.len() == 12);
+    assert!(!rope.is_loaded(0..5));
+    assert!(rope_string(&rope) == "????????????");
+
+    rope.ensure_loaded(3..7);
+    rope.ensure_loaded(0..5);
+
+    assert!(rope.is_loaded(0..7));
+    assert!(!rope.is_loaded(0..8));
+    assert!(rope.provider().fetches == vec![3..7, 0..3]);
+    assert!(rope_string(&rope) == "Hello, ?????");
+    assert!(rope.read_attributes(1) == (&1, 0..5));
+}
+
+#[test]
+fn remote_rope_forwards_edits() {
+    let provider    = TestProvider { rope: AttributedRope::from_str("Hello, world"), fetches: vec![], actions: vec![] };
+    let mut rope    = RemoteRope::new(provider, b'?');
+
+    rope.replace(5..7, b"!!".iter().cloned());
+    rope.replace(0..0, b">".iter().cloned());
+
+    assert!(rope.is_loaded(6..8) && rope.is_loaded(0..1));
+    assert!(rope_string(&rope) == ">?????!!?????");
+    assert!(rope.provider().actions.iter().map(|envelope| envelope.sequence).collect::<Vec<_>>() == vec![0, 1]);
+    assert!(rope.provider().rope.to_string_lossy() == ">Hello!!world");
+
+    rope.ensure_loaded(0..rope.len());
+    assert!(rope_string(&rope) == ">Hello!!world");
+}
+
+#[test]
+fn remote_rope_only_stores_loaded_cells() {
+    let provider    = TestProvider { rope: AttributedRope::from(vec![b'x'; 1_000_000]), fetches: vec![], actions: vec![] };
+    let mut rope    = RemoteRope::new(provider, b'?');
+
+    rope.ensure_loaded(500_000..500_004);
+    assert!(rope.len() == 1_000_000);
+    assert!(rope.read_cells(499_998..500_006).cloned().collect::<Vec<_>>() == b"??xxxx??".to_vec());
+
+    // Setting attributes does not load any cells
+    rope.set_attributes(499_000..501_000, 2);
+    assert!(!rope.is_loaded(499_999..500_000));
+    assert!(rope.is_loaded(500_000..500_004));
+    assert!(rope.read_attributes(500_001) == (&2, 500_000..500_004));
+    assert!(rope.read_attributes(499_999).0 == &0);
+
+    // Invalidated cells go back to being read as the placeholder
+    rope.invalidate(500_001..500_003);
+    assert!(rope.read_cells(500_000..500_004).cloned().collect::<Vec<_>>() == b"x??x".to_vec());
+
+    rope.ensure_loaded(500_000..500_004);
+    assert!(rope.provider().fetches == vec![500_000..500_004, 500_001..500_003]);
+    assert!(rope.provider().rope.read_attributes(500_001).0 == &2);
+}
+
+#[test]
+fn rope_follower_joins_mid_stream() {
+    let mut source      = RopeSyncSource::from(AttributedRope::<u8, i64>::from_str("Hello"));
+    let mut follower    = RopeFollower::from(AttributedRope::<u8, i64>::new());
+
+    source.replace(5..5, b", world".iter().cloned());
+    source.set_attributes(0..5, 1);
+
+    // The follower has missed the start of the stream, so it can't apply the deltas
+    for message in source.pull_messages() {
+        assert!(follower.receive(message) == RopeSyncStatus::NeedsSnapshot);
+    }
+
+    // Deltas generated before the snapshot was taken are ignored after it has been applied
+    source.replace(0..1, b"J".iter().cloned());
+    let snapshot = source.snapshot();
+    source.replace(12..12, b"!".iter().cloned());
+
+    assert!(follower.receive(snapshot) == RopeSyncStatus::InSync);
+    for message in source.pull_messages() {
+        assert!(follower.receive(message) == RopeSyncStatus::InSync);
+    }
+
+    assert!(follower.version() == Some(source.version()));
+    assert!(rope_string(&follower) == "Jello, world!");
+    assert!(follower.read_attribute_runs(0..13).collect::<Vec<_>>() == vec![(&1, 0..5), (&0, 5..13)]);
+}
+
+#[test]
+fn rope_follower_requests_snapshot_after_gap() {
+    let mut source      = RopeSyncSource::from(AttributedRope::<u8, i64>::from_str("abc"));
+    let mut follower    = RopeFollower::from(AttributedRope::<u8, i64>::new());
+
+    follower.receive(source.snapshot());
+
+    source.replace(0..0, b"1".iter().cloned());
+    source.replace(0..0, b"2".iter().cloned());
+    let mut messages = source.pull_messages();
+
+    // Missing a delta means the follower needs a new snapshot
+    assert!(follower.receive(messages.pop().unwrap()) == RopeSyncStatus::NeedsSnapshot);
+    assert!(follower.needs_snapshot());
+    assert!(rope_string(&follower) == "abc");
+
+    assert!(follower.receive(source.snapshot()) == RopeSyncStatus::InSync);
+    assert!(rope_string(&follower) == "21abc");
+
+    source.replace(5..5, b"d".iter().cloned());
+    source.pull_messages().into_iter().for_each(|message| { follower.receive(message); });
+    assert!(rope_string(&follower) == "21abcd");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn rope_sync_messages_serialize() {
+    let mut source      = RopeSyncSource::from(AttributedRope::<u8, i64>::from_str("abc"));
+    let mut follower    = RopeFollower::from(AttributedRope::<u8, i64>::new());
+
+    source.set_attributes(1..2, 3);
+    let snapshot = serde_json::to_string(&source.snapshot()).unwrap();
+    source.replace(3..3, b"d".iter().cloned());
+    let deltas = serde_json::to_string(&source.pull_messages()).unwrap();
+
+    follower.receive(serde_json::from_str(&snapshot).unwrap());
+    for message in serde_json::from_str::<Vec<RopeSyncMessage<u8, i64>>>(&deltas).unwrap() {
+        follower.receive(message);
+    }
+
+    assert!(rope_string(&follower) == "abcd");
+    assert!(follower.read_attributes(1) == (&3, 1..2));
+}
+
+#[test]
+fn pull_transaction_notifies_once() {
+    let notifications   = Rc::new(RefCell::new(0));
+    let notify          = Rc::clone(&notifications);
+    let mut rope        = PullRope::from(AttributedRope::<_, ()>::from(vec![1, 2, 3]), move || { *notify.borrow_mut() += 1; });
+
+    rope.transaction(|rope| {
+        rope.replace(0..0, vec![4]);
+
+        // Pulling during the transaction would usually cause a second notification
+        rope.pull_changes().for_each(|_| { });
+        rope.replace(4..4, vec![5]);
+    });
+
+    assert!(*notifications.borrow() == 1);
+    assert!(rope.read_cells(0..5).cloned().collect::<Vec<_>>() == vec![4, 1, 2, 3, 5]);
+}
+
+#[test]
+fn push_after_transaction() {
+    let pushed      = Rc::new(RefCell::new(vec![]));
+    let push_to     = Rc::clone(&pushed);
+    let mut rope    = PushAfterRope::from(AttributedRope::<_, ()>::from(vec![1, 2, 3]), move |action| { push_to.borrow_mut().push(action); });
+
+    rope.transaction(|rope| {
+        rope.replace(3..3, vec![4]);
+        rope.replace(4..4, vec![5]);
+        assert!(rope.len() == 5);
+    });
+
+    assert!(*pushed.borrow() == vec![RopeAction::Replace(3..3, vec![4, 5])]);
+}
+
+#[test]
+fn push_after_transaction_mirrors_attributes() {
+    let mut initial = AttributedRope::<u8, i64>::from("abcdefgh".bytes().collect::<Vec<_>>());
+    initial.set_attributes(4..8, 2);
+
+    let mirror      = Rc::new(RefCell::new(initial.clone()));
+    let push_to     = Rc::clone(&mirror);
+    let mut rope    = PushAfterRope::from(initial, move |action| { push_to.borrow_mut().edit(action); });
+
+    // Deletions followed by insertions at the same point, at the start of the rope and in the middle
+    rope.transaction(|rope| {
+        rope.replace(0..4, vec![]);
+        rope.replace(0..1, "x".bytes());
+        rope.replace(1..2, vec![]);
+        rope.replace(1..1, "yz".bytes());
+    });
+
+    let len = rope.len();
+    assert!(mirror.borrow().read_cells(0..len).cloned().collect::<Vec<_>>() == "xyzgh".bytes().collect::<Vec<_>>());
+    assert!(mirror.borrow().read_attribute_runs(0..len).collect::<Vec<_>>() == rope.read_attribute_runs(0..len).collect::<Vec<_>>());
+    assert!(rope.read_attribute_runs(0..len).collect::<Vec<_>>() == vec![(&2, 0..5)]);
+}
+
+#[test]
+fn undo_transaction() {
+    let mut rope = HistoryRope::from(AttributedRope::<_, ()>::from(vec![1, 2, 3]));
+
+    rope.transaction(|rope| {
+        rope.replace(3..3, vec![4]);
+        rope.replace(0..1, vec![]);
+    });
+
+    rope.undo();
+    assert!(rope.read_cells(0..5).cloned().collect::<Vec<_>>() == vec![1, 2, 3]);
+}
+
+#[test]
+fn pull_rope_metrics() {
+    let mut rope = PullRope::from(AttributedRope::<_, ()>::from(vec![1, 2, 3, 4, 5, 6, 7, 8]), || { });
+    assert!(rope.metrics().is_none());
+
+    rope.enable_metrics();
+
+    rope.replace(0..1, vec![9, 9]);
+    rope.replace(6..7, vec![]);
+    rope.pull_changes().for_each(|_| { });
+    rope.replace(2..3, vec![]);
+
+    let metrics = rope.metrics().unwrap();
+    assert!(metrics.num_edits == 3);
+    assert!(metrics.num_cells == 2);
+    assert!(metrics.num_pulls == 1);
+    assert!(metrics.max_pending_changes == 2);
+    assert!(metrics.last_pull_latency.is_some());
+}
+
+#[test]
+fn push_after_split_into_lines() {
+    let pushed      = Rc::new(RefCell::new(vec![]));
+    let push_to     = Rc::clone(&pushed);
+
+    let rope        = AttributedRope::<u8, ()>::new();
+    let mut rope    = PushAfterRope::from(rope, move |action| { push_to.borrow_mut().push(action); });
+    rope.set_granularity(ActionGranularity::SplitAfter(|c| *c == b'\n'));
+
+    rope.replace(0..0, b"a\nb\n".iter().cloned());
+
+    assert!(*pushed.borrow() == vec![RopeAction::Replace(0..0, b"a\n".to_vec()), RopeAction::Replace(2..2, b"b\n".to_vec())]);
+    assert!(rope_string(&rope) == "a\nb\n");
+}
+
+#[test]
+fn broadcast_batch_across_ropes() {
+    let batches     = Arc::new(Mutex::new(vec![]));
+    let batches_to  = Arc::clone(&batches);
+
+    let broadcast   = RopeBroadcast::<&'static str, u8, ()>::new();
+    broadcast.subscribe(move |batch| batches_to.lock().unwrap().push(batch.clone()));
+
+    let mut title   = PushAfterRope::from(AttributedRope::<u8, ()>::new(), broadcast.push_fn("title"));
+    let mut body    = PushAfterRope::from(AttributedRope::<u8, ()>::new(), broadcast.push_fn("body"));
+
+    broadcast.batch(|| {
+        title.replace(0..0, b"Title".iter().cloned());
+        body.replace(0..0, b"Body".iter().cloned());
+        assert!(batches.lock().unwrap().len() == 0);
+    });
+
+    title.replace(5..5, b"!".iter().cloned());
+
+    let batches = batches.lock().unwrap();
+    assert!(batches.len() == 2);
+    assert!(batches[0].transaction_id != batches[1].transaction_id);
+    assert!(batches[0].actions == vec![("title", RopeAction::Replace(0..0, b"Title".to_vec())), ("body", RopeAction::Replace(0..0, b"Body".to_vec()))]);
+    assert!(batches[1].actions == vec![("title", RopeAction::Replace(5..5, b"!".to_vec()))]);
+}
+
+#[test]
+fn push_callback_queues_edits() {
+    let pushed      = Rc::new(RefCell::new(vec![]));
+    let push_to     = Rc::clone(&pushed);
+    let queue       = RopeEditQueue::new();
+    let edit_queue  = queue.clone();
+
+    // Close brackets whenever one is opened
+    let rope        = AttributedRope::<u8, ()>::new();
+    let mut rope    = PushAfterRope::from(rope, move |action| {
+        if let RopeAction::Replace(range, cells) = &action {
+            if cells == b"(" { edit_queue.replace((range.start+1)..(range.start+1), b")".iter().cloned()); }
+        }
+
+        push_to.borrow_mut().push(action);
+    });
+    rope.set_edit_queue(queue.clone());
+
+    rope.replace(0..0, b"f".iter().cloned());
+    rope.replace(1..1, b"(".iter().cloned());
+    rope.replace(2..2, b"x".iter().cloned());
+
+    assert!(rope_string(&rope) == "f(x)");
+    assert!(queue.is_empty());
+    assert!(*pushed.borrow() == vec![
+        RopeAction::Replace(0..0, b"f".to_vec()),
+        RopeAction::Replace(1..1, b"(".to_vec()),
+        RopeAction::Replace(2..2, b")".to_vec()),
+        RopeAction::Replace(2..2, b"x".to_vec()),
+    ]);
+}
+
+#[test]
+fn double_buffered_rope_swaps_whole_batches() {
+    let mut rope        = PullRope::from(AttributedRope::<u8, ()>::new(), || { });
+    let mut buffered    = DoubleBufferedRope::from(AttributedRope::new());
+    let reader          = buffered.reader();
+
+    // Each batch adds a line of the same length, so a half-applied batch would show up as a length that isn't a multiple of 4
+    let render = std::thread::spawn(move || {
+        for _ in 0..1000 {
+            let frame = reader.read();
+            assert!(frame.len() % 4 == 0);
+        }
+    });
+
+    for line in 0..100 {
+        let len = rope.len();
+        rope.replace(len..len, format!("{:03}", line).bytes().take(1));
+        rope.replace((len+1)..(len+1), format!("{:03}", line).bytes().skip(1));
+        rope.replace((len+3)..(len+3), "\n".bytes());
+
+        buffered.apply_batch(rope.pull_changes().collect::<Vec<_>>());
+    }
+
+    render.join().unwrap();
+
+    assert!(buffered.read().len() == 400);
+    assert!(rope_string(&*buffered.read()).starts_with("000\n001\n"));
+}
+
+#[test]
+fn builder_chain_composes_wrappers() {
+    let notifications   = Rc::new(RefCell::new(vec![]));
+    let before_to       = Rc::clone(&notifications);
+    let after_to        = Rc::clone(&notifications);
+    let pull_to         = Rc::clone(&notifications);
+    let batches         = Arc::new(Mutex::new(vec![]));
+    let batches_to      = Arc::clone(&batches);
+
+    let broadcast       = RopeBroadcast::<&'static str, u8, ()>::new();
+    broadcast.subscribe(move |batch| batches_to.lock().unwrap().push(batch.clone()));
+
+    let mut rope = RopeBuilderChain::new(AttributedRope::<u8, ()>::new())
+        .push_after(move |_| after_to.borrow_mut().push("after"))
+        .broadcast(&broadcast, "text")
+        .push_before(move |_| before_to.borrow_mut().push("before"))
+        .pull(move || pull_to.borrow_mut().push("pull"))
+        .build();
+
+    rope.replace(0..0, "Hello".bytes());
+    rope.replace(5..5, "!".bytes());
+
+    assert!(*notifications.borrow() == vec!["before", "after", "pull", "before", "after"]);
+    assert!(batches.lock().unwrap().len() == 2);
+    assert!(rope.pull_changes().collect::<Vec<_>>() == vec![RopeAction::Replace(0..0, b"!".to_vec()), RopeAction::Replace(0..0, b"Hello".to_vec())]);
+}
+
+#[test]
+fn ropes_and_changes_move_to_worker_threads() {
+    let (send_changes, receive_changes) = mpsc::channel();
+
+    let mut rope    = PullRope::from(AttributedRope::<u8, i64>::new(), || {});
+    rope.replace(0..0, "Hello, world".bytes());
+    rope.set_attributes(0..5, 1);
+
+    let changes     = rope.pull_changes().collect::<Vec<_>>();
+    let frozen      = FrozenRope::from(AttributedRope::<u8, i64>::from_str("Hello, world"));
+
+    // Apply the pulled changes on another thread while reading a shared frozen rope
+    let worker = std::thread::spawn(move || {
+        let mut mirror = AttributedRope::<u8, i64>::new();
+        changes.into_iter().for_each(|change| mirror.edit(change));
+
+        assert!(frozen.read_cells(0..12).eq(mirror.read_cells(0..mirror.len())));
+        send_changes.send(mirror).unwrap();
+    });
+
+    worker.join().unwrap();
+    let mirror = receive_changes.recv().unwrap();
+    assert!(mirror.read_attributes(0) == (&1, 0..5));
+}
+
+#[test]
+fn pull_rope_versions() {
+    let mut rope = PullRope::from(AttributedRope::<u8, ()>::new(), || { });
+    assert!(rope.version() == 0);
+
+    rope.replace(0..0, "Hello".bytes());
+    rope.replace(5..5, ", World".bytes());
+    assert!(rope.version() == 2);
+
+    rope.transaction(|rope| {
+        rope.replace(0..0, ">".bytes());
+        rope.replace(1..1, " ".bytes());
+    });
+    assert!(rope.version() == 3);
+
+    // Pulling changes does not change the version
+    let consumer_version = rope.version();
+    assert!(rope.pull_changes().count() > 0);
+    assert!(rope.version() == 3);
+    assert!(rope.pulled_version() == 3);
+
+    // The consumer can pull the changes since the version it last saw
+    rope.replace(0..2, "".bytes());
+    assert!(rope.pull_changes_since(consumer_version).map(|changes| changes.collect::<Vec<_>>()) == Some(vec![RopeAction::Replace(0..2, vec![])]));
+
+    // A consumer that missed a pull gets no changes
+    rope.replace(0..0, "!".bytes());
+    assert!(rope.pull_changes_since(consumer_version).is_none());
+    assert!(rope.pull_changes_since(rope.pulled_version()).map(|changes| changes.count()) == Some(1));
+}
+
+#[test]
+fn peek_pull_rope_changes() {
+    let mut rope = PullRope::from(AttributedRope::<u8, ()>::from("Hello, World".bytes()), || { });
+    assert!(!rope.has_pending_changes());
+    assert!(rope.pending_change_count() == 0);
+
+    rope.replace(0..5, "Goodbye".bytes());
+    rope.replace(9..14, "Moon".bytes());
+
+    assert!(rope.has_pending_changes());
+    assert!(rope.pending_change_count() == 2);
+    assert!(rope.peek_changes()[0].original_range == (0..5));
+    assert!(rope.peek_changes()[0].new_range == (0..7));
+    assert!(rope.peek_changes()[1].original_range == (7..12));
+    assert!(rope.peek_changes()[1].new_range == (9..13));
+
+    // Peeking leaves the changes in place
+    assert!(rope.pull_changes().count() == 2);
+    assert!(!rope.has_pending_changes());
+    assert!(rope.peek_changes().is_empty());
+}
+
+#[test]
+fn pull_minimal_diffs() {
+    let mut rope = PullRope::from(AttributedRope::<u8, ()>::from("Hello, World".bytes()), || { });
+    rope.enable_minimal_diffs();
+
+    // Retyping the same text produces no changes
+    rope.replace(0..5, "Hello".bytes());
+    assert!(rope.pull_changes().count() == 0);
+
+    // Only the cells that are different are replaced
+    rope.replace(0..12, "Hello, There".bytes());
+    assert!(rope.pull_changes().collect::<Vec<_>>() == vec![RopeAction::Replace(7..12, "There".bytes().collect())]);
+
+    // Overlapping edits are narrowed against the rope as it was at the last pull
+    rope.replace(7..12, "World".bytes());
+    rope.replace(0..5, "Jello".bytes());
+    rope.replace(0..1, "H".bytes());
+    rope.replace(7..12, "Worlds".bytes());
+    assert!(rope.pull_changes().collect::<Vec<_>>() == vec![
+        RopeAction::Replace(12..12, "s".bytes().collect()),
+        RopeAction::Replace(7..12, "World".bytes().collect())]);
+
+    let mut mirror = AttributedRope::<u8, ()>::from("Hello, Worlds".bytes());
+    rope.replace(13..13, "!".bytes());
+    rope.replace(0..1, "Y".bytes());
+    rope.replace(0..1, "J".bytes());
+    rope.pull_changes().for_each(|action| mirror.edit(action));
+    assert!(mirror == "Jello, Worlds!");
+}
+
+#[test]
+fn pull_dirty_ranges() {
+    let mut rope = PullRope::from(AttributedRope::<u8, i64>::from("Hello, World".bytes()), || { });
+
+    rope.replace(0..5, "Goodbye".bytes());
+    rope.set_attributes(9..14, 1);
+
+    assert!(rope.pull_dirty_ranges().collect::<Vec<_>>() == vec![(0..5, 0..7, false), (7..12, 9..14, true)]);
+    assert!(!rope.has_pending_changes());
+    assert!(rope.pull_dirty_ranges().count() == 0);
+}
+
+#[test]
+fn pull_rope_consumers_have_separate_changes() {
+    let main_pulls      = Arc::new(Mutex::new(0));
+    let minimap_pulls   = Arc::new(Mutex::new(0));
+
+    let pulls           = Arc::clone(&main_pulls);
+    let mut rope        = PullRope::from(AttributedRope::<u8, ()>::new(), move || { *pulls.lock().unwrap() += 1; });
+    let pulls           = Arc::clone(&minimap_pulls);
+    let minimap         = rope.add_consumer(move || { *pulls.lock().unwrap() += 1; });
+
+    rope.replace(0..0, "Hello".bytes());
+    rope.replace(5..5, ", World".bytes());
+
+    assert!(*main_pulls.lock().unwrap() == 1);
+    assert!(*minimap_pulls.lock().unwrap() == 1);
+
+    // Pulling the main changes leaves the changes for the minimap in place
+    assert!(rope.pull_changes().count() > 0);
+    assert!(rope.consumer_has_pending_changes(minimap));
+
+    rope.replace(0..5, "Goodbye".bytes());
+    assert!(*main_pulls.lock().unwrap() == 2);
+    assert!(*minimap_pulls.lock().unwrap() == 1);
+
+    let mut mirror = AttributedRope::<u8, ()>::new();
+    rope.pull_consumer_changes(minimap).for_each(|action| mirror.edit(action));
+    assert!(mirror == "Goodbye, World");
+    assert!(!rope.consumer_has_pending_changes(minimap));
+    assert!(rope.has_pending_changes());
+
+    // The minimap is notified again once it has pulled its changes
+    rope.transaction(|rope| {
+        rope.replace(0..0, "> ".bytes());
+        rope.replace(0..0, "> ".bytes());
+    });
+    assert!(*minimap_pulls.lock().unwrap() == 2);
+
+    rope.remove_consumer(minimap);
+    assert!(!rope.consumer_has_pending_changes(minimap));
+    assert!(rope.pull_consumer_changes(minimap).count() == 0);
+}
+
+#[test]
+fn push_and_pull_functions_can_hold_state() {
+    let mut pushed      = vec![];
+    let mut before      = 0;
+    let mut notified    = 0;
+
+    {
+        let rope        = PullRope::from(AttributedRope::<u8, ()>::new(), || notified += 1);
+        let rope        = PushAfterRope::from(rope, |action| pushed.push(action));
+        let mut rope    = PushBeforeRope::from(rope, |_action| before += 1);
+
+        rope.replace(0..0, "Hello".bytes());
+        rope.replace(5..5, ", World".bytes());
+    }
+
+    assert!(pushed == vec![RopeAction::Replace(0..0, "Hello".bytes().collect()), RopeAction::Replace(5..5, ", World".bytes().collect())]);
+    assert!(before == 2);
+    assert!(notified == 1);
+}
+
+#[test]
+fn observers_receive_actions_and_notifications() {
+    struct ActionLog(Mutex<Vec<RopeAction<u8, ()>>>);
+    struct PullCounter(Mutex<usize>);
+
+    impl RopeObserver<u8, ()> for ActionLog {
+        fn action(&self, action: &RopeAction<u8, ()>) { self.0.lock().unwrap().push(action.clone()); }
+    }
+
+    impl RopeObserver<u8, ()> for PullCounter {
+        fn changes_available(&self) { *self.0.lock().unwrap() += 1; }
+    }
+
+    let log         = Arc::new(ActionLog(Mutex::new(vec![])));
+    let counter     = Arc::new(PullCounter(Mutex::new(0)));
+
+    let observer: Box<dyn RopeObserver<u8, ()>> = Box::new(Arc::clone(&counter));
+    let rope        = PullRope::from_observer(AttributedRope::<u8, ()>::new(), observer);
+    let mut rope    = PushAfterRope::from_observer(rope, Arc::clone(&log));
+
+    rope.replace(0..0, "Hello".bytes());
+    rope.replace(5..5, "!".bytes());
+
+    assert!(*log.0.lock().unwrap() == vec![RopeAction::Replace(0..0, "Hello".bytes().collect()), RopeAction::Replace(5..5, "!".bytes().collect())]);
+    assert!(*counter.0.lock().unwrap() == 1);
+}
+
+#[test]
+fn shared_observer_watches_several_ropes() {
+    struct EditCounter(Mutex<usize>);
+
+    impl RopeObserver<u8, ()> for EditCounter {
+        fn action(&self, _action: &RopeAction<u8, ()>) { *self.0.lock().unwrap() += 1; }
+    }
+
+    let counter                                     = Arc::new(EditCounter(Mutex::new(0)));
+    let observer: Arc<dyn RopeObserver<u8, ()>>     = counter.clone();
+
+    let mut first   = PushAfterRope::from_observer(AttributedRope::<u8, ()>::new(), Arc::clone(&observer));
+    let mut second  = PushBeforeRope::from_observer(AttributedRope::<u8, ()>::new(), Arc::clone(&observer));
+
+    first.replace(0..0, "Hello".bytes());
+    second.replace(0..0, "World".bytes());
+    first.replace(5..5, "!".bytes());
+
+    assert!(*counter.0.lock().unwrap() == 3);
+}
+
+#[test]
+fn reach_the_base_rope_through_wrappers() {
+    let rope        = PullRope::from(AttributedRope::<u8, ()>::from("Hello".bytes()), || { });
+    let mut rope    = PushAfterRope::from(rope, |_action| { });
+
+    rope.replace(5..5, ", World".bytes());
+
+    // Deref reaches the methods that are specific to the base rope
+    assert!(rope.stats().num_leaves >= 1);
+    assert!(rope.base().has_pending_changes());
+
+    // Edits made through base_mut() are not tracked by the wrapper
+    rope.base_mut().base_mut().replace(0..0, "> ".bytes());
+
+    let pull_rope = rope.into_inner();
+    assert!(pull_rope.peek_changes().len() == 1);
+    assert!(pull_rope.peek_changes()[0].original_range == (5..5));
+
+    let base = pull_rope.into_inner();
+    assert!(base == "> Hello, World");
+}
+
+#[test]
+fn slice_rope_edits_section_of_base() {
+    let base        = AttributedRope::<u8, i64>::from("Title\nBody text\nFooter".bytes());
+    let mut slice   = SliceRope::from(base, 6..15);
+
+    assert!(slice.len() == 9);
+    assert!(slice.read_cells(0..9).cloned().collect::<Vec<_>>() == "Body text".bytes().collect::<Vec<_>>());
+
+    // Edits are relative to the start of the slice, and the slice grows to include inserted cells
+    slice.replace(9..9, " here".bytes());
+    slice.set_attributes(0..4, 1);
+
+    assert!(slice.slice_range() == (6..20));
+    assert!(slice.read_attributes(2) == (&1, 0..4));
+    assert!(slice.read_attributes(6) == (&0, 4..14));
+    assert!(*slice.base() == "Title\nBody text here\nFooter");
+
+    // Edits to the base rope move the slice
+    slice.edit_base(RopeAction::Replace(0..5, "A longer title".bytes().collect()));
+    assert!(slice.slice_range() == (15..29));
+
+    slice.edit_base(RopeAction::Replace(29..29, "!".bytes().collect()));
+    slice.edit_base(RopeAction::Replace(15..15, "*".bytes().collect()));
+    assert!(slice.slice_range() == (16..30));
+
+    slice.edit_base(RopeAction::Replace(25..35, "".bytes().collect()));
+    assert!(slice.slice_range() == (16..25));
+    assert!(slice.read_cells(0..slice.len()).cloned().collect::<Vec<_>>() == "Body text".bytes().collect::<Vec<_>>());
+}
+
+#[test]
+fn filter_rope_hides_cells() {
+    // Attribute 1 marks a folded region
+    let mut base = AttributedRope::<u8, i64>::from("one\ttwo\rthree".bytes());
+    base.set_attributes(4..7, 1);
+
+    let mut filtered = FilterRope::from(base, |cell: &u8, attribute: &i64| !cell.is_ascii_control() && *attribute != 1);
+    let read_all     = |rope: &FilterRope<_, _>| rope.read_cells(0..rope.len()).cloned().collect::<Vec<u8>>();
+
+    assert!(read_all(&filtered) == b"onethree".to_vec());
+    assert!(filtered.to_base_index(3) == 8);
+    assert!(filtered.to_filtered_index(5) == 3);
+
+    // Insertions go before the visible cell at the insertion point
+    filtered.replace(3..3, " ".bytes());
+    assert!(read_all(&filtered) == b"one three".to_vec());
+    assert!(*filtered.base() == "one\ttwo\r three");
+
+    // Deleting a range deletes the hidden cells within it
+    filtered.replace(2..4, "".bytes());
+    assert!(read_all(&filtered) == b"onthree".to_vec());
+    assert!(*filtered.base() == "onthree");
+
+    // Changing the attributes in the base rope can hide cells
+    filtered.edit_base(RopeAction::SetAttributes(2..4, 1));
+    assert!(read_all(&filtered) == b"onree".to_vec());
+    assert!(filtered.read_attributes(3) == (&0, 2..5));
+    assert!(filtered.find(&b"nr"[..], 0) == Some(1));
+}
+
+#[test]
+fn split_actions_into_two_ropes() {
+    let mut combined    = AttributedRope::<u8, i64>::from("Hello, World".bytes());
+    let mut left        = AttributedRope::<u8, i64>::from("Hello,".bytes());
+    let mut right       = AttributedRope::<u8, i64>::from(" World".bytes());
+    let mut splitter    = RopeSplitter::new(6);
+
+    let actions = vec![
+        RopeAction::Replace(0..5, "Goodbye".bytes().collect()),
+        RopeAction::Replace(8..8, "!".bytes().collect()),
+        RopeAction::SetAttributes(3..11, 1),
+        RopeAction::Replace(5..10, "-".bytes().collect()),
+        RopeAction::Replace(11..11, "?".bytes().collect())
+    ];
+
+    for action in actions {
+        combined.edit(action.clone());
+
+        for split_action in splitter.send(vec![action]) {
+            match split_action {
+                SplitRopeAction::Left(action)   => left.edit(action),
+                SplitRopeAction::Right(action)  => right.edit(action)
+            }
+        }
+
+        let mut rejoined = left.clone();
+        rejoined.replace(left.len()..left.len(), right.read_cells(0..right.len()).cloned());
+        rejoined.copy_attributes_from(&right, 0..right.len(), left.len());
+
+        assert!(splitter.left_len() == left.len());
+        assert!(rejoined == combined, "{:?} != {:?}", rejoined, combined);
+    }
+}
+
+#[test]
+fn concatenate_several_segments() {
+    let mut segments    = RopeSegments::new();
+    let mut transcript  = AttributedRope::<u8, ()>::new();
+
+    segments.insert_segment(0);
+    segments.insert_segment(1);
+    segments.send_to(0, vec![RopeAction::Replace(0..0, "first ".bytes().collect())]).for_each(|action| transcript.edit(action));
+    segments.send_to(1, vec![RopeAction::Replace(0..0, "third".bytes().collect())]).for_each(|action| transcript.edit(action));
+
+    // Insert a segment between the other two
+    segments.insert_segment(1);
+    segments.send_to(1, vec![RopeAction::Replace(0..0, "second ".bytes().collect())]).for_each(|action| transcript.edit(action));
+    assert!(transcript == "first second third");
+
+    // Edits to a segment move the segments that follow it
+    segments.send_to(0, vec![RopeAction::Replace(0..5, "1st".bytes().collect())]).for_each(|action| transcript.edit(action));
+    segments.send_to(2, vec![RopeAction::Replace(0..5, "3rd".bytes().collect())]).for_each(|action| transcript.edit(action));
+    assert!(transcript == "1st second 3rd");
+    assert!(segments.segment_offset(2) == 11);
+
+    // Removing a segment removes its cells
+    segments.remove_segment(1).into_iter().for_each(|action| transcript.edit(action));
+    assert!(transcript == "1st 3rd");
+    assert!(segments.num_segments() == 2);
+    assert!(segments.len() == transcript.len());
+}
+
+#[test]
+fn read_and_edit_concatenated_ropes() {
+    let header      = AttributedRope::<u8, i64>::from("Title\n".bytes());
+    let body        = AttributedRope::<u8, i64>::from("Some text".bytes());
+    let mut rope    = ConcatRope::from(header, body);
+
+    assert!(rope.len() == 15);
+    assert!(rope.read_cells(3..10).cloned().collect::<Vec<_>>() == b"le\nSome".to_vec());
+
+    // Edits crossing the join are divided between the two ropes
+    rope.replace(4..11, "!\nNew".bytes());
+    assert!(*rope.first() == "Titl!\nNew");
+    assert!(*rope.second() == "text");
+
+    rope.set_attributes(8..11, 1);
+    assert!(rope.read_attributes(10) == (&1, 9..11));
+    assert!(rope.read_attributes(8) == (&1, 8..9));
+    assert!(rope.find(&b"Newte"[..], 0) == Some(6));
+
+    // Insertions at the join go into the second rope
+    rope.replace(9..9, " ".bytes());
+    assert!(*rope.second() == " text");
+}
+
+#[test]
+fn record_and_replay_edits() {
+    let mut rope = RecordingRope::from(AttributedRope::<u8, i64>::from("Hello".bytes()));
+    rope.enable_timestamps();
+
+    rope.replace(5..5, ", World".bytes());
+    rope.set_attributes(0..5, 1);
+    rope.replace_attributes(12..12, "!".bytes(), 2);
+
+    assert!(rope.recording().len() == 3);
+    assert!(rope.recording().iter().all(|recorded| recorded.timestamp.is_some()));
+    assert!(rope.recording()[0].action == RopeAction::Replace(5..5, ", World".bytes().collect()));
+
+    // Replaying the recording onto a copy of the original rope reproduces the edits
+    let mut mirror = AttributedRope::<u8, i64>::from("Hello".bytes());
+    rope.replay_onto(&mut mirror);
+    assert!(mirror == *rope.base());
+
+    let recording   = rope.take_recording();
+    let mut mirror  = AttributedRope::<u8, i64>::from("Hello".bytes());
+    replay_recording(&recording, &mut mirror);
+    assert!(mirror == *rope.base());
+    assert!(rope.recording().is_empty());
+}
+
+#[test]
+fn throttle_notifications() {
+    let notifications   = Arc::new(Mutex::new(0));
+    let counter         = Arc::clone(&notifications);
+
+    // Notify at most once an hour, or every 10 edits
+    let mut rope = RopeBuilderChain::new(AttributedRope::<u8, ()>::new())
+        .throttle(Duration::from_secs(3600), Some(10), move || { *counter.lock().unwrap() += 1; })
+        .build();
+
+    // The first edit notifies immediately
+    rope.replace(0..0, "0".bytes());
+    assert!(*notifications.lock().unwrap() == 1);
+
+    // The next 9 edits are throttled, and the 10th sends a notification
+    for _ in 0..9 { rope.replace(0..0, "x".bytes()); }
+    assert!(*notifications.lock().unwrap() == 1);
+
+    rope.replace(0..0, "x".bytes());
+    assert!(*notifications.lock().unwrap() == 2);
+
+    // A transaction counts as a single edit
+    rope.transaction(|rope| { for _ in 0..20 { rope.replace(0..0, "y".bytes()); } });
+    assert!(*notifications.lock().unwrap() == 2);
+
+    // Polling doesn't notify before the interval, but flushing does
+    rope.poll();
+    assert!(*notifications.lock().unwrap() == 2);
+    rope.flush();
+    assert!(*notifications.lock().unwrap() == 3);
+    rope.flush();
+    assert!(*notifications.lock().unwrap() == 3);
+
+    // Changes are accumulated like a pull rope
+    let mut mirror = AttributedRope::<u8, ()>::new();
+    rope.pull_changes().for_each(|action| mirror.edit(action));
+    assert!(mirror.len() == 31);
+    assert!(!rope.has_pending_changes());
+}
+
+#[test]
+fn mirror_pair_does_not_echo() {
+    let mirror      = MirrorPair::<u8, ()>::new();
+    let left        = Arc::new(Mutex::new(PushAfterRope::from(AttributedRope::<u8, ()>::new(), mirror.push_fn(MirrorSide::Left))));
+    let right       = Arc::new(Mutex::new(PushAfterRope::from(AttributedRope::<u8, ()>::new(), mirror.push_fn(MirrorSide::Right))));
+
+    let left_rope   = Arc::clone(&left);
+    let right_rope  = Arc::clone(&right);
+    mirror.connect(MirrorSide::Left, move |action| left_rope.lock().unwrap().edit(action));
+    mirror.connect(MirrorSide::Right, move |action| right_rope.lock().unwrap().edit(action));
+
+    // Edits to either side appear once on the other side
+    left.lock().unwrap().replace(0..0, "Hello".bytes());
+    right.lock().unwrap().replace(5..5, ", World".bytes());
+    left.lock().unwrap().replace(0..1, "J".bytes());
+
+    assert_eq!(left.lock().unwrap().base(), "Jello, World");
+    assert_eq!(right.lock().unwrap().base(), "Jello, World");
+
+    // Once a side is disconnected, its rope stops receiving edits
+    mirror.disconnect(MirrorSide::Left);
+    right.lock().unwrap().replace(0..0, "x".bytes());
+
+    assert_eq!(left.lock().unwrap().base(), "Jello, World");
+    assert_eq!(right.lock().unwrap().base(), "xJello, World");
+
+    mirror.disconnect(MirrorSide::Right);
+}
+
+#[test]
+fn wrappers_read_chunks_from_base_rope() {
+    let text        = "Hello, world ".repeat(100);
+    let base        = AttributedRope::<u8, ()>::from(text.bytes().collect::<Vec<_>>());
+    let num_chunks  = Rope::read_chunks(&base, 0..base.len()).count();
+    assert!(num_chunks < 100);
+
+    // Each wrapper should return the same chunks as the rope it wraps, rather than one chunk per cell
+    let pull        = PullRope::from(base.clone(), || { });
+    let push        = PushAfterRope::from(base.clone(), |_action| { });
+    let history     = HistoryRope::from(base.clone());
+    let throttle    = ThrottleRope::from(base.clone(), Duration::from_secs(1), None, || { });
+
+    assert!(pull.read_chunks(0..pull.len()).count() == num_chunks);
+    assert!(push.read_chunks(0..push.len()).count() == num_chunks);
+    assert!(history.read_chunks(0..history.len()).count() == num_chunks);
+    assert!(throttle.read_chunks(0..throttle.len()).count() == num_chunks);
+
+    // Searching uses the chunks
+    assert!(pull.starts_with(&"Hello, world Hello".bytes().collect::<Vec<_>>()));
+    assert!(pull.contains(&"world Hello, world".bytes().collect::<Vec<_>>()));
+    assert!(pull.read_chunks(0..pull.len()).flatten().eq(text.as_bytes().iter()));
+}
+
+#[cfg(loom)]
+#[test]
+fn loom_edit_queue_from_two_threads() {
+    loom::model(|| {
+        let queue   = RopeEditQueue::<u8, ()>::new();
+        let other   = queue.clone();
+        let thread  = loom::thread::spawn(move || other.replace(0..0, vec![1]));
+
+        queue.replace(0..0, vec![2]);
+        thread.join().unwrap();
+
+        let mut edits = vec![];
+        while let Some(edit) = queue.pop() { edits.push(edit); }
+
+        assert!(edits.len() == 2);
+        assert!(edits.contains(&RopeAction::Replace(0..0, vec![1])));
+        assert!(edits.contains(&RopeAction::Replace(0..0, vec![2])));
+    });
+}
@@ -0,0 +1,245 @@
+use super::pull_rope::*;
+
+use crate::api::*;
+
+use std::time::{Duration, Instant};
+use std::ops::{Range};
+
+///
+/// A throttle rope gathers changes in the same way as a `PullRope`, but limits how often its notification function is
+/// called
+///
+/// A notification is sent after an edit if the notification interval has passed since the last notification, or if
+/// the maximum number of edits have been made since then. This is useful for ropes that are edited very frequently (for
+/// example, a log that's being streamed into a rope) where waking up the consumer for every edit would be wasteful.
+///
+/// Edits that arrive too soon after a notification don't send one themselves, so `poll()` should be called from time
+/// to time (for example, from a timer running at the notification interval) to send the notification once it's due.
+/// `flush()` sends a notification immediately if there are edits that haven't been notified yet.
+///
+pub struct ThrottleRope<BaseRope, NotifyFn>
+where
+BaseRope: RopeMut,
+NotifyFn: FnMut() -> () {
+    /// The rope that gathers the changes
+    rope: PullRope<BaseRope, fn() -> ()>,
+
+    /// The function to call when changes are available
+    notify_fn: NotifyFn,
+
+    /// The minimum time between notifications
+    interval: Duration,
+
+    /// If set, a notification is sent after this many edits, even if the interval hasn't passed
+    max_edits: Option<usize>,
+
+    /// The number of edits since the last notification
+    edits_since_notify: usize,
+
+    /// When the last notification was sent
+    last_notify: Option<Instant>,
+
+    /// The number of transactions in progress (notifications are not sent while this is non-zero)
+    transaction_depth: usize
+}
+
+impl<BaseRope, NotifyFn> ThrottleRope<BaseRope, NotifyFn>
+where
+BaseRope: RopeMut,
+NotifyFn: FnMut() -> () {
+    ///
+    /// Creates a throttle rope that calls its notification function at most once per `interval`, or after every
+    /// `max_edits` edits if that's set
+    ///
+    pub fn from(rope: BaseRope, interval: Duration, max_edits: Option<usize>, notify_fn: NotifyFn) -> ThrottleRope<BaseRope, NotifyFn> {
+        let no_notification: fn() -> () = || { };
+
+        ThrottleRope {
+            rope:               PullRope::from(rope, no_notification),
+            notify_fn:          notify_fn,
+            interval:           interval,
+            max_edits:          max_edits,
+            edits_since_notify: 0,
+            last_notify:        None,
+            transaction_depth:  0
+        }
+    }
+
+    ///
+    /// Pulls the pending changes from this rope (see `PullRope::pull_changes()`)
+    ///
+    pub fn pull_changes<'a>(&'a mut self) -> impl 'a+Iterator<Item=RopeAction<BaseRope::Cell, BaseRope::Attribute>> {
+        self.rope.pull_changes()
+    }
+
+    ///
+    /// True if there are changes waiting to be pulled from this rope
+    ///
+    pub fn has_pending_changes(&self) -> bool {
+        self.rope.has_pending_changes()
+    }
+
+    ///
+    /// Sends a notification if there are edits that haven't been notified and the interval has passed since the last
+    /// notification
+    ///
+    pub fn poll(&mut self) {
+        if self.edits_since_notify > 0 && self.interval_passed() {
+            self.notify();
+        }
+    }
+
+    ///
+    /// Sends a notification immediately if there are edits that haven't been notified yet
+    ///
+    pub fn flush(&mut self) {
+        if self.edits_since_notify > 0 {
+            self.notify();
+        }
+    }
+
+    ///
+    /// Returns the pull rope that gathers the changes for this rope
+    ///
+    pub fn into_inner(self) -> PullRope<BaseRope, fn() -> ()> {
+        self.rope
+    }
+
+    ///
+    /// Borrows the pull rope that gathers the changes for this rope
+    ///
+    pub fn base(&self) -> &PullRope<BaseRope, fn() -> ()> {
+        &self.rope
+    }
+
+    ///
+    /// Borrows the pull rope that gathers the changes for this rope for editing
+    ///
+    /// This can be used to call the other functions that pull changes. Edits made through this reference are still
+    /// gathered, but they aren't counted towards the next notification.
+    ///
+    pub fn base_mut(&mut self) -> &mut PullRope<BaseRope, fn() -> ()> {
+        &mut self.rope
+    }
+
+    ///
+    /// True if the notification interval has passed since the last notification
+    ///
+    fn interval_passed(&self) -> bool {
+        match self.last_notify {
+            Some(last_notify)   => last_notify.elapsed() >= self.interval,
+            None                => true
+        }
+    }
+
+    ///
+    /// Calls the notification function and resets the throttle
+    ///
+    fn notify(&mut self) {
+        self.edits_since_notify = 0;
+        self.last_notify        = Some(Instant::now());
+
+        (self.notify_fn)();
+    }
+
+    ///
+    /// Counts an edit and sends a notification if one is due
+    ///
+    fn finish_edit(&mut self) {
+        self.edits_since_notify += 1;
+
+        if self.transaction_depth == 0 {
+            let max_edits_reached = self.max_edits.map(|max_edits| self.edits_since_notify >= max_edits).unwrap_or(false);
+
+            if max_edits_reached || self.interval_passed() {
+                self.notify();
+            }
+        }
+    }
+}
+
+impl<BaseRope, NotifyFn> Rope for ThrottleRope<BaseRope, NotifyFn>
+where
+BaseRope: RopeMut,
+NotifyFn: FnMut() -> () {
+    /// A 'cell' or character in the rope. For a UTF-8 rope this could be `u8`, for xample
+    type Cell = BaseRope::Cell;
+
+    /// The type of an attribute in the rope. Every cell range has an attribute attached to it
+    type Attribute = BaseRope::Attribute;
+
+    ///
+    /// Returns the number of cells in this rope
+    ///
+    #[inline]
+    fn len(&self) -> usize {
+        self.rope.len()
+    }
+
+    ///
+    /// Reads the cell values for a range in this rope
+    ///
+    #[inline]
+    fn read_cells<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a Self::Cell>> {
+        self.rope.read_cells(range)
+    }
+
+    ///
+    /// Reads the cells for a range in this rope as a series of slices, in order
+    ///
+    #[inline]
+    fn read_chunks<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=&'a [Self::Cell]>> {
+        self.rope.read_chunks(range)
+    }
+
+    ///
+    /// Returns the attributes set at the specified location and their extent
+    ///
+    #[inline]
+    fn read_attributes<'a>(&'a self, pos: usize) -> (&'a Self::Attribute, Range<usize>) {
+        self.rope.read_attributes(pos)
+    }
+
+    ///
+    /// Reads the attribute runs that intersect a range of this rope
+    ///
+    #[inline]
+    fn read_attribute_runs<'a>(&'a self, range: Range<usize>) -> Box<dyn 'a+Iterator<Item=(&'a Self::Attribute, Range<usize>)>> {
+        self.rope.read_attribute_runs(range)
+    }
+}
+
+impl<BaseRope, NotifyFn> RopeMut for ThrottleRope<BaseRope, NotifyFn>
+where
+BaseRope: RopeMut,
+NotifyFn: FnMut() -> () {
+    ///
+    /// Performs the specified editing action to this rope
+    ///
+    fn edit(&mut self, action: RopeAction<Self::Cell, Self::Attribute>) {
+        self.rope.edit(action);
+        self.finish_edit();
+    }
+
+    ///
+    /// Performs a series of edits to this rope as a single transaction, which counts as a single edit
+    ///
+    fn transaction<TransactionFn: FnOnce(&mut Self) -> ()>(&mut self, transaction_fn: TransactionFn) {
+        let edits_before = self.edits_since_notify;
+
+        self.transaction_depth += 1;
+        transaction_fn(self);
+        self.transaction_depth -= 1;
+
+        if self.edits_since_notify > edits_before {
+            self.edits_since_notify = edits_before;
+            self.finish_edit();
+        }
+    }
+}
+
+impl<BaseRope, NotifyFn> RopeSearch for ThrottleRope<BaseRope, NotifyFn>
+where
+BaseRope:       RopeMut,
+NotifyFn:       FnMut() -> (),
+BaseRope::Cell: PartialEq { }
@@ -0,0 +1,52 @@
+//!
+//! The mutex used by the parts of this crate that can be shared between threads
+//!
+//! By default this is `std::sync::Mutex`. The `parking_lot` feature switches to `parking_lot::Mutex`, and building with
+//! `--cfg loom` switches to `loom::sync::Mutex` so that the thread-safe types can be checked under the loom model checker.
+//! The lock is never held while calling out to code outside of this crate, so a poisoned lock indicates a bug in this
+//! crate and causes a panic.
+//!
+
+#[cfg(loom)]
+use loom::sync as mutex_impl;
+
+#[cfg(all(not(loom), feature = "parking_lot"))]
+use parking_lot as mutex_impl;
+
+#[cfg(all(not(loom), not(feature = "parking_lot")))]
+use std::sync as mutex_impl;
+
+pub (crate) use self::mutex_impl::{MutexGuard};
+
+///
+/// A mutual exclusion lock, using whichever implementation this crate was built with
+///
+pub (crate) struct Mutex<T>(mutex_impl::Mutex<T>);
+
+impl<T> Mutex<T> {
+    ///
+    /// Creates a new mutex containing the specified value
+    ///
+    pub (crate) fn new(value: T) -> Mutex<T> {
+        Mutex(mutex_impl::Mutex::new(value))
+    }
+
+    ///
+    /// Waits for the lock to become available, then returns a guard that releases it when dropped
+    ///
+    #[cfg(feature = "parking_lot")]
+    #[cfg(not(loom))]
+    #[inline]
+    pub (crate) fn lock(&self) -> MutexGuard<'_, T> {
+        self.0.lock()
+    }
+
+    ///
+    /// Waits for the lock to become available, then returns a guard that releases it when dropped
+    ///
+    #[cfg(any(loom, not(feature = "parking_lot")))]
+    #[inline]
+    pub (crate) fn lock(&self) -> MutexGuard<'_, T> {
+        self.0.lock().expect("Rope mutex poisoned")
+    }
+}
@@ -0,0 +1,77 @@
+//!
+//! Compile-time checks that the rope and action types can be sent to and shared between threads
+//!
+//! None of these functions are ever called: they fail to compile if one of the types stops being `Send` or `Sync` when
+//! its cells and attributes are (for example, because an `Rc` was added to it).
+//!
+
+#![allow(dead_code)]
+
+use crate::api::*;
+use crate::rope::*;
+use crate::stream_rope::*;
+
+fn assert_send<T: Send>() { }
+fn assert_send_sync<T: Send+Sync>() { }
+
+///
+/// The ropes and the types used to read them
+///
+fn ropes<Cell, Attribute>()
+where
+Cell:       'static+Send+Sync+Clone,
+Attribute:  'static+Send+Sync+PartialEq+Clone+Default {
+    assert_send_sync::<AttributedRope<Cell, Attribute>>();
+    assert_send_sync::<DoubleBufferedRope<Cell, Attribute>>();
+    assert_send_sync::<DoubleBufferedReader<Cell, Attribute>>();
+    assert_send_sync::<FrozenRope<Cell, Attribute>>();
+    assert_send_sync::<ProgressiveRope<Cell, Attribute>>();
+    assert_send_sync::<LayeredRope<Cell, u32, Attribute>>();
+    assert_send_sync::<RopeConcatenator<Cell, Attribute>>();
+    assert_send_sync::<AttributedRopeIterator<'static, Cell, Attribute>>();
+    assert_send_sync::<AttributedRopeChunkIterator<'static, Cell, Attribute>>();
+    assert_send_sync::<AttributedRopeRunIterator<'static, Cell, Attribute>>();
+    assert_send_sync::<AttributeTable<Attribute>>();
+    assert_send_sync::<LineIndex>();
+}
+
+///
+/// The actions and the batches of changes that are sent between ropes
+///
+fn actions<Cell, Attribute>()
+where
+Cell:       'static+Send+Sync,
+Attribute:  'static+Send+Sync {
+    assert_send_sync::<RopeAction<Cell, Attribute>>();
+    assert_send_sync::<RopeActionRef<'static, Cell, Attribute>>();
+    assert_send_sync::<PulledChange<Cell, Attribute>>();
+    assert_send_sync::<RopeBatch<u64, Cell, Attribute>>();
+}
+
+///
+/// The types that are designed to be shared between threads (these only need their cells and attributes to be `Send`)
+///
+fn shared<Cell, Attribute>()
+where
+Cell:       'static+Send+Clone,
+Attribute:  'static+Send+PartialEq+Clone+Default {
+    assert_send_sync::<RopeBroadcast<u64, Cell, Attribute>>();
+    assert_send_sync::<RopeEditQueue<Cell, Attribute>>();
+    assert_send_sync::<MirrorPair<Cell, Attribute>>();
+    assert_send::<RopeBatch<u64, Cell, Attribute>>();
+}
+
+///
+/// The streaming wrappers are `Send` and `Sync` if the rope and the callback function are
+///
+fn wrappers<Cell, Attribute, NotifyFn, PushFn>()
+where
+Cell:       'static+Send+Sync+Clone,
+Attribute:  'static+Send+Sync+PartialEq+Clone+Default,
+NotifyFn:   Send+Sync+FnMut() -> (),
+PushFn:     Send+Sync+FnMut(RopeAction<Cell, Attribute>) -> () {
+    assert_send_sync::<PullRope<AttributedRope<Cell, Attribute>, NotifyFn>>();
+    assert_send_sync::<PushAfterRope<AttributedRope<Cell, Attribute>, PushFn>>();
+    assert_send_sync::<HistoryRope<AttributedRope<Cell, Attribute>>>();
+    assert_send_sync::<SliceRope<AttributedRope<Cell, Attribute>>>();
+}
@@ -0,0 +1,29 @@
+//!
+//! Macros used to report what the ropes are doing via the `tracing` crate
+//!
+//! When the `tracing` feature is not enabled, these compile to nothing (and their arguments are not evaluated).
+//!
+
+/// Enters a span that lasts until the end of the current scope (its duration is the time taken by an operation)
+#[cfg(feature = "tracing")]
+macro_rules! rope_span {
+    ($($arg:tt)*) => { let _span = tracing::debug_span!($($arg)*).entered(); }
+}
+
+/// Enters a span that lasts until the end of the current scope (its duration is the time taken by an operation)
+#[cfg(not(feature = "tracing"))]
+macro_rules! rope_span {
+    ($($arg:tt)*) => { }
+}
+
+/// Reports an event for a change to the structure of a rope
+#[cfg(feature = "tracing")]
+macro_rules! rope_event {
+    ($($arg:tt)*) => { tracing::trace!($($arg)*); }
+}
+
+/// Reports an event for a change to the structure of a rope
+#[cfg(not(feature = "tracing"))]
+macro_rules! rope_event {
+    ($($arg:tt)*) => { }
+}